@@ -0,0 +1,21 @@
+// 構建腳本
+//
+// 從 proto/orban.proto 生成 Orban Protocol 的 Protocol Buffers 類型，
+// 啟用 `grpc` 功能時另生成 AgentGateway 服務的 tonic 客戶端與伺服器
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/orban.proto");
+
+    // 未指定 PROTOC 時使用內建的 protoc，不依賴系統安裝
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+
+    #[cfg(feature = "grpc")]
+    tonic_build::configure().compile(&["proto/orban.proto"], &["proto/"])?;
+
+    #[cfg(not(feature = "grpc"))]
+    prost_build::compile_protos(&["proto/orban.proto"], &["proto/"])?;
+
+    Ok(())
+}
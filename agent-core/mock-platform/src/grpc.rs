@@ -0,0 +1,129 @@
+// gRPC 端點
+//
+// 以 HTTP/2 連線前言辨識 gRPC 連線，每條 AgentGateway.Session 串流是一個會話，
+// 流程與 WebSocket 連線相同（見 session.rs）。模擬斷線時結束回應串流
+
+use crate::session::{serve, MessageSource};
+use crate::{SessionHandle, Shared};
+use futures::Stream;
+use orban_agent_core::network::pb;
+use orban_agent_core::network::pb::agent_gateway_server::{AgentGateway, AgentGatewayServer};
+use orban_agent_core::network::Message;
+use orban_agent_core::Result;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Notify};
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{debug, info, warn};
+
+/// HTTP/2 連線前言的開頭
+const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0";
+
+/// 依連線前言判斷是否為 gRPC（HTTP/2）連線
+pub(crate) async fn is_grpc_request(stream: &TcpStream) -> bool {
+    let mut buf = [0u8; HTTP2_PREFACE.len()];
+
+    loop {
+        match stream.peek(&mut buf).await {
+            Ok(0) | Err(_) => return false,
+            Ok(n) if n == buf.len() => return buf == HTTP2_PREFACE,
+            Ok(n) if !HTTP2_PREFACE.starts_with(&buf[..n]) => return false,
+            Ok(_) => tokio::time::sleep(Duration::from_millis(1)).await,
+        }
+    }
+}
+
+/// 處理一個 HTTP/2 連線直到關閉
+pub(crate) async fn serve_connection(shared: Arc<Shared>, stream: TcpStream) {
+    let service = AgentGatewayServer::new(Gateway { shared });
+
+    if let Err(e) = hyper::server::conn::Http::new()
+        .http2_only(true)
+        .serve_connection(stream, service)
+        .await
+    {
+        debug!("gRPC connection ended with error: {}", e);
+    }
+}
+
+struct Gateway {
+    shared: Arc<Shared>,
+}
+
+type SessionStream = Pin<Box<dyn Stream<Item = std::result::Result<pb::Message, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl AgentGateway for Gateway {
+    type SessionStream = SessionStream;
+
+    async fn session(
+        &self,
+        request: Request<Streaming<pb::Message>>,
+    ) -> std::result::Result<Response<SessionStream>, Status> {
+        let shared = self.shared.clone();
+        let session_id = shared.next_session_id();
+        info!("Session {} opened gRPC stream", session_id);
+
+        let (tx, rx) = mpsc::unbounded_channel::<Message>();
+        let close = Arc::new(Notify::new());
+        let session = SessionHandle {
+            tx,
+            close: close.clone(),
+            stall: None,
+        };
+
+        let mut source = GrpcSource(request.into_inner());
+        tokio::spawn(async move {
+            let compression = Mutex::new(None);
+            if let Err(e) = serve(&shared, session_id, &session, &compression, &mut source).await {
+                warn!("Session {} ended with error: {}", session_id, e);
+            }
+
+            shared.remove_session(session_id);
+            session.close.notify_one();
+            info!("Session {} closed", session_id);
+        });
+
+        Ok(Response::new(Box::pin(responses(self.shared.clone(), rx, close))))
+    }
+}
+
+/// 回應串流：收到關閉通知且佇列已清空後結束
+fn responses(
+    shared: Arc<Shared>,
+    rx: mpsc::UnboundedReceiver<Message>,
+    close: Arc<Notify>,
+) -> impl Stream<Item = std::result::Result<pb::Message, Status>> + Send {
+    futures::stream::unfold((shared, rx, close), |(shared, mut rx, close)| async move {
+        loop {
+            let msg = tokio::select! {
+                biased;
+                msg = rx.recv() => msg?,
+                _ = close.notified() => return None,
+            };
+
+            match pb::Message::try_from(shared.outgoing(msg)) {
+                Ok(encoded) => return Some((Ok(encoded), (shared, rx, close))),
+                Err(e) => warn!("Failed to encode message: {}", e),
+            }
+        }
+    })
+}
+
+/// 請求串流
+struct GrpcSource(Streaming<pb::Message>);
+
+impl MessageSource for GrpcSource {
+    async fn next_message(&mut self, _shared: &Shared, session_id: u64) -> Result<Option<Message>> {
+        match self.0.message().await {
+            Ok(Some(msg)) => Message::try_from(msg).map(Some),
+            Ok(None) => Ok(None),
+            Err(status) => {
+                debug!("Session {}: stream error: {}", session_id, status);
+                Ok(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+// HTTP 長輪詢端點
+//
+// 與 WebSocket 連線相同的認證、註冊與腳本流程，訊息以 JSON 經由 REST 端點交換：
+// - POST /api/v1/agents/{agent_id}/challenge   發出認證挑戰
+// - POST /api/v1/agents/{agent_id}/auth        驗證 AUTH_RESPONSE，回覆 AUTH_SUCCESS
+// - POST /api/v1/agents/{agent_id}/messages    接收 Agent 的訊息，有回覆時（REGISTER_ACK、ACK、KEY_ROTATION 與 HARDWARE_ATTESTATION 的結果）作為回應返回
+// - GET  /api/v1/agents/{agent_id}/messages    長輪詢待下發的訊息
+//
+// 每個 Agent 一個會話，重新認證沿用原會話；模擬斷線時結束進行中的輪詢並捨棄會話。
+// 停用 WebSocket 時，升級請求一律以 403 拒絕（模擬封鎖 WebSocket 的代理）
+
+use crate::script::messages;
+use crate::session::{auth_success, authenticate, run_script};
+use crate::{SessionHandle, Shared};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use orban_agent_core::network::{requires_ack, Message, MessageType};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// REST 端點的路徑前綴
+const API_PREFIX: &[u8] = b" /api/";
+
+/// 長輪詢等待時間上限（秒）
+const MAX_WAIT_SECS: u64 = 60;
+
+/// 經由 HTTP 連線的 Agent 會話
+pub(crate) struct HttpSession {
+    session_id: u64,
+    challenge: Mutex<Option<[u8; 32]>>,
+    tx: mpsc::UnboundedSender<Message>,
+    rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<Message>>,
+    close: Arc<Notify>,
+    script: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Drop for HttpSession {
+    fn drop(&mut self) {
+        if let Some(script) = self.script.lock().unwrap().take() {
+            script.abort();
+        }
+    }
+}
+
+/// 各 Agent 的 HTTP 會話
+pub(crate) type HttpSessions = Mutex<HashMap<String, Arc<HttpSession>>>;
+
+/// 依請求行判斷是否為 REST 請求，其餘視為 WebSocket 升級
+pub(crate) async fn is_api_request(stream: &TcpStream) -> bool {
+    let mut buf = [0u8; 16];
+
+    loop {
+        match stream.peek(&mut buf).await {
+            Ok(0) | Err(_) => return false,
+            Ok(n) if n == buf.len() || buf[..n].contains(&b'\n') => {
+                return buf[..n].windows(API_PREFIX.len()).any(|w| w == API_PREFIX);
+            }
+            Ok(_) => tokio::time::sleep(Duration::from_millis(1)).await,
+        }
+    }
+}
+
+/// 處理一個 HTTP 連線直到關閉
+pub(crate) async fn serve(shared: Arc<Shared>, stream: TcpStream) {
+    let service = service_fn(move |request| {
+        let shared = shared.clone();
+        async move { Ok::<_, Infallible>(handle(&shared, request).await) }
+    });
+
+    if let Err(e) = hyper::server::conn::Http::new()
+        .http1_only(true)
+        .serve_connection(stream, service)
+        .await
+    {
+        debug!("HTTP connection ended with error: {}", e);
+    }
+}
+
+/// 路由請求
+async fn handle(shared: &Arc<Shared>, request: Request<Body>) -> Response<Body> {
+    let segments: Vec<String> = request
+        .uri()
+        .path()
+        .trim_matches('/')
+        .split('/')
+        .map(str::to_string)
+        .collect();
+
+    let (agent_id, endpoint) = match segments.as_slice() {
+        [api, v1, agents, agent_id, endpoint] if api == "api" && v1 == "v1" && agents == "agents" => {
+            (agent_id.as_str(), endpoint.as_str())
+        }
+        _ if request.headers().contains_key("Sec-WebSocket-Key") => {
+            warn!("Rejecting WebSocket upgrade");
+            return status(StatusCode::FORBIDDEN);
+        }
+        _ => return status(StatusCode::NOT_FOUND),
+    };
+
+    match (request.method(), endpoint) {
+        (&Method::POST, "challenge") => challenge(shared, agent_id),
+        (&Method::POST, "auth") => match read_message(request).await {
+            Ok(msg) => auth(shared, agent_id, msg),
+            Err(response) => response,
+        },
+        (&Method::POST, "messages") => {
+            let session = match authorize(shared, agent_id, &request) {
+                Ok(session) => session,
+                Err(response) => return response,
+            };
+            match read_message(request).await {
+                Ok(msg) => receive(shared, agent_id, &session, msg),
+                Err(response) => response,
+            }
+        }
+        (&Method::GET, "messages") => {
+            let session = match authorize(shared, agent_id, &request) {
+                Ok(session) => session,
+                Err(response) => return response,
+            };
+            poll(shared, agent_id, &session, wait_secs(&request)).await
+        }
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+/// 發出認證挑戰，尚無會話時建立
+fn challenge(shared: &Shared, agent_id: &str) -> Response<Body> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let session = shared
+        .http_sessions
+        .lock()
+        .unwrap()
+        .entry(agent_id.to_string())
+        .or_insert_with(|| {
+            let session_id = shared.next_session_id();
+            info!("Session {} opened over HTTP for agent {}", session_id, agent_id);
+
+            let (tx, rx) = mpsc::unbounded_channel();
+            Arc::new(HttpSession {
+                session_id,
+                challenge: Mutex::new(None),
+                tx,
+                rx: tokio::sync::Mutex::new(rx),
+                close: Arc::new(Notify::new()),
+                script: Mutex::new(None),
+            })
+        })
+        .clone();
+
+    let challenge = rand::random::<[u8; 32]>();
+    *session.challenge.lock().unwrap() = Some(challenge);
+
+    reply(StatusCode::OK, &messages::auth_challenge(general_purpose::STANDARD.encode(challenge)))
+}
+
+/// 驗證簽名並簽發 JWT
+fn auth(shared: &Shared, agent_id: &str, response: Message) -> Response<Body> {
+    let Some(session) = shared.http_sessions.lock().unwrap().get(agent_id).cloned() else {
+        let error = messages::error("AUTH_FAILED", "No pending auth challenge", false).reply_to(&response);
+        return reply(StatusCode::UNAUTHORIZED, &error);
+    };
+
+    shared.record(session.session_id, response.clone());
+
+    let Some(challenge) = session.challenge.lock().unwrap().take() else {
+        let error = messages::error("AUTH_FAILED", "No pending auth challenge", false).reply_to(&response);
+        return reply(StatusCode::UNAUTHORIZED, &error);
+    };
+
+    let selection = match authenticate(shared, session.session_id, &challenge, &response) {
+        Ok((authenticated, _)) if authenticated != agent_id => {
+            let error = messages::error("AUTH_FAILED", "Agent ID mismatch", false).reply_to(&response);
+            return reply(StatusCode::UNAUTHORIZED, &error);
+        }
+        Ok((_, selection)) => selection,
+        Err(error) => return reply(StatusCode::UNAUTHORIZED, &error),
+    };
+
+    match auth_success(shared, agent_id, selection, &response) {
+        Ok(success) => {
+            info!("Session {}: agent {} authenticated over HTTP", session.session_id, agent_id);
+            reply(StatusCode::OK, &success)
+        }
+        Err(e) => {
+            warn!("Failed to issue token: {}", e);
+            status(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// 驗證 Bearer 令牌，返回 Agent 的會話
+#[allow(clippy::result_large_err)]
+fn authorize(
+    shared: &Shared,
+    agent_id: &str,
+    request: &Request<Body>,
+) -> std::result::Result<Arc<HttpSession>, Response<Body>> {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    let verified = token.and_then(|token| shared.verify_token(token).ok());
+    let session = shared.http_sessions.lock().unwrap().get(agent_id).cloned();
+
+    match (verified, session) {
+        (Some(subject), Some(session)) if subject == agent_id => Ok(session),
+        _ => Err(reply(
+            StatusCode::UNAUTHORIZED,
+            &messages::error("AUTH_FAILED", "Invalid or expired session", true),
+        )),
+    }
+}
+
+/// 處理 Agent 發送的訊息，有回覆時作為回應返回
+fn receive(shared: &Arc<Shared>, agent_id: &str, session: &Arc<HttpSession>, msg: Message) -> Response<Body> {
+    let session_id = session.session_id;
+    debug!("Session {}: received {:?}", session_id, msg.message_type);
+    shared.record(session_id, msg.clone());
+
+    match msg.message_type {
+        MessageType::AgentRegister => {
+            let registration = shared.register(agent_id, &msg);
+            if registration.message_type != MessageType::RegisterAck {
+                return reply(StatusCode::OK, &registration);
+            }
+            register_session(shared, session);
+            info!("Session {}: agent {} registered", session_id, agent_id);
+
+            // 重新註冊時重新執行腳本
+            let script = tokio::spawn(run_script(shared.clone(), session.tx.clone(), session_id, shared.recorded_len()));
+            if let Some(previous) = session.script.lock().unwrap().replace(script) {
+                previous.abort();
+            }
+
+            reply(StatusCode::OK, &registration)
+        }
+        MessageType::StateSync => {
+            register_session(shared, session);
+            info!("Session {}: agent {} resumed", session_id, agent_id);
+            status(StatusCode::NO_CONTENT)
+        }
+        MessageType::KeyRotation => reply(StatusCode::OK, &shared.rotate_key(agent_id, &msg)),
+        MessageType::HardwareAttestation => reply(StatusCode::OK, &shared.attest_hardware(agent_id, &msg)),
+        message_type if requires_ack(message_type) => match shared.ack(&msg) {
+            Some(ack) => reply(StatusCode::OK, &ack),
+            None => status(StatusCode::NO_CONTENT),
+        },
+        _ => status(StatusCode::NO_CONTENT),
+    }
+}
+
+/// 登記為已註冊的會話，供 `MockPlatform::send` 與 `disconnect_all` 使用
+fn register_session(shared: &Shared, session: &HttpSession) {
+    shared.add_session(
+        session.session_id,
+        SessionHandle {
+            tx: session.tx.clone(),
+            close: session.close.clone(),
+            stall: None,
+        },
+    );
+}
+
+/// 等待待下發的訊息，逾時返回空陣列；模擬斷線時捨棄會話並返回 503
+async fn poll(shared: &Shared, agent_id: &str, session: &HttpSession, wait_secs: u64) -> Response<Body> {
+    let mut rx = session.rx.lock().await;
+
+    let first = tokio::select! {
+        msg = rx.recv() => msg,
+        _ = tokio::time::sleep(Duration::from_secs(wait_secs)) => None,
+        _ = session.close.notified() => {
+            info!("Session {}: closed", session.session_id);
+            shared.remove_session(session.session_id);
+            shared.http_sessions.lock().unwrap().remove(agent_id);
+            return status(StatusCode::SERVICE_UNAVAILABLE);
+        }
+    };
+
+    let mut pending: Vec<Message> = first.into_iter().map(|msg| shared.outgoing(msg)).collect();
+    while let Ok(msg) = rx.try_recv() {
+        pending.push(shared.outgoing(msg));
+    }
+
+    json(StatusCode::OK, serde_json::to_string(&pending))
+}
+
+/// 長輪詢的等待時間
+fn wait_secs(request: &Request<Body>) -> u64 {
+    request
+        .uri()
+        .query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .find_map(|pair| pair.strip_prefix("wait_secs="))
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+        .min(MAX_WAIT_SECS)
+}
+
+/// 讀取請求內容中的訊息
+async fn read_message(request: Request<Body>) -> std::result::Result<Message, Response<Body>> {
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .map_err(|_| status(StatusCode::BAD_REQUEST))?;
+
+    let text = String::from_utf8_lossy(&body);
+    Message::from_json(&text).map_err(|e| {
+        warn!("Invalid message: {}", e);
+        reply(StatusCode::BAD_REQUEST, &messages::error("INVALID_MESSAGE", &e.to_string(), false))
+    })
+}
+
+fn reply(code: StatusCode, msg: &Message) -> Response<Body> {
+    json(code, msg.to_json())
+}
+
+fn json<E>(code: StatusCode, body: std::result::Result<String, E>) -> Response<Body> {
+    match body {
+        Ok(body) => Response::builder()
+            .status(code)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap(),
+        Err(_) => status(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    Response::builder().status(code).body(Body::empty()).unwrap()
+}
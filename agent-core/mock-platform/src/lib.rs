@@ -0,0 +1,664 @@
+//! Orban Platform 模擬伺服器
+//!
+//! 實現 Orban Protocol 的平台端，用於在無網路環境下端到端測試 Agent：
+//! - 發出認證挑戰並以 ed25519 驗證 Agent 簽名，協商協議版本與可選功能（含壓縮）
+//! - 完成註冊後依腳本下發 TaskAssign / PowChallenge / EarningsRecord / PayoutNotification
+//! - 重連的 Agent 以 StateSync 恢復會話
+//! - 對需要送達確認的訊息回覆 ACK，並依 message_id 去重
+//! - 提供 HTTP 長輪詢端點，可停用 WebSocket 以模擬封鎖 WebSocket 的網路
+//! - 同一埠上以 gRPC 雙向串流（AgentGateway.Session）提供相同流程
+//! - 記錄 Agent 發送的所有訊息，供測試斷言
+//! - `MockProxy` 模擬 HTTP CONNECT / SOCKS5 出站代理
+//! - 可啟用 TLS（含客戶端憑證），測試憑證固定與 mTLS
+//! - 可停止回應連線（不讀取也不回應 ping），模擬半開連線
+//! - 可以平台金鑰簽署下發的訊息，測試逐訊息簽名
+//! - 驗證 KEY_ROTATION 聲明，接受後撤銷舊公鑰；註冊時提交的備份公鑰可代替遺失的舊金鑰
+//! - 驗證註冊與 HARDWARE_ATTESTATION 中的硬體證明，拒絕已由其他 Agent 申報的 GPU
+
+mod grpc;
+mod http;
+mod proxy;
+mod script;
+mod session;
+mod tls;
+
+pub use proxy::MockProxy;
+pub use script::{messages, ScriptStep};
+pub use tls::MockTls;
+
+use chrono::{DateTime, Utc};
+use orban_agent_core::network::error_policy::codes;
+use orban_agent_core::network::orban_protocol::{AckPayload, AckStatus, MessagePayload};
+use orban_agent_core::network::{
+    attestation, rotation, signing, Authenticator, HardwareAttestationPayload, Message, MessageType, ProtocolFeature,
+    RotationAuthority, RotationStatement, WireCodec, SUPPORTED_PROTOCOL_VERSIONS,
+};
+use orban_agent_core::{Error, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// 模擬平台配置
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    /// 註冊完成後依序執行的腳本
+    pub script: Vec<ScriptStep>,
+
+    /// 支援的編碼格式
+    pub codecs: Vec<WireCodec>,
+
+    /// JWT 有效期（秒）
+    pub token_ttl_secs: u64,
+
+    /// 允許的 Agent 公鑰 (base64)，None 表示接受任何有效簽名
+    pub authorized_keys: Option<Vec<String>>,
+
+    /// 腳本中 Expect 步驟的等待上限
+    pub expect_timeout: Duration,
+
+    /// 前 N 則需要確認的訊息不回覆 ACK（模擬確認遺失）
+    pub drop_acks: usize,
+
+    /// 支援的協議版本
+    pub protocol_versions: Vec<u32>,
+
+    /// 支援的可選功能
+    pub features: Vec<ProtocolFeature>,
+
+    /// 協商壓縮後，編碼達到此大小（位元組）的訊息壓縮後發送
+    pub compression_threshold_bytes: usize,
+
+    /// 接受 WebSocket 升級，停用時只提供 HTTP 長輪詢端點
+    pub websocket: bool,
+
+    /// 以 TLS 提供 WebSocket（`wss://`），此時不提供 HTTP 長輪詢端點
+    pub tls: Option<MockTls>,
+
+    /// 以平台金鑰簽署下發的訊息（已帶簽名的訊息原樣發送）
+    pub sign_messages: bool,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            script: Vec::new(),
+            codecs: vec![WireCodec::Protobuf, WireCodec::Json],
+            token_ttl_secs: 86400,
+            authorized_keys: None,
+            expect_timeout: Duration::from_secs(30),
+            drop_acks: 0,
+            protocol_versions: SUPPORTED_PROTOCOL_VERSIONS.to_vec(),
+            features: vec![ProtocolFeature::StateSync, ProtocolFeature::Compression],
+            compression_threshold_bytes: 512,
+            websocket: true,
+            tls: None,
+            sign_messages: false,
+        }
+    }
+}
+
+/// Agent 發送的訊息記錄
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedMessage {
+    pub session_id: u64,
+    pub received_at: DateTime<Utc>,
+    pub message: Message,
+}
+
+/// 模擬平台
+pub struct MockPlatform {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    accept_handle: JoinHandle<()>,
+}
+
+/// 各連線共享的狀態
+pub(crate) struct Shared {
+    pub(crate) config: MockConfig,
+    pub(crate) jwt_secret: Vec<u8>,
+    signing_key: Authenticator,
+    recorded: Mutex<Vec<RecordedMessage>>,
+    received: Notify,
+    events: broadcast::Sender<RecordedMessage>,
+    sessions: Mutex<HashMap<u64, SessionHandle>>,
+    acked: Mutex<HashSet<String>>,
+    compressed_frames: AtomicU64,
+    pings: AtomicU64,
+    next_session_id: AtomicU64,
+    pub(crate) http_sessions: http::HttpSessions,
+    client_certificates: Mutex<Vec<Vec<u8>>>,
+    /// 各 Agent ID 註冊時提交的備份公鑰
+    backup_keys: Mutex<HashMap<String, String>>,
+    /// 已接受的金鑰輪換
+    rotations: Mutex<Vec<RotationStatement>>,
+    /// 已輪換而不再接受認證的公鑰
+    revoked_keys: Mutex<HashSet<String>>,
+    /// 各 Agent ID 認證時使用的公鑰
+    public_keys: Mutex<HashMap<String, String>>,
+    /// 各 Agent ID 最近一次接受的硬體證明
+    attestations: Mutex<HashMap<String, HardwareAttestationPayload>>,
+}
+
+/// 已註冊連線的控制端
+pub(crate) struct SessionHandle {
+    pub(crate) tx: mpsc::UnboundedSender<Message>,
+    pub(crate) close: Arc<Notify>,
+    /// 停止回應（僅 WebSocket 連線）
+    pub(crate) stall: Option<Arc<Notify>>,
+}
+
+impl MockPlatform {
+    /// 在本機隨機埠啟動
+    pub async fn start(config: MockConfig) -> Result<Self> {
+        Self::bind("127.0.0.1:0", config).await
+    }
+
+    /// 在指定位址啟動
+    pub async fn bind(addr: impl ToSocketAddrs, config: MockConfig) -> Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+
+        let (events, _) = broadcast::channel(1024);
+        let shared = Arc::new(Shared {
+            config,
+            jwt_secret: rand::random::<[u8; 32]>().to_vec(),
+            signing_key: Authenticator::generate(),
+            recorded: Mutex::new(Vec::new()),
+            received: Notify::new(),
+            events,
+            sessions: Mutex::new(HashMap::new()),
+            acked: Mutex::new(HashSet::new()),
+            compressed_frames: AtomicU64::new(0),
+            pings: AtomicU64::new(0),
+            next_session_id: AtomicU64::new(1),
+            http_sessions: Mutex::new(HashMap::new()),
+            client_certificates: Mutex::new(Vec::new()),
+            backup_keys: Mutex::new(HashMap::new()),
+            rotations: Mutex::new(Vec::new()),
+            revoked_keys: Mutex::new(HashSet::new()),
+            public_keys: Mutex::new(HashMap::new()),
+            attestations: Mutex::new(HashMap::new()),
+        });
+
+        let accept_shared = shared.clone();
+        let accept_handle = tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        warn!("Accept failed: {}", e);
+                        continue;
+                    }
+                };
+
+                let shared = accept_shared.clone();
+                tokio::spawn(async move {
+                    if let Some(tls) = shared.config.tls.clone() {
+                        let stream = match tls.accept(stream).await {
+                            Ok(stream) => stream,
+                            Err(e) => {
+                                warn!("TLS handshake with {} failed: {}", peer, e);
+                                return;
+                            }
+                        };
+
+                        if let Some(cert) = stream.get_ref().1.peer_certificates().and_then(|certs| certs.first()) {
+                            shared.client_certificates.lock().unwrap().push(cert.to_vec());
+                        }
+
+                        let session_id = shared.next_session_id();
+                        info!("Session {} connected from {} over TLS", session_id, peer);
+
+                        if let Err(e) = session::run(shared, stream, session_id).await {
+                            warn!("Session {} ended with error: {}", session_id, e);
+                        }
+                        info!("Session {} closed", session_id);
+                        return;
+                    }
+
+                    if grpc::is_grpc_request(&stream).await {
+                        grpc::serve_connection(shared, stream).await;
+                        return;
+                    }
+
+                    if !shared.config.websocket || http::is_api_request(&stream).await {
+                        http::serve(shared, stream).await;
+                        return;
+                    }
+
+                    let session_id = shared.next_session_id();
+                    info!("Session {} connected from {}", session_id, peer);
+
+                    if let Err(e) = session::run(shared, stream, session_id).await {
+                        warn!("Session {} ended with error: {}", session_id, e);
+                    }
+                    info!("Session {} closed", session_id);
+                });
+            }
+        });
+
+        info!("Mock platform listening on {}", addr);
+
+        Ok(Self {
+            addr,
+            shared,
+            accept_handle,
+        })
+    }
+
+    /// 監聽位址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 供 Agent 配置使用的平台 URL
+    pub fn url(&self) -> String {
+        match self.shared.config.tls {
+            Some(_) => format!("wss://localhost:{}", self.addr.port()),
+            None => format!("ws://{}", self.addr),
+        }
+    }
+
+    /// TLS 握手中 Agent 出示的客戶端憑證（DER）
+    pub fn client_certificates(&self) -> Vec<Vec<u8>> {
+        self.shared.client_certificates.lock().unwrap().clone()
+    }
+
+    /// 已註冊的連線數
+    pub fn session_count(&self) -> usize {
+        self.shared.sessions.lock().unwrap().len()
+    }
+
+    /// 向所有已註冊的 Agent 發送訊息，返回送達的連線數
+    pub fn send(&self, msg: Message) -> usize {
+        let sessions = self.shared.sessions.lock().unwrap();
+        sessions
+            .values()
+            .filter(|session| session.tx.send(msg.clone()).is_ok())
+            .count()
+    }
+
+    /// 主動關閉所有已註冊的連線（模擬網路中斷），返回關閉的連線數
+    pub fn disconnect_all(&self) -> usize {
+        let sessions = self.shared.sessions.lock().unwrap();
+        for session in sessions.values() {
+            session.close.notify_one();
+        }
+        sessions.len()
+    }
+
+    /// 已註冊的 WebSocket 連線停止讀取與回應 ping，但不關閉（模擬半開連線），返回受影響的連線數
+    ///
+    /// 這些連線不再計入 `session_count`
+    pub fn stall_all(&self) -> usize {
+        let mut sessions = self.shared.sessions.lock().unwrap();
+        let stalled: Vec<u64> = sessions
+            .iter()
+            .filter(|(_, session)| session.stall.is_some())
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in &stalled {
+            if let Some(stall) = sessions.remove(id).and_then(|session| session.stall) {
+                stall.notify_one();
+            }
+        }
+        stalled.len()
+    }
+
+    /// 平台簽名公鑰 (base64)，供 Agent 的 `network.signing.platform_public_key` 使用
+    pub fn signing_public_key(&self) -> String {
+        self.shared.signing_key.public_key_base64()
+    }
+
+    /// 收到的 WebSocket ping 數
+    pub fn pings_received(&self) -> u64 {
+        self.shared.pings.load(Ordering::Relaxed)
+    }
+
+    /// 目前為止收到的所有訊息
+    pub fn received(&self) -> Vec<RecordedMessage> {
+        self.shared.recorded.lock().unwrap().clone()
+    }
+
+    /// 目前為止收到的指定類型訊息
+    pub fn received_of(&self, message_type: MessageType) -> Vec<Message> {
+        self.shared
+            .recorded
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.message.message_type == message_type)
+            .map(|r| r.message.clone())
+            .collect()
+    }
+
+    /// 訂閱之後收到的訊息
+    pub fn subscribe(&self) -> broadcast::Receiver<RecordedMessage> {
+        self.shared.events.subscribe()
+    }
+
+    /// 等待指定類型的訊息（包含已收到的）
+    pub async fn wait_for(&self, message_type: MessageType, timeout: Duration) -> Option<Message> {
+        self.shared
+            .wait_for(None, message_type, 0, timeout)
+            .await
+            .map(|(_, msg)| msg)
+    }
+
+    /// 經由 HTTP 長輪詢連線的 Agent 數
+    pub fn http_session_count(&self) -> usize {
+        self.shared.http_sessions.lock().unwrap().len()
+    }
+
+    /// 收到的壓縮訊框數
+    pub fn compressed_frames(&self) -> u64 {
+        self.shared.compressed_frames.load(Ordering::Relaxed)
+    }
+
+    /// 驗證本平台簽發的 JWT，返回 Agent ID
+    pub fn verify_token(&self, token: &str) -> Result<String> {
+        self.shared.verify_token(token)
+    }
+
+    /// 已接受的金鑰輪換
+    pub fn key_rotations(&self) -> Vec<RotationStatement> {
+        self.shared.rotations.lock().unwrap().clone()
+    }
+
+    /// Agent 註冊時提交的備份公鑰
+    pub fn backup_key(&self, agent_id: &str) -> Option<String> {
+        self.shared.backup_keys.lock().unwrap().get(agent_id).cloned()
+    }
+
+    /// Agent 最近一次被接受的硬體證明
+    pub fn hardware_attestation(&self, agent_id: &str) -> Option<HardwareAttestationPayload> {
+        self.shared.attestations.lock().unwrap().get(agent_id).cloned()
+    }
+}
+
+impl Drop for MockPlatform {
+    fn drop(&mut self) {
+        self.accept_handle.abort();
+    }
+}
+
+impl Shared {
+    /// 分配會話編號（WebSocket 連線與 HTTP 會話共用）
+    pub(crate) fn next_session_id(&self) -> u64 {
+        self.next_session_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// 記錄收到的壓縮訊框
+    pub(crate) fn record_compressed(&self) {
+        self.compressed_frames.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_ping(&self) {
+        self.pings.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 記錄收到的訊息
+    pub(crate) fn record(&self, session_id: u64, message: Message) {
+        let record = RecordedMessage {
+            session_id,
+            received_at: Utc::now(),
+            message,
+        };
+
+        self.recorded.lock().unwrap().push(record.clone());
+        let _ = self.events.send(record);
+        self.received.notify_waiters();
+    }
+
+    /// 送達確認，重送的訊息回覆 Duplicate；需要模擬確認遺失時返回 None
+    pub(crate) fn ack(&self, msg: &Message) -> Option<Message> {
+        let mut acked = self.acked.lock().unwrap();
+        let dropped = acked.len() < self.config.drop_acks;
+        let first = acked.insert(msg.message_id.clone());
+
+        if first && dropped {
+            return None;
+        }
+
+        let status = if first { AckStatus::Received } else { AckStatus::Duplicate };
+        Some(Message::new(MessageType::Ack, MessagePayload::Ack(AckPayload { status })).reply_to(msg))
+    }
+
+    /// 記錄 Agent 認證時使用的公鑰，供驗證硬體證明
+    pub(crate) fn authenticated(&self, agent_id: &str, public_key: &str) {
+        self.public_keys.lock().unwrap().insert(agent_id.to_string(), public_key.to_string());
+    }
+
+    /// 處理 AGENT_REGISTER：驗證硬體證明（若有）並記錄備份公鑰（以最近一次註冊為準）
+    ///
+    /// 接受時回覆 REGISTER_ACK，硬體證明無效時回覆 ATTESTATION_REJECTED
+    pub(crate) fn register(&self, agent_id: &str, msg: &Message) -> Message {
+        let MessagePayload::AgentRegister(register) = &msg.payload else {
+            return messages::error("INVALID_MESSAGE", "Expected AGENT_REGISTER", false).reply_to(msg);
+        };
+
+        if let Some(attestation) = &register.attestation {
+            if let Err(reason) = self.accept_attestation(agent_id, attestation) {
+                return messages::error(codes::ATTESTATION_REJECTED, &reason, true).reply_to(msg);
+            }
+        }
+        if let Some(backup) = &register.backup_public_key {
+            self.backup_keys.lock().unwrap().insert(agent_id.to_string(), backup.clone());
+        }
+
+        messages::register_ack(agent_id.to_string()).reply_to(msg)
+    }
+
+    /// 處理 HARDWARE_ATTESTATION，接受時回覆 ACK，否則回覆 ATTESTATION_REJECTED
+    pub(crate) fn attest_hardware(&self, agent_id: &str, msg: &Message) -> Message {
+        let result = match &msg.payload {
+            MessagePayload::HardwareAttestation(attestation) => self.accept_attestation(agent_id, attestation),
+            _ => Err("Expected HARDWARE_ATTESTATION".to_string()),
+        };
+
+        match result {
+            Ok(()) => Message::new(MessageType::Ack, MessagePayload::Ack(AckPayload { status: AckStatus::Received }))
+                .reply_to(msg),
+            Err(reason) => messages::error(codes::ATTESTATION_REJECTED, &reason, true).reply_to(msg),
+        }
+    }
+
+    /// 驗證硬體證明並記錄為該 Agent 目前的硬體
+    ///
+    /// 須由所屬 Agent 以認證時的公鑰簽署、不早於先前接受的證明，且 GPU UUID 未由其他 Agent 申報
+    fn accept_attestation(&self, agent_id: &str, attestation: &HardwareAttestationPayload) -> std::result::Result<(), String> {
+        if attestation.agent_id != agent_id {
+            return Err("Attestation must be sent by the agent it names".to_string());
+        }
+        let Some(public_key) = self.public_keys.lock().unwrap().get(agent_id).cloned() else {
+            return Err("Agent not authenticated".to_string());
+        };
+        if let Err(e) = attestation::verify(attestation, &public_key) {
+            warn!("Rejected hardware attestation for {}: {}", agent_id, e);
+            return Err("Invalid hardware attestation".to_string());
+        }
+
+        let mut attestations = self.attestations.lock().unwrap();
+        if attestations
+            .get(agent_id)
+            .is_some_and(|previous| previous.issued_at > attestation.issued_at)
+        {
+            return Err("Attestation is older than the current one".to_string());
+        }
+        for gpu in &attestation.manifest.gpus {
+            let claimed_by = attestations
+                .iter()
+                .find(|(other, previous)| *other != agent_id && previous.manifest.gpus.iter().any(|g| g.uuid == gpu.uuid));
+            if let Some((other, _)) = claimed_by {
+                warn!("GPU {} claimed by {} is already attested by {}", gpu.uuid, agent_id, other);
+                return Err(format!("GPU {} is already attested by another agent", gpu.uuid));
+            }
+        }
+
+        info!(
+            "Agent {} attested {} GPU(s), manifest {}",
+            agent_id,
+            attestation.manifest.gpus.len(),
+            attestation.manifest_hash
+        );
+        attestations.insert(agent_id.to_string(), attestation.clone());
+        Ok(())
+    }
+
+    /// 公鑰是否已因輪換而撤銷
+    pub(crate) fn is_revoked(&self, public_key: &str) -> bool {
+        self.revoked_keys.lock().unwrap().contains(public_key)
+    }
+
+    /// 驗證 KEY_ROTATION，接受時回覆 ACK 並撤銷舊公鑰，否則回覆 KEY_ROTATION_REJECTED
+    ///
+    /// 以舊金鑰簽署時須由舊身分的會話送出，以備份金鑰簽署時須由新身分的會話送出
+    pub(crate) fn rotate_key(&self, session_agent_id: &str, msg: &Message) -> Message {
+        let reject = |reason: &str| messages::error(codes::KEY_ROTATION_REJECTED, reason, false).reply_to(msg);
+
+        let MessagePayload::KeyRotation(request) = &msg.payload else {
+            return reject("Expected KEY_ROTATION");
+        };
+        let statement = &request.statement;
+
+        let (sender, authority_key) = match statement.authority {
+            RotationAuthority::PreviousKey => (&statement.agent_id, Some(statement.public_key.clone())),
+            RotationAuthority::BackupKey => (
+                &statement.new_agent_id,
+                self.backup_keys.lock().unwrap().get(&statement.agent_id).cloned(),
+            ),
+        };
+        if sender != session_agent_id {
+            return reject("Rotation must be sent by the agent it names");
+        }
+        let Some(authority_key) = authority_key else {
+            return reject("No backup key registered");
+        };
+        if self.is_revoked(&statement.public_key) {
+            return reject("Key already rotated");
+        }
+        if let Err(e) = rotation::verify(request, &authority_key) {
+            warn!("Rejected key rotation for {}: {}", statement.agent_id, e);
+            return reject("Invalid rotation signature");
+        }
+
+        self.revoked_keys.lock().unwrap().insert(statement.public_key.clone());
+        {
+            let mut backup_keys = self.backup_keys.lock().unwrap();
+            if let Some(backup) = backup_keys.remove(&statement.agent_id) {
+                backup_keys.insert(statement.new_agent_id.clone(), backup);
+            }
+        }
+        // 舊 ID 的硬體證明失效，新 ID 註冊時重新證明
+        self.attestations.lock().unwrap().remove(&statement.agent_id);
+        self.rotations.lock().unwrap().push(statement.clone());
+        info!("Agent {} rotated its key to {}", statement.agent_id, statement.new_agent_id);
+
+        Message::new(MessageType::Ack, MessagePayload::Ack(AckPayload { status: AckStatus::Received })).reply_to(msg)
+    }
+
+    pub(crate) fn add_session(&self, session_id: u64, session: SessionHandle) {
+        self.sessions.lock().unwrap().insert(session_id, session);
+    }
+
+    pub(crate) fn remove_session(&self, session_id: u64) {
+        self.sessions.lock().unwrap().remove(&session_id);
+    }
+
+    /// 等待第 `skip` 筆之後符合條件的訊息，返回其在記錄中的位置
+    pub(crate) async fn wait_for(
+        &self,
+        session_id: Option<u64>,
+        message_type: MessageType,
+        skip: usize,
+        timeout: Duration,
+    ) -> Option<(usize, Message)> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            // 在檢查之前建立 Notified，避免錯過通知
+            let notified = self.received.notified();
+
+            let found = self
+                .recorded
+                .lock()
+                .unwrap()
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .find(|(_, r)| {
+                    r.message.message_type == message_type
+                        && session_id.is_none_or(|id| r.session_id == id)
+                })
+                .map(|(i, r)| (i, r.message.clone()));
+
+            if found.is_some() {
+                return found;
+            }
+
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// 記錄中的訊息數
+    pub(crate) fn recorded_len(&self) -> usize {
+        self.recorded.lock().unwrap().len()
+    }
+
+    /// 啟用簽名時簽署下發的訊息
+    pub(crate) fn outgoing(&self, msg: Message) -> Message {
+        if !self.config.sign_messages || msg.signature.is_some() {
+            return msg;
+        }
+
+        match signing::sign(msg.clone(), &self.signing_key) {
+            Ok(signed) => signed,
+            Err(e) => {
+                warn!("Failed to sign {:?}: {}", msg.message_type, e);
+                msg
+            }
+        }
+    }
+
+    /// 簽發 JWT
+    pub(crate) fn issue_token(&self, agent_id: &str) -> Result<String> {
+        let now = Utc::now().timestamp();
+        let claims = session::Claims {
+            sub: agent_id.to_string(),
+            iss: session::TOKEN_ISSUER.to_string(),
+            iat: now,
+            exp: now + self.config.token_ttl_secs as i64,
+        };
+
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(&self.jwt_secret),
+        )
+        .map_err(|e| Error::AuthenticationFailed(e.to_string()))
+    }
+
+    /// 驗證 JWT，返回 Agent ID
+    fn verify_token(&self, token: &str) -> Result<String> {
+        let mut validation = jsonwebtoken::Validation::default();
+        validation.set_issuer(&[session::TOKEN_ISSUER]);
+
+        jsonwebtoken::decode::<session::Claims>(
+            token,
+            &jsonwebtoken::DecodingKey::from_secret(&self.jwt_secret),
+            &validation,
+        )
+        .map(|data| data.claims.sub)
+        .map_err(|e| Error::AuthenticationFailed(e.to_string()))
+    }
+}
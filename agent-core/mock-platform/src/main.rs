@@ -0,0 +1,105 @@
+// Orban Platform 模擬伺服器 - 命令行入口
+//
+// 使用方式：
+//   mock-platform --listen 127.0.0.1:8080 --script scenario.json
+//
+// Agent 的 platform_url 設為 ws://127.0.0.1:8080 即可連線（HTTP 長輪詢使用相同位址）；
+// 收到的訊息以 JSON Lines 輸出到 stdout
+
+use anyhow::Context;
+use clap::Parser;
+use mock_platform::{MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::WireCodec;
+use std::path::PathBuf;
+use tokio::sync::broadcast::error::RecvError;
+
+#[derive(Parser)]
+#[command(name = "mock-platform")]
+#[command(about = "Orban Platform 模擬伺服器", long_about = None)]
+struct Cli {
+    /// 監聽位址
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// 腳本檔案 (JSON)
+    #[arg(long)]
+    script: Option<PathBuf>,
+
+    /// 只接受 JSON 子協議
+    #[arg(long)]
+    json_only: bool,
+
+    /// JWT 有效期（秒）
+    #[arg(long, default_value = "86400")]
+    token_ttl: u64,
+
+    /// 拒絕 WebSocket 升級，只提供 HTTP 長輪詢端點
+    #[arg(long)]
+    no_websocket: bool,
+
+    /// 以平台金鑰簽署下發的訊息，啟動時輸出公鑰
+    #[arg(long)]
+    sign_messages: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+
+    let script: Vec<ScriptStep> = match &cli.script {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read script {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Invalid script {}", path.display()))?
+        }
+        None => Vec::new(),
+    };
+
+    let codecs = if cli.json_only {
+        vec![WireCodec::Json]
+    } else {
+        vec![WireCodec::Protobuf, WireCodec::Json]
+    };
+
+    let platform = MockPlatform::bind(
+        &cli.listen,
+        MockConfig {
+            script,
+            codecs,
+            token_ttl_secs: cli.token_ttl,
+            websocket: !cli.no_websocket,
+            sign_messages: cli.sign_messages,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    eprintln!("Mock platform ready at {}", platform.url());
+    if cli.sign_messages {
+        eprintln!("Platform signing key: {}", platform.signing_public_key());
+    }
+
+    let mut events = platform.subscribe();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(record) => println!("{}", serde_json::to_string(&record)?),
+                Err(RecvError::Lagged(n)) => eprintln!("Dropped {} messages", n),
+                Err(RecvError::Closed) => break,
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    Ok(())
+}
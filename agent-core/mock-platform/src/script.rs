@@ -0,0 +1,221 @@
+// 腳本化的平台行為
+//
+// 腳本以 JSON 描述，例如：
+//
+// [
+//   { "send": { "message_id": "...", "timestamp": "...", "type": "POW_CHALLENGE", ... } },
+//   { "expect": "POW_RESPONSE" },
+//   { "sleep_ms": 500 }
+// ]
+
+use orban_agent_core::network::{Message, MessageType};
+use serde::{Deserialize, Serialize};
+
+/// 腳本步驟
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptStep {
+    /// 向 Agent 發送訊息
+    Send(Box<Message>),
+
+    /// 暫停指定毫秒
+    SleepMs(u64),
+
+    /// 等待 Agent 發送指定類型的訊息
+    Expect(MessageType),
+}
+
+/// 平台端訊息建構函數
+pub mod messages {
+    use chrono::Utc;
+    use orban_agent_core::network::orban_protocol::*;
+    use orban_agent_core::{EarningStatus, PayoutStatus, Pricing, TaskPayload, TaskRequirements};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    /// 預設定價
+    pub fn default_pricing() -> Pricing {
+        Pricing {
+            base_rate_usd_per_hour: Decimal::from_str("0.50").unwrap(),
+            gpu_multiplier: Decimal::from_str("1.5").unwrap(),
+            effective_rate: Decimal::from_str("0.75").unwrap(),
+        }
+    }
+
+    /// 創建認證挑戰訊息
+    pub fn auth_challenge(challenge: String) -> Message {
+        Message::new(
+            MessageType::AuthChallenge,
+            MessagePayload::AuthChallenge(AuthChallengePayload { challenge }),
+        )
+    }
+
+    /// 創建認證成功訊息
+    pub fn auth_success(jwt_token: String, expires_in: u64, protocol: Option<ProtocolSelection>) -> Message {
+        Message::new(
+            MessageType::AuthSuccess,
+            MessagePayload::AuthSuccess(AuthSuccessPayload {
+                jwt_token,
+                expires_in,
+                protocol,
+            }),
+        )
+    }
+
+    /// 創建註冊確認訊息
+    pub fn register_ack(agent_id: String) -> Message {
+        Message::new(
+            MessageType::RegisterAck,
+            MessagePayload::RegisterAck(RegisterAckPayload {
+                agent_id,
+                status: "active".to_string(),
+                pricing: default_pricing(),
+            }),
+        )
+    }
+
+    /// 創建任務分配訊息
+    pub fn task_assign(task_id: &str, min_vram_gb: u32) -> Message {
+        Message::new(
+            MessageType::TaskAssign,
+            MessagePayload::TaskAssign(TaskAssignPayload {
+                task_id: task_id.to_string(),
+                job_id: format!("job-{}", task_id),
+                priority: 5,
+                estimated_duration_sec: 60,
+                requirements: TaskRequirements {
+                    min_vram_gb,
+                    min_compute_capability: "7.5".to_string(),
+                    framework: "pytorch".to_string(),
+                    fp16: true,
+                },
+                payload: TaskPayload {
+                    model_url: "https://models.orban.ai/mock/model.safetensors".to_string(),
+                    model_hash: format!("sha256:{}", "0".repeat(64)),
+                    input_data_url: "https://data.orban.ai/mock/input.json".to_string(),
+                    output_url: "https://data.orban.ai/mock/output".to_string(),
+                    config: serde_json::json!({ "max_tokens": 128 }),
+                },
+                pricing: default_pricing(),
+            }),
+        )
+    }
+
+    /// 創建工作證明挑戰訊息
+    pub fn pow_challenge(challenge_id: &str, difficulty: u32) -> Message {
+        Message::new(
+            MessageType::PowChallenge,
+            MessagePayload::PowChallenge(PowChallengePayload {
+                challenge_id: challenge_id.to_string(),
+                nonce: hex::encode(rand::random::<[u8; 32]>()),
+                difficulty,
+                deadline: Utc::now() + chrono::Duration::seconds(30),
+            }),
+        )
+    }
+
+    /// 創建收益記錄訊息
+    pub fn earnings_record(task_id: &str, amount_usd: Decimal) -> Message {
+        Message::new(
+            MessageType::EarningsRecord,
+            MessagePayload::EarningsRecord(EarningsRecordPayload {
+                task_id: task_id.to_string(),
+                earnings: EarningsDetail {
+                    gpu_hours: 1.0,
+                    rate_usd_per_hour: amount_usd,
+                    amount_usd,
+                    bonus_multiplier: 1.0,
+                    final_amount_usd: amount_usd,
+                },
+                status: EarningStatus::Pending,
+                estimated_payout_date: (Utc::now() + chrono::Duration::days(7))
+                    .format("%Y-%m-%d")
+                    .to_string(),
+            }),
+        )
+    }
+
+    /// 創建支付通知訊息
+    pub fn payout_notification(payout_id: &str, net_amount_usd: Decimal) -> Message {
+        let today = Utc::now().date_naive();
+
+        Message::new(
+            MessageType::PayoutNotification,
+            MessagePayload::PayoutNotification(PayoutNotificationPayload {
+                payout_id: payout_id.to_string(),
+                period: PayoutPeriod {
+                    start: (today - chrono::Duration::days(7)).to_string(),
+                    end: today.to_string(),
+                },
+                summary: PayoutSummary {
+                    total_tasks: 1,
+                    total_gpu_hours: 1.0,
+                    gross_amount_usd: net_amount_usd,
+                    platform_fee_usd: Decimal::ZERO,
+                    net_amount_usd,
+                },
+                payment_method: "usdc".to_string(),
+                payment_address: "0x0000000000000000000000000000000000000000".to_string(),
+                status: PayoutStatus::Processing,
+            }),
+        )
+    }
+
+    /// 創建錯誤訊息
+    pub fn error(code: &str, message: &str, recoverable: bool) -> Message {
+        error_with_context(code, message, recoverable, None)
+    }
+
+    /// 創建附帶上下文的錯誤訊息
+    pub fn error_with_context(code: &str, message: &str, recoverable: bool, context: Option<serde_json::Value>) -> Message {
+        Message::new(
+            MessageType::Error,
+            MessagePayload::Error(ErrorPayload {
+                code: code.to_string(),
+                message: message.to_string(),
+                context,
+                recoverable,
+            }),
+        )
+    }
+
+    /// 平台取消任務
+    pub fn task_cancelled(task_id: &str) -> Message {
+        error_with_context(
+            "TASK_CANCELLED",
+            "Task cancelled by requester",
+            true,
+            Some(serde_json::json!({ "task_id": task_id })),
+        )
+    }
+
+    /// 平台限流
+    pub fn rate_limited(retry_after_secs: u64) -> Message {
+        error_with_context(
+            "RATE_LIMITED",
+            "Too many requests",
+            true,
+            Some(serde_json::json!({ "retry_after_secs": retry_after_secs })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_from_json() {
+        let pow = messages::pow_challenge("pow-001", 8);
+        let json = format!(
+            r#"[{{"send": {}}}, {{"expect": "POW_RESPONSE"}}, {{"sleep_ms": 100}}]"#,
+            pow.to_json().unwrap()
+        );
+
+        let script: Vec<ScriptStep> = serde_json::from_str(&json).unwrap();
+        assert_eq!(script.len(), 3);
+        assert!(matches!(&script[0], ScriptStep::Send(msg) if msg.message_type == MessageType::PowChallenge));
+        assert!(matches!(script[1], ScriptStep::Expect(MessageType::PowResponse)));
+        assert!(matches!(script[2], ScriptStep::SleepMs(100)));
+    }
+}
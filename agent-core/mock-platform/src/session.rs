@@ -0,0 +1,417 @@
+// 單一 Agent 連線的處理流程
+//
+// 認證挑戰 → 驗證簽名並協商協議 → 簽發 JWT → 等待註冊 → 執行腳本，期間記錄 Agent 發送的所有訊息。
+// 回覆以 `in_reply_to` 指向 Agent 的請求
+//
+// 重連的 Agent 在認證後發送 StateSync 取代註冊，此時不重新執行腳本。
+// 已輪換的公鑰不再通過認證。
+// 停止回應的連線不再讀取（因此也不回應 ping），直到連線被關閉。
+// gRPC 串流（見 grpc.rs）經由 `MessageSource` 使用相同的流程
+
+use crate::script::{messages, ScriptStep};
+use crate::{SessionHandle, Shared};
+use futures::{SinkExt, StreamExt};
+use orban_agent_core::network::{
+    agent_id_for_public_key, is_compressed, requires_ack, select_protocol, Authenticator, Compression, Message, MessagePayload, MessageType,
+    ProtocolFeature, WireCodec,
+};
+use orban_agent_core::network::orban_protocol::ProtocolSelection;
+use orban_agent_core::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, Notify};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, info, warn};
+
+/// JWT 簽發者
+pub(crate) const TOKEN_ISSUER: &str = "orban-mock-platform";
+
+/// JWT 聲明
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Claims {
+    pub sub: String,
+    pub iss: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+type WsSource<S> = futures::stream::SplitStream<WebSocketStream<S>>;
+
+/// 連線上 Agent 發送的訊息
+pub(crate) trait MessageSource: Send {
+    /// 讀取並解碼下一則訊息，連線結束時返回 `None`
+    fn next_message(&mut self, shared: &Shared, session_id: u64) -> impl Future<Output = Result<Option<Message>>> + Send;
+}
+
+impl<S> MessageSource for WsSource<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    async fn next_message(&mut self, shared: &Shared, session_id: u64) -> Result<Option<Message>> {
+        while let Some(frame) = self.next().await {
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(e) => {
+                    debug!("Session {}: read error: {}", session_id, e);
+                    return Ok(None);
+                }
+            };
+
+            match frame {
+                WsMessage::Close(_) => return Ok(None),
+                WsMessage::Ping(_) => shared.record_ping(),
+                _ => {}
+            }
+
+            if matches!(&frame, WsMessage::Binary(data) if is_compressed(data)) {
+                shared.record_compressed();
+            }
+
+            if let Some(msg) = WireCodec::decode(&frame)? {
+                return Ok(Some(msg));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// 處理一個 Agent 連線直到斷線
+pub(crate) async fn run<S>(shared: Arc<Shared>, stream: S, session_id: u64) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    // 依 Agent 的偏好順序選擇本平台支援的子協議
+    let selected = Arc::new(Mutex::new(WireCodec::Json));
+    let selected_cb = selected.clone();
+    let codecs = shared.config.codecs.clone();
+
+    #[allow(clippy::result_large_err)]
+    let callback = move |request: &Request, mut response: Response| {
+        let codec = request
+            .headers()
+            .get("Sec-WebSocket-Protocol")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|offered| {
+                offered
+                    .split(',')
+                    .filter_map(WireCodec::from_subprotocol)
+                    .find(|codec| codecs.contains(codec))
+            });
+
+        if let Some(codec) = codec {
+            response.headers_mut().insert(
+                "Sec-WebSocket-Protocol",
+                HeaderValue::from_static(codec.subprotocol()),
+            );
+            *selected_cb.lock().unwrap() = codec;
+        }
+
+        Ok(response)
+    };
+
+    let ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+        .await
+        .map_err(|e| Error::ConnectionFailed(e.to_string()))?;
+
+    let codec = *selected.lock().unwrap();
+    info!("Session {} negotiated {}", session_id, codec.subprotocol());
+
+    let (mut sink, mut source) = ws.split();
+
+    // 寫入任務：收到關閉通知且佇列已清空後關閉連線
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    let close = Arc::new(Notify::new());
+    let close_signal = close.clone();
+    let compression = Arc::new(Mutex::new(None::<Compression>));
+    let writer_compression = compression.clone();
+    let writer_shared = shared.clone();
+    let writer = tokio::spawn(async move {
+        loop {
+            let msg = tokio::select! {
+                biased;
+                msg = rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+                _ = close_signal.notified() => break,
+            };
+
+            let msg = writer_shared.outgoing(msg);
+
+            // 協商壓縮後才壓縮
+            let compression = *writer_compression.lock().unwrap();
+            let encoded = match compression {
+                Some(compression) => codec.encode_compressed(&msg, &compression).map(|(frame, _)| frame),
+                None => codec.encode(&msg),
+            };
+
+            let frame = match encoded {
+                Ok(frame) => frame,
+                Err(e) => {
+                    warn!("Failed to encode {:?}: {}", msg.message_type, e);
+                    continue;
+                }
+            };
+
+            if sink.send(frame).await.is_err() {
+                break;
+            }
+        }
+
+        let _ = sink.close().await;
+    });
+
+    let session = SessionHandle {
+        tx,
+        close,
+        stall: Some(Arc::new(Notify::new())),
+    };
+    let result = serve(&shared, session_id, &session, &compression, &mut source).await;
+
+    // 送出佇列中的訊息後關閉連線
+    shared.remove_session(session_id);
+    session.close.notify_one();
+    let _ = writer.await;
+
+    result
+}
+
+/// 認證、註冊與腳本執行
+pub(crate) async fn serve(
+    shared: &Arc<Shared>,
+    session_id: u64,
+    session: &SessionHandle,
+    compression: &Mutex<Option<Compression>>,
+    source: &mut impl MessageSource,
+) -> Result<()> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let tx = &session.tx;
+
+    // 1. 認證挑戰
+    let challenge = rand::random::<[u8; 32]>();
+    let _ = tx.send(messages::auth_challenge(general_purpose::STANDARD.encode(challenge)));
+
+    let response = match next_message(shared, session_id, source).await? {
+        Some(msg) => msg,
+        None => return Ok(()),
+    };
+
+    let (agent_id, selection) = match authenticate(shared, session_id, &challenge, &response) {
+        Ok(authenticated) => authenticated,
+        Err(error) => {
+            let _ = tx.send(*error);
+            return Ok(());
+        }
+    };
+
+    let compressed = selection
+        .as_ref()
+        .is_some_and(|selection| selection.features.contains(&ProtocolFeature::Compression));
+
+    let _ = tx.send(auth_success(shared, &agent_id, selection, &response)?);
+
+    if compressed {
+        *compression.lock().unwrap() = Some(Compression {
+            threshold: shared.config.compression_threshold_bytes,
+            level: 3,
+        });
+    }
+    info!("Session {}: agent {} authenticated", session_id, agent_id);
+
+    // 2. 註冊或恢復會話（輪換金鑰的會話只送出 KEY_ROTATION）
+    let resumed = loop {
+        match next_message(shared, session_id, source).await? {
+            Some(msg) if msg.message_type == MessageType::AgentRegister => {
+                let reply = shared.register(&agent_id, &msg);
+                let registered = reply.message_type == MessageType::RegisterAck;
+                let _ = tx.send(reply);
+                if registered {
+                    break false;
+                }
+            }
+            Some(msg) if msg.message_type == MessageType::StateSync => break true,
+            Some(msg) if msg.message_type == MessageType::KeyRotation => {
+                let _ = tx.send(shared.rotate_key(&agent_id, &msg));
+            }
+            Some(msg) => {
+                warn!(
+                    "Session {}: expected AGENT_REGISTER or STATE_SYNC, got {:?}",
+                    session_id, msg.message_type
+                );
+                return Ok(());
+            }
+            None => return Ok(()),
+        }
+    };
+
+    shared.add_session(
+        session_id,
+        SessionHandle {
+            tx: tx.clone(),
+            close: session.close.clone(),
+            stall: session.stall.clone(),
+        },
+    );
+
+    if resumed {
+        info!("Session {}: agent {} resumed", session_id, agent_id);
+    } else {
+        info!("Session {}: agent {} registered", session_id, agent_id);
+    }
+
+    // 3. 腳本
+    let script = (!resumed).then(|| {
+        tokio::spawn(run_script(shared.clone(), tx.clone(), session_id, shared.recorded_len()))
+    });
+
+    // 4. 記錄後續訊息直到斷線
+    let stall = session.stall.clone().unwrap_or_default();
+    loop {
+        let msg = tokio::select! {
+            msg = next_message(shared, session_id, source) => msg?,
+            _ = stall.notified() => {
+                info!("Session {}: stalled", session_id);
+                session.close.notified().await;
+                None
+            }
+        };
+        let Some(msg) = msg else {
+            break;
+        };
+
+        if msg.message_type == MessageType::KeyRotation {
+            let _ = tx.send(shared.rotate_key(&agent_id, &msg));
+        } else if msg.message_type == MessageType::HardwareAttestation {
+            let _ = tx.send(shared.attest_hardware(&agent_id, &msg));
+        } else if requires_ack(msg.message_type) {
+            if let Some(ack) = shared.ack(&msg) {
+                let _ = tx.send(ack);
+            }
+        }
+    }
+
+    if let Some(script) = script {
+        script.abort();
+    }
+    Ok(())
+}
+
+/// 驗證 AUTH_RESPONSE 的簽名並協商協議，返回 Agent ID 與協商結果
+///
+/// 驗證失敗時返回應回覆給 Agent 的 ERROR 訊息
+pub(crate) fn authenticate(
+    shared: &Shared,
+    session_id: u64,
+    challenge: &[u8],
+    response: &Message,
+) -> std::result::Result<(String, Option<ProtocolSelection>), Box<Message>> {
+    let auth = match &response.payload {
+        MessagePayload::AuthResponse(auth) => auth,
+        _ => {
+            return Err(Box::new(
+                messages::error("AUTH_FAILED", "Expected AUTH_RESPONSE", false).reply_to(response),
+            ))
+        }
+    };
+
+    let authorized = shared
+        .config
+        .authorized_keys
+        .as_ref()
+        .is_none_or(|keys| keys.contains(&auth.public_key));
+
+    let verified = authorized
+        && Authenticator::verify_with_public_key(&auth.public_key, challenge, &auth.signature).unwrap_or(false);
+
+    if !verified || shared.is_revoked(&auth.public_key) {
+        warn!("Session {}: signature verification failed", session_id);
+        return Err(Box::new(
+            messages::error("AUTH_FAILED", "Signature verification failed", false).reply_to(response),
+        ));
+    }
+
+    // Agent ID 須由所提供的公鑰推導
+    if agent_id_for_public_key(&auth.public_key).ok().as_deref() != Some(auth.agent_id.as_str()) {
+        warn!("Session {}: agent ID {} does not match public key", session_id, auth.agent_id);
+        return Err(Box::new(
+            messages::error("AUTH_FAILED", "Agent ID does not match public key", false).reply_to(response),
+        ));
+    }
+
+    // 未提供協議能力的舊版 Agent 不回覆協商結果
+    let selection = match &auth.protocol {
+        Some(offer) => match select_protocol(offer, &shared.config.protocol_versions, &shared.config.features) {
+            Some(selection) => Some(selection),
+            None => {
+                warn!("Session {}: no common protocol version in {:?}", session_id, offer.versions);
+                return Err(Box::new(
+                    messages::error("UNSUPPORTED_VERSION", "No common protocol version", false).reply_to(response),
+                ));
+            }
+        },
+        None => None,
+    };
+
+    shared.authenticated(&auth.agent_id, &auth.public_key);
+    Ok((auth.agent_id.clone(), selection))
+}
+
+/// 簽發 JWT 並建立 AUTH_SUCCESS 回覆
+pub(crate) fn auth_success(
+    shared: &Shared,
+    agent_id: &str,
+    selection: Option<ProtocolSelection>,
+    response: &Message,
+) -> Result<Message> {
+    let token = shared.issue_token(agent_id)?;
+    Ok(messages::auth_success(token, shared.config.token_ttl_secs, selection).reply_to(response))
+}
+
+/// 讀取下一則訊息並記錄，連線結束時返回 `None`
+async fn next_message(shared: &Shared, session_id: u64, source: &mut impl MessageSource) -> Result<Option<Message>> {
+    let msg = source.next_message(shared, session_id).await?;
+
+    if let Some(msg) = &msg {
+        debug!("Session {}: received {:?}", session_id, msg.message_type);
+        shared.record(session_id, msg.clone());
+    }
+
+    Ok(msg)
+}
+
+/// 依序執行腳本
+pub(crate) async fn run_script(shared: Arc<Shared>, tx: mpsc::UnboundedSender<Message>, session_id: u64, mut cursor: usize) {
+    for step in shared.config.script.clone() {
+        match step {
+            ScriptStep::Send(msg) => {
+                if tx.send(*msg).is_err() {
+                    return;
+                }
+            }
+            ScriptStep::SleepMs(ms) => {
+                tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+            }
+            ScriptStep::Expect(message_type) => {
+                match shared
+                    .wait_for(Some(session_id), message_type, cursor, shared.config.expect_timeout)
+                    .await
+                {
+                    Some((index, _)) => cursor = index + 1,
+                    None => {
+                        warn!("Session {}: timed out waiting for {:?}", session_id, message_type);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    debug!("Session {}: script finished", session_id);
+}
@@ -0,0 +1,129 @@
+// 模擬平台的 TLS：自建 CA 簽發 localhost 憑證，可要求客戶端憑證（接受任何自簽憑證並記錄）
+
+use orban_agent_core::network::spki_pin;
+use orban_agent_core::{Error, Result};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{DigitallySignedStruct, DistinguishedName, ServerConfig, SignatureScheme};
+use std::fmt;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+/// 模擬平台的 TLS 設定
+#[derive(Clone)]
+pub struct MockTls {
+    acceptor: TlsAcceptor,
+    ca_pem: String,
+    spki_pin: String,
+}
+
+impl MockTls {
+    /// 以新建的 CA 簽發 localhost 憑證，`client_auth` 時要求客戶端憑證
+    pub fn generate(client_auth: bool) -> Result<Self> {
+        let invalid = |e: rcgen::Error| Error::InvalidConfig(e.to_string());
+
+        let ca_key = rcgen::KeyPair::generate().map_err(invalid)?;
+        let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).map_err(invalid)?;
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        ca_params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "Orban Mock Platform CA");
+        let ca = ca_params.self_signed(&ca_key).map_err(invalid)?;
+
+        let key = rcgen::KeyPair::generate().map_err(invalid)?;
+        let cert = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+            .map_err(invalid)?
+            .signed_by(&key, &ca, &ca_key)
+            .map_err(invalid)?;
+
+        let provider = Arc::new(ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+
+        let builder = if client_auth {
+            builder.with_client_cert_verifier(Arc::new(AnyClientCert { provider }))
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let config = builder
+            .with_single_cert(
+                vec![cert.der().clone(), ca.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+            )
+            .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+            ca_pem: ca.pem(),
+            spki_pin: spki_pin(cert.der())?,
+        })
+    }
+
+    /// CA 憑證（PEM），供 Agent 的 `ca_bundle` 使用
+    pub fn ca_pem(&self) -> &str {
+        &self.ca_pem
+    }
+
+    /// 平台憑證的 SPKI 指紋
+    pub fn spki_pin(&self) -> &str {
+        &self.spki_pin
+    }
+
+    pub(crate) async fn accept(&self, stream: TcpStream) -> std::io::Result<TlsStream<TcpStream>> {
+        self.acceptor.accept(stream).await
+    }
+}
+
+impl fmt::Debug for MockTls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockTls").field("spki_pin", &self.spki_pin).finish()
+    }
+}
+
+/// 接受任何客戶端憑證，僅驗證握手簽名；憑證由平台記錄供測試檢查
+#[derive(Debug)]
+struct AnyClientCert {
+    provider: Arc<CryptoProvider>,
+}
+
+impl ClientCertVerifier for AnyClientCert {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> std::result::Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
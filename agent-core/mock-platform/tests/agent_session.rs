@@ -0,0 +1,207 @@
+// Agent 與模擬平台的端到端測試
+
+mod common;
+
+use common::{earnings_history_len, spawn_agent, TIMEOUT};
+use futures::{SinkExt, StreamExt};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::orban_protocol::create_auth_response;
+use orban_agent_core::network::{agent_id_for_public_key, Authenticator, MessagePayload, MessageType, WireCodec};
+use rust_decimal::Decimal;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+#[tokio::test]
+async fn test_agent_session_over_each_codec() {
+    // (Agent 偏好, 平台支援)
+    let cases = [
+        (WireCodec::Protobuf, vec![WireCodec::Protobuf, WireCodec::Json]),
+        (WireCodec::Json, vec![WireCodec::Protobuf, WireCodec::Json]),
+        (WireCodec::Protobuf, vec![WireCodec::Json]),
+    ];
+
+    for (preferred, codecs) in cases {
+        let earnings_before = earnings_history_len();
+
+        let platform = MockPlatform::start(MockConfig {
+            script: vec![
+                ScriptStep::Send(Box::new(messages::pow_challenge("pow-001", 8))),
+                ScriptStep::Expect(MessageType::PowResponse),
+                ScriptStep::Send(Box::new(messages::earnings_record("task-001", Decimal::new(125, 2)))),
+                ScriptStep::Send(Box::new(messages::payout_notification("payout-001", Decimal::new(125, 2)))),
+            ],
+            codecs,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let agent = spawn_agent(platform.url(), |config| config.network.codec = preferred).await;
+
+        // 認證與註冊
+        let auth = platform
+            .wait_for(MessageType::AuthResponse, TIMEOUT)
+            .await
+            .expect("agent did not authenticate");
+        let agent_id = match auth.payload {
+            MessagePayload::AuthResponse(payload) => {
+                assert_eq!(payload.agent_id, agent_id_for_public_key(&payload.public_key).unwrap());
+                payload.agent_id
+            }
+            other => panic!("unexpected payload {:?}", other),
+        };
+
+        let register = platform
+            .wait_for(MessageType::AgentRegister, TIMEOUT)
+            .await
+            .expect("agent did not register");
+        match register.payload {
+            MessagePayload::AgentRegister(payload) => {
+                assert_eq!(payload.agent_id, agent_id);
+                assert_eq!(payload.display_name.as_deref(), Some("mock-agent"));
+                assert_eq!(payload.hardware.gpus.len(), 1);
+                assert_eq!(payload.hardware.gpus[0].vram_gb, 24);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+
+        // 工作證明
+        let pow = platform
+            .wait_for(MessageType::PowResponse, TIMEOUT)
+            .await
+            .expect("agent did not answer PoW challenge");
+        match pow.payload {
+            MessagePayload::PowResponse(payload) => {
+                assert_eq!(payload.challenge_id, "pow-001");
+                let hash = hex::decode(&payload.response).unwrap();
+                assert!(leading_zero_bits(&hash) >= 8);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+
+        // 收益記錄寫入本地
+        let deadline = tokio::time::Instant::now() + TIMEOUT;
+        while earnings_history_len() <= earnings_before {
+            assert!(tokio::time::Instant::now() < deadline, "earnings were not recorded");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        assert_eq!(platform.session_count(), 1);
+        agent.abort();
+    }
+}
+
+#[tokio::test]
+async fn test_negotiated_codec() {
+    for (codecs, expected) in [
+        (vec![WireCodec::Protobuf, WireCodec::Json], Some(WireCodec::Protobuf)),
+        (vec![WireCodec::Json], Some(WireCodec::Json)),
+    ] {
+        let platform = MockPlatform::start(MockConfig {
+            codecs,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let mut request = tokio_tungstenite::tungstenite::client::IntoClientRequest::into_client_request(
+            platform.url(),
+        )
+        .unwrap();
+        request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            WireCodec::offer(WireCodec::Protobuf).parse().unwrap(),
+        );
+
+        let (_ws, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+        let selected = response
+            .headers()
+            .get("Sec-WebSocket-Protocol")
+            .and_then(|v| v.to_str().ok())
+            .and_then(WireCodec::from_subprotocol);
+
+        assert_eq!(selected, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_rejects_invalid_signature() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    // 未提供子協議，使用 JSON
+    let (mut ws, _) = tokio_tungstenite::connect_async(platform.url()).await.unwrap();
+
+    let frame = ws.next().await.unwrap().unwrap();
+    let challenge = match WireCodec::decode(&frame).unwrap().unwrap().payload {
+        MessagePayload::AuthChallenge(payload) => payload.challenge,
+        other => panic!("unexpected payload {:?}", other),
+    };
+
+    // 以另一把密鑰簽名，但聲稱是 agent 的公鑰
+    let agent = Authenticator::generate();
+    let impostor = Authenticator::generate();
+    let (signature, _) = impostor.respond_to_challenge(&challenge).unwrap();
+
+    let response = create_auth_response(
+        agent.agent_id().to_string(),
+        signature,
+        agent.public_key_base64(),
+        None,
+    );
+    ws.send(WsMessage::Text(response.to_json().unwrap())).await.unwrap();
+
+    let frame = ws.next().await.unwrap().unwrap();
+    match WireCodec::decode(&frame).unwrap().unwrap().payload {
+        MessagePayload::Error(payload) => {
+            assert_eq!(payload.code, "AUTH_FAILED");
+            assert!(!payload.recoverable);
+        }
+        other => panic!("unexpected payload {:?}", other),
+    }
+
+    assert_eq!(platform.session_count(), 0);
+}
+
+#[tokio::test]
+async fn test_rejects_agent_id_not_derived_from_key() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(platform.url()).await.unwrap();
+
+    let frame = ws.next().await.unwrap().unwrap();
+    let challenge = match WireCodec::decode(&frame).unwrap().unwrap().payload {
+        MessagePayload::AuthChallenge(payload) => payload.challenge,
+        other => panic!("unexpected payload {:?}", other),
+    };
+
+    // 簽名有效，但沿用舊版配置中的 agent_id
+    let agent = Authenticator::generate();
+    let (signature, public_key) = agent.respond_to_challenge(&challenge).unwrap();
+
+    let response = create_auth_response("agent-myhost".to_string(), signature, public_key, None);
+    ws.send(WsMessage::Text(response.to_json().unwrap())).await.unwrap();
+
+    let frame = ws.next().await.unwrap().unwrap();
+    match WireCodec::decode(&frame).unwrap().unwrap().payload {
+        MessagePayload::Error(payload) => {
+            assert_eq!(payload.code, "AUTH_FAILED");
+            assert_eq!(payload.message, "Agent ID does not match public key");
+        }
+        other => panic!("unexpected payload {:?}", other),
+    }
+
+    assert_eq!(platform.session_count(), 0);
+}
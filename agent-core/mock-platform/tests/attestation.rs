@@ -0,0 +1,336 @@
+// 硬體證明：註冊時送出、硬體變更時重送，平台拒絕重複申報的 GPU
+
+mod common;
+
+use common::{agent_config, simulated_gpus, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform};
+use orban_agent_core::gpu::{GPUDetector, GPUDevice, SimulatedGPU};
+use orban_agent_core::network::{
+    attestation, Authenticator, HardwareAttestationPayload, MessagePayload, MessageType, OrbanClient, PollingClient, TransportKind,
+};
+use orban_agent_core::{
+    AgentConfig, Capabilities, Error, GPUVendor, HardwareManifest, Location, MemoryInfo, OrbanAgent, Result,
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// 可在執行中更換 UUID 的模擬 GPU（模擬被替換的顯示卡）
+struct SwappableGPU {
+    inner: SimulatedGPU,
+    uuid: Arc<Mutex<String>>,
+}
+
+impl GPUDevice for SwappableGPU {
+    fn index(&self) -> u32 {
+        self.inner.index()
+    }
+
+    fn vendor(&self) -> GPUVendor {
+        self.inner.vendor()
+    }
+
+    fn name(&self) -> Result<String> {
+        self.inner.name()
+    }
+
+    fn memory_info(&self) -> Result<MemoryInfo> {
+        self.inner.memory_info()
+    }
+
+    fn utilization(&self) -> Result<f32> {
+        self.inner.utilization()
+    }
+
+    fn temperature(&self) -> Result<f32> {
+        self.inner.temperature()
+    }
+
+    fn power_usage(&self) -> Result<f32> {
+        self.inner.power_usage()
+    }
+
+    fn fan_speed(&self) -> Result<f32> {
+        self.inner.fan_speed()
+    }
+
+    fn compute_capability(&self) -> Result<String> {
+        self.inner.compute_capability()
+    }
+
+    fn pcie_bandwidth(&self) -> Result<u32> {
+        self.inner.pcie_bandwidth()
+    }
+
+    fn uuid(&self) -> Result<String> {
+        Ok(self.uuid.lock().unwrap().clone())
+    }
+
+    fn compute_pow(&self, challenge: &[u8], difficulty: u32) -> Result<Vec<u8>> {
+        self.inner.compute_pow(challenge, difficulty)
+    }
+}
+
+fn agent_key(config: &AgentConfig) -> Authenticator {
+    Authenticator::from_private_key_file(Path::new(&config.private_key_path)).unwrap()
+}
+
+fn capabilities() -> Capabilities {
+    Capabilities {
+        supported_frameworks: vec!["pytorch".to_string()],
+        max_batch_size: 32,
+        fp16_support: true,
+        int8_support: true,
+    }
+}
+
+fn location() -> Location {
+    Location {
+        country: "TW".to_string(),
+        region: "asia-east1".to_string(),
+        latency_to_platform_ms: 0,
+    }
+}
+
+fn gpu_uuids(manifest: &HardwareManifest) -> Vec<String> {
+    manifest.gpus.iter().map(|gpu| gpu.uuid.clone()).collect()
+}
+
+/// 等待平台接受指定 Agent 申報這些 GPU
+async fn wait_for_attested(platform: &MockPlatform, agent_id: &str, uuids: &[&str]) -> HardwareAttestationPayload {
+    let start = std::time::Instant::now();
+    loop {
+        if let Some(current) = platform.hardware_attestation(agent_id) {
+            if gpu_uuids(&current.manifest) == uuids {
+                return current;
+            }
+        }
+        assert!(start.elapsed() < TIMEOUT, "{:?} not attested", uuids);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_registration_carries_signed_manifest() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |_| {});
+    let key = agent_key(&config);
+    let gpus = simulated_gpus();
+    let expected = gpus.hardware_manifest();
+
+    let mut agent = OrbanAgent::with_gpu_detector(config, gpus).await.unwrap();
+    let agent = tokio::spawn(async move { agent.start().await });
+
+    let register = platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+    let MessagePayload::AgentRegister(register) = register.payload else {
+        panic!("unexpected payload {:?}", register.payload);
+    };
+    let signed = register.attestation.expect("registration without attestation");
+
+    attestation::verify(&signed, &key.public_key_base64()).unwrap();
+    assert_eq!(signed.agent_id, key.agent_id());
+    assert_eq!(signed.manifest, expected);
+    assert_eq!(signed.manifest.gpus[0].driver_version.as_deref(), Some("simulated"));
+
+    let accepted = platform.hardware_attestation(key.agent_id()).expect("attestation not accepted");
+    assert_eq!(accepted.manifest_hash, signed.manifest_hash);
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_swapped_gpu_is_attested_again() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |config| {
+        config.network.hardware_check_interval_secs = 1;
+    });
+    let key = agent_key(&config);
+
+    let uuid = Arc::new(Mutex::new("GPU-11111111-0000-0000-0000-000000000000".to_string()));
+    let gpu = SwappableGPU {
+        inner: SimulatedGPU::new(0, "Simulated RTX 4090", 24),
+        uuid: uuid.clone(),
+    };
+    let gpus = GPUDetector::from_devices(vec![Arc::new(gpu)]).unwrap();
+
+    let mut agent = OrbanAgent::with_gpu_detector(config, gpus).await.unwrap();
+    let agent = tokio::spawn(async move { agent.start().await });
+
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+    let registered = platform.hardware_attestation(key.agent_id()).unwrap();
+    assert_eq!(gpu_uuids(&registered.manifest), ["GPU-11111111-0000-0000-0000-000000000000"]);
+
+    // 更換顯示卡
+    *uuid.lock().unwrap() = "GPU-22222222-0000-0000-0000-000000000000".to_string();
+
+    let resent = platform
+        .wait_for(MessageType::HardwareAttestation, TIMEOUT)
+        .await
+        .expect("hardware change not attested");
+    let MessagePayload::HardwareAttestation(resent) = resent.payload else {
+        panic!("unexpected payload {:?}", resent.payload);
+    };
+    assert_eq!(gpu_uuids(&resent.manifest), ["GPU-22222222-0000-0000-0000-000000000000"]);
+    assert_ne!(resent.manifest_hash, registered.manifest_hash);
+
+    // 平台以新的清單為準，之後的檢查不再重送
+    let current = platform.hardware_attestation(key.agent_id()).unwrap();
+    assert_eq!(current.manifest_hash, resent.manifest_hash);
+
+    tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+    assert_eq!(platform.received_of(MessageType::HardwareAttestation).len(), 1);
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_rejected_manifest_not_resent_until_changed() {
+    // 不支援 StateSync，重連後重送註冊
+    let platform = MockPlatform::start(MockConfig {
+        features: Vec::new(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    // 另一個 Agent 已申報這張 GPU
+    let claimed = SwappableGPU {
+        inner: SimulatedGPU::new(0, "Simulated RTX 4090", 24),
+        uuid: Arc::new(Mutex::new("GPU-33333333-0000-0000-0000-000000000000".to_string())),
+    };
+    let claimed = GPUDetector::from_devices(vec![Arc::new(claimed)]).unwrap();
+    let owner = agent_config(platform.url(), |_| {});
+    let client = OrbanClient::new(&owner).await.unwrap();
+    client.connect().await.unwrap();
+    client
+        .register(
+            claimed.get_hardware_info(),
+            claimed.hardware_manifest(),
+            capabilities(),
+            location(),
+            owner.availability.clone(),
+        )
+        .await
+        .unwrap();
+
+    let config = agent_config(platform.url(), |config| {
+        config.network.hardware_check_interval_secs = 1;
+    });
+    let key = agent_key(&config);
+
+    let uuid = Arc::new(Mutex::new("GPU-11111111-0000-0000-0000-000000000000".to_string()));
+    let gpu = SwappableGPU {
+        inner: SimulatedGPU::new(0, "Simulated RTX 4090", 24),
+        uuid: uuid.clone(),
+    };
+    let gpus = GPUDetector::from_devices(vec![Arc::new(gpu)]).unwrap();
+
+    let mut agent = OrbanAgent::with_gpu_detector(config, gpus).await.unwrap();
+    let agent = tokio::spawn(async move { agent.start().await });
+
+    let registered =
+        wait_for_attested(&platform, key.agent_id(), &["GPU-11111111-0000-0000-0000-000000000000"]).await;
+
+    // 換上已被申報的 GPU：平台拒絕，之後的檢查不再重送同一清單
+    *uuid.lock().unwrap() = "GPU-33333333-0000-0000-0000-000000000000".to_string();
+    platform
+        .wait_for(MessageType::HardwareAttestation, TIMEOUT)
+        .await
+        .expect("hardware change not attested");
+
+    tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+    assert_eq!(platform.received_of(MessageType::HardwareAttestation).len(), 1);
+    let kept = platform.hardware_attestation(key.agent_id()).unwrap();
+    assert_eq!(kept.manifest_hash, registered.manifest_hash);
+
+    // 重連後的註冊仍帶平台接受的清單，被拒絕的清單不隨之送出
+    assert_eq!(platform.disconnect_all(), 2);
+    let start = std::time::Instant::now();
+    let reregistered = loop {
+        let registrations = platform.received_of(MessageType::AgentRegister);
+        if let Some(msg) = registrations.into_iter().skip(2).last() {
+            break msg;
+        }
+        assert!(start.elapsed() < TIMEOUT, "agent did not register again");
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    };
+    let MessagePayload::AgentRegister(reregistered) = reregistered.payload else {
+        panic!("unexpected payload {:?}", reregistered.payload);
+    };
+    assert_eq!(reregistered.attestation.unwrap().manifest_hash, registered.manifest_hash);
+
+    // 再次更換後重新送出，平台接受新的清單
+    *uuid.lock().unwrap() = "GPU-22222222-0000-0000-0000-000000000000".to_string();
+    wait_for_attested(&platform, key.agent_id(), &["GPU-22222222-0000-0000-0000-000000000000"]).await;
+    assert_eq!(platform.received_of(MessageType::HardwareAttestation).len(), 2);
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_gpu_claimed_by_another_agent_rejected() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let gpus = simulated_gpus();
+
+    let first = agent_config(platform.url(), |_| {});
+    let client = OrbanClient::new(&first).await.unwrap();
+    client.connect().await.unwrap();
+    client
+        .register(
+            gpus.get_hardware_info(),
+            gpus.hardware_manifest(),
+            capabilities(),
+            location(),
+            first.availability.clone(),
+        )
+        .await
+        .unwrap();
+
+    // 另一個 Agent 註冊時申報同一張 GPU
+    let second = agent_config(platform.url(), |_| {});
+    let spoofer = OrbanClient::new(&second).await.unwrap();
+    spoofer.connect().await.unwrap();
+    let result = spoofer
+        .register(
+            gpus.get_hardware_info(),
+            gpus.hardware_manifest(),
+            capabilities(),
+            location(),
+            second.availability.clone(),
+        )
+        .await;
+    match result {
+        Err(Error::ProtocolError(message)) => assert!(message.contains("ATTESTATION_REJECTED"), "{}", message),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(platform.hardware_attestation(agent_key(&second).agent_id()).is_none());
+
+    // 經 HTTP 長輪詢重送時同樣拒絕
+    let third = agent_config(platform.url(), |config| {
+        config.network.transport = TransportKind::Http;
+    });
+    let polling = PollingClient::new(&third).await.unwrap();
+    polling.connect().await.unwrap();
+    let own = simulated_gpus();
+    polling
+        .register(
+            own.get_hardware_info(),
+            own.hardware_manifest(),
+            capabilities(),
+            location(),
+            third.availability.clone(),
+        )
+        .await
+        .unwrap();
+
+    match polling.attest_hardware(gpus.hardware_manifest()).await {
+        Err(Error::PlatformError { code, .. }) => assert_eq!(code, "ATTESTATION_REJECTED"),
+        other => panic!("unexpected result {:?}", other),
+    }
+    let kept = platform.hardware_attestation(agent_key(&third).agent_id()).unwrap();
+    assert_eq!(kept.manifest, own.hardware_manifest());
+}
@@ -0,0 +1,75 @@
+// 端到端測試共用工具
+
+#![allow(dead_code)]
+
+use orban_agent_core::gpu::{GPUDetector, SimulatedGPU};
+use orban_agent_core::network::Authenticator;
+use orban_agent_core::{AgentConfig, Availability, OrbanAgent, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 將收益等本地資料導向臨時目錄
+pub fn data_home() -> &'static Path {
+    static DIR: OnceLock<tempfile::TempDir> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_DATA_HOME", dir.path());
+        dir
+    })
+    .path()
+}
+
+/// 本地收益記錄筆數
+pub fn earnings_history_len() -> usize {
+    let path: PathBuf = data_home().join("orban-agent").join("earnings.json");
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|data| data["history"].as_array().map(|h| h.len()))
+        .unwrap_or(0)
+}
+
+/// 以新生成的密鑰建立 Agent 配置
+pub fn agent_config(platform_url: String, configure: impl FnOnce(&mut AgentConfig)) -> AgentConfig {
+    let agent_dir = data_home().join(format!("agent-{}", uuid::Uuid::new_v4()));
+    let key_path = agent_dir.join("agent.key");
+    std::fs::create_dir_all(&agent_dir).unwrap();
+    let auth = Authenticator::generate();
+    auth.save_private_key(&key_path).unwrap();
+
+    let mut config = AgentConfig {
+        display_name: Some("mock-agent".to_string()),
+        platform_url,
+        private_key_path: key_path.to_string_lossy().to_string(),
+        key: Default::default(),
+        availability: Availability {
+            hours_per_day: 24,
+            reliability_score: 1.0,
+        },
+        network: Default::default(),
+        data_dir: agent_dir,
+    };
+    configure(&mut config);
+    config
+}
+
+/// 單張模擬 RTX 4090，UUID 每次不同（平台拒絕多個 Agent 申報同一 GPU）
+pub fn simulated_gpus() -> GPUDetector {
+    let gpu = SimulatedGPU::new(0, "Simulated RTX 4090", 24).with_uuid(format!("GPU-{}", uuid::Uuid::new_v4()));
+    GPUDetector::from_devices(vec![Arc::new(gpu)]).unwrap()
+}
+
+/// 以模擬 GPU 啟動 Agent
+pub async fn spawn_agent(platform_url: String, configure: impl FnOnce(&mut AgentConfig)) -> JoinHandle<Result<()>> {
+    let config = agent_config(platform_url, configure);
+
+    let mut agent = OrbanAgent::with_gpu_detector(config, simulated_gpus())
+        .await
+        .unwrap();
+
+    tokio::spawn(async move { agent.start().await })
+}
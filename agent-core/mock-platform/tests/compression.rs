@@ -0,0 +1,65 @@
+// 訊息壓縮
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{MessageType, ProtocolFeature, WireCodec};
+
+#[tokio::test]
+async fn test_compressed_messages_both_directions() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![ScriptStep::Send(Box::new(messages::task_assign("task-001", 12)))],
+        compression_threshold_bytes: 0,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.codec = WireCodec::Json;
+        config.network.compression_threshold_bytes = 0;
+    })
+    .await;
+
+    // 平台壓縮的 TASK_ASSIGN 由 Agent 解壓處理
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept compressed task");
+
+    // 協商後較大的訊息（AGENT_REGISTER 等）壓縮發送，壓縮後未變小的訊息原樣發送
+    assert!(platform.compressed_frames() >= 1);
+    assert!(platform.compressed_frames() < platform.received().len() as u64);
+
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_no_compression_unless_negotiated() {
+    for (features, enabled) in [
+        (vec![ProtocolFeature::StateSync], true),
+        (vec![ProtocolFeature::StateSync, ProtocolFeature::Compression], false),
+    ] {
+        let platform = MockPlatform::start(MockConfig {
+            features,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let agent = spawn_agent(platform.url(), |config| {
+            config.network.compression = enabled;
+            config.network.compression_threshold_bytes = 0;
+        })
+        .await;
+
+        platform
+            .wait_for(MessageType::AgentRegister, TIMEOUT)
+            .await
+            .expect("agent did not register");
+        assert_eq!(platform.compressed_frames(), 0);
+
+        agent.abort();
+    }
+}
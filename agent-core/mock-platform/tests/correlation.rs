@@ -0,0 +1,107 @@
+// 請求回覆關聯與送達確認
+
+mod common;
+
+use common::{agent_config, simulated_gpus, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform};
+use orban_agent_core::network::{MessageType, OrbanClient};
+use orban_agent_core::{Capabilities, ExecutionMetrics, Location, ProofOfWork, TaskResult};
+use std::time::Duration;
+
+/// 連線並註冊，不啟動事件循環
+async fn registered_client(platform: &MockPlatform) -> OrbanClient {
+    let config = agent_config(platform.url(), |config| {
+        config.network.ack_timeout_ms = 200;
+    });
+    let client = OrbanClient::new(&config).await.unwrap();
+
+    client.connect().await.unwrap();
+    let gpus = simulated_gpus();
+    client
+        .register(
+            gpus.get_hardware_info(),
+            gpus.hardware_manifest(),
+            Capabilities {
+                supported_frameworks: vec!["pytorch".to_string()],
+                max_batch_size: 32,
+                fp16_support: true,
+                int8_support: true,
+            },
+            Location {
+                country: "TW".to_string(),
+                region: "asia-east1".to_string(),
+                latency_to_platform_ms: 0,
+            },
+            config.availability.clone(),
+        )
+        .await
+        .unwrap();
+
+    client
+}
+
+async fn complete_task(client: &OrbanClient, task_id: &str) {
+    client
+        .complete_task(
+            task_id,
+            TaskResult {
+                output_url: "https://data.orban.ai/mock/output".to_string(),
+                output_hash: format!("sha256:{}", "0".repeat(64)),
+                execution_time_sec: 60,
+                gpu_time_sec: 60,
+            },
+            ProofOfWork {
+                method: "sha256".to_string(),
+                challenge_id: "pow-001".to_string(),
+                response: vec![0; 32],
+                gpu_signature: "GPU-00000000".to_string(),
+            },
+            ExecutionMetrics {
+                avg_gpu_utilization: 0.9,
+                peak_memory_gb: 12.0,
+                energy_kwh: 0.1,
+            },
+        )
+        .await
+        .unwrap();
+}
+
+async fn wait_until_acked(client: &OrbanClient) {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while client.unacked_messages() > 0 {
+        assert!(tokio::time::Instant::now() < deadline, "message was not acknowledged");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_task_complete_acknowledged() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let client = registered_client(&platform).await;
+
+    complete_task(&client, "task-001").await;
+    wait_until_acked(&client).await;
+
+    // 已確認的訊息不再重送
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(platform.received_of(MessageType::TaskComplete).len(), 1);
+}
+
+#[tokio::test]
+async fn test_task_complete_retransmitted_until_acked() {
+    let platform = MockPlatform::start(MockConfig {
+        drop_acks: 1,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let client = registered_client(&platform).await;
+
+    complete_task(&client, "task-001").await;
+    wait_until_acked(&client).await;
+
+    // 重送沿用原 message_id
+    let sent = platform.received_of(MessageType::TaskComplete);
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].message_id, sent[1].message_id);
+}
@@ -0,0 +1,129 @@
+// 平台錯誤訊息的處理策略：中止任務、限流暫停、不可恢復時停止
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::orban_protocol::HeartbeatPayload;
+use orban_agent_core::network::{AgentStatus, MessagePayload, MessageType};
+use orban_agent_core::Error;
+use std::time::Duration;
+
+fn heartbeats(platform: &MockPlatform) -> Vec<HeartbeatPayload> {
+    platform
+        .received_of(MessageType::Heartbeat)
+        .into_iter()
+        .filter_map(|msg| match msg.payload {
+            MessagePayload::Heartbeat(payload) => Some(payload),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 等待第 `skip` 筆之後符合條件的心跳
+async fn wait_for_heartbeat(platform: &MockPlatform, skip: usize, matches: impl Fn(&HeartbeatPayload) -> bool) {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while !heartbeats(platform).iter().skip(skip).any(&matches) {
+        assert!(tokio::time::Instant::now() < deadline, "expected heartbeat not received");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_task_cancelled_aborts_task() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.heartbeat_interval_secs = 1;
+    })
+    .await;
+
+    wait_for_heartbeat(&platform, 0, |hb| hb.status == AgentStatus::Working).await;
+
+    let seen = heartbeats(&platform).len();
+    assert_eq!(platform.send(messages::task_cancelled("task-001")), 1);
+
+    // 任務中止後不再回報為進行中
+    wait_for_heartbeat(&platform, seen + 1, |hb| {
+        hb.status == AgentStatus::Idle && hb.current_task_id.is_none()
+    })
+    .await;
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_rate_limited_pauses_heartbeats() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.heartbeat_interval_secs = 1;
+    })
+    .await;
+
+    wait_for_heartbeat(&platform, 0, |_| true).await;
+    assert_eq!(platform.send(messages::rate_limited(3)), 1);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let paused = heartbeats(&platform).len();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(heartbeats(&platform).len(), paused, "heartbeats sent while rate limited");
+
+    // 暫停結束後恢復心跳
+    wait_for_heartbeat(&platform, paused, |_| true).await;
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_recoverable_error_keeps_session() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::error("DOWNLOAD_FAILED", "Model mirror unavailable", true))),
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent stopped after a recoverable error");
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_unrecoverable_error_stops_agent() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![ScriptStep::Send(Box::new(messages::error(
+            "AGENT_SUSPENDED",
+            "Agent suspended by operator",
+            false,
+        )))],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    let result = tokio::time::timeout(TIMEOUT, agent).await.unwrap().unwrap();
+    assert!(
+        matches!(&result, Err(Error::PlatformError { code, .. }) if code == "AGENT_SUSPENDED"),
+        "unexpected result {:?}",
+        result
+    );
+}
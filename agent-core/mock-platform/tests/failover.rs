@@ -0,0 +1,88 @@
+// 多平台端點：啟動時選用健康端點、連續失敗後故障轉移並回報實際區域
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform};
+use orban_agent_core::config::EndpointConfig;
+use orban_agent_core::network::{MessagePayload, MessageType};
+use orban_agent_core::types::Location;
+
+fn endpoint(platform: &MockPlatform, region: &str) -> EndpointConfig {
+    EndpointConfig {
+        url: platform.url(),
+        region: Some(region.to_string()),
+    }
+}
+
+/// 無法連線的位址
+async fn dead_url() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("ws://{}", listener.local_addr().unwrap())
+}
+
+async fn registered_location(platform: &MockPlatform) -> Location {
+    let register = platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+    match register.payload {
+        MessagePayload::AgentRegister(payload) => payload.location,
+        other => panic!("unexpected payload {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_startup_skips_unreachable_endpoint() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let live = endpoint(&platform, "europe-west1");
+
+    let agent = spawn_agent(dead_url().await, |config| {
+        config.network.endpoints = vec![live];
+    })
+    .await;
+
+    assert_eq!(registered_location(&platform).await.region, "europe-west1");
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_failover_after_repeated_failures() {
+    let first = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let second = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let endpoints = vec![endpoint(&first, "asia-east1"), endpoint(&second, "us-central1")];
+
+    let agent = spawn_agent(first.url(), |config| {
+        config.network.endpoints = endpoints;
+        config.network.failover_after_failures = 2;
+        config.network.reconnect_base_delay_ms = 100;
+    })
+    .await;
+
+    // 啟動時依延遲選用其中一個端點
+    let (active, standby, standby_region) = tokio::select! {
+        Some(_) = first.wait_for(MessageType::AgentRegister, TIMEOUT) => (first, second, "us-central1"),
+        Some(_) = second.wait_for(MessageType::AgentRegister, TIMEOUT) => (second, first, "asia-east1"),
+        else => panic!("agent did not register"),
+    };
+
+    // 目前端點停止服務
+    let active_addr = active.local_addr();
+    active.disconnect_all();
+    drop(active);
+
+    assert_eq!(registered_location(&standby).await.region, standby_region);
+
+    // 原端點恢復後，新端點斷線仍重連到新端點
+    let restored = MockPlatform::bind(active_addr, MockConfig::default()).await.unwrap();
+    assert_eq!(standby.disconnect_all(), 1);
+    standby
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not reconnect to the failover endpoint");
+    assert_eq!(restored.session_count(), 0);
+    assert!(!agent.is_finished());
+
+    agent.abort();
+}
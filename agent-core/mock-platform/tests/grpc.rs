@@ -0,0 +1,121 @@
+// gRPC 雙向串流傳輸
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{MessagePayload, MessageType, ProtocolFeature, TransportKind};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_session_over_grpc() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+            ScriptStep::Send(Box::new(messages::pow_challenge("pow-001", 1))),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.transport = TransportKind::Grpc;
+        config.network.heartbeat_interval_secs = 1;
+    })
+    .await;
+
+    let response = platform
+        .wait_for(MessageType::AuthResponse, TIMEOUT)
+        .await
+        .expect("agent did not authenticate");
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept the task");
+    platform
+        .wait_for(MessageType::PowResponse, TIMEOUT)
+        .await
+        .expect("agent did not answer the challenge");
+    platform
+        .wait_for(MessageType::Heartbeat, TIMEOUT)
+        .await
+        .expect("agent did not send heartbeats");
+
+    // HTTP/2 負責分框，不協商壓縮
+    match response.payload {
+        MessagePayload::AuthResponse(auth) => {
+            let offer = auth.protocol.expect("protocol offer");
+            assert!(offer.features.contains(&ProtocolFeature::StateSync));
+            assert!(!offer.features.contains(&ProtocolFeature::Compression));
+        }
+        other => panic!("unexpected payload {:?}", other),
+    }
+
+    assert_eq!(platform.session_count(), 1);
+    assert_eq!(platform.http_session_count(), 0);
+    assert_eq!(platform.compressed_frames(), 0);
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_grpc_stream_resumed_with_state_sync() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.transport = TransportKind::Grpc;
+        config.network.reconnect_base_delay_ms = 50;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept task");
+
+    // 平台結束串流，Agent 開啟新的串流並以 StateSync 恢復會話
+    assert_eq!(platform.disconnect_all(), 1);
+
+    let sync = platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not resume session");
+    match sync.payload {
+        MessagePayload::StateSync(payload) => {
+            assert_eq!(payload.active_tasks.len(), 1);
+            assert_eq!(payload.active_tasks[0].task_id, "task-001");
+        }
+        other => panic!("unexpected payload {:?}", other),
+    }
+    assert_eq!(platform.received_of(MessageType::AgentRegister).len(), 1);
+
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.session_count() != 1 {
+        assert!(tokio::time::Instant::now() < deadline, "session was not re-established");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // 新串流可繼續下發訊息
+    assert_eq!(platform.send(messages::pow_challenge("pow-001", 1)), 1);
+    platform
+        .wait_for(MessageType::PowResponse, TIMEOUT)
+        .await
+        .expect("agent stopped handling messages");
+
+    assert!(!agent.is_finished());
+    agent.abort();
+}
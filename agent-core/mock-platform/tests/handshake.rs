@@ -0,0 +1,121 @@
+// 協議版本與功能協商
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{
+    Message, MessagePayload, MessageType, ProtocolFeature, WireCodec, SUPPORTED_PROTOCOL_VERSIONS,
+};
+use orban_agent_core::Error;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_agent_offers_protocol_capabilities() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    let auth = platform
+        .wait_for(MessageType::AuthResponse, TIMEOUT)
+        .await
+        .expect("agent did not authenticate");
+
+    let offer = match auth.payload {
+        MessagePayload::AuthResponse(payload) => payload.protocol.expect("agent did not offer a protocol"),
+        other => panic!("unexpected payload {:?}", other),
+    };
+    assert_eq!(offer.versions, SUPPORTED_PROTOCOL_VERSIONS);
+    assert_eq!(offer.codecs.len(), 2);
+    assert!(offer.features.contains(&ProtocolFeature::StateSync));
+
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_reregisters_when_state_sync_not_negotiated() {
+    let platform = MockPlatform::start(MockConfig {
+        features: Vec::new(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.reconnect_base_delay_ms = 50;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.disconnect_all() == 0 {
+        assert!(tokio::time::Instant::now() < deadline, "session was not registered");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.received_of(MessageType::AgentRegister).len() < 2 {
+        assert!(tokio::time::Instant::now() < deadline, "agent did not register again");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    assert!(platform.received_of(MessageType::StateSync).is_empty());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_no_common_protocol_version() {
+    let platform = MockPlatform::start(MockConfig {
+        protocol_versions: vec![u32::MAX],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    let result = tokio::time::timeout(TIMEOUT, agent).await.unwrap().unwrap();
+    match result {
+        Err(Error::ProtocolError(message)) => assert!(message.contains("No common protocol version")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_ignores_unknown_message_type() {
+    let unknown = Message::from_json(
+        r#"{
+            "message_id": "msg-unknown",
+            "timestamp": "2026-01-01T00:00:00Z",
+            "type": "QUOTA_UPDATE",
+            "quota": {"gpu_hours": 12}
+        }"#,
+    )
+    .unwrap();
+
+    // Protocol Buffers 無法編碼未知訊息，使用 JSON
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(unknown)),
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+        ],
+        codecs: vec![WireCodec::Json],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent stopped handling messages after an unknown type");
+
+    assert!(!agent.is_finished());
+    agent.abort();
+}
@@ -0,0 +1,53 @@
+// 心跳與全雙工收發
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{AgentStatus, MessagePayload, MessageType};
+
+#[tokio::test]
+async fn test_heartbeat_reports_active_task() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.heartbeat_interval_secs = 1;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept task");
+
+    // 接收循環等待平台訊息時，心跳仍持續發送
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    loop {
+        let heartbeat = platform
+            .received_of(MessageType::Heartbeat)
+            .into_iter()
+            .find_map(|msg| match msg.payload {
+                MessagePayload::Heartbeat(payload) if payload.status == AgentStatus::Working => Some(payload),
+                _ => None,
+            });
+
+        if let Some(heartbeat) = heartbeat {
+            assert_eq!(heartbeat.current_task_id.as_deref(), Some("task-001"));
+            assert_eq!(heartbeat.gpu_status.len(), 1);
+            break;
+        }
+
+        assert!(tokio::time::Instant::now() < deadline, "agent did not send heartbeat");
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    agent.abort();
+}
@@ -0,0 +1,224 @@
+// 入站訊息防護：重放、時鐘偏差、速率與訊框大小違規的訊息被捨棄，Agent 繼續運作
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{Message, MessagePayload, MessageType, ProtocolFeature};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 等待平台收到指定數量的訊息
+async fn wait_for_count(platform: &MockPlatform, message_type: MessageType, count: usize) {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.received_of(message_type).len() < count {
+        assert!(tokio::time::Instant::now() < deadline, "expected {} {:?} messages", count, message_type);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// 時間戳偏移指定秒數的挑戰
+fn skewed_challenge(challenge_id: &str, offset_secs: i64) -> Message {
+    let mut msg = messages::pow_challenge(challenge_id, 1);
+    msg.timestamp += chrono::Duration::seconds(offset_secs);
+    msg
+}
+
+/// 難以壓縮的內容
+fn incompressible(len: usize) -> String {
+    hex::encode((0..len / 2).map(|_| rand::random::<u8>()).collect::<Vec<_>>())
+}
+
+#[tokio::test]
+async fn test_replayed_messages_dropped() {
+    let challenge = messages::pow_challenge("pow-001", 1);
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(challenge.clone())),
+            ScriptStep::Send(Box::new(challenge.clone())),
+            ScriptStep::Send(Box::new(messages::pow_challenge("pow-002", 1))),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.reconnect_base_delay_ms = 100;
+    })
+    .await;
+
+    // 訊息依序處理，第二則挑戰的回應表示重複的挑戰已被捨棄
+    wait_for_count(&platform, MessageType::PowResponse, 2).await;
+
+    // 重連後重放同一則挑戰同樣被拒絕
+    assert_eq!(platform.disconnect_all(), 1);
+    platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not reconnect");
+    platform.send(challenge);
+    platform.send(messages::pow_challenge("pow-003", 1));
+
+    wait_for_count(&platform, MessageType::PowResponse, 3).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(platform.received_of(MessageType::PowResponse).len(), 3);
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_messages_outside_clock_skew_dropped() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(skewed_challenge("pow-stale", -3600))),
+            ScriptStep::Send(Box::new(skewed_challenge("pow-future", 3600))),
+            ScriptStep::Send(Box::new(skewed_challenge("pow-fresh", -60))),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.inbound.max_clock_skew_secs = 120;
+    })
+    .await;
+
+    wait_for_count(&platform, MessageType::PowResponse, 1).await;
+    let responses = platform.received_of(MessageType::PowResponse);
+    assert_eq!(responses.len(), 1);
+    match &responses[0].payload {
+        MessagePayload::PowResponse(payload) => {
+            assert_eq!(payload.challenge_id, "pow-fresh");
+        }
+        other => panic!("unexpected payload {:?}", other),
+    }
+
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_per_type_rate_limit() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::pow_challenge("pow-001", 1))),
+            ScriptStep::Send(Box::new(messages::pow_challenge("pow-002", 1))),
+            ScriptStep::Send(Box::new(messages::pow_challenge("pow-003", 1))),
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.inbound.rate_limits = HashMap::from([(MessageType::PowChallenge, 2)]);
+    })
+    .await;
+
+    // 其他類型不受影響
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept the task");
+    wait_for_count(&platform, MessageType::PowResponse, 2).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(platform.received_of(MessageType::PowResponse).len(), 2);
+
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_oversized_frame_forces_reconnect() {
+    let platform = MockPlatform::start(MockConfig {
+        features: vec![ProtocolFeature::StateSync],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.inbound.max_frame_bytes = 16 * 1024;
+        config.network.reconnect_base_delay_ms = 100;
+    })
+    .await;
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    assert_eq!(platform.send(messages::error("NOTICE", &incompressible(64 * 1024), true)), 1);
+
+    // 訊框未完整讀取，連線無法繼續使用
+    platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not reconnect after oversized frame");
+
+    platform.send(messages::pow_challenge("pow-001", 1));
+    platform
+        .wait_for(MessageType::PowResponse, TIMEOUT)
+        .await
+        .expect("agent stopped processing messages");
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_decompression_bomb_dropped() {
+    let platform = MockPlatform::start(MockConfig {
+        compression_threshold_bytes: 0,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.inbound.max_frame_bytes = 16 * 1024;
+    })
+    .await;
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    // 壓縮後遠小於上限，解壓後超過上限
+    platform.send(messages::error("NOTICE", &"x".repeat(1024 * 1024), true));
+    platform.send(messages::pow_challenge("pow-001", 1));
+
+    platform
+        .wait_for(MessageType::PowResponse, TIMEOUT)
+        .await
+        .expect("agent stopped processing messages");
+
+    // 僅捨棄該訊息，連線照常使用
+    assert!(platform.received_of(MessageType::StateSync).is_empty());
+    assert_eq!(platform.session_count(), 1);
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_replayed_messages_dropped_over_http() {
+    let challenge = messages::pow_challenge("pow-001", 1);
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(challenge.clone())),
+            ScriptStep::Send(Box::new(challenge)),
+            ScriptStep::Send(Box::new(messages::pow_challenge("pow-002", 1))),
+        ],
+        websocket: false,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    wait_for_count(&platform, MessageType::PowResponse, 2).await;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(platform.received_of(MessageType::PowResponse).len(), 2);
+    assert!(!agent.is_finished());
+    agent.abort();
+}
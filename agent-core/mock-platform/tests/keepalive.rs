@@ -0,0 +1,85 @@
+// 連線保活：ping/pong、半開連線偵測與延遲量測
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform};
+use orban_agent_core::network::{MessagePayload, MessageType};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_registration_reports_measured_latency() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.keepalive_interval_secs = 1;
+    })
+    .await;
+
+    let register = platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+    match register.payload {
+        MessagePayload::AgentRegister(payload) => assert!(payload.location.latency_to_platform_ms >= 1),
+        other => panic!("unexpected payload {:?}", other),
+    }
+
+    // 連線建立後即開始發送 ping
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.pings_received() < 2 {
+        assert!(tokio::time::Instant::now() < deadline, "agent did not send pings");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_missed_pongs_force_reconnect() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.keepalive_interval_secs = 1;
+        config.network.keepalive_timeout_secs = 1;
+        config.network.keepalive_max_missed = 2;
+        config.network.reconnect_base_delay_ms = 100;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    // 連線未關閉但平台不再回應
+    assert_eq!(platform.stall_all(), 1);
+
+    platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not reconnect after missing pongs");
+    assert_eq!(platform.session_count(), 1);
+    assert!(!agent.is_finished());
+
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_keepalive_disabled() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.keepalive_interval_secs = 0;
+        config.network.heartbeat_interval_secs = 1;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::Heartbeat, TIMEOUT)
+        .await
+        .expect("agent did not send heartbeats");
+    assert_eq!(platform.pings_received(), 0);
+
+    agent.abort();
+}
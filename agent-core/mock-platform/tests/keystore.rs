@@ -0,0 +1,68 @@
+// 加密保存的身分金鑰
+
+mod common;
+
+use common::{agent_config, simulated_gpus, spawn_agent, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform};
+use orban_agent_core::network::{keystore, Authenticator, KeyFormat, KeyStorage, MessagePayload, MessageType, Passphrase};
+use orban_agent_core::{AgentConfig, OrbanAgent};
+use std::path::Path;
+
+/// 將 Agent 的原始私鑰轉存為加密金鑰，返回其公鑰
+fn encrypt_key(config: &mut AgentConfig, passphrase: &str) -> String {
+    let path = Path::new(&config.private_key_path);
+    let public_key = Authenticator::from_private_key_file(path)
+        .unwrap()
+        .public_key_base64();
+
+    config.key.storage = KeyStorage::Encrypted;
+    config.key.passphrase = Some(Passphrase::new(passphrase.to_string()));
+    assert!(keystore::migrate(&config.key, path).unwrap());
+    assert_eq!(keystore::format(path).unwrap(), KeyFormat::Encrypted);
+
+    public_key
+}
+
+#[tokio::test]
+async fn test_agent_authenticates_with_encrypted_key() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let mut public_key = String::new();
+    let agent = spawn_agent(platform.url(), |config| {
+        public_key = encrypt_key(config, "correct horse battery staple");
+    })
+    .await;
+
+    let response = platform
+        .wait_for(MessageType::AuthResponse, TIMEOUT)
+        .await
+        .expect("agent did not authenticate");
+    match response.payload {
+        MessagePayload::AuthResponse(auth) => assert_eq!(auth.public_key, public_key),
+        other => panic!("unexpected payload {:?}", other),
+    }
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_encrypted_key_requires_passphrase() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let mut config = agent_config(platform.url(), |_| {});
+    encrypt_key(&mut config, "correct horse battery staple");
+
+    config.key.passphrase = Some(Passphrase::new("wrong".to_string()));
+    assert!(OrbanAgent::with_gpu_detector(config.clone(), simulated_gpus()).await.is_err());
+
+    config.key.passphrase = None;
+    config.key.passphrase_env = "ORBAN_TEST_UNSET_PASSPHRASE".to_string();
+    assert!(OrbanAgent::with_gpu_detector(config, simulated_gpus()).await.is_err());
+
+    assert_eq!(platform.session_count(), 0);
+}
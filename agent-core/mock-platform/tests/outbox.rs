@@ -0,0 +1,135 @@
+// 離線發件匣：斷線與重啟後補送任務結果
+
+mod common;
+
+use common::{agent_config, simulated_gpus, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform};
+use orban_agent_core::network::{MessageType, OrbanClient, Outbox};
+use orban_agent_core::{AgentConfig, Capabilities, ExecutionMetrics, Location, ProofOfWork, TaskResult};
+use std::time::Duration;
+
+async fn register(client: &OrbanClient, config: &AgentConfig) {
+    let gpus = simulated_gpus();
+    client
+        .register(
+            gpus.get_hardware_info(),
+            gpus.hardware_manifest(),
+            Capabilities {
+                supported_frameworks: vec!["pytorch".to_string()],
+                max_batch_size: 32,
+                fp16_support: true,
+                int8_support: true,
+            },
+            Location {
+                country: "TW".to_string(),
+                region: "asia-east1".to_string(),
+                latency_to_platform_ms: 0,
+            },
+            config.availability.clone(),
+        )
+        .await
+        .unwrap();
+}
+
+async fn complete_task(client: &OrbanClient, task_id: &str) {
+    client
+        .complete_task(
+            task_id,
+            TaskResult {
+                output_url: "https://data.orban.ai/mock/output".to_string(),
+                output_hash: format!("sha256:{}", "0".repeat(64)),
+                execution_time_sec: 60,
+                gpu_time_sec: 60,
+            },
+            ProofOfWork {
+                method: "sha256".to_string(),
+                challenge_id: "pow-001".to_string(),
+                response: vec![0; 32],
+                gpu_signature: "GPU-00000000".to_string(),
+            },
+            ExecutionMetrics {
+                avg_gpu_utilization: 0.9,
+                peak_memory_gb: 12.0,
+                energy_kwh: 0.1,
+            },
+        )
+        .await
+        .unwrap();
+}
+
+async fn wait_until_delivered(client: &OrbanClient) {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while client.outbox_depth() > 0 {
+        assert!(tokio::time::Instant::now() < deadline, "outbox was not drained");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_results_survive_restart() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |_| {});
+
+    // 未連線時完成任務，結果保留在磁碟
+    let offline = OrbanClient::new(&config).await.unwrap();
+    complete_task(&offline, "task-001").await;
+    complete_task(&offline, "task-002").await;
+    drop(offline);
+
+    assert_eq!(Outbox::pending_count(&config.data_dir).unwrap(), 2);
+
+    // 重啟後註冊完成即依序補送
+    let client = OrbanClient::new(&config).await.unwrap();
+    assert_eq!(client.outbox_depth(), 2);
+
+    client.connect().await.unwrap();
+    register(&client, &config).await;
+    wait_until_delivered(&client).await;
+
+    let task_ids: Vec<String> = platform
+        .received_of(MessageType::TaskComplete)
+        .into_iter()
+        .map(|msg| match msg.payload {
+            orban_agent_core::network::MessagePayload::TaskComplete(payload) => payload.task_id,
+            other => panic!("unexpected payload {:?}", other),
+        })
+        .collect();
+    assert_eq!(task_ids, ["task-001", "task-002"]);
+    assert_eq!(Outbox::pending_count(&config.data_dir).unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_results_replayed_after_reconnect() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |config| {
+        config.network.reconnect_base_delay_ms = 300;
+    });
+
+    let client = OrbanClient::new(&config).await.unwrap();
+    client.connect().await.unwrap();
+    register(&client, &config).await;
+
+    let receiver = client.clone();
+    let receive_loop = tokio::spawn(async move {
+        loop {
+            receiver.receive().await.unwrap();
+        }
+    });
+
+    // 斷線期間完成任務
+    assert_eq!(platform.disconnect_all(), 1);
+    complete_task(&client, "task-001").await;
+
+    platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not resume session");
+    wait_until_delivered(&client).await;
+
+    // 可能重複送出，但都是同一則訊息
+    let sent = platform.received_of(MessageType::TaskComplete);
+    assert!(!sent.is_empty());
+    assert!(sent.iter().all(|msg| msg.message_id == sent[0].message_id));
+
+    receive_loop.abort();
+}
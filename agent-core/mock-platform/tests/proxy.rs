@@ -0,0 +1,96 @@
+// 出站代理：WebSocket 通道與 HTTP 長輪詢皆經由代理
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, MockProxy, ScriptStep};
+use orban_agent_core::config::ProxyConfig;
+use orban_agent_core::network::MessageType;
+
+#[tokio::test]
+async fn test_websocket_through_http_connect_proxy() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let proxy = MockProxy::start(Some(("agent", "s3cret"))).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.proxy = ProxyConfig {
+            url: Some(proxy.url("http")),
+            no_proxy: Vec::new(),
+            use_env: false,
+        };
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept the task through the proxy");
+
+    let platform_addr = platform.url().trim_start_matches("ws://").to_string();
+    assert_eq!(proxy.tunnels(), vec![platform_addr]);
+    assert_eq!(platform.http_session_count(), 0);
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_http_fallback_through_socks5_proxy() {
+    let platform = MockPlatform::start(MockConfig {
+        websocket: false,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let proxy = MockProxy::start(Some(("agent", "s3cret"))).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.heartbeat_interval_secs = 1;
+        config.network.proxy = ProxyConfig {
+            url: Some(proxy.url("socks5h")),
+            no_proxy: Vec::new(),
+            use_env: false,
+        };
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::Heartbeat, TIMEOUT)
+        .await
+        .expect("agent did not send heartbeats over HTTP through the proxy");
+
+    // WebSocket 嘗試與 HTTP 長輪詢皆經由代理
+    let tunnels = proxy.tunnels();
+    assert!(tunnels.len() >= 2, "unexpected tunnels {:?}", tunnels);
+    assert_eq!(platform.http_session_count(), 1);
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_no_proxy_connects_directly() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let proxy = MockProxy::start(None).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.proxy = ProxyConfig {
+            url: Some(proxy.url("http")),
+            no_proxy: vec!["127.0.0.1".to_string()],
+            use_env: false,
+        };
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    assert!(proxy.tunnels().is_empty());
+    agent.abort();
+}
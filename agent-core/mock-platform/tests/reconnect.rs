@@ -0,0 +1,67 @@
+// 斷線重連與會話恢復
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{MessagePayload, MessageType};
+
+#[tokio::test]
+async fn test_reconnect_resumes_session_with_state_sync() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.reconnect_base_delay_ms = 50;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept task");
+
+    // 模擬網路中斷
+    assert_eq!(platform.disconnect_all(), 1);
+
+    let sync = platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not resume session");
+
+    match sync.payload {
+        MessagePayload::StateSync(payload) => {
+            assert_eq!(payload.active_tasks.len(), 1);
+            assert_eq!(payload.active_tasks[0].task_id, "task-001");
+        }
+        other => panic!("unexpected payload {:?}", other),
+    }
+
+    // 恢復會話不重新註冊，且新連線可繼續下發訊息
+    let received = platform.received();
+    assert_eq!(platform.received_of(MessageType::AgentRegister).len(), 1);
+    assert_eq!(platform.received_of(MessageType::AuthResponse).len(), 2);
+    assert_ne!(received.first().unwrap().session_id, received.last().unwrap().session_id);
+
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.session_count() != 1 {
+        assert!(tokio::time::Instant::now() < deadline, "session was not re-established");
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    assert_eq!(platform.send(messages::task_assign("task-002", 12)), 1);
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.received_of(MessageType::TaskAccept).len() < 2 {
+        assert!(tokio::time::Instant::now() < deadline, "agent stopped handling messages");
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    agent.abort();
+}
@@ -0,0 +1,131 @@
+// 協議會話記錄與離線重放
+
+mod common;
+
+use common::{agent_config, simulated_gpus, spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::recorder::{self, REDACTED};
+use orban_agent_core::network::{Direction, MessagePayload, MessageType, ReplayTransport, SessionRecord, SessionRecorder};
+use orban_agent_core::OrbanAgent;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 等待記錄檔中出現指定方向與類型的訊息
+async fn wait_for_record(path: &Path, direction: Direction, message_type: MessageType) -> Vec<SessionRecord> {
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    loop {
+        let records = recorder::read_session(path).unwrap_or_default();
+        if records
+            .iter()
+            .any(|record| record.direction == direction && record.message.message_type == message_type)
+        {
+            return records;
+        }
+        assert!(tokio::time::Instant::now() < deadline, "{:?} {:?} was not recorded", direction, message_type);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+fn recorded(records: &[SessionRecord], direction: Direction, message_type: MessageType) -> bool {
+    records
+        .iter()
+        .any(|record| record.direction == direction && record.message.message_type == message_type)
+}
+
+#[tokio::test]
+async fn test_session_recorded_and_replayed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.jsonl");
+
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 16))),
+            ScriptStep::Send(Box::new(messages::pow_challenge("pow-001", 1))),
+        ],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let record_path = path.clone();
+    let agent = spawn_agent(platform.url(), move |config| {
+        config.network.record_path = Some(record_path);
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::PowResponse, TIMEOUT)
+        .await
+        .expect("agent did not answer the challenge");
+    let records = wait_for_record(&path, Direction::Outbound, MessageType::PowResponse).await;
+    agent.abort();
+
+    assert!(recorded(&records, Direction::Inbound, MessageType::AuthChallenge));
+    assert!(recorded(&records, Direction::Outbound, MessageType::AgentRegister));
+    assert!(recorded(&records, Direction::Inbound, MessageType::TaskAssign));
+    assert!(recorded(&records, Direction::Outbound, MessageType::TaskAccept));
+    assert!(recorded(&records, Direction::Inbound, MessageType::PowChallenge));
+
+    // 會話令牌與認證簽名不寫入記錄檔
+    for record in &records {
+        match &record.message.payload {
+            MessagePayload::AuthSuccess(payload) => assert_eq!(payload.jwt_token, REDACTED),
+            MessagePayload::AuthResponse(payload) => assert_eq!(payload.signature, REDACTED),
+            _ => {}
+        }
+    }
+
+    // 以模擬 GPU 離線重放平台送出的訊息
+    let config = agent_config("replay://".to_string(), |_| {});
+    let transport = Arc::new(ReplayTransport::new("agent-replay", &config, SessionRecorder::disabled()).unwrap());
+    let mut replay = OrbanAgent::offline(config, simulated_gpus(), transport.clone()).unwrap();
+
+    let mut sent = Vec::new();
+    for record in records
+        .iter()
+        .filter(|record| record.direction == Direction::Inbound && recorder::reaches_agent(&record.message))
+    {
+        replay.replay_message(record.message.clone()).await.unwrap();
+        sent.extend(transport.take_sent());
+    }
+
+    assert!(sent.iter().any(|msg| matches!(
+        &msg.payload,
+        MessagePayload::TaskAccept(payload) if payload.task_id == "task-001"
+    )));
+    assert!(sent.iter().any(|msg| matches!(
+        &msg.payload,
+        MessagePayload::PowResponse(payload) if payload.challenge_id == "pow-001"
+    )));
+}
+
+#[tokio::test]
+async fn test_session_recorded_over_http() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.jsonl");
+
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![ScriptStep::Send(Box::new(messages::pow_challenge("pow-001", 1)))],
+        websocket: false,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let record_path = path.clone();
+    let agent = spawn_agent(platform.url(), move |config| {
+        config.network.record_path = Some(record_path);
+    })
+    .await;
+
+    let records = wait_for_record(&path, Direction::Outbound, MessageType::PowResponse).await;
+    agent.abort();
+
+    assert!(recorded(&records, Direction::Outbound, MessageType::AgentRegister));
+    assert!(recorded(&records, Direction::Inbound, MessageType::PowChallenge));
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .all(|line| serde_json::from_str::<serde_json::Value>(line).is_ok()));
+}
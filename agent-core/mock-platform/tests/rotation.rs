@@ -0,0 +1,133 @@
+// 金鑰輪換與備份金鑰恢復
+
+mod common;
+
+use common::{agent_config, spawn_agent, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform};
+use orban_agent_core::network::rotation::{self, RotationStatus};
+use orban_agent_core::network::{
+    identity, Authenticator, MessagePayload, MessageType, OrbanClient, RotationAuthority, RotationReason,
+    TransportKind,
+};
+use orban_agent_core::{AgentConfig, Error};
+use std::path::Path;
+
+fn current_key(config: &AgentConfig) -> Authenticator {
+    Authenticator::from_private_key_file(Path::new(&config.private_key_path)).unwrap()
+}
+
+fn statuses(config: &AgentConfig) -> Vec<RotationStatus> {
+    rotation::history(&config.data_dir)
+        .unwrap()
+        .into_iter()
+        .map(|event| event.status)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_key_rotation_acknowledged() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |_| {});
+    let previous = current_key(&config);
+
+    let new_key = rotation::rotate_key(&config, RotationReason::Routine).await.unwrap();
+    assert_ne!(new_key.agent_id(), previous.agent_id());
+    assert_eq!(current_key(&config).public_key_base64(), new_key.public_key_base64());
+    assert!(!rotation::pending_key_path(Path::new(&config.private_key_path)).exists());
+
+    let rotations = platform.key_rotations();
+    assert_eq!(rotations.len(), 1);
+    assert_eq!(rotations[0].agent_id, previous.agent_id());
+    assert_eq!(rotations[0].new_agent_id, new_key.agent_id());
+    assert_eq!(rotations[0].authority, RotationAuthority::PreviousKey);
+    assert_eq!(statuses(&config), vec![RotationStatus::Requested, RotationStatus::Accepted]);
+
+    // 舊金鑰改名保存，平台不再接受
+    let archived = std::fs::read_dir(Path::new(&config.private_key_path).parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "old"))
+        .expect("previous key not archived");
+    let mut old = config.clone();
+    old.private_key_path = archived.to_string_lossy().to_string();
+    old.data_dir = config.data_dir.join("previous");
+    assert!(OrbanClient::new(&old).await.unwrap().connect().await.is_err());
+
+    // 以新金鑰啟動
+    let agent = spawn_agent(platform.url(), |agent| {
+        agent.private_key_path = config.private_key_path.clone();
+        agent.data_dir = config.data_dir.clone();
+    })
+    .await;
+    let register = platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+    match register.payload {
+        MessagePayload::AgentRegister(register) => assert_eq!(register.agent_id, new_key.agent_id()),
+        other => panic!("unexpected payload {:?}", other),
+    }
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_rejected_rotation_keeps_current_key() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |config| {
+        config.network.transport = TransportKind::Http;
+    });
+    let previous = current_key(&config);
+
+    // 平台沒有此 Agent 的備份公鑰
+    let unregistered = Authenticator::generate();
+    let result = rotation::recover_key(&config, &unregistered, RotationReason::Lost).await;
+    match result {
+        Err(Error::PlatformError { code, .. }) => assert_eq!(code, "KEY_ROTATION_REJECTED"),
+        other => panic!("unexpected result {:?}", other.map(|key| key.agent_id().to_string())),
+    }
+
+    assert_eq!(current_key(&config).public_key_base64(), previous.public_key_base64());
+    assert!(!rotation::pending_key_path(Path::new(&config.private_key_path)).exists());
+    assert!(platform.key_rotations().is_empty());
+    assert_eq!(statuses(&config), vec![RotationStatus::Requested, RotationStatus::Rejected]);
+}
+
+#[tokio::test]
+async fn test_recover_lost_key_with_backup_key() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |_| {});
+    let previous = current_key(&config);
+
+    let backup = Authenticator::generate();
+    identity::set_backup_public_key(&config.data_dir, &previous, backup.public_key_base64()).unwrap();
+
+    // 註冊時提交備份公鑰
+    let agent = spawn_agent(platform.url(), |agent| {
+        agent.private_key_path = config.private_key_path.clone();
+        agent.data_dir = config.data_dir.clone();
+    })
+    .await;
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+    agent.abort();
+    assert_eq!(platform.backup_key(previous.agent_id()), Some(backup.public_key_base64()));
+
+    // 身分金鑰遺失
+    std::fs::remove_file(&config.private_key_path).unwrap();
+
+    // 與註冊不符的備份金鑰在送出前拒絕
+    let wrong = Authenticator::generate();
+    assert!(rotation::recover_key(&config, &wrong, RotationReason::Lost).await.is_err());
+
+    let new_key = rotation::recover_key(&config, &backup, RotationReason::Lost).await.unwrap();
+    assert_eq!(current_key(&config).public_key_base64(), new_key.public_key_base64());
+
+    let rotations = platform.key_rotations();
+    assert_eq!(rotations.len(), 1);
+    assert_eq!(rotations[0].agent_id, previous.agent_id());
+    assert_eq!(rotations[0].authority, RotationAuthority::BackupKey);
+    assert_eq!(platform.backup_key(new_key.agent_id()), Some(backup.public_key_base64()));
+    assert_eq!(platform.backup_key(previous.agent_id()), None);
+}
@@ -0,0 +1,91 @@
+// JWT 會話生命週期
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform};
+use orban_agent_core::network::{MessagePayload, MessageType};
+use orban_agent_core::Error;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_reauthenticates_before_token_expiry() {
+    let platform = MockPlatform::start(MockConfig {
+        token_ttl_secs: 2,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.token_refresh_margin_secs = 1;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    // 在新連線上重新認證並以 StateSync 恢復會話
+    let sync = platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not refresh its session");
+    assert!(matches!(sync.payload, MessagePayload::StateSync(_)));
+
+    assert!(platform.received_of(MessageType::AuthResponse).len() >= 2);
+    assert_eq!(platform.received_of(MessageType::AgentRegister).len(), 1);
+
+    // 舊連線在切換後關閉
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.session_count() != 1 {
+        assert!(tokio::time::Instant::now() < deadline, "old session was not closed");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_rejected_authentication() {
+    let platform = MockPlatform::start(MockConfig {
+        authorized_keys: Some(Vec::new()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    let result = tokio::time::timeout(TIMEOUT, agent).await.unwrap().unwrap();
+    match result {
+        Err(Error::AuthenticationFailed(message)) => assert!(message.contains("AUTH_FAILED")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_platform_revokes_session() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register");
+
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.send(messages::error("AUTH_FAILED", "Session revoked", false)) == 0 {
+        assert!(tokio::time::Instant::now() < deadline, "session was not registered");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let result = tokio::time::timeout(TIMEOUT, agent).await.unwrap().unwrap();
+    match result {
+        Err(Error::AuthenticationFailed(message)) => assert!(message.contains("Session revoked")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,104 @@
+// 逐訊息簽名：Agent 簽署上報，平台下發的任務須以固定公鑰驗證
+
+mod common;
+
+use common::{spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{signing, Authenticator, MessagePayload, MessageType};
+
+fn assign_task() -> Vec<ScriptStep> {
+    vec![ScriptStep::Send(Box::new(messages::task_assign("task-001", 12)))]
+}
+
+/// 拒絕原因
+async fn rejection(platform: &MockPlatform) -> String {
+    let reject = platform
+        .wait_for(MessageType::TaskReject, TIMEOUT)
+        .await
+        .expect("agent did not reject the task");
+    match reject.payload {
+        MessagePayload::TaskReject(payload) => payload.reason,
+        other => panic!("unexpected payload {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_signed_messages_in_both_directions() {
+    let platform = MockPlatform::start(MockConfig {
+        script: assign_task(),
+        sign_messages: true,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let platform_key = platform.signing_public_key();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.heartbeat_interval_secs = 1;
+        config.network.signing.sign_outbound = true;
+        config.network.signing.platform_public_key = Some(platform_key);
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept the signed task");
+
+    let heartbeat = platform
+        .wait_for(MessageType::Heartbeat, TIMEOUT)
+        .await
+        .expect("agent did not send heartbeats");
+    let agent_key = match &platform.received_of(MessageType::AuthResponse)[0].payload {
+        MessagePayload::AuthResponse(payload) => payload.public_key.clone(),
+        other => panic!("unexpected payload {:?}", other),
+    };
+    signing::verify(&heartbeat, &agent_key).expect("heartbeat signature invalid");
+
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_unsigned_task_rejected() {
+    let platform = MockPlatform::start(MockConfig {
+        script: assign_task(),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let pinned = Authenticator::generate().public_key_base64();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.signing.platform_public_key = Some(pinned);
+    })
+    .await;
+
+    assert_eq!(rejection(&platform).await, "signature_verification_failed");
+    assert!(platform.received_of(MessageType::TaskAccept).is_empty());
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_task_signed_by_other_key_rejected() {
+    let forger = Authenticator::generate();
+    let forged = signing::sign(messages::task_assign("task-001", 12), &forger).unwrap();
+
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![ScriptStep::Send(Box::new(forged))],
+        sign_messages: true,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let platform_key = platform.signing_public_key();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.signing.platform_public_key = Some(platform_key);
+    })
+    .await;
+
+    assert_eq!(rejection(&platform).await, "signature_verification_failed");
+    assert!(!agent.is_finished());
+    agent.abort();
+}
@@ -0,0 +1,106 @@
+// 平台連線的 TLS：自訂 CA、憑證固定與 Agent 身分綁定的客戶端憑證
+
+mod common;
+
+use common::{data_home, spawn_agent, TIMEOUT};
+use mock_platform::{MockConfig, MockPlatform, MockTls};
+use orban_agent_core::config::TlsConfig;
+use orban_agent_core::network::{Authenticator, MessageType};
+use orban_agent_core::Error;
+use std::path::PathBuf;
+
+/// 將 CA 憑證寫入臨時檔案
+fn ca_bundle(tls: &MockTls) -> PathBuf {
+    let path = data_home().join(format!("ca-{}.pem", uuid::Uuid::new_v4()));
+    std::fs::write(&path, tls.ca_pem()).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn test_pinned_session_with_identity_client_certificate() {
+    let tls = MockTls::generate(true).unwrap();
+    let platform = MockPlatform::start(MockConfig {
+        tls: Some(tls.clone()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let mut key_path = String::new();
+    let agent = spawn_agent(platform.url(), |config| {
+        key_path = config.private_key_path.clone();
+        config.network.tls = TlsConfig {
+            ca_bundle: Some(ca_bundle(&tls)),
+            system_roots: false,
+            pinned_spki: vec![tls.spki_pin().to_string()],
+            client_auth: true,
+            client_cert: None,
+        };
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::AgentRegister, TIMEOUT)
+        .await
+        .expect("agent did not register over TLS");
+
+    // 客戶端憑證的公鑰即 Agent 的身分金鑰
+    let identity = Authenticator::from_private_key_file(&key_path).unwrap();
+    let certificates = platform.client_certificates();
+    assert_eq!(certificates.len(), 1);
+    let (_, cert) = x509_parser::parse_x509_certificate(&certificates[0]).unwrap();
+    assert_eq!(cert.public_key().subject_public_key.data.as_ref(), identity.public_key_bytes());
+
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_pin_mismatch_is_reported() {
+    let tls = MockTls::generate(false).unwrap();
+    let platform = MockPlatform::start(MockConfig {
+        tls: Some(tls.clone()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let other = MockTls::generate(false).unwrap();
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.tls = TlsConfig {
+            ca_bundle: Some(ca_bundle(&tls)),
+            pinned_spki: vec![other.spki_pin().to_string()],
+            ..Default::default()
+        };
+    })
+    .await;
+
+    // TLS 驗證失敗不改用 HTTP 長輪詢
+    let result = tokio::time::timeout(TIMEOUT, agent).await.unwrap().unwrap();
+    match result {
+        Err(Error::TlsError(reason)) => {
+            assert!(reason.contains("certificate pinning failed for localhost"), "{}", reason);
+            assert!(reason.contains(tls.spki_pin()) && reason.contains(other.spki_pin()), "{}", reason);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(platform.session_count(), 0);
+}
+
+#[tokio::test]
+async fn test_untrusted_platform_certificate_is_rejected() {
+    let platform = MockPlatform::start(MockConfig {
+        tls: Some(MockTls::generate(false).unwrap()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |_| {}).await;
+
+    let result = tokio::time::timeout(TIMEOUT, agent).await.unwrap().unwrap();
+    assert!(
+        matches!(&result, Err(Error::TlsError(reason)) if reason.contains("UnknownIssuer")),
+        "unexpected result {:?}",
+        result
+    );
+}
@@ -0,0 +1,118 @@
+// 傳輸層：WebSocket 被封鎖時改用 HTTP 長輪詢
+
+mod common;
+
+use common::{agent_config, simulated_gpus, spawn_agent, TIMEOUT};
+use mock_platform::{messages, MockConfig, MockPlatform, ScriptStep};
+use orban_agent_core::network::{MessageType, PollingClient, TransportKind};
+use orban_agent_core::{Capabilities, Error, Location};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_falls_back_to_http_when_websocket_blocked() {
+    let platform = MockPlatform::start(MockConfig {
+        script: vec![
+            ScriptStep::Send(Box::new(messages::task_assign("task-001", 12))),
+            ScriptStep::Expect(MessageType::TaskAccept),
+        ],
+        websocket: false,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.heartbeat_interval_secs = 1;
+    })
+    .await;
+
+    platform
+        .wait_for(MessageType::TaskAccept, TIMEOUT)
+        .await
+        .expect("agent did not accept the task over HTTP");
+    platform
+        .wait_for(MessageType::Heartbeat, TIMEOUT)
+        .await
+        .expect("agent did not send heartbeats over HTTP");
+
+    assert_eq!(platform.http_session_count(), 1);
+    assert!(!agent.is_finished());
+    agent.abort();
+}
+
+#[tokio::test]
+async fn test_no_fallback_when_websocket_required() {
+    let platform = MockPlatform::start(MockConfig {
+        websocket: false,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let agent = spawn_agent(platform.url(), |config| {
+        config.network.transport = TransportKind::WebSocket;
+    })
+    .await;
+
+    let result = tokio::time::timeout(TIMEOUT, agent).await.unwrap().unwrap();
+    assert!(matches!(result, Err(Error::UpgradeFailed(_))), "unexpected result {:?}", result);
+    assert_eq!(platform.http_session_count(), 0);
+}
+
+#[tokio::test]
+async fn test_http_session_resumes_after_disconnect() {
+    let platform = MockPlatform::start(MockConfig::default()).await.unwrap();
+    let config = agent_config(platform.url(), |config| {
+        config.network.transport = TransportKind::Http;
+        config.network.reconnect_base_delay_ms = 50;
+    });
+
+    let client = Arc::new(PollingClient::new(&config).await.unwrap());
+    client.connect().await.unwrap();
+    let gpus = simulated_gpus();
+    client
+        .register(
+            gpus.get_hardware_info(),
+            gpus.hardware_manifest(),
+            Capabilities {
+                supported_frameworks: vec!["pytorch".to_string()],
+                max_batch_size: 32,
+                fp16_support: true,
+                int8_support: true,
+            },
+            Location {
+                country: "TW".to_string(),
+                region: "asia-east1".to_string(),
+                latency_to_platform_ms: 0,
+            },
+            config.availability.clone(),
+        )
+        .await
+        .unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let receiver = client.clone();
+    let receive_loop = tokio::spawn(async move {
+        loop {
+            let _ = tx.send(receiver.receive().await.unwrap());
+        }
+    });
+
+    // 平台捨棄會話，Agent 重新認證並以 StateSync 恢復
+    let deadline = tokio::time::Instant::now() + TIMEOUT;
+    while platform.disconnect_all() == 0 {
+        assert!(tokio::time::Instant::now() < deadline, "session was not registered");
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    platform
+        .wait_for(MessageType::StateSync, TIMEOUT)
+        .await
+        .expect("agent did not resume session");
+
+    assert_eq!(platform.send(messages::task_assign("task-002", 12)), 1);
+    let msg = tokio::time::timeout(TIMEOUT, rx.recv()).await.unwrap().unwrap();
+    assert_eq!(msg.message_type, MessageType::TaskAssign);
+
+    receive_loop.abort();
+}
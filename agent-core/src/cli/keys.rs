@@ -0,0 +1,322 @@
+//! Keys 命令實現 - 管理 Agent 身分金鑰
+
+use crate::network::keystore::{self, KeyStorage, Passphrase};
+use crate::network::rotation::{self, RotationStatus};
+use crate::network::{auth, identity, Authenticator, IdentityRecord, RotationAuthority, RotationReason};
+use crate::{Result, Error, daemon::DaemonManager, config::Config};
+use super::start::agent_config;
+use colored::Colorize;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+/// 生成身分金鑰，已存在時需指定 `force` 才覆寫
+pub async fn init(force: bool) -> Result<()> {
+    let mut config = Config::load()?;
+    let path = PathBuf::from(&config.private_key_path);
+
+    if path.exists() && !force {
+        println!("{} Agent key already exists: {}", "⚠".yellow(), path.display());
+        println!("  Use {} to replace it", "orban-agent keys init --force".cyan());
+        return Ok(());
+    }
+
+    unlock(&mut config, None, KeyAccess::Replace)?;
+    let authenticator = create_key(&config, &path)?;
+    println!("{} Generated agent key", "✓".green());
+    print_identity(&config, &path, &authenticator);
+
+    Ok(())
+}
+
+/// 顯示 Agent ID（由公鑰推導）與公鑰
+pub async fn show() -> Result<()> {
+    let mut config = Config::load()?;
+    unlock(&mut config, None, KeyAccess::Read)?;
+    let authenticator = load(&config)?;
+
+    print_identity(&config, Path::new(&config.private_key_path), &authenticator);
+    Ok(())
+}
+
+/// 以新金鑰取代目前的金鑰：以舊金鑰簽署輪換聲明，平台確認後才改用新金鑰，舊金鑰保留於同目錄
+pub async fn rotate(reason: RotationReason) -> Result<()> {
+    if refuse_while_running()? {
+        return Ok(());
+    }
+
+    let mut config = Config::load()?;
+    unlock(&mut config, None, KeyAccess::Migrate)?;
+    let path = PathBuf::from(&config.private_key_path);
+    let previous = load(&config)?;
+
+    let authenticator = submit(&config, rotation::rotate_key(&agent_config(&config), reason).await)?;
+    println!("{} Rotated agent key", "✓".green());
+    println!("    Previous agent ID: {}", previous.agent_id());
+    println!("    Previous public key: {}", previous.public_key_base64().dimmed());
+    print_identity(&config, &path, &authenticator);
+
+    Ok(())
+}
+
+/// 身分金鑰遺失或外洩時，以 `keys backup` 生成的離線備份金鑰簽署輪換聲明並改用新金鑰
+pub async fn recover(backup_key: &Path, reason: RotationReason) -> Result<()> {
+    if refuse_while_running()? {
+        return Ok(());
+    }
+
+    let mut config = Config::load()?;
+    let backup = read_key_file(backup_key)?;
+
+    unlock(&mut config, None, KeyAccess::Replace)?;
+    let path = PathBuf::from(&config.private_key_path);
+    ensure_parent_dir(&path)?;
+
+    let authenticator = submit(&config, rotation::recover_key(&agent_config(&config), &backup, reason).await)?;
+    println!("{} Recovered agent identity with backup key", "✓".green());
+    print_identity(&config, &path, &authenticator);
+
+    Ok(())
+}
+
+/// 生成離線備份金鑰並寫入 `output`，下次註冊時將其公鑰提交給平台
+pub async fn backup(output: &Path, force: bool) -> Result<()> {
+    if output.exists() && !force {
+        println!("{} File already exists: {}", "⚠".yellow(), output.display());
+        println!("  Use {} to replace it", "orban-agent keys backup --force".cyan());
+        return Ok(());
+    }
+
+    let mut config = Config::load()?;
+    unlock(&mut config, None, KeyAccess::Read)?;
+    let authenticator = load(&config)?;
+
+    let backup = Authenticator::generate();
+    auth::write_key_file(output, format!("{}\n", backup.private_key_base64()).as_bytes())?;
+    identity::set_backup_public_key(&config.data_dir, &authenticator, backup.public_key_base64())?;
+
+    println!("{} Generated backup key: {}", "✓".green(), output.display());
+    println!("    Backup public key: {}", backup.public_key_base64().dimmed());
+    println!("  {}", "Store this file offline; it can take over this agent's identity".dimmed());
+    println!("  The backup key is submitted the next time the agent registers");
+
+    Ok(())
+}
+
+/// 顯示本地的金鑰輪換記錄
+pub async fn history() -> Result<()> {
+    let config = Config::load()?;
+    let events = rotation::history(&config.data_dir)?;
+
+    if events.is_empty() {
+        println!("No key rotations recorded");
+        return Ok(());
+    }
+
+    for event in events {
+        let status = match event.status {
+            RotationStatus::Accepted => "accepted".green(),
+            RotationStatus::Rejected => "rejected".red(),
+            RotationStatus::Failed => "failed".yellow(),
+            RotationStatus::Requested => "requested".normal(),
+        };
+        let authority = match event.statement.authority {
+            RotationAuthority::PreviousKey => "previous key",
+            RotationAuthority::BackupKey => "backup key",
+        };
+
+        println!(
+            "{}  {:<9}  {} -> {}",
+            event.at.format("%Y-%m-%d %H:%M:%S"),
+            status,
+            event.statement.agent_id,
+            event.statement.new_agent_id
+        );
+        println!("    Signed by {} ({:?})", authority, event.statement.reason);
+        if let Some(detail) = &event.detail {
+            println!("    {}", detail.dimmed());
+        }
+    }
+
+    Ok(())
+}
+
+/// 輪換期間 Agent 須已停止，返回是否因此中止
+fn refuse_while_running() -> Result<bool> {
+    let daemon = DaemonManager::new()?;
+    if !daemon.is_running() {
+        return Ok(false);
+    }
+
+    println!("{} Agent is running (PID: {})", "⚠".yellow(), daemon.read_pid()?);
+    println!("  Use {} to stop it before rotating the key", "orban-agent stop".cyan());
+    Ok(true)
+}
+
+/// 說明未完成的輪換
+fn submit(config: &Config, result: Result<Authenticator>) -> Result<Authenticator> {
+    match result {
+        Ok(authenticator) => Ok(authenticator),
+        Err(e @ Error::PlatformError { .. }) => {
+            println!("{} Platform rejected the key rotation: {}", "✗".red(), e);
+            println!("  The current key is unchanged");
+            Err(e)
+        }
+        Err(e) => {
+            let pending = rotation::pending_key_path(Path::new(&config.private_key_path));
+            println!("{} Key rotation not confirmed: {}", "✗".red(), e);
+            println!("  The new key is kept at {} and reused when you retry", pending.display());
+            Err(e)
+        }
+    }
+}
+
+/// 匯出私鑰（base64），未指定輸出文件時印出到標準輸出
+pub async fn export(output: Option<&Path>) -> Result<()> {
+    let mut config = Config::load()?;
+    unlock(&mut config, None, KeyAccess::Read)?;
+    let authenticator = load(&config)?;
+    let encoded = format!("{}\n", authenticator.private_key_base64());
+
+    match output {
+        Some(path) => {
+            auth::write_key_file(path, encoded.as_bytes())?;
+            println!("{} Exported agent key to {}", "✓".green(), path.display());
+            println!("  {}", "Anyone with this file can act as this agent; keep it safe".dimmed());
+        }
+        None => print!("{}", encoded),
+    }
+
+    Ok(())
+}
+
+/// 匯入私鑰，接受 `keys export` 的 base64 格式或 32 字節的原始私鑰
+pub async fn import(file: &Path, force: bool) -> Result<()> {
+    let mut config = Config::load()?;
+    let path = PathBuf::from(&config.private_key_path);
+
+    let authenticator = read_key_file(file)?;
+
+    if path.exists() && !force {
+        println!("{} Agent key already exists: {}", "⚠".yellow(), path.display());
+        println!("  Use {} to replace it", "orban-agent keys import --force".cyan());
+        return Ok(());
+    }
+
+    unlock(&mut config, None, KeyAccess::Replace)?;
+    ensure_parent_dir(&path)?;
+    keystore::save(&config.key, &path, &authenticator)?;
+    println!("{} Imported agent key", "✓".green());
+    print_identity(&config, &path, &authenticator);
+
+    Ok(())
+}
+
+/// 金鑰的使用方式，決定是否需要密碼
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyAccess {
+    /// 僅載入目前的金鑰
+    Read,
+
+    /// 載入目前的金鑰，並可能依 `key.storage` 保存（啟動時轉存、輪換的新金鑰）
+    Migrate,
+
+    /// 以新金鑰取代目前的金鑰
+    Replace,
+}
+
+/// 準備載入或保存金鑰所需的密碼
+///
+/// 載入加密金鑰或以 `encrypted` 保存金鑰時需要密碼，依序取自 `passphrase_fd`、
+/// `passphrase_env` 環境變數、`passphrase_file`，皆未提供時在終端提示輸入
+pub(crate) fn unlock(config: &mut Config, passphrase_fd: Option<i32>, access: KeyAccess) -> Result<()> {
+    let encrypted = access != KeyAccess::Replace && keystore::is_encrypted(Path::new(&config.private_key_path));
+    let new_key = !encrypted && access != KeyAccess::Read && config.key.storage == KeyStorage::Encrypted;
+    if !encrypted && !new_key {
+        return Ok(());
+    }
+
+    if let Some(fd) = passphrase_fd {
+        config.key.passphrase = Some(keystore::read_passphrase_fd(fd)?);
+        return Ok(());
+    }
+    if keystore::passphrase(&config.key)?.is_some() || !std::io::stdin().is_terminal() {
+        // 無法提示時由載入金鑰時回報缺少密碼
+        return Ok(());
+    }
+
+    let passphrase = if new_key {
+        let passphrase = prompt("New key passphrase: ")?;
+        if passphrase.is_empty() {
+            return Err(Error::InvalidConfig("Key passphrase must not be empty".to_string()));
+        }
+        if prompt("Confirm passphrase: ")? != passphrase {
+            return Err(Error::InvalidConfig("Passphrases do not match".to_string()));
+        }
+        passphrase
+    } else {
+        prompt("Key passphrase: ")?
+    };
+
+    config.key.passphrase = Some(Passphrase::new(passphrase));
+    Ok(())
+}
+
+fn prompt(message: &str) -> Result<String> {
+    Ok(rpassword::prompt_password(message)?)
+}
+
+/// 依配置載入身分金鑰
+pub(crate) fn load(config: &Config) -> Result<Authenticator> {
+    keystore::load(&config.key, Path::new(&config.private_key_path))
+}
+
+/// 首次啟動時生成身分金鑰，返回是否新生成
+pub(crate) fn ensure_key(config: &Config) -> Result<bool> {
+    let path = Path::new(&config.private_key_path);
+    if path.exists() {
+        return Ok(false);
+    }
+
+    create_key(config, path)?;
+    Ok(true)
+}
+
+/// 生成並保存新的身分金鑰
+fn create_key(config: &Config, path: &Path) -> Result<Authenticator> {
+    ensure_parent_dir(path)?;
+
+    let generated = Authenticator::generate();
+    keystore::save(&config.key, path, &generated)?;
+    keystore::load(&config.key, path)
+}
+
+fn ensure_parent_dir(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => Ok(std::fs::create_dir_all(parent)?),
+        _ => Ok(()),
+    }
+}
+
+/// 讀取 `keys export` 的 base64 格式或 32 字節的原始私鑰
+fn read_key_file(file: &Path) -> Result<Authenticator> {
+    let contents = std::fs::read(file)?;
+    match std::str::from_utf8(&contents) {
+        Ok(text) if contents.len() != 32 => Authenticator::from_private_key_base64(text),
+        _ => Authenticator::from_secret_bytes(&contents),
+    }
+}
+
+fn print_identity(config: &Config, path: &Path, authenticator: &Authenticator) {
+    println!();
+    println!("  {} {}", "Agent ID:".bold(), authenticator.agent_id());
+    println!("  {} {}", "Display name:".bold(), config.display_name);
+    println!("  {} {}", "Public key:".bold(), authenticator.public_key_base64());
+    println!("  {} {}", "Key file:".bold(), path.display());
+    if let Some(backup) = IdentityRecord::load(&config.data_dir).ok().flatten().and_then(|record| record.backup_public_key) {
+        println!("  {} {}", "Backup key:".bold(), backup);
+    }
+    if let Ok(format) = keystore::format(path) {
+        println!("  {} {}", "Storage:".bold(), format);
+    }
+}
+
@@ -5,6 +5,8 @@ pub mod stop;
 pub mod status;
 pub mod earnings;
 pub mod logs;
+pub mod replay;
+pub mod keys;
 
 use crate::Result;
 
@@ -0,0 +1,162 @@
+//! Replay 命令實現
+
+use crate::gpu::{GPUDetector, GPUDevice, SimulatedGPU};
+use crate::network::recorder::{self, Direction, SessionRecord};
+use crate::network::{Message, MessagePayload, MessageType, ReplayTransport, SessionRecorder};
+use crate::{config::Config, AgentConfig, Availability, OrbanAgent, Result};
+use colored::Colorize;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Agent 回應平台訊息時送出的訊息類型，用於與原始記錄比對
+const RESPONSE_TYPES: &[MessageType] = &[
+    MessageType::TaskAccept,
+    MessageType::TaskReject,
+    MessageType::TaskProgress,
+    MessageType::TaskComplete,
+    MessageType::TaskFailed,
+    MessageType::PowResponse,
+];
+
+/// 執行 replay 命令
+pub async fn execute(file: &Path, output: Option<&Path>) -> Result<()> {
+    let records = recorder::read_session(file)?;
+
+    println!("{}", "╔════════════════════════════════════════╗".cyan());
+    println!("{}", "║   Replaying Recorded Session          ║".cyan());
+    println!("{}", "╚════════════════════════════════════════╝".cyan());
+    println!();
+
+    let agent_id = recorded_agent_id(&records);
+    let config = replay_config();
+    let gpu_detector = simulated_gpus(&records)?;
+    println!("  {} Loaded {} recorded messages", "✓".green(), records.len());
+    println!("    Agent: {}", agent_id);
+    println!("    GPUs:  {} simulated", gpu_detector.device_count());
+
+    // 重放時 Agent 送出的訊息另存為同格式的記錄，便於與原始記錄比對
+    let recorder = match output {
+        Some(path) => {
+            std::fs::File::create(path)?;
+            println!("    Output: {}", path.display());
+            SessionRecorder::create(path)?
+        }
+        None => SessionRecorder::disabled(),
+    };
+    println!();
+
+    let transport = Arc::new(ReplayTransport::new(agent_id, &config, recorder)?);
+    let mut agent = OrbanAgent::offline(config, gpu_detector, transport.clone())?;
+
+    let mut replayed = 0;
+    let mut responses = 0;
+    for record in &records {
+        if record.direction != Direction::Inbound || !recorder::reaches_agent(&record.message) {
+            continue;
+        }
+
+        println!(
+            "  {} {} {:?} {}",
+            "→".blue(),
+            record.at.format("%H:%M:%S%.3f"),
+            record.message.message_type,
+            record.message.message_id.dimmed()
+        );
+        replayed += 1;
+
+        let result = agent.replay_message(record.message.clone()).await;
+
+        for msg in transport.take_sent() {
+            if RESPONSE_TYPES.contains(&msg.message_type) {
+                responses += 1;
+            }
+            println!("      {} {:?} {}", "←".green(), msg.message_type, describe(&msg));
+        }
+
+        if let Err(e) = result {
+            println!();
+            println!("  {} Agent stopped: {}", "✗".red(), e);
+            break;
+        }
+    }
+
+    let recorded = records
+        .iter()
+        .filter(|record| record.direction == Direction::Outbound)
+        .filter(|record| RESPONSE_TYPES.contains(&record.message.message_type))
+        .count();
+
+    println!();
+    println!("  {} Replayed {} platform messages", "✓".green(), replayed);
+    println!("    Agent responses: {} (recorded session: {})", responses, recorded);
+    if let Some(error) = agent.last_error() {
+        println!("    Last error:      {} ({})", error.code, error.action);
+    }
+
+    Ok(())
+}
+
+/// 記錄中註冊的 agent_id
+fn recorded_agent_id(records: &[SessionRecord]) -> String {
+    records
+        .iter()
+        .find_map(|record| match &record.message.payload {
+            MessagePayload::AgentRegister(payload) => Some(payload.agent_id.clone()),
+            MessagePayload::AuthResponse(payload) => Some(payload.agent_id.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| "replay".to_string())
+}
+
+/// 離線重放的配置
+fn replay_config() -> AgentConfig {
+    AgentConfig {
+        display_name: None,
+        platform_url: "replay://".to_string(),
+        private_key_path: String::new(),
+        key: Default::default(),
+        availability: Availability {
+            hours_per_day: 24,
+            reliability_score: 1.0,
+        },
+        network: Default::default(),
+        data_dir: Config::default_data_dir(),
+    }
+}
+
+/// 依記錄中註冊的硬體建立模擬 GPU，無註冊訊息時使用單張 RTX 4090
+fn simulated_gpus(records: &[SessionRecord]) -> Result<GPUDetector> {
+    let registered = records.iter().find_map(|record| match &record.message.payload {
+        MessagePayload::AgentRegister(payload) if !payload.hardware.gpus.is_empty() => {
+            Some(payload.hardware.gpus.clone())
+        }
+        _ => None,
+    });
+
+    let devices: Vec<Arc<dyn GPUDevice>> = match registered {
+        Some(gpus) => gpus
+            .into_iter()
+            .map(|gpu| {
+                Arc::new(
+                    SimulatedGPU::new(gpu.index, gpu.model, gpu.vram_gb)
+                        .with_compute_capability(gpu.compute_capability),
+                ) as Arc<dyn GPUDevice>
+            })
+            .collect(),
+        None => vec![Arc::new(SimulatedGPU::new(0, "Simulated RTX 4090", 24))],
+    };
+
+    GPUDetector::from_devices(devices)
+}
+
+/// 訊息摘要
+fn describe(msg: &Message) -> String {
+    match &msg.payload {
+        MessagePayload::TaskAccept(payload) => payload.task_id.clone(),
+        MessagePayload::TaskReject(payload) => format!("{} ({})", payload.task_id, payload.reason),
+        MessagePayload::TaskComplete(payload) => payload.task_id.clone(),
+        MessagePayload::TaskFailed(payload) => payload.task_id.clone(),
+        MessagePayload::PowResponse(payload) => payload.challenge_id.clone(),
+        _ => String::new(),
+    }
+}
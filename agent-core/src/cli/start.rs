@@ -1,11 +1,15 @@
 //! Start 命令實現
 
 use crate::{Result, Error, daemon::{DaemonManager, AgentState}, config::Config};
+use crate::network::keystore;
+use super::keys::KeyAccess;
 use colored::Colorize;
+use std::path::PathBuf;
 use tracing::{info, error};
 
-/// 執行 start 命令
-pub async fn execute(foreground: bool) -> Result<()> {
+/// 執行 start 命令，指定 `record` 時記錄收發的協議訊息，
+/// `passphrase_fd` 為讀取加密金鑰密碼的文件描述符
+pub async fn execute(foreground: bool, record: Option<PathBuf>, passphrase_fd: Option<i32>) -> Result<()> {
     let daemon = DaemonManager::new()?;
 
     // 檢查是否已經在運行
@@ -24,10 +28,36 @@ pub async fn execute(foreground: bool) -> Result<()> {
     println!();
 
     // 加載配置
-    let config = Config::load()?;
+    let mut config = Config::load()?;
     println!("  {} Loaded configuration", "✓".green());
     println!("    Platform: {}", config.platform_url);
 
+    // 首次啟動時生成身分金鑰，原始私鑰依 `key.storage` 轉存；
+    // 轉入後台前解鎖並檢查金鑰，權限過寬時拒絕啟動
+    super::keys::unlock(&mut config, passphrase_fd, KeyAccess::Migrate)?;
+    if super::keys::ensure_key(&config)? {
+        println!("  {} Generated agent key: {}", "✓".green(), config.private_key_path);
+    } else if keystore::migrate(&config.key, std::path::Path::new(&config.private_key_path))? {
+        println!("  {} Migrated agent key to {} storage", "✓".green(), config.key.storage);
+    }
+    let authenticator = super::keys::load(&config)?;
+    println!("    Agent ID: {}", authenticator.agent_id());
+    println!("    Public key: {}", authenticator.public_key_base64());
+    if let Some(legacy) = config.legacy_agent_id.as_deref().filter(|id| *id != authenticator.agent_id()) {
+        println!(
+            "  {} Ignoring agent_id \"{}\" in config.toml; the agent ID is derived from the key (use display_name to name this agent)",
+            "⚠".yellow(),
+            legacy
+        );
+    }
+
+    // 轉為絕對路徑，Windows 後台進程以此路徑重新啟動
+    if let Some(path) = record {
+        let path = std::path::absolute(&path)?;
+        println!("    Recording: {}", path.display());
+        config.network.record_path = Some(path);
+    }
+
     if foreground {
         // 前台運行模式（用於調試）
         println!();
@@ -83,6 +113,9 @@ async fn daemonize_unix(daemon: &DaemonManager, config: Config) -> Result<()> {
                 is_running: true,
                 uptime_seconds: 0,
                 tasks_completed: 0,
+                compression: Default::default(),
+                last_error: None,
+                latency: Default::default(),
             };
             daemon.save_state(&state)?;
 
@@ -111,14 +144,37 @@ async fn daemonize_windows(daemon: &DaemonManager, config: Config) -> Result<()>
     // 在 Windows 上，我們使用 CREATE_NO_WINDOW 標誌啟動新進程
     let exe_path = std::env::current_exe()?;
 
-    let child = Command::new(exe_path)
-        .arg("start")
-        .arg("--foreground")
-        .stdin(Stdio::null())
+    let mut command = Command::new(exe_path);
+    command.arg("start").arg("--foreground");
+    if let Some(path) = &config.network.record_path {
+        command.arg("--record").arg(path);
+    }
+    // 啟動時解鎖的密碼經由繼承的匿名管道（後台進程的標準輸入）交給後台進程，
+    // 不放入環境變數
+    let passphrase = match &config.key.passphrase {
+        Some(passphrase) => {
+            let (reader, writer) = std::io::pipe()?;
+            command.arg("--passphrase-fd").arg("0").stdin(Stdio::from(reader));
+            Some((passphrase, writer))
+        }
+        None => {
+            command.stdin(Stdio::null());
+            None
+        }
+    };
+
+    let child = command
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()?;
 
+    // 寫入後關閉管道，後台進程讀至 EOF
+    if let Some((passphrase, mut writer)) = passphrase {
+        use std::io::Write;
+        writer.write_all(passphrase.as_str().as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+
     let pid = child.id();
     daemon.write_pid(pid)?;
 
@@ -129,6 +185,9 @@ async fn daemonize_windows(daemon: &DaemonManager, config: Config) -> Result<()>
         is_running: true,
         uptime_seconds: 0,
         tasks_completed: 0,
+        compression: Default::default(),
+        last_error: None,
+        latency: Default::default(),
     };
     daemon.save_state(&state)?;
 
@@ -150,19 +209,8 @@ async fn run_agent(config: Config) -> Result<()> {
 
     println!("  {} Connecting to platform...", "✓".green());
 
-    // 創建 AgentConfig 從 Config
-    let agent_config = crate::AgentConfig {
-        agent_id: config.agent_id.clone(),
-        platform_url: config.platform_url.clone(),
-        private_key_path: config.private_key_path.clone(),
-        availability: crate::types::Availability {
-            hours_per_day: if config.availability.always_on { 24 } else { 12 },
-            reliability_score: 0.95,
-        },
-    };
-
     // 創建並啟動 Agent
-    let mut agent = crate::OrbanAgent::new(agent_config).await?;
+    let mut agent = crate::OrbanAgent::new(agent_config(&config)).await?;
 
     println!();
     println!("{} Agent started successfully!", "✓".green());
@@ -177,3 +225,19 @@ async fn run_agent(config: Config) -> Result<()> {
     info!("Agent shutting down...");
     Ok(())
 }
+
+/// 從 Config 創建 AgentConfig
+pub(crate) fn agent_config(config: &Config) -> crate::AgentConfig {
+    crate::AgentConfig {
+        display_name: Some(config.display_name.clone()),
+        platform_url: config.platform_url.clone(),
+        private_key_path: config.private_key_path.clone(),
+        key: config.key.clone(),
+        availability: crate::types::Availability {
+            hours_per_day: if config.availability.always_on { 24 } else { 12 },
+            reliability_score: 0.95,
+        },
+        network: config.network.clone(),
+        data_dir: config.data_dir.clone(),
+    }
+}
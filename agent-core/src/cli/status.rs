@@ -1,6 +1,6 @@
 //! Status 命令實現
 
-use crate::{Result, daemon::DaemonManager, earnings::EarningsTracker, gpu::GPUDetector};
+use crate::{Result, config::Config, daemon::DaemonManager, earnings::EarningsTracker, gpu::GPUDetector, network::{CompressionStats, ErrorRecord, ErrorSource, LatencyStats, Outbox}};
 use colored::Colorize;
 use chrono::Utc;
 
@@ -30,13 +30,26 @@ pub async fn execute(verbose: bool) -> Result<()> {
                 .num_seconds();
             println!("  {} {}", "Uptime:".bold(), format_duration(uptime as u64));
             println!("  {} {}", "Started:".bold(), state.started_at.format("%Y-%m-%d %H:%M:%S UTC"));
+            print_latency(&state.latency, verbose);
 
             if verbose {
                 println!("  {} {}", "Tasks Completed:".bold(), state.tasks_completed);
+                print_compression(&state.compression);
             }
+
+            print_last_error(state.last_error.as_ref());
         }
+
+        print_outbox();
     } else {
         println!("  {} {}", "Status:".bold(), "Stopped".red());
+
+        // 停止原因可能是不可恢復的平台錯誤
+        if let Ok(state) = daemon.load_state() {
+            print_last_error(state.last_error.as_ref());
+        }
+
+        print_outbox();
         println!();
         println!("  Start with: {}", "orban-agent start".cyan());
     }
@@ -68,6 +81,103 @@ pub async fn execute(verbose: bool) -> Result<()> {
     Ok(())
 }
 
+/// 打印發件匣中尚未送達平台的訊息數
+fn print_outbox() {
+    let data_dir = Config::load()
+        .map(|config| config.data_dir)
+        .unwrap_or_else(|_| Config::default().data_dir);
+
+    match Outbox::pending_count(&data_dir) {
+        Ok(0) => println!("  {} {}", "Outbox:".bold(), "empty".green()),
+        Ok(depth) => println!("  {} {}", "Outbox:".bold(), format!("{} pending", depth).yellow()),
+        Err(e) => println!("  {} {}", "Outbox:".bold(), format!("unavailable ({})", e).red()),
+    }
+}
+
+/// 打印最近一次錯誤
+fn print_last_error(record: Option<&ErrorRecord>) {
+    let Some(record) = record else {
+        return;
+    };
+
+    let source = match record.source {
+        ErrorSource::Platform => "platform",
+        ErrorSource::Agent => "agent",
+    };
+    let summary = format!("{} {}: {}", source, record.code, record.message);
+
+    println!(
+        "  {} {}",
+        "Last Error:".bold(),
+        if record.recoverable { summary.yellow() } else { summary.red() }
+    );
+    println!(
+        "    {} {} ({})",
+        "Action:".dimmed(),
+        record.action,
+        record.occurred_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+}
+
+/// 打印與平台之間的往返時間，verbose 時顯示分佈
+fn print_latency(stats: &LatencyStats, verbose: bool) {
+    if stats.samples == 0 {
+        println!("  {} {}", "Latency:".bold(), "not measured yet".dimmed());
+        return;
+    }
+
+    println!(
+        "  {} {:.1} ms (p50 {:.1} ms, p95 {:.1} ms, {} samples)",
+        "Latency:".bold(),
+        stats.last_ms,
+        stats.p50_ms,
+        stats.p95_ms,
+        stats.samples
+    );
+
+    if verbose {
+        let largest = stats.histogram.iter().filter_map(|bucket| bucket.le_ms).max().unwrap_or(0);
+        for bucket in stats.histogram.iter().filter(|bucket| bucket.count > 0) {
+            let label = match bucket.le_ms {
+                Some(le) => format!("<= {} ms", le),
+                None => format!("> {} ms", largest),
+            };
+            println!("    {:>10} {}", label.dimmed(), "#".repeat(bucket.count.div_ceil(2)));
+        }
+    }
+}
+
+/// 打印壓縮節省的流量
+fn print_compression(stats: &CompressionStats) {
+    if stats.messages_compressed == 0 {
+        println!("  {} {}", "Compression:".bold(), "no messages compressed".dimmed());
+        return;
+    }
+
+    println!(
+        "  {} {} messages, {} saved ({:.0}% of original size)",
+        "Compression:".bold(),
+        stats.messages_compressed,
+        format_bytes(stats.bytes_saved()),
+        stats.ratio() * 100.0
+    );
+}
+
+/// 格式化位元組數
+fn format_bytes(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+
+    let bytes_f = bytes as f64;
+    if bytes_f >= MIB {
+        format!("{:.1} MiB", bytes_f / MIB)
+    } else if bytes_f >= KIB {
+        format!("{:.1} KiB", bytes_f / KIB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
 /// 打印章節標題
 fn print_section(title: &str) {
     println!("{}", format!("─── {} ───", title).dimmed());
@@ -1,26 +1,42 @@
 // 任務執行引擎
 
 use super::Sandbox;
+use crate::config::NetworkConfig;
 use crate::gpu::GPUDetector;
+use crate::network::ProxySettings;
 use crate::types::{TaskPayload, TaskResult};
 use crate::error::{Error, Result};
-use tracing::{info, warn};
-use std::time::Instant;
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 /// 任務執行器
 pub struct TaskExecutor {
     gpu_detector: GPUDetector,
     sandbox: Sandbox,
+    http: reqwest::Client,
+    download_dir: PathBuf,
 }
 
 impl TaskExecutor {
-    /// 創建新的任務執行器
-    pub fn new(gpu_detector: GPUDetector) -> Result<Self> {
+    /// 創建新的任務執行器，檔案下載與上傳使用與平台連線相同的代理設定
+    pub fn new(gpu_detector: GPUDetector, network: &NetworkConfig) -> Result<Self> {
         let sandbox = Sandbox::new()?;
 
+        let proxy = ProxySettings::from_config(&network.proxy)?;
+        let http = proxy
+            .apply(reqwest::Client::builder())
+            .connect_timeout(Duration::from_secs(network.connection_timeout_secs))
+            .build()?;
+
         Ok(Self {
             gpu_detector,
             sandbox,
+            http,
+            download_dir: std::env::temp_dir().join("orban-agent"),
         })
     }
 
@@ -59,24 +75,59 @@ impl TaskExecutor {
         })
     }
 
-    /// 下載文件
+    /// 下載文件，`expected_hash` 為 `sha256:<hex>` 格式，空字串表示不驗證
     async fn download_file(&self, url: &str, expected_hash: &str) -> Result<String> {
-        // TODO: 實現文件下載
-        // 1. 使用 reqwest 下載
-        // 2. 驗證哈希
-        // 3. 保存到臨時目錄
-        warn!("File download not yet implemented: {}", url);
-        Ok("/tmp/model.bin".to_string())
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Error::DownloadFailed(format!("{}: {}", url, e)))?;
+
+        tokio::fs::create_dir_all(&self.download_dir).await?;
+        let path = self.download_dir.join(hex::encode(Sha256::digest(url.as_bytes())));
+        let mut file = tokio::fs::File::create(&path).await?;
+
+        let mut hasher = Sha256::new();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| Error::DownloadFailed(format!("{}: {}", url, e)))?;
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+
+        let actual_hash = format!("sha256:{}", hex::encode(hasher.finalize()));
+        if !expected_hash.is_empty() && !actual_hash.eq_ignore_ascii_case(expected_hash) {
+            let _ = tokio::fs::remove_file(&path).await;
+            return Err(Error::DownloadFailed(format!(
+                "Hash mismatch for {}: expected {}, got {}",
+                url, expected_hash, actual_hash
+            )));
+        }
+
+        debug!("Downloaded {} to {}", url, path.display());
+        Ok(path.to_string_lossy().into_owned())
     }
 
-    /// 上傳文件
+    /// 上傳文件，返回 `sha256:<hex>` 格式的哈希
     async fn upload_file(&self, path: &str, url: &str) -> Result<String> {
-        // TODO: 實現文件上傳
-        // 1. 讀取文件
-        // 2. 計算哈希
-        // 3. 上傳到 S3
-        warn!("File upload not yet implemented: {}", path);
-        Ok("sha256:abc123".to_string())
+        let data = tokio::fs::read(path)
+            .await
+            .map_err(|e| Error::UploadFailed(format!("{}: {}", path, e)))?;
+        let hash = format!("sha256:{}", hex::encode(Sha256::digest(&data)));
+
+        self.http
+            .put(url)
+            .body(data)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Error::UploadFailed(format!("{}: {}", url, e)))?;
+
+        debug!("Uploaded {} to {}", path, url);
+        Ok(hash)
     }
 }
 
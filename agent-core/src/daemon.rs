@@ -20,6 +20,18 @@ pub struct AgentState {
     pub is_running: bool,
     pub uptime_seconds: u64,
     pub tasks_completed: u32,
+
+    /// 出站訊息壓縮統計
+    #[serde(default)]
+    pub compression: crate::network::CompressionStats,
+
+    /// 與平台之間的往返時間
+    #[serde(default)]
+    pub latency: crate::network::LatencyStats,
+
+    /// 最近一次平台或本地錯誤
+    #[serde(default)]
+    pub last_error: Option<crate::network::ErrorRecord>,
 }
 
 impl DaemonManager {
@@ -199,6 +211,9 @@ impl DaemonManager {
             is_running: true,
             uptime_seconds: 0,
             tasks_completed: 0,
+            compression: Default::default(),
+            last_error: None,
+            latency: Default::default(),
         });
 
         f(&mut state);
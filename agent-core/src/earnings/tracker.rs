@@ -12,7 +12,8 @@ use tracing::info;
 /// 收益追蹤器
 pub struct EarningsTracker {
     data: EarningsData,
-    storage_path: PathBuf,
+    /// None 表示不寫入檔案
+    storage_path: Option<PathBuf>,
 }
 
 impl EarningsTracker {
@@ -24,20 +25,32 @@ impl EarningsTracker {
         let data = if storage_path.exists() {
             Self::load_from_file(&storage_path)?
         } else {
-            EarningsData {
-                total_earnings: Decimal::ZERO,
-                today_earnings: Decimal::ZERO,
-                pending_earnings: Decimal::ZERO,
-                history: Vec::new(),
-            }
+            Self::empty()
         };
 
         Ok(Self {
             data,
-            storage_path,
+            storage_path: Some(storage_path),
         })
     }
 
+    /// 創建不讀寫檔案的收益追蹤器（重放記錄的會話時使用）
+    pub fn in_memory() -> Self {
+        Self {
+            data: Self::empty(),
+            storage_path: None,
+        }
+    }
+
+    fn empty() -> EarningsData {
+        EarningsData {
+            total_earnings: Decimal::ZERO,
+            today_earnings: Decimal::ZERO,
+            pending_earnings: Decimal::ZERO,
+            history: Vec::new(),
+        }
+    }
+
     /// 獲取存儲路徑
     fn get_storage_path() -> Result<PathBuf> {
         let mut path = dirs::data_local_dir()
@@ -58,8 +71,12 @@ impl EarningsTracker {
 
     /// 保存到文件
     fn save_to_file(&self) -> Result<()> {
+        let Some(storage_path) = &self.storage_path else {
+            return Ok(());
+        };
+
         let content = serde_json::to_string_pretty(&self.data)?;
-        fs::write(&self.storage_path, content)?;
+        fs::write(storage_path, content)?;
         Ok(())
     }
 
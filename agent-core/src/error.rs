@@ -24,6 +24,27 @@ pub enum Error {
     #[error("Authentication failed: {0}")]
     AuthenticationFailed(String),
 
+    #[error("Protocol error: {0}")]
+    ProtocolError(String),
+
+    #[error("Request timed out: {0}")]
+    RequestTimeout(String),
+
+    #[error("Outbox full ({0} messages)")]
+    OutboxFull(usize),
+
+    #[error("WebSocket upgrade failed: {0}")]
+    UpgradeFailed(String),
+
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
+    #[error("Inbound message rejected: {0}")]
+    InboundRejected(crate::network::InboundViolation),
+
+    #[error("Platform error {code}: {message}")]
+    PlatformError { code: String, message: String },
+
     #[error("WebSocket error: {0}")]
     WebSocketError(#[from] tokio_tungstenite::tungstenite::Error),
 
@@ -71,6 +92,9 @@ pub enum Error {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
+    #[error("Protobuf decode error: {0}")]
+    ProtobufError(#[from] prost::DecodeError),
+
     // Agent 狀態錯誤
     #[error("Agent not registered")]
     NotRegistered,
@@ -91,8 +115,13 @@ impl Error {
     pub fn is_recoverable(&self) -> bool {
         match self {
             Error::ConnectionFailed(_) => true,
+            Error::UpgradeFailed(_) => true,
+            Error::RequestTimeout(_) => true,
             Error::DownloadFailed(_) => true,
             Error::TaskTimeout => true,
+            // 拒絕該訊息後繼續
+            Error::SignatureVerificationFailed => true,
+            Error::InboundRejected(_) => true,
             Error::GPUNotFound => false,
             Error::InsufficientVRAM { .. } => false,
             Error::AuthenticationFailed(_) => false,
@@ -106,14 +135,15 @@ impl Error {
             Error::GPUNotFound => "GPU_NOT_AVAILABLE",
             Error::InsufficientVRAM { .. } => "INSUFFICIENT_VRAM",
             Error::GPUError(_) => "GPU_ERROR",
-            Error::ConnectionFailed(_) => "CONNECTION_FAILED",
+            Error::ConnectionFailed(_) | Error::UpgradeFailed(_) | Error::TlsError(_) => "CONNECTION_FAILED",
             Error::AuthenticationFailed(_) => "AUTH_FAILED",
             Error::TaskExecutionFailed(_) => "TASK_EXECUTION_FAILED",
             Error::DownloadFailed(_) => "DOWNLOAD_FAILED",
             Error::UploadFailed(_) => "UPLOAD_FAILED",
-            Error::TaskTimeout => "TIMEOUT",
+            Error::TaskTimeout | Error::RequestTimeout(_) => "TIMEOUT",
             Error::OutOfMemory => "OOM_ERROR",
             Error::SignatureVerificationFailed => "VALIDATION_FAILED",
+            Error::InboundRejected(_) => "PROTOCOL_VIOLATION",
             _ => "UNKNOWN_ERROR",
         }
     }
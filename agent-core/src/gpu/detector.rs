@@ -1,5 +1,5 @@
 use super::device::{GPUDevice, GPUDeviceRef};
-use crate::types::{HardwareInfo, GPUInfo, GPUStatus, CPUInfo, TaskRequirements};
+use crate::types::{HardwareInfo, HardwareManifest, GPUInfo, GPUStatus, CPUInfo, TaskRequirements};
 use crate::error::{Error, Result};
 use std::sync::Arc;
 use tracing::{info, warn};
@@ -77,6 +77,21 @@ impl GPUDetector {
         })
     }
 
+    /// 使用指定的設備創建偵測器（如模擬 GPU）
+    pub fn from_devices(devices: Vec<GPUDeviceRef>) -> Result<Self> {
+        if devices.is_empty() {
+            return Err(Error::GPUNotFound);
+        }
+
+        let mut system_info = System::new_all();
+        system_info.refresh_all();
+
+        Ok(Self {
+            devices,
+            system_info: Arc::new(system_info),
+        })
+    }
+
     /// 偵測 NVIDIA GPU
     #[cfg(feature = "nvidia")]
     fn detect_nvidia() -> Result<Vec<GPUDeviceRef>> {
@@ -152,6 +167,37 @@ impl GPUDetector {
         }
     }
 
+    /// 獲取硬體清單
+    ///
+    /// 每次呼叫重新讀取設備資訊，無法讀取的 GPU 不列入（與先前的清單比對即可發現變更）
+    pub fn hardware_manifest(&self) -> HardwareManifest {
+        let mut gpus: Vec<_> = self
+            .devices
+            .iter()
+            .filter_map(|device| {
+                device
+                    .get_manifest()
+                    .inspect_err(|e| warn!("Failed to read GPU {} for hardware manifest: {}", device.index(), e))
+                    .ok()
+            })
+            .collect();
+        gpus.sort_by_key(|gpu| gpu.index);
+
+        let cpu = self.get_cpu_info();
+
+        HardwareManifest {
+            gpus,
+            cpu_model: cpu.model,
+            cpu_cores: cpu.cores,
+            cpu_threads: cpu.threads,
+            memory_bytes: self.system_info.total_memory(),
+            os: System::name().unwrap_or_default(),
+            os_version: System::os_version().unwrap_or_default(),
+            kernel_version: System::kernel_version().unwrap_or_default(),
+            arch: System::cpu_arch().unwrap_or_default(),
+        }
+    }
+
     /// 獲取 CPU 資訊
     fn get_cpu_info(&self) -> CPUInfo {
         let cpus = self.system_info.cpus();
@@ -209,15 +255,18 @@ impl GPUDetector {
     }
 
     /// 計算工作證明 (使用GPU加速)
+    ///
+    /// GPU 簽名取自第一個設備，與硬體清單中的資訊一致
     pub fn compute_pow(&self, challenge: &super::PowChallenge) -> Result<super::PowResponse> {
-        use super::pow::{GpuPowComputer, PowConfig};
+        use super::pow::{GpuPowComputer, GpuSignature, PowConfig};
 
         let config = PowConfig {
             difficulty: challenge.difficulty,
             max_compute_time_sec: 10,
         };
 
-        let computer = GpuPowComputer::new(config)?;
+        let device = self.devices.first().ok_or(Error::GPUNotFound)?;
+        let computer = GpuPowComputer::with_signature(config, GpuSignature::from_device(device.as_ref())?);
         computer.compute(challenge)
     }
 }
@@ -1,4 +1,4 @@
-use crate::types::{GPUInfo, GPUStatus, GPUVendor, MemoryInfo, TaskRequirements};
+use crate::types::{GPUInfo, GPUStatus, GPUVendor, GpuManifest, MemoryInfo, TaskRequirements};
 use crate::error::Result;
 use std::sync::Arc;
 
@@ -53,6 +53,26 @@ pub trait GPUDevice: Send + Sync {
     /// 獲取設備 UUID (用於唯一識別)
     fn uuid(&self) -> Result<String>;
 
+    /// 獲取 PCI 匯流排位址
+    fn pci_bus_id(&self) -> Option<String> {
+        None
+    }
+
+    /// 獲取 PCI 廠商與裝置 ID (如 "10de:2684")
+    fn pci_device_id(&self) -> Option<String> {
+        None
+    }
+
+    /// 獲取驅動程式版本
+    fn driver_version(&self) -> Option<String> {
+        None
+    }
+
+    /// 獲取驅動程式支援的 CUDA 版本 (僅 NVIDIA)
+    fn cuda_version(&self) -> Option<String> {
+        None
+    }
+
     /// 獲取完整的 GPU 資訊
     fn get_info(&self) -> Result<GPUInfo> {
         let memory = self.memory_info()?;
@@ -67,6 +87,22 @@ pub trait GPUDevice: Send + Sync {
         })
     }
 
+    /// 獲取硬體清單中的設備項目
+    fn get_manifest(&self) -> Result<GpuManifest> {
+        Ok(GpuManifest {
+            index: self.index(),
+            vendor: self.vendor(),
+            model: self.name()?,
+            uuid: self.uuid()?,
+            pci_bus_id: self.pci_bus_id(),
+            pci_device_id: self.pci_device_id(),
+            vram_bytes: self.memory_info()?.total,
+            compute_capability: self.compute_capability()?,
+            driver_version: self.driver_version(),
+            cuda_version: self.cuda_version(),
+        })
+    }
+
     /// 獲取即時狀態
     fn get_status(&self) -> Result<GPUStatus> {
         let memory = self.memory_info()?;
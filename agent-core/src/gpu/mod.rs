@@ -3,6 +3,7 @@
 mod detector;
 mod device;
 mod pow;
+mod simulated;
 
 #[cfg(feature = "nvidia")]
 mod nvidia;
@@ -16,6 +17,7 @@ mod apple;
 pub use detector::GPUDetector;
 pub use device::{GPUDevice, DeviceType};
 pub use pow::{GpuPowComputer, PowChallenge, PowResponse, PowConfig, GpuSignature};
+pub use simulated::SimulatedGPU;
 
 use crate::types::{GPUInfo, GPUStatus, MemoryInfo, HardwareInfo, TaskRequirements};
 use crate::error::Result;
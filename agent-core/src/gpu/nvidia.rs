@@ -129,6 +129,25 @@ impl GPUDevice for NvidiaGPU {
         Ok(self.device.uuid()?)
     }
 
+    fn pci_bus_id(&self) -> Option<String> {
+        self.device.pci_info().ok().map(|pci| pci.bus_id)
+    }
+
+    fn pci_device_id(&self) -> Option<String> {
+        // 高 16 位為裝置 ID，低 16 位為廠商 ID，依慣例輸出為「廠商:裝置」
+        let id = self.device.pci_info().ok()?.pci_device_id;
+        Some(format!("{:04x}:{:04x}", id & 0xffff, id >> 16))
+    }
+
+    fn driver_version(&self) -> Option<String> {
+        self.nvml.sys_driver_version().ok()
+    }
+
+    fn cuda_version(&self) -> Option<String> {
+        let version = self.nvml.sys_cuda_driver_version().ok()?;
+        Some(format!("{}.{}", version / 1000, (version % 1000) / 10))
+    }
+
     fn compute_pow(&self, challenge: &[u8], difficulty: u32) -> Result<Vec<u8>> {
         // 簡化的 PoW 實現
         // 實際應該使用 CUDA kernel 進行並行計算
@@ -3,6 +3,7 @@
 // 实现GPU工作量证明，防止虚假节点
 // 通过GPU并行计算密集型哈希来验证真实GPU存在
 
+use super::device::GPUDevice;
 use crate::error::{Error, Result};
 use sha2::{Sha256, Digest};
 use std::time::{Duration, Instant};
@@ -69,6 +70,18 @@ pub struct GpuSignature {
     pub compute_capability: Option<String>,
 }
 
+impl GpuSignature {
+    /// 由已侦测的设备生成签名，与注册时签名的硬件清单一致
+    pub fn from_device(device: &dyn GPUDevice) -> Result<Self> {
+        Ok(Self {
+            device_uuid: device.uuid()?,
+            device_model: device.name()?,
+            cuda_version: device.cuda_version(),
+            compute_capability: device.compute_capability().ok(),
+        })
+    }
+}
+
 /// GPU PoW 计算器
 pub struct GpuPowComputer {
     config: PowConfig,
@@ -82,6 +95,11 @@ impl GpuPowComputer {
         Ok(Self { config, gpu_info })
     }
 
+    /// 以指定的GPU签名创建PoW计算器
+    pub fn with_signature(config: PowConfig, gpu_info: GpuSignature) -> Self {
+        Self { config, gpu_info }
+    }
+
     /// 获取GPU签名
     fn get_gpu_signature() -> Result<GpuSignature> {
         #[cfg(feature = "nvidia")]
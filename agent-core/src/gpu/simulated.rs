@@ -0,0 +1,138 @@
+use super::device::GPUDevice;
+use crate::types::{GPUVendor, MemoryInfo};
+use crate::error::Result;
+use sha2::{Sha256, Digest};
+
+/// 模擬 GPU 設備
+///
+/// 用於無 GPU 環境下的端到端測試與離線重現，回報固定的硬體規格
+#[derive(Debug, Clone)]
+pub struct SimulatedGPU {
+    index: u32,
+    model: String,
+    vram_gb: u32,
+    compute_capability: String,
+    uuid: String,
+}
+
+impl SimulatedGPU {
+    pub fn new(index: u32, model: impl Into<String>, vram_gb: u32) -> Self {
+        Self {
+            index,
+            model: model.into(),
+            vram_gb,
+            compute_capability: "8.9".to_string(),
+            uuid: format!("GPU-simulated-{}", index),
+        }
+    }
+
+    /// 設定計算能力版本
+    pub fn with_compute_capability(mut self, compute_capability: impl Into<String>) -> Self {
+        self.compute_capability = compute_capability.into();
+        self
+    }
+
+    /// 設定設備 UUID（多個模擬 Agent 連接同一平台時須各不相同）
+    pub fn with_uuid(mut self, uuid: impl Into<String>) -> Self {
+        self.uuid = uuid.into();
+        self
+    }
+}
+
+impl GPUDevice for SimulatedGPU {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn vendor(&self) -> GPUVendor {
+        GPUVendor::NVIDIA
+    }
+
+    fn name(&self) -> Result<String> {
+        Ok(self.model.clone())
+    }
+
+    fn memory_info(&self) -> Result<MemoryInfo> {
+        let total = (self.vram_gb as u64) * 1024 * 1024 * 1024;
+        Ok(MemoryInfo {
+            total,
+            free: total,
+            used: 0,
+        })
+    }
+
+    fn utilization(&self) -> Result<f32> {
+        Ok(0.0)
+    }
+
+    fn temperature(&self) -> Result<f32> {
+        Ok(40.0)
+    }
+
+    fn power_usage(&self) -> Result<f32> {
+        Ok(30.0)
+    }
+
+    fn fan_speed(&self) -> Result<f32> {
+        Ok(0.3)
+    }
+
+    fn compute_capability(&self) -> Result<String> {
+        Ok(self.compute_capability.clone())
+    }
+
+    fn pcie_bandwidth(&self) -> Result<u32> {
+        Ok(32)
+    }
+
+    fn uuid(&self) -> Result<String> {
+        Ok(self.uuid.clone())
+    }
+
+    fn pci_bus_id(&self) -> Option<String> {
+        Some(format!("00000000:{:02x}:00.0", self.index + 1))
+    }
+
+    fn driver_version(&self) -> Option<String> {
+        Some("simulated".to_string())
+    }
+
+    fn compute_pow(&self, challenge: &[u8], difficulty: u32) -> Result<Vec<u8>> {
+        let mut nonce: u64 = 0;
+        loop {
+            let mut hasher = Sha256::new();
+            hasher.update(challenge);
+            hasher.update(nonce.to_le_bytes());
+            let hash = hasher.finalize();
+
+            let leading_zeros = hash.iter().take_while(|&&b| b == 0).count();
+            if leading_zeros >= difficulty as usize {
+                return Ok(hash.to_vec());
+            }
+
+            nonce += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TaskRequirements;
+
+    #[test]
+    fn test_simulated_gpu_requirements() {
+        let gpu = SimulatedGPU::new(0, "Simulated RTX 4090", 24);
+
+        let info = gpu.get_info().unwrap();
+        assert_eq!(info.vram_gb, 24);
+
+        let requirements = TaskRequirements {
+            min_vram_gb: 12,
+            min_compute_capability: "7.5".to_string(),
+            framework: "pytorch".to_string(),
+            fp16: true,
+        };
+        assert!(gpu.meets_requirements(&requirements).unwrap());
+    }
+}
@@ -20,17 +20,29 @@ pub mod cli;
 pub use error::{Error, Result};
 pub use types::*;
 
-use tracing::{info, warn, error};
+use tracing::{debug, info, warn, error};
 use tokio::sync::mpsc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 /// Orban Agent 主控制器
 pub struct OrbanAgent {
     config: AgentConfig,
     gpu_detector: gpu::GPUDetector,
-    network_client: network::OrbanClient,
+    network_client: Arc<dyn network::Transport>,
+    /// 驗證平台訊息的簽名
+    signer: network::MessageSigner,
     task_executor: compute::TaskExecutor,
     earnings_tracker: earnings::EarningsTracker,
+    /// 平台限流時暫停心跳直到此時間
+    backoff_until: Arc<Mutex<Option<Instant>>>,
+    /// 平台最近一次接受的硬體清單，與目前的清單不同時重新送出硬體證明
+    attested_manifest: Arc<Mutex<Option<HardwareManifest>>>,
+    /// 平台最近一次拒絕的硬體清單，清單未再變更前不重複送出
+    last_rejected_manifest: Arc<Mutex<Option<HardwareManifest>>>,
+    last_error: Option<network::ErrorRecord>,
+    /// 離線重放，不寫入狀態檔
+    offline: bool,
 }
 
 impl OrbanAgent {
@@ -40,10 +52,17 @@ impl OrbanAgent {
 
         // 偵測 GPU 硬體
         let gpu_detector = gpu::GPUDetector::detect_all()?;
+
+        Self::with_gpu_detector(config, gpu_detector).await
+    }
+
+    /// 使用指定的 GPU 偵測器創建 Agent（用於模擬 GPU 環境）
+    pub async fn with_gpu_detector(config: AgentConfig, gpu_detector: gpu::GPUDetector) -> Result<Self> {
         info!("Detected {} GPU(s)", gpu_detector.device_count());
 
-        // 創建網路客戶端
-        let network_client = network::OrbanClient::new(&config).await?;
+        // 創建網路傳輸層
+        let network_client = network::transport::create(&config).await?;
+        let signer = network::MessageSigner::from_config(&config.network.signing)?;
 
         // 創建任務執行器
         let devices: Vec<_> = gpu_detector.get_all_devices().to_vec();
@@ -56,17 +75,57 @@ impl OrbanAgent {
             config,
             gpu_detector,
             network_client,
+            signer,
             task_executor,
             earnings_tracker,
+            backoff_until: Arc::new(Mutex::new(None)),
+            attested_manifest: Arc::new(Mutex::new(None)),
+            last_rejected_manifest: Arc::new(Mutex::new(None)),
+            last_error: None,
+            offline: false,
+        })
+    }
+
+    /// 使用指定的傳輸層創建離線 Agent（重放記錄的會話）
+    ///
+    /// 不驗證訊息簽名（記錄中的簽名已去除），收益與錯誤不寫入本地檔案
+    pub fn offline(
+        config: AgentConfig,
+        gpu_detector: gpu::GPUDetector,
+        network_client: Arc<dyn network::Transport>,
+    ) -> Result<Self> {
+        let devices: Vec<_> = gpu_detector.get_all_devices().to_vec();
+        let task_executor = compute::TaskExecutor::new(devices)?;
+
+        Ok(Self {
+            config,
+            gpu_detector,
+            network_client,
+            signer: network::MessageSigner::default(),
+            task_executor,
+            earnings_tracker: earnings::EarningsTracker::in_memory(),
+            backoff_until: Arc::new(Mutex::new(None)),
+            attested_manifest: Arc::new(Mutex::new(None)),
+            last_rejected_manifest: Arc::new(Mutex::new(None)),
+            last_error: None,
+            offline: true,
         })
     }
 
+    /// 處理重放的平台訊息，與事件循環相同：可恢復的錯誤記錄後返回 Ok
+    pub async fn replay_message(&mut self, msg: network::Message) -> Result<()> {
+        if let Err(e) = self.handle_message(msg).await {
+            self.handle_local_error(e)?;
+        }
+        Ok(())
+    }
+
     /// 啟動 Agent
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting Orban Agent...");
 
-        // 連接到 Orban Platform
-        self.network_client.connect().await?;
+        // 連接到 Orban Platform（WebSocket 無法使用時可能改用 HTTP 長輪詢）
+        self.network_client = network::transport::connect(&self.config, self.network_client.clone()).await?;
 
         // 註冊 Agent
         self.register_agent().await?;
@@ -82,13 +141,15 @@ impl OrbanAgent {
         info!("Registering agent with platform...");
 
         let hardware_info = self.gpu_detector.get_hardware_info();
+        let manifest = self.gpu_detector.hardware_manifest();
         let capabilities = self.get_capabilities();
         let location = self.get_location();
         let availability = self.config.availability.clone();
 
         self.network_client
-            .register(hardware_info, capabilities, location, availability)
+            .register(hardware_info, manifest.clone(), capabilities, location, availability)
             .await?;
+        *self.attested_manifest.lock().unwrap() = Some(manifest);
 
         info!("Agent registered successfully");
         Ok(())
@@ -101,31 +162,131 @@ impl OrbanAgent {
         let (tx, mut rx) = mpsc::channel(100);
         let network_client = self.network_client.clone();
 
-        // 心跳任務
-        tokio::spawn(async move {
+        // 心跳任務：經由出站佇列發送，不受接收循環阻塞
+        let devices = self.gpu_detector.get_all_devices().to_vec();
+        let interval_secs = self.config.network.heartbeat_interval_secs.max(1);
+        let daemon = daemon::DaemonManager::new().ok();
+        let backoff_until = self.backoff_until.clone();
+        let heartbeat = tokio::spawn(async move {
+            let started = std::time::Instant::now();
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+            interval.tick().await;
+
             loop {
-                tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-                // TODO: 發送心跳
+                interval.tick().await;
+
+                if backoff_until.lock().unwrap().is_some_and(|until| Instant::now() < until) {
+                    debug!("Rate limited by platform, skipping heartbeat");
+                    continue;
+                }
+
+                let mut active_tasks = network_client.active_tasks().await;
+                active_tasks.sort_by_key(|task| task.started_at);
+                let status = if active_tasks.is_empty() {
+                    network::AgentStatus::Idle
+                } else {
+                    network::AgentStatus::Working
+                };
+                let current_task_id = active_tasks.first().map(|task| task.task_id.clone());
+                let gpu_status = devices.iter().filter_map(|device| device.get_status().ok()).collect();
+
+                if let Err(e) = network_client
+                    .send_heartbeat(status, current_task_id, gpu_status, started.elapsed().as_secs())
+                    .await
+                {
+                    debug!("Failed to send heartbeat: {}", e);
+                }
+
+                // 供 `orban-agent status` 顯示，僅在以 CLI 啟動（已有狀態檔）時更新
+                if let Some(daemon) = daemon.as_ref().filter(|d| d.state_file().exists()) {
+                    let stats = network_client.compression_stats();
+                    let latency = network_client.latency();
+                    if let Err(e) = daemon.update_state(|state| {
+                        state.compression = stats;
+                        state.latency = latency;
+                    }) {
+                        debug!("Failed to update agent state: {}", e);
+                    }
+                }
             }
         });
 
-        // 接收任務
-        loop {
-            tokio::select! {
-                Some(msg) = self.network_client.receive() => {
-                    self.handle_message(msg).await?;
+        // 硬體監測：定期重新讀取硬體清單，與平台接受或拒絕的都不同時交由事件循環重新送出硬體證明
+        let check_secs = self.config.network.hardware_check_interval_secs;
+        let hardware_watch = (check_secs > 0).then(|| {
+            let detector = self.gpu_detector.clone();
+            let attested = self.attested_manifest.clone();
+            let rejected = self.last_rejected_manifest.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(std::time::Duration::from_secs(check_secs));
+                interval.tick().await;
+
+                loop {
+                    interval.tick().await;
+
+                    let manifest = detector.hardware_manifest();
+                    if attested.lock().unwrap().as_ref() == Some(&manifest)
+                        || rejected.lock().unwrap().as_ref() == Some(&manifest)
+                    {
+                        continue;
+                    }
+                    if tx.send(AgentEvent::HardwareChanged(manifest)).await.is_err() {
+                        break;
+                    }
                 }
-                Some(event) = rx.recv() => {
-                    self.handle_event(event).await?;
+            })
+        });
+
+        // 接收任務：同一個接收 future 跨越各輪 select 保留，處理事件時不會中斷進行中的重連或令牌更新
+        let network_client = self.network_client.clone();
+        let mut receive = network_client.receive();
+        let result: Result<()> = async {
+            loop {
+                tokio::select! {
+                    biased;
+
+                    // 斷線與令牌更新由客戶端處理，返回錯誤表示會話無法恢復
+                    msg = &mut receive => {
+                        receive = network_client.receive();
+                        let msg = msg.inspect_err(|e| {
+                            self.record_error(network::ErrorRecord::local(e, &network::ErrorAction::Shutdown));
+                        })?;
+                        if let Err(e) = self.handle_message(msg).await {
+                            self.handle_local_error(e)?;
+                        }
+                    }
+                    Some(event) = rx.recv() => {
+                        if let Err(e) = self.handle_event(event).await {
+                            self.handle_local_error(e)?;
+                        }
+                    }
                 }
             }
         }
+        .await;
+
+        heartbeat.abort();
+        if let Some(hardware_watch) = hardware_watch {
+            hardware_watch.abort();
+        }
+        result
     }
 
     /// 處理來自平台的訊息
     async fn handle_message(&mut self, msg: network::Message) -> Result<()> {
         use network::{MessageType, MessagePayload};
 
+        // 簽名無效的任務仍需回覆，平台不再等待
+        if let Err(e) = self.signer.verify(&msg) {
+            if let MessagePayload::TaskAssign(payload) = &msg.payload {
+                self.network_client
+                    .reject_task(&payload.task_id, "signature_verification_failed")
+                    .await?;
+            }
+            return Err(e);
+        }
+
         match msg.message_type {
             MessageType::TaskAssign => {
                 info!("Received task assignment");
@@ -145,6 +306,17 @@ impl OrbanAgent {
                     self.handle_earnings_record(payload).await?;
                 }
             }
+            MessageType::Error => {
+                if let MessagePayload::Error(payload) = msg.payload {
+                    self.handle_platform_error(payload).await?;
+                }
+            }
+            MessageType::Unknown => {
+                // 較新版本的平台新增的訊息類型
+                if let MessagePayload::Unknown(payload) = &msg.payload {
+                    warn!("Ignoring unsupported message type {:?} ({})", payload.message_type, msg.message_id);
+                }
+            }
             _ => {
                 warn!("Unknown message type: {:?}", msg.message_type);
             }
@@ -153,18 +325,83 @@ impl OrbanAgent {
         Ok(())
     }
 
+    /// 依錯誤處理策略處理平台錯誤，不可恢復時返回錯誤以停止 Agent
+    async fn handle_platform_error(&mut self, payload: network::orban_protocol::ErrorPayload) -> Result<()> {
+        use network::ErrorAction;
+
+        let action = ErrorAction::for_platform_error(&payload);
+        self.record_error(network::ErrorRecord::platform(&payload, &action));
+
+        match action {
+            // 會話撤銷由傳輸層重新認證，不會到達此處
+            ErrorAction::Continue | ErrorAction::Reauthenticate => {
+                warn!("Platform error {}: {}", payload.code, payload.message);
+            }
+            ErrorAction::Backoff(delay) => {
+                warn!("Rate limited by platform, pausing heartbeats for {}s: {}", delay.as_secs(), payload.message);
+                *self.backoff_until.lock().unwrap() = Some(Instant::now() + delay);
+            }
+            ErrorAction::AbortTask(task_id) => {
+                warn!("Aborting task {} ({}): {}", task_id, payload.code, payload.message);
+                self.network_client.abort_task(&task_id).await;
+            }
+            ErrorAction::Shutdown => {
+                error!("Unrecoverable platform error {}: {}", payload.code, payload.message);
+                return Err(Error::PlatformError {
+                    code: payload.code,
+                    message: payload.message,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 處理本地錯誤：可恢復時記錄後繼續，否則返回錯誤以停止 Agent
+    fn handle_local_error(&mut self, err: Error) -> Result<()> {
+        // 平台錯誤已於處理時記錄
+        if matches!(err, Error::PlatformError { .. }) {
+            return Err(err);
+        }
+
+        let action = network::ErrorAction::for_local_error(&err);
+        self.record_error(network::ErrorRecord::local(&err, &action));
+
+        if action == network::ErrorAction::Shutdown {
+            return Err(err);
+        }
+
+        warn!("Recoverable error ({}): {}", err.error_code(), err);
+        Ok(())
+    }
+
+    /// 記錄最近一次錯誤，以 CLI 啟動（已有狀態檔）時寫入狀態檔供 `orban-agent status` 顯示
+    fn record_error(&mut self, record: network::ErrorRecord) {
+        let daemon = (!self.offline).then(|| daemon::DaemonManager::new().ok()).flatten();
+        if let Some(daemon) = daemon.filter(|d| d.state_file().exists()) {
+            if let Err(e) = daemon.update_state(|state| state.last_error = Some(record.clone())) {
+                debug!("Failed to update agent state: {}", e);
+            }
+        }
+
+        self.last_error = Some(record);
+    }
+
+    /// 最近一次錯誤
+    pub fn last_error(&self) -> Option<&network::ErrorRecord> {
+        self.last_error.as_ref()
+    }
+
     /// 處理任務分配
     async fn handle_task_assign(&mut self, payload: network::TaskAssignPayload) -> Result<()> {
         // 檢查是否有足夠的資源
         if !self.can_accept_task(&payload.requirements) {
-            // TODO: Implement reject_task
-            // self.network_client.reject_task(&payload.task_id, "insufficient_resources").await?;
+            self.network_client.reject_task(&payload.task_id, "insufficient_resources").await?;
             return Ok(());
         }
 
-        // 接受任務
-        // TODO: Implement accept_task
-        // self.network_client.accept_task(&payload.task_id).await?;
+        // 接受任務（記錄為進行中，重連時回報）
+        self.network_client.accept_task(&payload.task_id).await?;
 
         // 執行任務
         // TODO: Convert payload to Task
@@ -213,7 +450,32 @@ impl OrbanAgent {
 
     /// 處理內部事件
     async fn handle_event(&mut self, event: AgentEvent) -> Result<()> {
-        // TODO: 實現事件處理
+        match event {
+            AgentEvent::HardwareChanged(manifest) => self.handle_hardware_change(manifest).await,
+            // TODO: 實現其他事件處理
+            _ => Ok(()),
+        }
+    }
+
+    /// 硬體變更時重新送出硬體證明
+    ///
+    /// 平台拒絕時記錄後繼續運行，是否分配任務由平台決定，同一清單不再重複送出；發送失敗時於下次檢查重試
+    async fn handle_hardware_change(&mut self, manifest: HardwareManifest) -> Result<()> {
+        info!("Hardware changed, sending new attestation for {} GPU(s)", manifest.gpus.len());
+
+        match self.network_client.attest_hardware(manifest.clone()).await {
+            Ok(()) => {
+                info!("Hardware attestation accepted");
+                *self.attested_manifest.lock().unwrap() = Some(manifest);
+                *self.last_rejected_manifest.lock().unwrap() = None;
+            }
+            Err(Error::PlatformError { code, message }) => {
+                warn!("Platform rejected hardware attestation ({}): {}", code, message);
+                *self.last_rejected_manifest.lock().unwrap() = Some(manifest);
+            }
+            Err(e) => return Err(e),
+        }
+
         Ok(())
     }
 
@@ -241,8 +503,15 @@ impl OrbanAgent {
         // TODO: 實際偵測地理位置
         Location {
             country: "TW".to_string(),
-            region: "asia-east1".to_string(),
-            latency_to_platform_ms: 0,
+            // 實際使用的平台端點所在區域
+            region: self
+                .network_client
+                .endpoints()
+                .current()
+                .region
+                .unwrap_or_else(|| "asia-east1".to_string()),
+            // 連線認證時已量測
+            latency_to_platform_ms: self.network_client.latency().reported_ms(),
         }
     }
 
@@ -257,10 +526,20 @@ impl OrbanAgent {
 /// Agent 配置
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct AgentConfig {
-    pub agent_id: String,
+    /// 註冊時提供給平台的顯示名稱；Agent ID 由身分金鑰推導
+    #[serde(default)]
+    pub display_name: Option<String>,
     pub platform_url: String,
     pub private_key_path: String,
+    /// 私鑰的保存方式與加密金鑰的密碼
+    #[serde(default)]
+    pub key: config::KeyConfig,
     pub availability: Availability,
+    #[serde(default)]
+    pub network: config::NetworkConfig,
+    /// 數據目錄（離線發件匣等）
+    #[serde(default = "config::Config::default_data_dir")]
+    pub data_dir: std::path::PathBuf,
 }
 
 /// Agent 事件
@@ -269,6 +548,8 @@ pub enum AgentEvent {
     TaskCompleted(String),
     TaskFailed(String, String),
     GPUError(String),
+    /// 硬體清單與平台收到的不同
+    HardwareChanged(HardwareManifest),
 }
 
 #[cfg(test)]
@@ -2,9 +2,11 @@
 //!
 //! 命令行工具用於管理 Orban GPU Agent
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
+use orban_agent_core::network::RotationReason;
 use orban_agent_core::Result;
+use std::path::PathBuf;
 use std::process;
 
 /// Orban Agent - GPU 算力貢獻工具
@@ -25,6 +27,14 @@ enum Commands {
         /// 前台運行模式（用於調試）
         #[arg(short, long)]
         foreground: bool,
+
+        /// 記錄收發的協議訊息（JSONL）
+        #[arg(long, value_name = "FILE")]
+        record: Option<PathBuf>,
+
+        /// 從此文件描述符讀取加密金鑰的密碼
+        #[arg(long, value_name = "FD")]
+        passphrase_fd: Option<i32>,
     },
 
     /// 停止運行中的 Agent
@@ -55,10 +65,109 @@ enum Commands {
         lines: usize,
     },
 
+    /// 以模擬 GPU 離線重放記錄的會話
+    Replay {
+        /// `start --record` 產生的記錄檔
+        file: PathBuf,
+
+        /// 將重放時 Agent 送出的訊息記錄於此檔案
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// 管理 Agent 身分金鑰
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+
     /// 顯示版本信息
     Version,
 }
 
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// 生成身分金鑰（僅擁有者可讀寫）
+    Init {
+        /// 覆寫已存在的金鑰
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// 顯示 Agent ID 與公鑰
+    Show,
+
+    /// 以新金鑰取代目前的金鑰，平台確認後生效，舊金鑰保留於同目錄
+    Rotate {
+        /// 輪換原因
+        #[arg(long, value_enum, default_value = "routine")]
+        reason: ReasonArg,
+    },
+
+    /// 以離線備份金鑰恢復遺失或外洩的身分，改用新金鑰
+    Recover {
+        /// `keys backup` 生成的備份金鑰
+        #[arg(long, value_name = "FILE")]
+        backup_key: PathBuf,
+
+        /// 輪換原因
+        #[arg(long, value_enum, default_value = "lost")]
+        reason: ReasonArg,
+    },
+
+    /// 生成離線備份金鑰，其公鑰於下次註冊時提交給平台
+    Backup {
+        /// 備份金鑰的輸出檔案
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// 覆寫已存在的檔案
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// 顯示金鑰輪換記錄
+    History,
+
+    /// 匯出私鑰（base64）
+    Export {
+        /// 寫入此檔案，未指定時印出到標準輸出
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// 匯入 `keys export` 匯出的私鑰
+    Import {
+        /// 私鑰檔案（base64 或 32 字節原始私鑰）
+        file: PathBuf,
+
+        /// 覆寫已存在的金鑰
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// 金鑰輪換原因
+#[derive(Clone, Copy, ValueEnum)]
+enum ReasonArg {
+    /// 例行更換
+    Routine,
+    /// 金鑰可能外洩
+    Compromised,
+    /// 金鑰遺失
+    Lost,
+}
+
+impl From<ReasonArg> for RotationReason {
+    fn from(reason: ReasonArg) -> Self {
+        match reason {
+            ReasonArg::Routine => RotationReason::Routine,
+            ReasonArg::Compromised => RotationReason::Compromised,
+            ReasonArg::Lost => RotationReason::Lost,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     // 初始化日誌
@@ -67,8 +176,8 @@ async fn main() {
     let cli = Cli::parse();
 
     let result = match cli.command {
-        Commands::Start { foreground } => {
-            orban_agent_core::cli::start::execute(foreground).await
+        Commands::Start { foreground, record, passphrase_fd } => {
+            orban_agent_core::cli::start::execute(foreground, record, passphrase_fd).await
         }
         Commands::Stop => {
             orban_agent_core::cli::stop::execute().await
@@ -82,6 +191,27 @@ async fn main() {
         Commands::Logs { follow, lines } => {
             orban_agent_core::cli::logs::execute(follow, lines).await
         }
+        Commands::Replay { file, output } => {
+            orban_agent_core::cli::replay::execute(&file, output.as_deref()).await
+        }
+        Commands::Keys { command } => match command {
+            KeysCommand::Init { force } => orban_agent_core::cli::keys::init(force).await,
+            KeysCommand::Show => orban_agent_core::cli::keys::show().await,
+            KeysCommand::Rotate { reason } => orban_agent_core::cli::keys::rotate(reason.into()).await,
+            KeysCommand::Recover { backup_key, reason } => {
+                orban_agent_core::cli::keys::recover(&backup_key, reason.into()).await
+            }
+            KeysCommand::Backup { output, force } => {
+                orban_agent_core::cli::keys::backup(&output, force).await
+            }
+            KeysCommand::History => orban_agent_core::cli::keys::history().await,
+            KeysCommand::Export { output } => {
+                orban_agent_core::cli::keys::export(output.as_deref()).await
+            }
+            KeysCommand::Import { file, force } => {
+                orban_agent_core::cli::keys::import(&file, force).await
+            }
+        },
         Commands::Version => {
             print_version();
             Ok(())
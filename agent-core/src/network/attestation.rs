@@ -0,0 +1,164 @@
+// 硬體證明
+//
+// 註冊時申報的硬體資訊僅為聲明，平台無法確認屬於哪個 Agent。硬體證明將硬體清單綁定到身分金鑰：
+// - 清單（HardwareManifest）包含 GPU UUID、PCI ID、驅動與 CUDA 版本、CPU、記憶體與作業系統
+// - 清單的標準 JSON（見 signing::canonical_json，未取得的欄位為 null）以 SHA-256 雜湊
+// - 簽名內容為 `ATTESTATION_CONTEXT` 接上 `{agent_id, issued_at, manifest_hash}` 的標準 JSON
+// - 註冊時隨 AGENT_REGISTER 送出；Agent 定期重新讀取清單，變更時以 HARDWARE_ATTESTATION 重新送出
+//
+// 平台驗證簽名與雜湊後可比對各 Agent 申報的 GPU UUID，發現偽造或被替換的 GPU
+
+use super::auth::Authenticator;
+use super::orban_protocol::HardwareAttestationPayload;
+use super::signing;
+use crate::error::{Error, Result};
+use crate::types::HardwareManifest;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// 硬體證明的簽名前綴
+pub const ATTESTATION_CONTEXT: &[u8] = b"orban-hardware-attestation-v1\n";
+
+/// 清單的雜湊（`sha256:<hex>`）
+pub fn manifest_hash(manifest: &HardwareManifest) -> Result<String> {
+    let canonical = signing::canonical_json(manifest)?;
+    Ok(format!("sha256:{}", hex::encode(Sha256::digest(canonical))))
+}
+
+/// 證明的簽名內容
+pub fn signing_input(agent_id: &str, manifest_hash: &str, issued_at: &DateTime<Utc>) -> Result<Vec<u8>> {
+    let claim = serde_json::json!({
+        "agent_id": agent_id,
+        "issued_at": issued_at,
+        "manifest_hash": manifest_hash,
+    });
+
+    Ok([ATTESTATION_CONTEXT, &signing::canonical_json(&claim)?].concat())
+}
+
+/// 以身分金鑰簽署硬體清單
+pub fn sign(authenticator: &Authenticator, manifest: HardwareManifest) -> Result<HardwareAttestationPayload> {
+    let agent_id = authenticator.agent_id().to_string();
+    let manifest_hash = manifest_hash(&manifest)?;
+    let issued_at = Utc::now();
+    let signature = authenticator.sign(&signing_input(&agent_id, &manifest_hash, &issued_at)?);
+
+    Ok(HardwareAttestationPayload {
+        agent_id,
+        manifest,
+        manifest_hash,
+        issued_at,
+        signature,
+    })
+}
+
+/// 驗證硬體證明，`public_key` 為 Agent 認證時使用的公鑰 (base64)
+///
+/// Agent ID 須由公鑰推導，雜湊須與清單相符，簽名須有效
+pub fn verify(attestation: &HardwareAttestationPayload, public_key: &str) -> Result<()> {
+    if super::identity::agent_id_for_public_key(public_key)? != attestation.agent_id {
+        return Err(Error::ProtocolError(format!(
+            "Agent ID {} does not match its public key",
+            attestation.agent_id
+        )));
+    }
+    if manifest_hash(&attestation.manifest)? != attestation.manifest_hash {
+        return Err(Error::ProtocolError("Manifest hash does not match the manifest".to_string()));
+    }
+
+    let input = signing_input(&attestation.agent_id, &attestation.manifest_hash, &attestation.issued_at)?;
+    if !Authenticator::verify_with_public_key(public_key, &input, &attestation.signature).unwrap_or(false) {
+        return Err(Error::SignatureVerificationFailed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{create_hardware_attestation, Message, MessagePayload};
+    use crate::types::{GPUVendor, GpuManifest};
+
+    fn manifest() -> HardwareManifest {
+        HardwareManifest {
+            gpus: vec![GpuManifest {
+                index: 0,
+                vendor: GPUVendor::NVIDIA,
+                model: "NVIDIA GeForce RTX 4090".to_string(),
+                uuid: "GPU-6f1c2a3b-0000-0000-0000-000000000000".to_string(),
+                pci_bus_id: Some("00000000:01:00.0".to_string()),
+                pci_device_id: Some("10de:2684".to_string()),
+                vram_bytes: 24 * 1024 * 1024 * 1024,
+                compute_capability: "8.9".to_string(),
+                driver_version: Some("550.54.14".to_string()),
+                cuda_version: None,
+            }],
+            cpu_model: "AMD Ryzen 9 7950X".to_string(),
+            cpu_cores: 16,
+            cpu_threads: 32,
+            memory_bytes: 64 * 1024 * 1024 * 1024,
+            os: "Ubuntu".to_string(),
+            os_version: "22.04".to_string(),
+            kernel_version: "6.5.0-35-generic".to_string(),
+            arch: "x86_64".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_attestation_signed_by_identity_key() {
+        let key = Authenticator::generate();
+        let attestation = sign(&key, manifest()).unwrap();
+        verify(&attestation, &key.public_key_base64()).unwrap();
+
+        // 經 Protocol Buffers 往返後簽名仍有效
+        let msg = create_hardware_attestation(attestation.clone());
+        let decoded = Message::from_protobuf(&msg.to_protobuf().unwrap()).unwrap();
+        match decoded.payload {
+            MessagePayload::HardwareAttestation(decoded) => {
+                assert_eq!(decoded, attestation);
+                verify(&decoded, &key.public_key_base64()).unwrap();
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+
+        // 其他 Agent 不能冒用
+        let other = Authenticator::generate();
+        assert!(verify(&attestation, &other.public_key_base64()).is_err());
+    }
+
+    #[test]
+    fn test_tampered_manifest_rejected() {
+        let key = Authenticator::generate();
+        let attestation = sign(&key, manifest()).unwrap();
+
+        // 替換 GPU 而沿用原雜湊
+        let mut swapped = attestation.clone();
+        swapped.manifest.gpus[0].uuid = "GPU-00000000-0000-0000-0000-000000000001".to_string();
+        assert!(verify(&swapped, &key.public_key_base64()).is_err());
+
+        // 重新計算雜湊但沒有重新簽名
+        swapped.manifest_hash = manifest_hash(&swapped.manifest).unwrap();
+        assert!(matches!(
+            verify(&swapped, &key.public_key_base64()),
+            Err(Error::SignatureVerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_manifest_hash_is_canonical() {
+        let manifest = manifest();
+        let canonical = String::from_utf8(signing::canonical_json(&manifest).unwrap()).unwrap();
+
+        // 鍵依字典序排列、無空白，未取得的欄位為 null
+        assert!(canonical.starts_with(r#"{"arch":"x86_64","cpu_cores":16,"#), "{}", canonical);
+        assert!(canonical.contains(r#""cuda_version":null"#), "{}", canonical);
+        assert!(!canonical.contains(": ") && !canonical.contains(", "), "{}", canonical);
+
+        // 接收端解析的欄位順序與排版不影響雜湊
+        let pretty = serde_json::to_string_pretty(&manifest).unwrap();
+        let parsed: HardwareManifest = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(manifest_hash(&parsed).unwrap(), manifest_hash(&manifest).unwrap());
+        assert!(manifest_hash(&manifest).unwrap().starts_with("sha256:"));
+    }
+}
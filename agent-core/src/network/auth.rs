@@ -2,13 +2,15 @@
 
 use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
 use rand::rngs::OsRng;
-use sha2::{Sha256, Digest};
+use super::identity;
 use crate::error::{Error, Result};
 use std::fs;
 use std::path::Path;
 use base64::{Engine as _, engine::general_purpose};
 
 /// 認證器
+///
+/// Agent ID 一律由公鑰推導（見 identity.rs）
 pub struct Authenticator {
     signing_key: SigningKey,
     verifying_key: VerifyingKey,
@@ -17,20 +19,35 @@ pub struct Authenticator {
 
 impl Authenticator {
     /// 從私鑰文件創建認證器
-    pub fn from_private_key_file<P: AsRef<Path>>(path: P, agent_id: String) -> Result<Self> {
-        let secret_bytes = fs::read(path)?;
-        if secret_bytes.len() != 32 {
-            return Err(Error::InvalidConfig(
-                "Private key must be 32 bytes".to_string(),
-            ));
+    ///
+    /// 私鑰文件可被其他使用者存取時拒絕載入
+    pub fn from_private_key_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(Error::FileNotFound(format!(
+                "{} (run `orban-agent keys init` to generate a key)",
+                path.display()
+            )));
         }
 
-        let mut key_bytes = [0u8; 32];
-        key_bytes.copy_from_slice(&secret_bytes);
-        let signing_key = SigningKey::from_bytes(&key_bytes);
-        let verifying_key = signing_key.verifying_key();
+        check_key_permissions(path)?;
+        Self::from_secret_bytes(&fs::read(path)?)
+    }
+
+    /// 從 32 字節的私鑰種子創建認證器
+    pub fn from_secret_bytes(secret_bytes: &[u8]) -> Result<Self> {
+        let key_bytes: [u8; 32] = secret_bytes.try_into().map_err(|_| {
+            Error::InvalidConfig("Private key must be 32 bytes".to_string())
+        })?;
+
+        Ok(Self::from_signing_key(SigningKey::from_bytes(&key_bytes)))
+    }
 
-        Ok(Self { signing_key, verifying_key, agent_id })
+    /// 從 base64 編碼的私鑰種子創建認證器（`keys export` 的格式）
+    pub fn from_private_key_base64(encoded: &str) -> Result<Self> {
+        let secret_bytes = general_purpose::STANDARD.decode(encoded.trim())
+            .map_err(|e| Error::InvalidConfig(format!("Invalid private key encoding: {}", e)))?;
+        Self::from_secret_bytes(&secret_bytes)
     }
 
     /// 生成新的密鑰對
@@ -40,35 +57,53 @@ impl Authenticator {
         let mut secret_bytes = [0u8; 32];
         rng.fill_bytes(&mut secret_bytes);
 
-        let signing_key = SigningKey::from_bytes(&secret_bytes);
+        Self::from_signing_key(SigningKey::from_bytes(&secret_bytes))
+    }
+
+    fn from_signing_key(signing_key: SigningKey) -> Self {
         let verifying_key = signing_key.verifying_key();
-        let agent_id = Self::derive_agent_id(&verifying_key);
+        let agent_id = identity::derive_agent_id(verifying_key.as_bytes());
 
         Self { signing_key, verifying_key, agent_id }
     }
 
-    /// 從公鑰推導 Agent ID
-    fn derive_agent_id(public_key: &VerifyingKey) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(public_key.as_bytes());
-        let hash = hasher.finalize();
+    /// 保存私鑰到文件（僅擁有者可讀寫）
+    pub fn save_private_key<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_key_file(path.as_ref(), self.signing_key.as_bytes())
+    }
 
-        // 使用哈希的前 16 字節作為 ID
-        let id_bytes = &hash[..16];
-        format!("agent-{}", hex::encode(id_bytes))
+    /// 私鑰種子（供加密保存）
+    pub(crate) fn secret_bytes(&self) -> [u8; 32] {
+        self.signing_key.to_bytes()
     }
 
-    /// 保存私鑰到文件
-    pub fn save_private_key<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        fs::write(path, self.signing_key.as_bytes())?;
-        Ok(())
+    /// 私鑰種子的 base64 編碼（供備份與遷移）
+    pub fn private_key_base64(&self) -> String {
+        general_purpose::STANDARD.encode(self.signing_key.as_bytes())
     }
 
-    /// 獲取 Agent ID
+    /// 獲取 Agent ID（由公鑰推導）
     pub fn agent_id(&self) -> &str {
         &self.agent_id
     }
 
+    /// 獲取公鑰
+    pub fn public_key_bytes(&self) -> [u8; 32] {
+        self.verifying_key.to_bytes()
+    }
+
+    /// 私鑰的 PKCS#8 v1 DER 編碼（供 TLS 客戶端憑證使用）
+    pub(crate) fn private_key_pkcs8(&self) -> Vec<u8> {
+        // SEQUENCE { INTEGER 0, SEQUENCE { OID 1.3.101.112 }, OCTET STRING { OCTET STRING <seed> } }
+        const PREFIX: [u8; 16] = [
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+        ];
+
+        let mut der = PREFIX.to_vec();
+        der.extend_from_slice(self.signing_key.as_bytes());
+        der
+    }
+
     /// 獲取公鑰 (base64 編碼)
     pub fn public_key_base64(&self) -> String {
         general_purpose::STANDARD.encode(self.verifying_key.as_bytes())
@@ -80,6 +115,11 @@ impl Authenticator {
         general_purpose::STANDARD.encode(signature.to_bytes())
     }
 
+    /// 簽署任意內容，返回 base64 編碼的簽名
+    pub fn sign(&self, data: &[u8]) -> String {
+        general_purpose::STANDARD.encode(self.signing_key.sign(data).to_bytes())
+    }
+
     /// 驗證簽名
     pub fn verify_signature(&self, message: &[u8], signature: &str) -> Result<bool> {
         let signature_bytes = general_purpose::STANDARD.decode(signature)
@@ -94,6 +134,26 @@ impl Authenticator {
         }
     }
 
+    /// 使用指定公鑰 (base64 編碼) 驗證簽名
+    pub fn verify_with_public_key(public_key: &str, message: &[u8], signature: &str) -> Result<bool> {
+        let key_bytes = general_purpose::STANDARD.decode(public_key)
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+
+        let key_bytes: [u8; 32] = key_bytes.try_into()
+            .map_err(|_| Error::EncryptionError("Public key must be 32 bytes".to_string()))?;
+
+        let verifying_key = VerifyingKey::from_bytes(&key_bytes)
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+
+        let signature_bytes = general_purpose::STANDARD.decode(signature)
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+
+        let signature = Signature::from_slice(&signature_bytes)
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+
+        Ok(verifying_key.verify(message, &signature).is_ok())
+    }
+
     /// 響應認證挑戰
     pub fn respond_to_challenge(&self, challenge: &str) -> Result<(String, String)> {
         // 解碼挑戰
@@ -108,6 +168,67 @@ impl Authenticator {
     }
 }
 
+/// 檢查私鑰文件權限，群組或其他使用者可存取時返回錯誤
+#[cfg(unix)]
+pub fn check_key_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)?.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return Err(Error::InvalidConfig(format!(
+            "Private key {} is accessible by other users (mode {:03o}); run `chmod 600 {}`",
+            path.display(),
+            mode,
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// 檢查私鑰文件權限（非 Unix 平台依賴目錄的存取控制）
+#[cfg(not(unix))]
+pub fn check_key_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// 以擁有者可讀寫（0600）的權限寫入金鑰文件
+///
+/// 先寫入同目錄的暫存文件再改名，寫入中斷時不會留下不完整的私鑰
+pub(crate) fn write_key_file(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::InvalidConfig(format!("Invalid key path: {}", path.display())))?;
+    let temp = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let result = (|| {
+        let mut file = options.open(&temp)?;
+        // 暫存文件若已存在，open 不會改變其權限
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.map_err(Error::from)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,5 +262,59 @@ mod tests {
 
         assert!(!signature.is_empty());
         assert!(!public_key.is_empty());
+
+        let verified = Authenticator::verify_with_public_key(
+            &public_key,
+            b"random_challenge_data",
+            &signature,
+        ).unwrap();
+        assert!(verified);
+
+        let other = Authenticator::generate();
+        let verified = Authenticator::verify_with_public_key(
+            &other.public_key_base64(),
+            b"random_challenge_data",
+            &signature,
+        ).unwrap();
+        assert!(!verified);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_key_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.key");
+        let auth = Authenticator::generate();
+        auth.save_private_key(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+
+        let loaded = Authenticator::from_private_key_file(&path).unwrap();
+        assert_eq!(loaded.public_key_bytes(), auth.public_key_bytes());
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(matches!(
+            Authenticator::from_private_key_file(&path),
+            Err(Error::InvalidConfig(_))
+        ));
+
+        // 覆寫時恢復為僅擁有者可讀寫
+        auth.save_private_key(&path).unwrap();
+        assert!(Authenticator::from_private_key_file(&path).is_ok());
+    }
+
+    #[test]
+    fn test_private_key_base64_round_trip() {
+        let auth = Authenticator::generate();
+        let imported = Authenticator::from_private_key_base64(
+            &format!("{}\n", auth.private_key_base64()),
+        ).unwrap();
+
+        assert_eq!(imported.public_key_bytes(), auth.public_key_bytes());
+        assert_eq!(imported.agent_id(), auth.agent_id());
+        assert!(Authenticator::from_private_key_base64("c2hvcnQ=").is_err());
     }
 }
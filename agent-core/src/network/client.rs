@@ -1,117 +1,635 @@
 // Orban WebSocket 客戶端
+//
+// 設定 `transport = "grpc"` 時改經由 gRPC 雙向串流（見 grpc.rs），認證、會話恢復與送達確認相同
 
+use super::attestation;
 use super::auth::Authenticator;
-use super::orban_protocol::{Message, MessageType, MessagePayload, AgentStatus};
+use super::orban_protocol::{
+    Message, MessageType, MessagePayload, AgentStatus, ActiveTaskInfo, TaskErrorInfo, TaskMetrics,
+    KeyRotationPayload, MetricsBatchPayload, ProtocolFeature,
+};
+use super::codec::WireCodec;
+use super::compression::{Compression, CompressionStats};
+use super::connection::{Connection, Inbound, KeepaliveSettings, OutboundQueue, OutboundStats, WsStream};
+use super::correlation::{self, Correlator};
+use super::endpoints::{Endpoint, Endpoints};
+#[cfg(feature = "grpc")]
+use super::grpc::GrpcLink;
+use super::handshake::{self, NegotiatedProtocol};
+use super::inbound::InboundGuard;
+use super::latency::{LatencyStats, LatencyTracker};
+use super::error_policy::{self, codes, ErrorAction};
+use super::outbox::Outbox;
+use super::proxy::ProxySettings;
+use super::recorder::{Direction, SessionRecorder};
 use super::reconnect::ReconnectStrategy;
+use super::session::SessionToken;
+use super::signing::MessageSigner;
+use super::tls::TlsSettings;
+use super::transport::{LocalState, TransportKind};
 use crate::types::*;
 use crate::error::{Error, Result};
 use crate::AgentConfig;
 
-use tokio::net::TcpStream;
-use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream, tungstenite};
+use tokio_tungstenite::{client_async_with_config, tungstenite};
 use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
-use tokio_tungstenite::tungstenite::http;
 use tungstenite::client::IntoClientRequest;
+use tungstenite::protocol::WebSocketConfig;
 use futures::{StreamExt, SinkExt};
-use tracing::{info, warn, error};
+use tracing::{debug, info, warn, error};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+/// 入站通道容量
+const INBOUND_CAPACITY: usize = 256;
+
+/// 已建立、尚未啟動讀寫任務的連線
+enum Link {
+    WebSocket(WsStream),
+    #[cfg(feature = "grpc")]
+    Grpc(GrpcLink),
+}
+
+impl Link {
+    /// 送出認證期間的訊息
+    async fn send(&mut self, codec: WireCodec, msg: &Message) -> Result<()> {
+        match self {
+            Link::WebSocket(ws) => Ok(ws.send(codec.encode(msg)?).await?),
+            #[cfg(feature = "grpc")]
+            Link::Grpc(link) => link.send(msg).await,
+        }
+    }
+
+    /// 讀取下一則訊息（認證期間使用）
+    async fn next(&mut self) -> Result<Message> {
+        let ws = match self {
+            Link::WebSocket(ws) => ws,
+            #[cfg(feature = "grpc")]
+            Link::Grpc(link) => return link.next().await,
+        };
+
+        while let Some(msg) = ws.next().await {
+            match msg {
+                Ok(WsMessage::Close(_)) => {
+                    warn!("WebSocket closed by server");
+                    return Err(Error::ConnectionFailed("Connection closed".to_string()));
+                }
+                Ok(frame) => {
+                    if let Some(msg) = WireCodec::decode(&frame)? {
+                        return Ok(msg);
+                    }
+                }
+                Err(e) => {
+                    error!("WebSocket error: {}", e);
+                    return Err(Error::WebSocketError(e));
+                }
+            }
+        }
+
+        Err(Error::ConnectionFailed("Connection closed".to_string()))
+    }
+}
 
 /// Orban 客戶端
 #[derive(Clone)]
 pub struct OrbanClient {
     config: Arc<AgentConfig>,
+    endpoints: Arc<Endpoints>,
     authenticator: Arc<Authenticator>,
-    ws: Arc<Mutex<Option<WebSocketStream<MaybeTlsStream<TcpStream>>>>>,
+    backup_public_key: Option<String>,
+    signer: MessageSigner,
+    proxy: Arc<ProxySettings>,
+    tls: TlsSettings,
+    connection: Arc<Mutex<Option<Connection>>>,
+    generation: Arc<AtomicU64>,
+    outbound: Arc<OutboundQueue>,
+    keepalive: Option<KeepaliveSettings>,
+    latency: Arc<LatencyTracker>,
+    recorder: Arc<SessionRecorder>,
+    inbound_tx: mpsc::Sender<Inbound>,
+    events: Arc<Mutex<mpsc::Receiver<Inbound>>>,
+    correlator: Arc<Correlator>,
+    outbox: Arc<Outbox>,
     reconnect_strategy: Arc<Mutex<ReconnectStrategy>>,
-    jwt_token: Arc<Mutex<Option<String>>>,
+    jwt_token: Arc<Mutex<Option<SessionToken>>>,
+    protocol: Arc<Mutex<Option<NegotiatedProtocol>>>,
+    registration: Arc<Mutex<Option<Message>>>,
+    next_refresh: Arc<Mutex<Option<DateTime<Utc>>>>,
+    active_tasks: Arc<Mutex<HashMap<String, ActiveTaskInfo>>>,
+    last_heartbeat: Arc<Mutex<DateTime<Utc>>>,
 }
 
 impl OrbanClient {
     /// 創建新的客戶端
     pub async fn new(config: &AgentConfig) -> Result<Self> {
-        let authenticator = Authenticator::from_private_key_file(
-            &config.private_key_path,
-            config.agent_id.clone(),
-        )?;
+        Self::with_endpoints(config, Arc::new(Endpoints::from_config(config)?)).await
+    }
+
+    /// 使用共用的平台端點創建客戶端
+    pub async fn with_endpoints(config: &AgentConfig, endpoints: Arc<Endpoints>) -> Result<Self> {
+        Self::with_state(config, endpoints, LocalState::open(config)?).await
+    }
+
+    /// 使用共用的平台端點與已開啟的本地狀態創建客戶端
+    pub async fn with_state(config: &AgentConfig, endpoints: Arc<Endpoints>, state: LocalState) -> Result<Self> {
+        let LocalState { authenticator, outbox, backup_public_key } = state;
+        let signer = MessageSigner::from_config(&config.network.signing)?;
+        let proxy = ProxySettings::from_config(&config.network.proxy)?;
+        let tls = TlsSettings::from_config(&config.network.tls, &authenticator)?;
+
+        let reconnect_strategy = ReconnectStrategy::with_limits(
+            config.network.reconnect_max_retries,
+            Duration::from_millis(config.network.reconnect_base_delay_ms),
+            Duration::from_secs(config.network.reconnect_max_delay_secs),
+        );
+
+        let outbound = Arc::new(OutboundQueue::new(config.network.outbound_queue_capacity));
+        let correlator = Arc::new(Correlator::new(Duration::from_millis(config.network.ack_timeout_ms)));
+
+        let (inbound_tx, inbound_rx) = mpsc::channel(INBOUND_CAPACITY);
+        let (events_tx, events_rx) = mpsc::channel(INBOUND_CAPACITY);
+
+        tokio::spawn(dispatch(
+            inbound_rx,
+            events_tx,
+            InboundGuard::from_config(&config.network.inbound),
+            correlator.clone(),
+            outbox.clone(),
+            outbound.clone(),
+            Duration::from_secs(config.network.connection_timeout_secs),
+        ));
 
         Ok(Self {
             config: Arc::new(config.clone()),
-            authenticator: Arc::new(authenticator),
-            ws: Arc::new(Mutex::new(None)),
-            reconnect_strategy: Arc::new(Mutex::new(ReconnectStrategy::new())),
+            endpoints,
+            authenticator,
+            backup_public_key,
+            signer,
+            proxy: Arc::new(proxy),
+            tls,
+            connection: Arc::new(Mutex::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
+            outbound,
+            keepalive: KeepaliveSettings::from_config(&config.network),
+            latency: Arc::new(LatencyTracker::default()),
+            recorder: Arc::new(SessionRecorder::from_config(&config.network)?),
+            inbound_tx,
+            events: Arc::new(Mutex::new(events_rx)),
+            correlator,
+            outbox,
+            reconnect_strategy: Arc::new(Mutex::new(reconnect_strategy)),
             jwt_token: Arc::new(Mutex::new(None)),
+            protocol: Arc::new(Mutex::new(None)),
+            registration: Arc::new(Mutex::new(None)),
+            next_refresh: Arc::new(Mutex::new(None)),
+            active_tasks: Arc::new(Mutex::new(HashMap::new())),
+            last_heartbeat: Arc::new(Mutex::new(Utc::now())),
         })
     }
 
     /// 連接到平台
     pub async fn connect(&self) -> Result<()> {
-        info!("Connecting to Orban Platform at {}", self.config.platform_url);
+        let (link, protocol, token) = self.open_session().await?;
+        self.install_session(link, protocol, token).await;
 
-        let url = format!("{}/agent/v1/connect", self.config.platform_url);
+        // 重置重連策略
+        self.reconnect_strategy.lock().await.reset();
+
+        Ok(())
+    }
+
+    /// 傳輸方式名稱
+    pub fn transport_name(&self) -> &'static str {
+        match self.config.network.transport {
+            TransportKind::Grpc => "grpc",
+            _ => "websocket",
+        }
+    }
+
+    /// 建立新連線並完成認證，不影響目前的連線
+    async fn open_session(&self) -> Result<(Link, NegotiatedProtocol, SessionToken)> {
+        let endpoint = self.endpoints.current();
+        info!("Connecting to Orban Platform at {}", endpoint.url);
+
+        let (mut link, codec) = match self.config.network.transport {
+            #[cfg(feature = "grpc")]
+            TransportKind::Grpc => (Link::Grpc(self.open_grpc(&endpoint.url).await?), WireCodec::Protobuf),
+            _ => self.open_websocket(&endpoint.url).await?,
+        };
+
+        // 執行認證並協商協議
+        let (token, protocol) = self.authenticate(&mut link, codec).await?;
+
+        Ok((link, protocol, token))
+    }
+
+    /// 開啟 gRPC 串流，訊息一律以 Protocol Buffers 編碼
+    #[cfg(feature = "grpc")]
+    async fn open_grpc(&self, url: &str) -> Result<GrpcLink> {
+        GrpcLink::open(
+            url,
+            &self.config.network,
+            self.proxy.clone(),
+            &self.tls,
+            &self.outbound,
+            self.outbox.clone(),
+            self.recorder.clone(),
+        )
+        .await
+    }
+
+    /// 建立 WebSocket 連線，返回平台選定的編碼格式
+    async fn open_websocket(&self, url: &str) -> Result<(Link, WireCodec)> {
+        let url = format!("{}/agent/v1/connect", url.trim_end_matches('/'));
 
         // 使用 IntoClientRequest trait 添加子協議
-        let mut request = url.into_client_request()
+        let mut request = url.as_str().into_client_request()
             .map_err(|e| Error::ConnectionFailed(format!("Invalid URL: {}", e)))?;
 
-        // 添加子協議頭
+        // 添加子協議頭，依偏好順序提供所有支援的編碼格式
+        let offer = WireCodec::offer(self.config.network.codec);
         request.headers_mut().insert(
             "Sec-WebSocket-Protocol",
-            "agent.orban.v1".parse().unwrap()
+            offer.parse().unwrap()
         );
 
-        let (ws_stream, _) = connect_async(request)
+        // 需要時先經由代理建立通道，再於其上進行 TLS 與 WebSocket 握手
+        let target = reqwest::Url::parse(&url)
+            .map_err(|e| Error::ConnectionFailed(format!("Invalid URL: {}", e)))?;
+        let stream = self
+            .proxy
+            .connect(&target, Duration::from_secs(self.config.network.connection_timeout_secs))
+            .await?;
+        let stream = self.tls.wrap(stream, &target).await?;
+
+        // 超過上限的訊框不緩衝，讀取時即中止
+        let max_frame_bytes = self.config.network.inbound.max_frame_bytes;
+        let ws_config = WebSocketConfig {
+            max_message_size: Some(max_frame_bytes),
+            max_frame_size: Some(max_frame_bytes),
+            ..Default::default()
+        };
+
+        // 代理或防火牆拒絕升級時可改用 HTTP 長輪詢；TLS 驗證失敗則不改用
+        let (ws_stream, response) = client_async_with_config(request, stream, Some(ws_config))
             .await
-            .map_err(|e| Error::ConnectionFailed(e.to_string()))?;
+            .map_err(handshake_error)?;
 
-        info!("WebSocket connection established");
+        // 平台選定的子協議決定編碼格式
+        let codec = WireCodec::from_header(
+            response
+                .headers()
+                .get("Sec-WebSocket-Protocol")
+                .and_then(|v| v.to_str().ok()),
+        );
 
-        *self.ws.lock().await = Some(ws_stream);
+        info!("WebSocket connection established (subprotocol: {})", codec.subprotocol());
 
-        // 執行認證
-        self.authenticate().await?;
+        Ok((Link::WebSocket(ws_stream), codec))
+    }
 
-        // 重置重連策略
+    /// 關閉舊連線並切換到新連線
+    async fn install_session(&self, link: Link, protocol: NegotiatedProtocol, token: SessionToken) {
+        let codec = protocol.codec;
+        let compression = protocol.supports(ProtocolFeature::Compression).then(|| Compression {
+            threshold: self.config.network.compression_threshold_bytes,
+            level: self.config.network.compression_level,
+        });
+        *self.protocol.lock().await = Some(protocol);
+
+        let margin = Duration::from_secs(self.config.network.token_refresh_margin_secs);
+        *self.next_refresh.lock().await = Some(token.refresh_at(margin));
+        *self.jwt_token.lock().await = Some(token);
+
+        let mut connection = self.connection.lock().await;
+        if let Some(old) = connection.take() {
+            old.close().await;
+        }
+
+        // 舊連線的入站訊息自此被忽略
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        *connection = Some(match link {
+            Link::WebSocket(ws) => Connection::spawn(
+                ws,
+                codec,
+                compression,
+                generation,
+                &self.outbound,
+                self.outbox.clone(),
+                self.inbound_tx.clone(),
+                self.keepalive,
+                self.latency.clone(),
+                self.config.network.inbound.max_frame_bytes,
+                self.recorder.clone(),
+            ),
+            #[cfg(feature = "grpc")]
+            Link::Grpc(link) => link.spawn(generation, self.inbound_tx.clone(), self.recorder.clone()),
+        });
+    }
+
+    /// 關閉目前的連線
+    async fn close_connection(&self) {
+        if let Some(old) = self.connection.lock().await.take() {
+            old.close().await;
+        }
+    }
+
+    /// 斷線重連並恢復會話
+    ///
+    /// 依重連策略退避重試，重新認證後發送 StateSync 回報進行中的任務，
+    /// 平台據此保留任務而不重新註冊（平台不支援 StateSync 時重新註冊）。
+    /// 連續失敗達門檻時切換平台端點，切換後以新端點的區域重新註冊。
+    /// 認證被拒或重試次數用盡時返回錯誤
+    pub async fn reconnect(&self) -> Result<()> {
+        // 丟棄已失效的連線
+        self.close_connection().await;
+
+        let mut failover = None;
+        loop {
+            let delay = self.reconnect_strategy.lock().await.next_delay();
+            let delay = delay.ok_or_else(|| {
+                Error::ConnectionFailed("Max reconnection attempts reached".to_string())
+            })?;
+
+            tokio::time::sleep(delay).await;
+
+            match self.connect().await {
+                Ok(()) => {
+                    self.endpoints.record_success();
+                    break;
+                }
+                Err(e @ Error::AuthenticationFailed(_)) => return Err(e),
+                Err(e) => {
+                    warn!("Reconnection failed: {}", e);
+
+                    if let Some(endpoint) = self.endpoints.record_failure() {
+                        failover = Some(endpoint);
+                        // 新端點不需等待舊端點累積的退避
+                        self.reconnect_strategy.lock().await.reset();
+                    }
+                }
+            }
+        }
+
+        match failover {
+            Some(endpoint) => self.register_at(&endpoint).await?,
+            None => self.resume_session().await?,
+        }
+
+        // 下次斷線重新計算重試次數
         self.reconnect_strategy.lock().await.reset();
 
+        info!("Session resumed");
         Ok(())
     }
 
-    /// 認證
-    async fn authenticate(&self) -> Result<()> {
+    /// 在令牌過期前重新認證
+    ///
+    /// 先在新連線上完成認證，再切換並關閉舊連線，進行中的任務不受影響。
+    /// 新連線失敗時保留舊連線並稍後重試，令牌過期後改為完整重連
+    async fn refresh_session(&self) -> Result<()> {
+        info!("Session token expiring, re-authenticating...");
+
+        match self.open_session().await {
+            Ok((link, protocol, token)) => {
+                let expires_at = token.expires_at();
+
+                self.install_session(link, protocol, token).await;
+                self.reconnect_strategy.lock().await.reset();
+
+                if let Err(e) = self.resume_session().await {
+                    warn!("Failed to sync state after re-authentication: {}", e);
+                    return self.reconnect().await;
+                }
+
+                info!("Session refreshed, token valid until {}", expires_at);
+                Ok(())
+            }
+            Err(e @ Error::AuthenticationFailed(_)) => Err(e),
+            Err(e) => {
+                warn!("Re-authentication failed: {}", e);
+
+                let expired = self.jwt_token.lock().await.as_ref().is_none_or(|t| t.is_expired());
+                let delay = self.reconnect_strategy.lock().await.next_delay();
+
+                match delay {
+                    Some(delay) if !expired => {
+                        let delay = chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero());
+                        *self.next_refresh.lock().await = Some(Utc::now() + delay);
+                        Ok(())
+                    }
+                    _ => self.reconnect().await,
+                }
+            }
+        }
+    }
+
+    /// 恢復會話
+    ///
+    /// 平台支援時發送 StateSync，否則重送上次的註冊
+    async fn resume_session(&self) -> Result<()> {
+        let state_sync = self
+            .protocol
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|p| p.supports(ProtocolFeature::StateSync));
+
+        if state_sync {
+            return self.sync_state().await;
+        }
+
+        let registration = self.registration.lock().await.clone();
+        match registration {
+            Some(previous) => {
+                info!("Platform does not support StateSync, registering again");
+                let msg = Message::new(previous.message_type, previous.payload);
+                self.send_registration(&msg).await?;
+                self.replay_outbox().await
+            }
+            None => self.replay_outbox().await,
+        }
+    }
+
+    /// 切換端點後以新端點的區域重新註冊，並同步進行中的任務
+    async fn register_at(&self, endpoint: &Endpoint) -> Result<()> {
+        let registration = {
+            let mut registration = self.registration.lock().await;
+            if let (Some(msg), Some(region)) = (registration.as_mut(), &endpoint.region) {
+                if let MessagePayload::AgentRegister(payload) = &mut msg.payload {
+                    payload.location.region = region.clone();
+                }
+            }
+            registration.clone()
+        };
+
+        let Some(previous) = registration else {
+            return self.resume_session().await;
+        };
+
+        info!("Registering with failover endpoint {}", endpoint.url);
+        let msg = Message::new(previous.message_type, previous.payload);
+        self.send_registration(&msg).await?;
+
+        let state_sync = self
+            .protocol
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|p| p.supports(ProtocolFeature::StateSync));
+        // StateSync 之後會重送發件匣
+        if state_sync && !self.active_tasks.lock().await.is_empty() {
+            return self.sync_state().await;
+        }
+
+        self.replay_outbox().await
+    }
+
+    /// 發送狀態同步
+    async fn sync_state(&self) -> Result<()> {
+        let active_tasks: Vec<ActiveTaskInfo> =
+            self.active_tasks.lock().await.values().cloned().collect();
+
+        info!("Syncing state with {} active task(s)", active_tasks.len());
+
+        let msg = super::orban_protocol::create_state_sync(
+            self.authenticator.agent_id().to_string(),
+            *self.last_heartbeat.lock().await,
+            active_tasks,
+        );
+
+        self.send_message(&msg).await?;
+
+        // 斷線期間保存及舊連線上可能遺失的訊息
+        self.replay_outbox().await
+    }
+
+    /// 依序重送發件匣中的訊息
+    ///
+    /// 仍在出站佇列中的訊息可能因此重複送出，平台依 message_id 去重
+    async fn replay_outbox(&self) -> Result<()> {
+        let pending = self.outbox.pending()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        info!("Replaying {} outbox message(s)", pending.len());
+
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+        for msg in pending {
+            self.outbound.push(msg.clone(), timeout).await?;
+            if correlation::requires_ack(msg.message_type) {
+                self.correlator.track(&msg);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 認證並協商協議版本與功能
+    async fn authenticate(&self, link: &mut Link, codec: WireCodec) -> Result<(SessionToken, NegotiatedProtocol)> {
         info!("Authenticating with platform...");
 
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+
         // 接收認證挑戰
-        let challenge_msg = self.receive_message().await?;
+        let challenge = tokio::time::timeout(timeout, async {
+            loop {
+                let msg = self.read_message(link).await?;
+                match msg.payload {
+                    MessagePayload::AuthChallenge(challenge) => return Ok(challenge),
+                    MessagePayload::Error(err) => return Err(Self::rejected(&err.code, &err.message)),
+                    _ => warn!("Ignoring {:?} message before auth challenge", msg.message_type),
+                }
+            }
+        })
+        .await
+        .map_err(|_| Error::RequestTimeout("auth challenge".to_string()))??;
 
-        if let MessagePayload::AuthChallenge(challenge) = challenge_msg.payload {
-            // 響應挑戰
-            let (signature, public_key) = self
-                .authenticator
-                .respond_to_challenge(&challenge.challenge)?;
+        // 響應挑戰
+        let (signature, public_key) = self
+            .authenticator
+            .respond_to_challenge(&challenge.challenge)?;
 
-            let response = super::orban_protocol::create_auth_response(
-                self.authenticator.agent_id().to_string(),
-                signature,
-                public_key,
-            );
+        let response = super::orban_protocol::create_auth_response(
+            self.authenticator.agent_id().to_string(),
+            signature,
+            public_key,
+            Some(handshake::offer(codec, &self.offered_features())),
+        );
 
-            self.send_message(&response).await?;
+        link.send(codec, &response).await?;
+        self.recorder.record(Direction::Outbound, &response);
+        let sent_at = std::time::Instant::now();
 
-            // 接收認證成功
-            let auth_success = self.receive_message().await?;
+        // 接收認證結果
+        let auth_success = tokio::time::timeout(timeout, self.read_reply(link, &response))
+            .await
+            .map_err(|_| Error::RequestTimeout(format!("{:?} {}", response.message_type, response.message_id)))??;
 
-            if let MessagePayload::AuthSuccess(success) = auth_success.payload {
-                *self.jwt_token.lock().await = Some(success.jwt_token);
-                info!("Authentication successful");
-                Ok(())
-            } else {
-                Err(Error::AuthenticationFailed("Invalid response".to_string()))
+        // 認證往返即為首筆延遲樣本，註冊時可回報
+        self.latency.record(sent_at.elapsed());
+
+        match auth_success.payload {
+            MessagePayload::AuthSuccess(success) => {
+                let token = SessionToken::parse(
+                    &success.jwt_token,
+                    success.expires_in,
+                    self.authenticator.agent_id(),
+                )?;
+                info!("Authentication successful, token valid until {}", token.expires_at());
+
+                let protocol = handshake::accept(success.protocol, codec, &self.offered_features())?;
+                info!(
+                    "Using protocol v{} ({}), features: {:?}",
+                    protocol.version,
+                    protocol.codec.subprotocol(),
+                    protocol.features
+                );
+
+                Ok((token, protocol))
             }
-        } else {
-            Err(Error::AuthenticationFailed(
-                "Expected auth challenge".to_string(),
-            ))
+            MessagePayload::Error(err) if err.code == codes::UNSUPPORTED_VERSION => Err(Error::ProtocolError(format!(
+                "Platform does not support protocol versions {:?}: {}",
+                handshake::SUPPORTED_PROTOCOL_VERSIONS,
+                err.message
+            ))),
+            MessagePayload::Error(err) => Err(Self::rejected(&err.code, &err.message)),
+            _ => Err(Error::AuthenticationFailed("Invalid response".to_string())),
+        }
+    }
+
+    /// 平台拒絕認證或會話
+    pub(super) fn rejected(code: &str, message: &str) -> Error {
+        Error::AuthenticationFailed(format!("Rejected by platform ({}): {}", code, message))
+    }
+
+    /// 配置中啟用的可選協議功能，gRPC 由 HTTP/2 分框，不提供壓縮
+    fn offered_features(&self) -> Vec<ProtocolFeature> {
+        let compression = self.config.network.compression && self.config.network.transport != TransportKind::Grpc;
+
+        handshake::AGENT_FEATURES
+            .iter()
+            .copied()
+            .filter(|feature| *feature != ProtocolFeature::Compression || compression)
+            .collect()
+    }
+
+    /// 目前連線協商的協議，尚未連線時為 `None`
+    pub async fn protocol(&self) -> Option<NegotiatedProtocol> {
+        self.protocol.lock().await.clone()
+    }
+
+    /// 目前有效的會話令牌，供需要認證的 REST 請求使用
+    pub async fn session_token(&self) -> Result<String> {
+        match self.jwt_token.lock().await.as_ref() {
+            Some(token) if !token.is_expired() => Ok(token.as_str().to_string()),
+            Some(_) => Err(Error::AuthenticationFailed("Session token expired".to_string())),
+            None => Err(Error::AuthenticationFailed("Not authenticated".to_string())),
         }
     }
 
@@ -119,6 +637,7 @@ impl OrbanClient {
     pub async fn register(
         &self,
         hardware: HardwareInfo,
+        manifest: HardwareManifest,
         capabilities: Capabilities,
         location: Location,
         availability: Availability,
@@ -127,77 +646,265 @@ impl OrbanClient {
 
         let msg = super::orban_protocol::create_agent_register(
             self.authenticator.agent_id().to_string(),
+            self.config.display_name.clone(),
+            self.backup_public_key.clone(),
+            Some(attestation::sign(&self.authenticator, manifest)?),
             hardware,
             capabilities,
             location,
             availability,
         );
 
-        self.send_message(&msg).await?;
+        *self.registration.lock().await = Some(msg.clone());
+        self.send_registration(&msg).await?;
+
+        // 上次運行未送達的訊息
+        self.replay_outbox().await
+    }
 
-        // 接收註冊確認
-        let ack = self.receive_message().await?;
+    /// 發送註冊並等待確認
+    async fn send_registration(&self, msg: &Message) -> Result<()> {
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+        let reply = self.request(msg, timeout).await?;
 
-        if matches!(ack.message_type, MessageType::RegisterAck) {
-            info!("Agent registered successfully");
-            Ok(())
-        } else {
-            Err(Error::Unknown("Registration failed".to_string()))
+        match reply.payload {
+            MessagePayload::RegisterAck(_) => {
+                info!("Agent registered successfully");
+                *self.last_heartbeat.lock().await = Utc::now();
+                Ok(())
+            }
+            MessagePayload::Error(err) => Err(Error::ProtocolError(format!(
+                "Registration rejected ({}): {}",
+                err.code, err.message
+            ))),
+            _ => Err(Error::ProtocolError(format!(
+                "Unexpected reply to registration: {:?}",
+                reply.message_type
+            ))),
         }
     }
 
     /// 發送訊息
+    ///
+    /// 訊息加入出站佇列後即返回，由寫入任務送出；
+    /// 佇列已滿時最多等待連接超時，之後返回錯誤。
+    /// 任務結果、進度與指標先寫入發件匣，未連線時保留到重連後送出；
+    /// 啟用簽名時在寫入發件匣前簽署
     pub async fn send_message(&self, msg: &Message) -> Result<()> {
-        let json = msg.to_json()?;
+        let msg = self.signer.sign(msg, &self.authenticator)?;
+        let msg = msg.as_ref();
 
-        if let Some(ws) = self.ws.lock().await.as_mut() {
-            ws.send(WsMessage::Text(json))
-                .await
-                .map_err(|e| Error::WebSocketError(e))?;
-            Ok(())
-        } else {
-            Err(Error::ConnectionFailed("Not connected".to_string()))
+        let durable = Outbox::stores(msg.message_type);
+        if durable && !self.outbox.persist(msg)? {
+            debug!("{:?} message {} already in outbox", msg.message_type, msg.message_id);
+            return Ok(());
         }
+
+        if self.connection.lock().await.is_none() {
+            if durable {
+                info!("Not connected, {:?} message {} kept in outbox", msg.message_type, msg.message_id);
+                return Ok(());
+            }
+            return Err(Error::ConnectionFailed("Not connected".to_string()));
+        }
+
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+        if let Err(e) = self.outbound.push(msg.clone(), timeout).await {
+            if !durable {
+                return Err(e);
+            }
+            warn!("{:?} message {} kept in outbox: {}", msg.message_type, msg.message_id, e);
+        }
+
+        if correlation::requires_ack(msg.message_type) {
+            self.correlator.track(msg);
+        }
+
+        Ok(())
     }
 
-    /// 接收訊息
-    pub async fn receive(&self) -> Option<Message> {
-        match self.receive_message().await {
-            Ok(msg) => Some(msg),
-            Err(e) => {
-                error!("Failed to receive message: {}", e);
-                None
+    /// 發送請求並等待平台回覆
+    ///
+    /// 回覆以 `in_reply_to` 關聯，期間收到的其他訊息照常交由事件循環
+    pub async fn request(&self, msg: &Message, timeout: Duration) -> Result<Message> {
+        let reply = self.correlator.expect_reply(&msg.message_id);
+
+        if let Err(e) = self.send_message(msg).await {
+            self.correlator.cancel(&msg.message_id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(timeout, reply).await {
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(_)) => Err(Error::ConnectionFailed("Request cancelled".to_string())),
+            Err(_) => {
+                self.correlator.cancel(&msg.message_id);
+                Err(Error::RequestTimeout(format!("{:?} {}", msg.message_type, msg.message_id)))
             }
         }
     }
 
-    /// 接收訊息 (內部)
-    async fn receive_message(&self) -> Result<Message> {
-        if let Some(ws) = self.ws.lock().await.as_mut() {
-            while let Some(msg) = ws.next().await {
-                match msg {
-                    Ok(WsMessage::Text(text)) => {
-                        tracing::debug!("Received message: {}", text);
-                        return Message::from_json(&text);
-                    }
-                    Ok(WsMessage::Binary(data)) => {
-                        // TODO: 支援 protobuf
-                        warn!("Received binary message (protobuf not yet supported)");
-                    }
-                    Ok(WsMessage::Close(_)) => {
-                        warn!("WebSocket closed by server");
-                        return Err(Error::ConnectionFailed("Connection closed".to_string()));
-                    }
-                    Err(e) => {
-                        error!("WebSocket error: {}", e);
-                        return Err(Error::WebSocketError(e));
+    /// 送出金鑰輪換聲明，平台以 ACK 確認或以 ERROR 拒絕
+    pub async fn rotate_key(&self, rotation: KeyRotationPayload) -> Result<()> {
+        let msg = super::orban_protocol::create_key_rotation(rotation);
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+
+        error_policy::ack_or_platform_error(self.request(&msg, timeout).await?, "key rotation")
+    }
+
+    /// 硬體變更時重新送出簽名的硬體清單，平台以 ACK 確認或以 ERROR 拒絕
+    ///
+    /// 之後重新註冊時沿用新的清單
+    pub async fn attest_hardware(&self, manifest: HardwareManifest) -> Result<()> {
+        let attestation = attestation::sign(&self.authenticator, manifest)?;
+        let msg = super::orban_protocol::create_hardware_attestation(attestation.clone());
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+
+        error_policy::ack_or_platform_error(
+            self.request(&msg, timeout).await?,
+            "hardware attestation",
+        )?;
+
+        // 平台接受後才用於重新註冊，被拒絕的清單不隨註冊送出
+        if let Some(MessagePayload::AgentRegister(registration)) = self
+            .registration
+            .lock()
+            .await
+            .as_mut()
+            .map(|msg| &mut msg.payload)
+        {
+            registration.attestation = Some(attestation);
+        }
+        Ok(())
+    }
+
+    /// 出站佇列統計
+    pub fn outbound_stats(&self) -> OutboundStats {
+        self.outbound.stats()
+    }
+
+    /// 平台端點
+    pub fn endpoints(&self) -> Arc<Endpoints> {
+        self.endpoints.clone()
+    }
+
+    /// 已開啟的身分金鑰、發件匣與身分記錄
+    pub fn local_state(&self) -> LocalState {
+        LocalState {
+            authenticator: self.authenticator.clone(),
+            outbox: self.outbox.clone(),
+            backup_public_key: self.backup_public_key.clone(),
+        }
+    }
+
+    /// 與平台之間的往返時間統計
+    pub fn latency(&self) -> LatencyStats {
+        self.latency.snapshot()
+    }
+
+    /// 壓縮統計
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.outbound.compression_stats()
+    }
+
+    /// 發件匣中待送達的訊息數
+    pub fn outbox_depth(&self) -> usize {
+        self.outbox.len()
+    }
+
+    /// 接收訊息
+    ///
+    /// 連線中斷時自動重連並恢復會話，令牌即將過期時重新認證。
+    /// 僅在無法恢復（重連次數用盡、平台拒絕會話）時返回錯誤
+    pub async fn receive(&self) -> Result<Message> {
+        loop {
+            let refresh_at = *self.next_refresh.lock().await;
+            let refresh_in = refresh_at
+                .map(|at| (at - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+                .unwrap_or(Duration::MAX);
+
+            let received = tokio::select! {
+                received = self.receive_message() => received,
+                _ = tokio::time::sleep(refresh_in) => {
+                    self.refresh_session().await?;
+                    continue;
+                }
+            };
+
+            match received {
+                Ok(msg) => {
+                    // 平台拒絕目前的會話
+                    if let MessagePayload::Error(err) = &msg.payload {
+                        match ErrorAction::for_platform_error(err) {
+                            ErrorAction::Reauthenticate => {
+                                warn!("Session rejected by platform, re-authenticating: {}", err.message);
+                                self.refresh_session().await?;
+                                continue;
+                            }
+                            ErrorAction::Shutdown if err.code == codes::AUTH_FAILED => {
+                                self.jwt_token.lock().await.take();
+                                return Err(Self::rejected(&err.code, &err.message));
+                            }
+                            _ => {}
+                        }
                     }
-                    _ => {}
+
+                    return Ok(msg);
+                }
+                Err(e @ (Error::ConnectionFailed(_) | Error::WebSocketError(_))) => {
+                    warn!("Connection lost: {}", e);
+                    self.reconnect().await?;
+                }
+                Err(Error::InboundRejected(violation)) => {
+                    warn!("Dropping inbound message: {}", violation);
+                }
+                Err(e) => {
+                    error!("Failed to receive message: {}", e);
                 }
             }
         }
+    }
+
+    /// 接收目前連線的下一則訊息 (內部)
+    async fn receive_message(&self) -> Result<Message> {
+        if self.connection.lock().await.is_none() {
+            return Err(Error::ConnectionFailed("Not connected".to_string()));
+        }
+
+        let mut events = self.events.lock().await;
+
+        loop {
+            let (generation, item) = events
+                .recv()
+                .await
+                .ok_or_else(|| Error::ConnectionFailed("Inbound channel closed".to_string()))?;
+
+            if generation == self.generation.load(Ordering::SeqCst) {
+                return item;
+            }
+        }
+    }
 
-        Err(Error::ConnectionFailed("Not connected".to_string()))
+    /// 從尚未拆分的連線讀取下一則訊息（認證期間使用）
+    async fn read_message(&self, link: &mut Link) -> Result<Message> {
+        let msg = link.next().await?;
+        tracing::debug!("Received {:?} message {}", msg.message_type, msg.message_id);
+        self.recorder.record(Direction::Inbound, &msg);
+        Ok(msg)
+    }
+
+    /// 從尚未拆分的連線讀取指定請求的回覆，略過其他訊息
+    async fn read_reply(&self, link: &mut Link, request: &Message) -> Result<Message> {
+        loop {
+            let msg = self.read_message(link).await?;
+
+            let is_reply = msg.in_reply_to.as_deref() == Some(request.message_id.as_str());
+            if is_reply || (msg.in_reply_to.is_none() && msg.message_type == MessageType::Error) {
+                return Ok(msg);
+            }
+
+            warn!("Ignoring {:?} message while waiting for reply", msg.message_type);
+        }
     }
 
     /// 發送心跳
@@ -216,7 +923,9 @@ impl OrbanClient {
             uptime_sec,
         );
 
-        self.send_message(&msg).await
+        self.send_message(&msg).await?;
+        *self.last_heartbeat.lock().await = Utc::now();
+        Ok(())
     }
 
     /// 接受任務
@@ -228,9 +937,65 @@ impl OrbanClient {
             0,
         );
 
+        self.send_message(&msg).await?;
+
+        self.active_tasks.lock().await.insert(
+            task_id.to_string(),
+            ActiveTaskInfo {
+                task_id: task_id.to_string(),
+                progress: 0.0,
+                started_at: Utc::now(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// 更新進行中任務的進度（重連時透過 StateSync 回報）
+    pub async fn update_task_progress(&self, task_id: &str, progress: f32) {
+        if let Some(task) = self.active_tasks.lock().await.get_mut(task_id) {
+            task.progress = progress.clamp(0.0, 1.0);
+        }
+    }
+
+    /// 回報任務進度
+    pub async fn send_task_progress(
+        &self,
+        task_id: &str,
+        progress: f32,
+        stage: &str,
+        metrics: TaskMetrics,
+    ) -> Result<()> {
+        self.update_task_progress(task_id, progress).await;
+
+        let msg = super::orban_protocol::create_task_progress(
+            task_id.to_string(),
+            progress.clamp(0.0, 1.0),
+            stage.to_string(),
+            metrics,
+        );
+
         self.send_message(&msg).await
     }
 
+    /// 上報批次指標
+    pub async fn send_metrics_batch(&self, metrics: MetricsBatchPayload) -> Result<()> {
+        let msg = Message::new(MessageType::MetricsBatch, MessagePayload::MetricsBatch(metrics));
+        self.send_message(&msg).await
+    }
+
+    /// 進行中的任務
+    pub async fn active_tasks(&self) -> Vec<ActiveTaskInfo> {
+        self.active_tasks.lock().await.values().cloned().collect()
+    }
+
+    /// 中止平台已取消的任務，不再於心跳與狀態同步中回報
+    pub async fn abort_task(&self, task_id: &str) {
+        if self.active_tasks.lock().await.remove(task_id).is_some() {
+            info!("Task {} aborted", task_id);
+        }
+    }
+
     /// 拒絕任務
     pub async fn reject_task(&self, task_id: &str, reason: &str) -> Result<()> {
         let msg = super::orban_protocol::create_task_reject(
@@ -243,44 +1008,158 @@ impl OrbanClient {
     }
 
     /// 完成任務
-    pub async fn complete_task(&self, task_id: &str, result: TaskResult) -> Result<()> {
-        // TODO: 實現完整的任務完成訊息
+    ///
+    /// 平台確認前定期重送
+    pub async fn complete_task(
+        &self,
+        task_id: &str,
+        result: TaskResult,
+        proof_of_work: ProofOfWork,
+        metrics: ExecutionMetrics,
+    ) -> Result<()> {
+        let msg = super::orban_protocol::create_task_complete(
+            task_id.to_string(),
+            result,
+            proof_of_work,
+            metrics,
+        );
+
+        self.send_message(&msg).await?;
+        self.active_tasks.lock().await.remove(task_id);
         info!("Task {} completed", task_id);
         Ok(())
     }
 
-    /// 發送 PoW 響應
-    pub async fn send_pow_response(&self, response: crate::gpu::PowResponse) -> Result<()> {
-        use super::orban_protocol::{Message, MessageType, MessagePayload, PowResponsePayload, GpuSignature};
+    /// 回報任務失敗
+    ///
+    /// 平台確認前定期重送
+    pub async fn fail_task(&self, task_id: &str, error: TaskErrorInfo) -> Result<()> {
+        let msg = super::orban_protocol::create_task_failed(task_id.to_string(), error, None);
 
-        info!("Sending PoW response for challenge: {}", response.challenge_id);
+        self.send_message(&msg).await?;
+        self.active_tasks.lock().await.remove(task_id);
+        warn!("Task {} failed", task_id);
+        Ok(())
+    }
 
-        let payload = PowResponsePayload {
-            challenge_id: response.challenge_id,
-            response: hex::encode(response.response),  // Convert Vec<u8> to hex string
-            computation_time_ms: response.computation_time_ms as u32,
-            gpu_signature: GpuSignature {
-                device_uuid: response.gpu_signature.device_uuid,
-                cuda_version: response.gpu_signature.cuda_version,
-            },
-        };
+    /// 尚未獲平台確認的訊息數
+    pub fn unacked_messages(&self) -> usize {
+        self.correlator.unacked_count()
+    }
 
-        let msg = Message {
-            message_id: uuid::Uuid::new_v4().to_string(),
-            timestamp: chrono::Utc::now(),
-            message_type: MessageType::PowResponse,
-            payload: MessagePayload::PowResponse(payload),
-        };
+    /// 發送 PoW 響應
+    pub async fn send_pow_response(&self, response: crate::gpu::PowResponse) -> Result<()> {
+        info!("Sending PoW response for challenge: {}", response.challenge_id);
 
+        let msg = super::orban_protocol::create_pow_response(response);
         self.send_message(&msg).await
     }
 
     /// 斷線
     pub async fn disconnect(&self) -> Result<()> {
-        if let Some(mut ws) = self.ws.lock().await.take() {
-            ws.close(None).await.ok();
+        if let Some(connection) = self.connection.lock().await.take() {
+            connection.close().await;
             info!("Disconnected from platform");
         }
         Ok(())
     }
 }
+
+/// WebSocket 握手失敗的錯誤分類
+///
+/// 只有平台以非 101 回應拒絕升級才視為升級失敗（`auto` 模式改用 HTTP 長輪詢）；
+/// 401 為認證失敗，5xx、連線中斷與協議錯誤為一般連線失敗，交由重連處理
+fn handshake_error(err: tungstenite::Error) -> Error {
+    match err {
+        tungstenite::Error::Http(response) => {
+            let status = response.status();
+            let reason = format!("WebSocket upgrade rejected: HTTP {}", status);
+            if status == tungstenite::http::StatusCode::UNAUTHORIZED {
+                Error::AuthenticationFailed(reason)
+            } else if status.is_server_error() {
+                Error::ConnectionFailed(reason)
+            } else {
+                Error::UpgradeFailed(reason)
+            }
+        }
+        e => Error::ConnectionFailed(format!("WebSocket handshake failed: {}", e)),
+    }
+}
+
+/// 分派任務
+///
+/// 檢查入站訊息、交付回覆與送達確認、重送逾時未確認的訊息，其餘入站訊息送往事件循環。
+/// 防護狀態隨客戶端保留，重連後重放舊連線的訊息同樣被拒絕
+async fn dispatch(
+    mut inbound: mpsc::Receiver<Inbound>,
+    events: mpsc::Sender<Inbound>,
+    guard: InboundGuard,
+    correlator: Arc<Correlator>,
+    outbox: Arc<Outbox>,
+    outbound: Arc<OutboundQueue>,
+    send_timeout: Duration,
+) {
+    loop {
+        let retransmit_at = correlator.next_retransmit();
+
+        tokio::select! {
+            item = inbound.recv() => {
+                let Some((generation, item)) = item else {
+                    return;
+                };
+                let item = item.and_then(|msg| guard.check(&msg).map(|_| msg));
+
+                // 平台的任何回覆都表示原訊息已送達
+                if let Ok(Message { in_reply_to: Some(request_id), .. }) = &item {
+                    outbox.remove(request_id);
+                }
+
+                let item = match item {
+                    Ok(msg) => match correlator.resolve(msg) {
+                        Some(msg) => Ok(msg),
+                        None => continue,
+                    },
+                    Err(e) => Err(e),
+                };
+
+                if events.send((generation, item)).await.is_err() {
+                    return;
+                }
+            }
+            _ = tokio::time::sleep_until(retransmit_at.unwrap_or_else(tokio::time::Instant::now)), if retransmit_at.is_some() => {
+                for msg in correlator.due() {
+                    if let Err(e) = outbound.push(msg, send_timeout).await {
+                        warn!("Failed to retransmit message: {}", e);
+                    }
+                }
+            }
+            _ = correlator.rescheduled() => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tungstenite::http::Response;
+
+    fn rejected(status: u16) -> Error {
+        handshake_error(tungstenite::Error::Http(Response::builder().status(status).body(None).unwrap()))
+    }
+
+    #[test]
+    fn test_handshake_error_classification() {
+        assert!(matches!(rejected(200), Error::UpgradeFailed(_)));
+        assert!(matches!(rejected(403), Error::UpgradeFailed(_)));
+        assert!(matches!(rejected(426), Error::UpgradeFailed(_)));
+        assert!(matches!(rejected(401), Error::AuthenticationFailed(_)));
+        assert!(matches!(rejected(503), Error::ConnectionFailed(_)));
+
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(matches!(handshake_error(tungstenite::Error::Io(reset)), Error::ConnectionFailed(_)));
+        assert!(matches!(
+            handshake_error(tungstenite::Error::Protocol(tungstenite::error::ProtocolError::WrongHttpMethod)),
+            Error::ConnectionFailed(_)
+        ));
+    }
+}
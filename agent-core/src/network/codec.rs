@@ -0,0 +1,201 @@
+// 訊息編碼格式
+//
+// 編碼格式透過 WebSocket 子協議協商：
+// - `agent.orban.v1`        JSON 文字訊框
+// - `agent.orban.v1+proto`  Protocol Buffers 二進制訊框
+//
+// 協商壓縮後，較大的訊息以 zstd 壓縮為二進制訊框（見 compression 模組），兩種格式皆適用
+
+use super::compression::{self, Compression};
+use super::inbound::InboundViolation;
+use super::orban_protocol::Message;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
+
+/// JSON 子協議
+pub const SUBPROTOCOL_JSON: &str = "agent.orban.v1";
+
+/// Protocol Buffers 子協議
+pub const SUBPROTOCOL_PROTOBUF: &str = "agent.orban.v1+proto";
+
+/// 訊息編碼格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireCodec {
+    Json,
+    #[default]
+    Protobuf,
+}
+
+impl WireCodec {
+    /// 對應的 WebSocket 子協議
+    pub fn subprotocol(&self) -> &'static str {
+        match self {
+            WireCodec::Json => SUBPROTOCOL_JSON,
+            WireCodec::Protobuf => SUBPROTOCOL_PROTOBUF,
+        }
+    }
+
+    /// 從子協議名稱解析
+    pub fn from_subprotocol(name: &str) -> Option<Self> {
+        match name.trim() {
+            SUBPROTOCOL_JSON => Some(WireCodec::Json),
+            SUBPROTOCOL_PROTOBUF => Some(WireCodec::Protobuf),
+            _ => None,
+        }
+    }
+
+    /// 從 `Sec-WebSocket-Protocol` 回應頭解析
+    ///
+    /// 平台未回應子協議時視為舊版平台，使用 JSON
+    pub fn from_header(header: Option<&str>) -> Self {
+        header
+            .and_then(Self::from_subprotocol)
+            .unwrap_or(WireCodec::Json)
+    }
+
+    /// 所有支援的編碼格式，偏好的格式在前
+    pub fn preference(preferred: WireCodec) -> [WireCodec; 2] {
+        match preferred {
+            WireCodec::Protobuf => [WireCodec::Protobuf, WireCodec::Json],
+            WireCodec::Json => [WireCodec::Json, WireCodec::Protobuf],
+        }
+    }
+
+    /// 客戶端提供的子協議列表 (依偏好排序)
+    pub fn offer(preferred: WireCodec) -> String {
+        Self::preference(preferred).map(|codec| codec.subprotocol()).join(", ")
+    }
+
+    /// 伺服端從客戶端提供的列表中選擇子協議
+    ///
+    /// 依客戶端的偏好順序選擇第一個支援的格式
+    pub fn select(offered: &str) -> Option<Self> {
+        offered.split(',').find_map(Self::from_subprotocol)
+    }
+
+    /// 編碼訊息為 WebSocket 訊框
+    pub fn encode(&self, msg: &Message) -> Result<WsMessage> {
+        match self {
+            WireCodec::Json => Ok(WsMessage::Text(msg.to_json()?)),
+            WireCodec::Protobuf => Ok(WsMessage::Binary(msg.to_protobuf()?)),
+        }
+    }
+
+    /// 編碼訊息，達到壓縮門檻時壓縮
+    ///
+    /// 返回訊框與壓縮前後的大小，未壓縮時為 `None`
+    pub fn encode_compressed(
+        &self,
+        msg: &Message,
+        compression: &Compression,
+    ) -> Result<(WsMessage, Option<(usize, usize)>)> {
+        let frame = self.encode(msg)?;
+        let compressed = match &frame {
+            WsMessage::Text(text) => compression.compress(text.as_bytes())?,
+            WsMessage::Binary(data) => compression.compress(data)?,
+            _ => None,
+        };
+
+        match compressed {
+            Some(compressed) => {
+                let sizes = (frame.len(), compressed.len());
+                Ok((WsMessage::Binary(compressed), Some(sizes)))
+            }
+            None => Ok((frame, None)),
+        }
+    }
+
+    /// 解碼 WebSocket 訊框
+    ///
+    /// 依訊框類型與內容解碼，不受協商結果限制；控制訊框返回 `None`
+    pub fn decode(frame: &WsMessage) -> Result<Option<Message>> {
+        Self::decode_limited(frame, compression::MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// 解碼 WebSocket 訊框，訊框或解壓後超過 `max_size` 時返回 `Error::InboundRejected`
+    pub fn decode_limited(frame: &WsMessage, max_size: usize) -> Result<Option<Message>> {
+        if frame.len() > max_size {
+            return Err(InboundViolation::FrameTooLarge {
+                size: frame.len(),
+                limit: max_size,
+            }
+            .into());
+        }
+
+        match frame {
+            WsMessage::Text(text) => Message::from_json(text).map(Some),
+            WsMessage::Binary(data) if compression::is_compressed(data) => {
+                let data = compression::decompress(data, max_size)?;
+                if data.first() == Some(&b'{') {
+                    let text = std::str::from_utf8(&data).map_err(|e| Error::ProtocolError(e.to_string()))?;
+                    Message::from_json(text).map(Some)
+                } else {
+                    Message::from_protobuf(&data).map(Some)
+                }
+            }
+            WsMessage::Binary(data) => Message::from_protobuf(data).map(Some),
+            WsMessage::Close(_) => Err(Error::ConnectionFailed("Connection closed".to_string())),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{create_task_reject, MessageType};
+
+    #[test]
+    fn test_subprotocol_negotiation() {
+        let offer = WireCodec::offer(WireCodec::Protobuf);
+        assert_eq!(WireCodec::select(&offer), Some(WireCodec::Protobuf));
+        assert_eq!(WireCodec::select(SUBPROTOCOL_JSON), Some(WireCodec::Json));
+        assert_eq!(WireCodec::select("agent.orban.v2"), None);
+
+        assert_eq!(WireCodec::from_header(Some(SUBPROTOCOL_PROTOBUF)), WireCodec::Protobuf);
+        assert_eq!(WireCodec::from_header(None), WireCodec::Json);
+    }
+
+    #[test]
+    fn test_encode_decode_frames() {
+        let msg = create_task_reject(
+            "task-001".to_string(),
+            "insufficient_vram".to_string(),
+            "Required 12GB, available 8GB".to_string(),
+        );
+
+        for codec in [WireCodec::Json, WireCodec::Protobuf] {
+            let frame = codec.encode(&msg).unwrap();
+            let decoded = WireCodec::decode(&frame).unwrap().unwrap();
+            assert_eq!(decoded.message_type, MessageType::TaskReject);
+            assert_eq!(decoded.message_id, msg.message_id);
+        }
+
+        assert!(WireCodec::decode(&WsMessage::Ping(vec![])).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_compressed_frames() {
+        let msg = create_task_reject("task-001".to_string(), "insufficient_vram".to_string(), "x".repeat(512));
+        let compression = Compression { threshold: 256, level: 3 };
+
+        for codec in [WireCodec::Json, WireCodec::Protobuf] {
+            let (frame, sizes) = codec.encode_compressed(&msg, &compression).unwrap();
+            let (before, after) = sizes.unwrap();
+            assert!(after < before);
+            assert!(matches!(&frame, WsMessage::Binary(data) if compression::is_compressed(data)));
+
+            let decoded = WireCodec::decode(&frame).unwrap().unwrap();
+            assert_eq!(decoded.message_type, MessageType::TaskReject);
+            assert_eq!(decoded.message_id, msg.message_id);
+        }
+
+        // 未達門檻時與未壓縮的編碼相同
+        let small = create_task_reject("task-001".to_string(), "busy".to_string(), String::new());
+        let (frame, sizes) = WireCodec::Json.encode_compressed(&small, &compression).unwrap();
+        assert!(sizes.is_none());
+        assert!(matches!(frame, WsMessage::Text(_)));
+    }
+}
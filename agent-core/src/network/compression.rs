@@ -0,0 +1,160 @@
+// 訊息壓縮
+//
+// 雙方協商 `compression` 功能後，編碼結果超過門檻的訊息以 zstd 壓縮，
+// 整個 zstd 訊框作為 WebSocket 二進制訊框發送：
+// - 以 zstd 魔數開頭，接收端不需協商結果即可辨識（Protocol Buffers 保留對應的欄位號）
+// - 解壓後以 `{` 開頭者為 JSON，否則為 Protocol Buffers
+// - 壓縮後未變小的訊息原樣發送
+
+use super::inbound::InboundViolation;
+use crate::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// zstd 訊框魔數
+pub const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// 解壓後的大小上限
+pub const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// 壓縮設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// 編碼後達到此大小（位元組）才壓縮
+    pub threshold: usize,
+
+    /// zstd 壓縮等級
+    pub level: i32,
+}
+
+impl Compression {
+    /// 壓縮已編碼的訊息，未達門檻或壓縮後未變小時返回 `None`
+    pub fn compress(&self, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        if data.len() < self.threshold {
+            return Ok(None);
+        }
+
+        let compressed = zstd::bulk::compress(data, self.level)
+            .map_err(|e| Error::ProtocolError(format!("zstd compression failed: {}", e)))?;
+
+        Ok((compressed.len() < data.len()).then_some(compressed))
+    }
+}
+
+/// 是否為壓縮訊框
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(&ZSTD_MAGIC)
+}
+
+/// 解壓縮訊框，解壓後超過 `limit` 時返回 `Error::InboundRejected`
+pub fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    // 訊框標頭記錄了原始大小時先行檢查，不配置超過上限的緩衝區
+    if let Ok(Some(size)) = zstd::zstd_safe::get_frame_content_size(data) {
+        if size > limit as u64 {
+            return Err(InboundViolation::FrameTooLarge {
+                size: usize::try_from(size).unwrap_or(usize::MAX),
+                limit,
+            }
+            .into());
+        }
+    }
+
+    zstd::bulk::decompress(data, limit)
+        .map_err(|e| Error::ProtocolError(format!("zstd decompression failed: {}", e)))
+}
+
+/// 壓縮統計
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionStats {
+    /// 已壓縮的訊息數
+    pub messages_compressed: u64,
+
+    /// 壓縮前的位元組數
+    pub bytes_before: u64,
+
+    /// 壓縮後的位元組數
+    pub bytes_after: u64,
+}
+
+impl CompressionStats {
+    /// 節省的位元組數
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+
+    /// 壓縮後相對原始大小的比例
+    pub fn ratio(&self) -> f64 {
+        if self.bytes_before == 0 {
+            1.0
+        } else {
+            self.bytes_after as f64 / self.bytes_before as f64
+        }
+    }
+}
+
+/// 跨連線累計的壓縮統計
+#[derive(Debug, Default)]
+pub(crate) struct CompressionCounters {
+    messages_compressed: AtomicU64,
+    bytes_before: AtomicU64,
+    bytes_after: AtomicU64,
+}
+
+impl CompressionCounters {
+    pub(crate) fn record(&self, before: usize, after: usize) {
+        self.messages_compressed.fetch_add(1, Ordering::Relaxed);
+        self.bytes_before.fetch_add(before as u64, Ordering::Relaxed);
+        self.bytes_after.fetch_add(after as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CompressionStats {
+        CompressionStats {
+            messages_compressed: self.messages_compressed.load(Ordering::Relaxed),
+            bytes_before: self.bytes_before.load(Ordering::Relaxed),
+            bytes_after: self.bytes_after.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_threshold() {
+        let compression = Compression { threshold: 64, level: 3 };
+
+        assert!(compression.compress(b"{}").unwrap().is_none());
+
+        let data = "{\"gpu_status\": [1, 1, 1, 1]}".repeat(16);
+        let compressed = compression.compress(data.as_bytes()).unwrap().unwrap();
+        assert!(is_compressed(&compressed));
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed, MAX_DECOMPRESSED_SIZE).unwrap(), data.as_bytes());
+    }
+
+    #[test]
+    fn test_decompression_bomb_rejected() {
+        let compression = Compression { threshold: 64, level: 3 };
+        let compressed = compression.compress(&vec![b'x'; 64 * 1024]).unwrap().unwrap();
+
+        assert!(matches!(
+            decompress(&compressed, 1024),
+            Err(Error::InboundRejected(InboundViolation::FrameTooLarge { size: 65536, limit: 1024 }))
+        ));
+        assert_eq!(decompress(&compressed, 64 * 1024).unwrap().len(), 64 * 1024);
+    }
+
+    #[test]
+    fn test_stats() {
+        let counters = CompressionCounters::default();
+        counters.record(1000, 250);
+        counters.record(500, 250);
+
+        let stats = counters.snapshot();
+        assert_eq!(stats.messages_compressed, 2);
+        assert_eq!(stats.bytes_saved(), 1000);
+        assert!((stats.ratio() - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
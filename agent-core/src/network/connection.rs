@@ -0,0 +1,709 @@
+// 全雙工 WebSocket 連線
+//
+// 每條連線拆分為讀取任務與寫入任務：
+// - 讀取任務解碼訊框後送入入站通道，並標記所屬連線世代
+// - 寫入任務從出站佇列取出訊息，控制訊息（心跳、PoW 等）優先於一般訊息；
+//   協商壓縮後，超過門檻的訊息壓縮後寫入
+//
+// - 保活任務定期經由寫入任務發送 ping，讀取任務收到 pong 時記錄往返時間；
+//   連續遺失 pong 時視為半開連線，回報斷線以觸發重連
+//
+// 出站佇列由客戶端持有、跨連線保留，切換連線時未送出的訊息由下一條連線送出。
+// gRPC 連線沒有寫入任務，出站佇列直接作為串流的請求（見 grpc.rs）
+
+use super::codec::WireCodec;
+use super::compression::{Compression, CompressionCounters, CompressionStats};
+use super::correlation::requires_ack;
+use super::inbound::InboundViolation;
+use super::latency::LatencyTracker;
+use super::orban_protocol::{Message, MessageType};
+use super::outbox::Outbox;
+use super::recorder::{Direction, SessionRecorder};
+use super::tls::PlatformStream;
+use crate::error::{Error, Result};
+
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::{self, error::CapacityError, protocol::Message as WsMessage};
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, error, warn};
+
+pub(crate) type WsStream = WebSocketStream<PlatformStream>;
+
+/// 入站訊息（連線世代, 解碼結果）
+pub(crate) type Inbound = (u64, Result<Message>);
+
+/// 控制訊息佇列容量
+const CONTROL_QUEUE_CAPACITY: usize = 64;
+
+/// 關閉連線時等待寫入任務結束的上限
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 待寫入的 ping 容量
+const PING_QUEUE_CAPACITY: usize = 4;
+
+/// 出站訊息優先級
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// 時效性訊息，優先送出
+    Control,
+    /// 一般訊息
+    Normal,
+}
+
+impl Priority {
+    /// 依訊息類型決定優先級
+    pub fn of(message_type: MessageType) -> Self {
+        match message_type {
+            MessageType::Heartbeat
+            | MessageType::PowResponse
+            | MessageType::AuthResponse
+            | MessageType::StateSync
+            | MessageType::Error => Priority::Control,
+            _ => Priority::Normal,
+        }
+    }
+}
+
+/// 出站佇列統計
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboundStats {
+    /// 佇列中的控制訊息數
+    pub control_queued: usize,
+
+    /// 佇列中的一般訊息數
+    pub normal_queued: usize,
+
+    /// 一般訊息佇列容量
+    pub capacity: usize,
+
+    /// 一般訊息佇列的最高深度
+    pub high_water_mark: usize,
+
+    /// 因佇列已滿而等待的發送次數
+    pub blocked_sends: u64,
+
+    /// 已寫入的訊框數
+    pub frames_sent: u64,
+}
+
+/// 寫入任務持有的接收端
+struct OutboundReceivers {
+    control: mpsc::Receiver<Message>,
+    normal: mpsc::Receiver<Message>,
+
+    /// 寫入失敗、待下一條連線重送的訊息
+    unsent: Option<Message>,
+}
+
+/// 有界出站佇列
+pub(crate) struct OutboundQueue {
+    control_tx: mpsc::Sender<Message>,
+    normal_tx: mpsc::Sender<Message>,
+    receivers: Arc<Mutex<OutboundReceivers>>,
+    capacity: usize,
+    high_water_mark: AtomicUsize,
+    blocked_sends: AtomicU64,
+    frames_sent: Arc<AtomicU64>,
+    compression: Arc<CompressionCounters>,
+}
+
+impl OutboundQueue {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (control_tx, control) = mpsc::channel(CONTROL_QUEUE_CAPACITY);
+        let (normal_tx, normal) = mpsc::channel(capacity);
+
+        Self {
+            control_tx,
+            normal_tx,
+            receivers: Arc::new(Mutex::new(OutboundReceivers {
+                control,
+                normal,
+                unsent: None,
+            })),
+            capacity,
+            high_water_mark: AtomicUsize::new(0),
+            blocked_sends: AtomicU64::new(0),
+            frames_sent: Arc::new(AtomicU64::new(0)),
+            compression: Arc::new(CompressionCounters::default()),
+        }
+    }
+
+    /// 加入佇列
+    ///
+    /// 佇列已滿時最多等待 `timeout`，期間寫入任務持續消化佇列
+    pub(crate) async fn push(&self, msg: Message, timeout: Duration) -> Result<()> {
+        let priority = Priority::of(msg.message_type);
+        let tx = match priority {
+            Priority::Control => &self.control_tx,
+            Priority::Normal => &self.normal_tx,
+        };
+
+        let permit = match tx.try_reserve() {
+            Ok(permit) => permit,
+            Err(mpsc::error::TrySendError::Full(())) => {
+                self.blocked_sends.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "Outbound {:?} queue full, waiting to send {:?}",
+                    priority, msg.message_type
+                );
+
+                tokio::time::timeout(timeout, tx.reserve())
+                    .await
+                    .map_err(|_| Error::ConnectionFailed("Outbound queue full".to_string()))?
+                    .map_err(|_| Error::ConnectionFailed("Outbound queue closed".to_string()))?
+            }
+            Err(mpsc::error::TrySendError::Closed(())) => {
+                return Err(Error::ConnectionFailed("Outbound queue closed".to_string()));
+            }
+        };
+
+        permit.send(msg);
+
+        if priority == Priority::Normal {
+            let depth = self.normal_depth();
+            let previous = self.high_water_mark.fetch_max(depth, Ordering::Relaxed);
+
+            // 首次超過四分之三容量時提示
+            if depth > previous && depth * 4 >= self.capacity * 3 && previous * 4 < self.capacity * 3 {
+                warn!("Outbound queue at {}/{} messages", depth, self.capacity);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn normal_depth(&self) -> usize {
+        self.normal_tx.max_capacity() - self.normal_tx.capacity()
+    }
+
+    /// 佇列統計
+    pub(crate) fn stats(&self) -> OutboundStats {
+        OutboundStats {
+            control_queued: self.control_tx.max_capacity() - self.control_tx.capacity(),
+            normal_queued: self.normal_depth(),
+            capacity: self.capacity,
+            high_water_mark: self.high_water_mark.load(Ordering::Relaxed),
+            blocked_sends: self.blocked_sends.load(Ordering::Relaxed),
+            frames_sent: self.frames_sent.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 壓縮統計
+    pub(crate) fn compression_stats(&self) -> CompressionStats {
+        self.compression.snapshot()
+    }
+
+    /// 依優先級取出佇列中訊息的串流，作為 gRPC 串流的請求
+    ///
+    /// 首次輪詢時才取得佇列，訊息交給串流即記錄為已送出；
+    /// 收到 `shutdown` 或串流被丟棄後釋放佇列，由下一條連線接手
+    #[cfg(feature = "grpc")]
+    pub(crate) fn stream(
+        &self,
+        shutdown: oneshot::Receiver<()>,
+        outbox: Arc<Outbox>,
+        recorder: Arc<SessionRecorder>,
+    ) -> impl futures::Stream<Item = Message> + Send + 'static {
+        let sent = SentLog {
+            frames_sent: self.frames_sent.clone(),
+            compression: self.compression.clone(),
+            outbox,
+            recorder,
+        };
+
+        futures::stream::unfold(
+            (self.receivers.clone(), None, shutdown, sent),
+            |(receivers, guard, mut shutdown, sent)| async move {
+                let mut guard: tokio::sync::OwnedMutexGuard<OutboundReceivers> = match guard {
+                    Some(guard) => guard,
+                    None => tokio::select! {
+                        guard = receivers.clone().lock_owned() => guard,
+                        _ = &mut shutdown => return None,
+                    },
+                };
+
+                let OutboundReceivers { control, normal, unsent } = &mut *guard;
+                let msg = match unsent.take() {
+                    Some(msg) => msg,
+                    None => tokio::select! {
+                        biased;
+                        _ = &mut shutdown => return None,
+                        Some(msg) = control.recv() => msg,
+                        Some(msg) = normal.recv() => msg,
+                        else => return None,
+                    },
+                };
+
+                sent.record(&msg, None);
+                Some((msg, (receivers, Some(guard), shutdown, sent)))
+            },
+        )
+    }
+}
+
+/// 保活設定
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct KeepaliveSettings {
+    pub(crate) interval: Duration,
+    pub(crate) timeout: Duration,
+    pub(crate) max_missed: u32,
+}
+
+impl KeepaliveSettings {
+    /// 依配置建立，間隔為 0 時停用
+    pub(crate) fn from_config(config: &crate::config::NetworkConfig) -> Option<Self> {
+        (config.keepalive_interval_secs > 0).then(|| Self {
+            interval: Duration::from_secs(config.keepalive_interval_secs),
+            timeout: Duration::from_secs(config.keepalive_timeout_secs),
+            max_missed: config.keepalive_max_missed.max(1),
+        })
+    }
+}
+
+/// 保活任務的下一步
+#[derive(Debug, PartialEq, Eq)]
+enum KeepaliveTick {
+    /// 發送 ping（負載為序號）
+    Ping(Vec<u8>),
+    /// 上一個 ping 尚未逾時
+    Wait,
+    /// 連續遺失的 pong 數已達上限
+    Dead(u32),
+}
+
+#[derive(Debug, Default)]
+struct PingState {
+    next_seq: u64,
+    pending: Option<(u64, Instant)>,
+    missed: u32,
+}
+
+/// 單一連線的保活狀態，由保活任務與讀取任務共用
+struct Keepalive {
+    settings: KeepaliveSettings,
+    state: std::sync::Mutex<PingState>,
+    latency: Arc<LatencyTracker>,
+}
+
+impl Keepalive {
+    fn new(settings: KeepaliveSettings, latency: Arc<LatencyTracker>) -> Self {
+        Self {
+            settings,
+            state: std::sync::Mutex::new(PingState::default()),
+            latency,
+        }
+    }
+
+    fn tick(&self, now: Instant) -> KeepaliveTick {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some((_, sent_at)) = state.pending {
+            if now.duration_since(sent_at) < self.settings.timeout {
+                return KeepaliveTick::Wait;
+            }
+
+            state.pending = None;
+            state.missed += 1;
+            if state.missed >= self.settings.max_missed {
+                return KeepaliveTick::Dead(state.missed);
+            }
+        }
+
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.pending = Some((seq, now));
+        KeepaliveTick::Ping(seq.to_be_bytes().to_vec())
+    }
+
+    /// 收到 pong，負載與待回應的 ping 相符時記錄往返時間
+    fn pong(&self, payload: &[u8], now: Instant) {
+        let mut state = self.state.lock().unwrap();
+
+        match state.pending {
+            Some((seq, sent_at)) if payload == seq.to_be_bytes() => {
+                let rtt = now.duration_since(sent_at);
+                debug!("Pong {} after {:?}", seq, rtt);
+
+                self.latency.record(rtt);
+                state.pending = None;
+                state.missed = 0;
+            }
+            _ => debug!("Ignoring unsolicited pong"),
+        }
+    }
+}
+
+/// 一條已認證的連線
+pub(crate) struct Connection {
+    shutdown: Option<oneshot::Sender<()>>,
+    writer: Option<JoinHandle<()>>,
+    reader: JoinHandle<()>,
+    keepalive: Option<JoinHandle<()>>,
+}
+
+impl Connection {
+    /// 拆分連線並啟動讀寫任務，啟用保活時另啟動保活任務
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn spawn(
+        ws: WsStream,
+        codec: WireCodec,
+        compression: Option<Compression>,
+        generation: u64,
+        outbound: &OutboundQueue,
+        outbox: Arc<Outbox>,
+        inbound: mpsc::Sender<Inbound>,
+        keepalive: Option<KeepaliveSettings>,
+        latency: Arc<LatencyTracker>,
+        max_frame_bytes: usize,
+        recorder: Arc<SessionRecorder>,
+    ) -> Self {
+        let (sink, source) = ws.split();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let (ping_tx, ping_rx) = mpsc::channel(PING_QUEUE_CAPACITY);
+        let keepalive = keepalive.map(|settings| Arc::new(Keepalive::new(settings, latency)));
+
+        let writer = tokio::spawn(write_loop(
+            sink,
+            FrameEncoder { codec, compression },
+            generation,
+            outbound.receivers.clone(),
+            SentLog {
+                frames_sent: outbound.frames_sent.clone(),
+                compression: outbound.compression.clone(),
+                outbox,
+                recorder: recorder.clone(),
+            },
+            shutdown_rx,
+            ping_rx,
+            inbound.clone(),
+        ));
+        let reader = tokio::spawn(read_loop(
+            source,
+            generation,
+            inbound.clone(),
+            keepalive.clone(),
+            max_frame_bytes,
+            recorder,
+        ));
+        let keepalive = keepalive.map(|keepalive| tokio::spawn(keepalive_loop(keepalive, ping_tx, generation, inbound)));
+
+        Self {
+            shutdown: Some(shutdown_tx),
+            writer: Some(writer),
+            reader,
+            keepalive,
+        }
+    }
+
+    /// 由讀取任務與出站串流組成的連線（gRPC），出站串流收到 `shutdown` 後結束
+    #[cfg(feature = "grpc")]
+    pub(crate) fn streaming(shutdown: oneshot::Sender<()>, reader: JoinHandle<()>) -> Self {
+        Self {
+            shutdown: Some(shutdown),
+            writer: None,
+            reader,
+            keepalive: None,
+        }
+    }
+
+    /// 停止讀寫任務並關閉連線
+    ///
+    /// 正在寫入的訊息會完成，佇列中其餘訊息留給下一條連線
+    pub(crate) async fn close(mut self) {
+        self.reader.abort();
+        if let Some(keepalive) = self.keepalive.take() {
+            keepalive.abort();
+        }
+
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+
+        if let Some(mut writer) = self.writer.take() {
+            if tokio::time::timeout(CLOSE_TIMEOUT, &mut writer).await.is_err() {
+                warn!("Writer did not stop in time, aborting");
+                writer.abort();
+            }
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+        if let Some(keepalive) = self.keepalive.take() {
+            keepalive.abort();
+        }
+        if let Some(writer) = self.writer.take() {
+            writer.abort();
+        }
+    }
+}
+
+/// 訊框寫入連線後的記錄
+struct SentLog {
+    frames_sent: Arc<AtomicU64>,
+    compression: Arc<CompressionCounters>,
+    outbox: Arc<Outbox>,
+    recorder: Arc<SessionRecorder>,
+}
+
+impl SentLog {
+    /// `compressed` 為壓縮前後的大小
+    fn record(&self, msg: &Message, compressed: Option<(usize, usize)>) {
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+        self.recorder.record(Direction::Outbound, msg);
+
+        match compressed {
+            Some((before, after)) => {
+                self.compression.record(before, after);
+                debug!(
+                    "Sent {:?} message {} ({} -> {} bytes)",
+                    msg.message_type, msg.message_id, before, after
+                );
+            }
+            None => debug!("Sent {:?} message {}", msg.message_type, msg.message_id),
+        }
+
+        // 不需確認的訊息寫入連線後即移出發件匣
+        if Outbox::stores(msg.message_type) && !requires_ack(msg.message_type) {
+            self.outbox.remove(&msg.message_id);
+        }
+    }
+}
+
+/// 連線協商的編碼格式與壓縮設定
+struct FrameEncoder {
+    codec: WireCodec,
+    compression: Option<Compression>,
+}
+
+impl FrameEncoder {
+    /// 編碼訊息，返回訊框與壓縮前後的大小
+    fn encode(&self, msg: &Message) -> Result<(WsMessage, Option<(usize, usize)>)> {
+        match &self.compression {
+            Some(compression) => self.codec.encode_compressed(msg, compression),
+            None => self.codec.encode(msg).map(|frame| (frame, None)),
+        }
+    }
+}
+
+/// 寫入任務
+#[allow(clippy::too_many_arguments)]
+async fn write_loop(
+    mut sink: futures::stream::SplitSink<WsStream, WsMessage>,
+    encoder: FrameEncoder,
+    generation: u64,
+    receivers: Arc<Mutex<OutboundReceivers>>,
+    sent: SentLog,
+    mut shutdown: oneshot::Receiver<()>,
+    mut pings: mpsc::Receiver<Vec<u8>>,
+    inbound: mpsc::Sender<Inbound>,
+) {
+    // 等待上一條連線的寫入任務釋放佇列
+    let mut guard = tokio::select! {
+        guard = receivers.lock() => guard,
+        _ = &mut shutdown => return,
+    };
+    let OutboundReceivers { control, normal, unsent } = &mut *guard;
+
+    loop {
+        let msg = match unsent.take() {
+            Some(msg) => msg,
+            None => tokio::select! {
+                biased;
+                _ = &mut shutdown => break,
+                Some(payload) = pings.recv() => {
+                    if let Err(e) = sink.send(WsMessage::Ping(payload)).await {
+                        warn!("Failed to write ping: {}", e);
+                        let _ = inbound.send((generation, Err(Error::WebSocketError(e)))).await;
+                        return;
+                    }
+                    continue;
+                }
+                Some(msg) = control.recv() => msg,
+                Some(msg) = normal.recv() => msg,
+                else => break,
+            },
+        };
+
+        let (frame, compressed) = match encoder.encode(&msg) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                error!("Failed to encode {:?} message {}: {}", msg.message_type, msg.message_id, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = sink.send(frame).await {
+            warn!("Failed to write {:?} message {}: {}", msg.message_type, msg.message_id, e);
+            *unsent = Some(msg);
+            let _ = inbound.send((generation, Err(Error::WebSocketError(e)))).await;
+            return;
+        }
+
+        sent.record(&msg, compressed);
+    }
+
+    let _ = sink.close().await;
+}
+
+/// 讀取任務
+async fn read_loop(
+    mut source: futures::stream::SplitStream<WsStream>,
+    generation: u64,
+    inbound: mpsc::Sender<Inbound>,
+    keepalive: Option<Arc<Keepalive>>,
+    max_frame_bytes: usize,
+    recorder: Arc<SessionRecorder>,
+) {
+    while let Some(frame) = source.next().await {
+        let item = match frame {
+            Ok(WsMessage::Pong(payload)) => {
+                if let Some(keepalive) = &keepalive {
+                    keepalive.pong(&payload, Instant::now());
+                }
+                continue;
+            }
+            Ok(WsMessage::Close(_)) => {
+                warn!("WebSocket closed by server");
+                Err(Error::ConnectionFailed("Connection closed".to_string()))
+            }
+            Ok(frame) => match WireCodec::decode_limited(&frame, max_frame_bytes) {
+                Ok(Some(msg)) => {
+                    debug!("Received {:?} message {}", msg.message_type, msg.message_id);
+                    recorder.record(Direction::Inbound, &msg);
+                    Ok(msg)
+                }
+                Ok(None) => continue,
+                Err(e) => Err(e),
+            },
+            // 超過上限的訊框未完整讀取，連線無法繼續使用
+            Err(tungstenite::Error::Capacity(CapacityError::MessageTooLong { size, max_size })) => {
+                warn!("Dropping oversized frame of {} bytes, reconnecting", size);
+                let violation = InboundViolation::FrameTooLarge { size, limit: max_size };
+                let _ = inbound.send((generation, Err(violation.into()))).await;
+                break;
+            }
+            Err(e) => Err(Error::WebSocketError(e)),
+        };
+
+        let lost = matches!(item, Err(Error::ConnectionFailed(_) | Error::WebSocketError(_)));
+        if inbound.send((generation, item)).await.is_err() || lost {
+            return;
+        }
+    }
+
+    let _ = inbound
+        .send((generation, Err(Error::ConnectionFailed("Connection closed".to_string()))))
+        .await;
+}
+
+/// 保活任務：定期發送 ping，連續遺失 pong 時回報斷線
+async fn keepalive_loop(
+    keepalive: Arc<Keepalive>,
+    pings: mpsc::Sender<Vec<u8>>,
+    generation: u64,
+    inbound: mpsc::Sender<Inbound>,
+) {
+    let mut ticker = tokio::time::interval(keepalive.settings.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+
+        match keepalive.tick(Instant::now()) {
+            KeepaliveTick::Ping(payload) => {
+                if pings.send(payload).await.is_err() {
+                    return;
+                }
+            }
+            KeepaliveTick::Wait => {}
+            KeepaliveTick::Dead(missed) => {
+                warn!("No pong from platform for {} consecutive pings, dropping connection", missed);
+                let _ = inbound
+                    .send((
+                        generation,
+                        Err(Error::ConnectionFailed(format!("Keepalive timeout ({} pongs missed)", missed))),
+                    ))
+                    .await;
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{create_heartbeat, create_task_reject, AgentStatus};
+
+    #[test]
+    fn test_message_priority() {
+        assert_eq!(Priority::of(MessageType::Heartbeat), Priority::Control);
+        assert_eq!(Priority::of(MessageType::PowResponse), Priority::Control);
+        assert_eq!(Priority::of(MessageType::TaskProgress), Priority::Normal);
+        assert_eq!(Priority::of(MessageType::TaskComplete), Priority::Normal);
+    }
+
+    #[test]
+    fn test_keepalive_missed_pongs() {
+        let settings = KeepaliveSettings {
+            interval: Duration::from_secs(15),
+            timeout: Duration::from_secs(10),
+            max_missed: 2,
+        };
+        let latency = Arc::new(LatencyTracker::default());
+        let keepalive = Keepalive::new(settings, latency.clone());
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // 回應的 pong 記錄往返時間並重置遺失計數
+        let KeepaliveTick::Ping(payload) = keepalive.tick(at(0)) else {
+            panic!("expected ping");
+        };
+        keepalive.pong(&payload, at(0) + Duration::from_millis(40));
+        assert_eq!(latency.snapshot().samples, 1);
+
+        assert!(matches!(keepalive.tick(at(15)), KeepaliveTick::Ping(_)));
+        assert_eq!(keepalive.tick(at(20)), KeepaliveTick::Wait);
+        assert!(matches!(keepalive.tick(at(30)), KeepaliveTick::Ping(_)));
+
+        // 舊 ping 的 pong 不計入
+        keepalive.pong(&payload, at(31));
+        assert_eq!(latency.snapshot().samples, 1);
+        assert_eq!(keepalive.tick(at(45)), KeepaliveTick::Dead(2));
+    }
+
+    #[tokio::test]
+    async fn test_outbound_backpressure() {
+        let queue = OutboundQueue::new(2);
+        let timeout = Duration::from_millis(50);
+        let reject = || create_task_reject("task-001".to_string(), "busy".to_string(), String::new());
+
+        queue.push(reject(), timeout).await.unwrap();
+        queue.push(reject(), timeout).await.unwrap();
+
+        // 一般佇列已滿，控制訊息仍可送入
+        let heartbeat = create_heartbeat("agent-001".to_string(), AgentStatus::Idle, None, vec![], 0);
+        queue.push(heartbeat, timeout).await.unwrap();
+
+        assert!(matches!(
+            queue.push(reject(), timeout).await,
+            Err(Error::ConnectionFailed(_))
+        ));
+
+        let stats = queue.stats();
+        assert_eq!(stats.normal_queued, 2);
+        assert_eq!(stats.control_queued, 1);
+        assert_eq!(stats.high_water_mark, 2);
+        assert_eq!(stats.blocked_sends, 1);
+    }
+}
@@ -0,0 +1,212 @@
+// 請求與回覆關聯
+//
+// 平台的回覆以 `in_reply_to` 指向請求的 `message_id`：
+// - 等待回覆的請求先登記，分派任務收到對應回覆時交付給請求方
+// - 需要送達確認的訊息在收到 ACK 前定期重送，重送沿用原 message_id，由平台去重
+// - 其餘訊息（任務分配、PoW 挑戰等）交由事件循環處理
+
+use super::orban_protocol::{Message, MessageType};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{oneshot, Notify};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// 重送間隔最多加倍的次數
+const MAX_BACKOFF_EXPONENT: u32 = 5;
+
+/// 是否需要平台的送達確認
+pub fn requires_ack(message_type: MessageType) -> bool {
+    matches!(message_type, MessageType::TaskComplete | MessageType::TaskFailed)
+}
+
+/// 等待確認的訊息
+struct Unacked {
+    msg: Message,
+    attempts: u32,
+    retry_at: Instant,
+}
+
+/// 回覆關聯表
+pub(crate) struct Correlator {
+    pending: Mutex<HashMap<String, oneshot::Sender<Message>>>,
+    unacked: Mutex<HashMap<String, Unacked>>,
+    rescheduled: Notify,
+    ack_timeout: Duration,
+}
+
+impl Correlator {
+    pub(crate) fn new(ack_timeout: Duration) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            unacked: Mutex::new(HashMap::new()),
+            rescheduled: Notify::new(),
+            ack_timeout,
+        }
+    }
+
+    /// 登記等待回覆的請求
+    pub(crate) fn expect_reply(&self, message_id: &str) -> oneshot::Receiver<Message> {
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(message_id.to_string(), tx);
+        rx
+    }
+
+    /// 取消等待（請求逾時或發送失敗）
+    pub(crate) fn cancel(&self, message_id: &str) {
+        self.pending.lock().unwrap().remove(message_id);
+    }
+
+    /// 追蹤需要送達確認的訊息
+    pub(crate) fn track(&self, msg: &Message) {
+        self.unacked.lock().unwrap().insert(
+            msg.message_id.clone(),
+            Unacked {
+                msg: msg.clone(),
+                attempts: 0,
+                retry_at: Instant::now() + self.ack_timeout,
+            },
+        );
+        self.rescheduled.notify_one();
+    }
+
+    /// 交付回覆與確認
+    ///
+    /// 返回未被請求認領的訊息，應交由事件循環處理
+    pub(crate) fn resolve(&self, msg: Message) -> Option<Message> {
+        let Some(request_id) = msg.in_reply_to.clone() else {
+            return Some(msg);
+        };
+
+        // 平台對已追蹤訊息的任何回覆都表示已送達
+        let acked = self.unacked.lock().unwrap().remove(&request_id).is_some();
+        let waiter = self.pending.lock().unwrap().remove(&request_id);
+
+        if msg.message_type == MessageType::Ack {
+            if let Some(tx) = waiter {
+                // 以 ACK 確認的請求（如 KEY_ROTATION）交給請求方
+                let _ = tx.send(msg);
+            } else if acked {
+                debug!("Message {} acknowledged", request_id);
+            } else {
+                debug!("Ignoring ack for unknown message {}", request_id);
+            }
+            return None;
+        }
+
+        match waiter {
+            // 請求方已放棄等待時交由事件循環處理
+            Some(tx) => tx.send(msg).err(),
+            None => Some(msg),
+        }
+    }
+
+    /// 下次需要重送的時間
+    pub(crate) fn next_retransmit(&self) -> Option<Instant> {
+        self.unacked.lock().unwrap().values().map(|u| u.retry_at).min()
+    }
+
+    /// 取出已逾時的訊息並排定下次重送
+    pub(crate) fn due(&self) -> Vec<Message> {
+        let now = Instant::now();
+        let mut due = Vec::new();
+
+        for unacked in self.unacked.lock().unwrap().values_mut() {
+            if unacked.retry_at > now {
+                continue;
+            }
+
+            unacked.attempts += 1;
+            unacked.retry_at = now + self.ack_timeout * 2u32.pow(unacked.attempts.min(MAX_BACKOFF_EXPONENT));
+
+            warn!(
+                "No ack for {:?} message {}, retransmitting (attempt {})",
+                unacked.msg.message_type, unacked.msg.message_id, unacked.attempts
+            );
+            due.push(unacked.msg.clone());
+        }
+
+        due.sort_by_key(|msg| msg.timestamp);
+        due
+    }
+
+    /// 等待重送時間變更
+    pub(crate) async fn rescheduled(&self) {
+        self.rescheduled.notified().await
+    }
+
+    /// 未確認的訊息數
+    pub(crate) fn unacked_count(&self) -> usize {
+        self.unacked.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{
+        create_task_reject, AckPayload, AckStatus, MessagePayload,
+    };
+
+    fn ack(request: &Message) -> Message {
+        Message::new(
+            MessageType::Ack,
+            MessagePayload::Ack(AckPayload {
+                status: AckStatus::Received,
+            }),
+        )
+        .reply_to(request)
+    }
+
+    #[tokio::test]
+    async fn test_resolve_reply() {
+        let correlator = Correlator::new(Duration::from_secs(10));
+        let request = create_task_reject("task-001".to_string(), "busy".to_string(), String::new());
+        let reply_rx = correlator.expect_reply(&request.message_id);
+
+        // 未關聯的訊息交由事件循環
+        let unsolicited = create_task_reject("task-002".to_string(), "busy".to_string(), String::new());
+        assert!(correlator.resolve(unsolicited).is_some());
+
+        let reply = Message::new(MessageType::Error, request.payload.clone()).reply_to(&request);
+        assert!(correlator.resolve(reply.clone()).is_none());
+        assert_eq!(reply_rx.await.unwrap().message_id, reply.message_id);
+
+        // 請求已逾時，回覆交由事件循環
+        let late = correlator.expect_reply("msg-late");
+        drop(late);
+        let mut reply = reply;
+        reply.in_reply_to = Some("msg-late".to_string());
+        assert!(correlator.resolve(reply).is_some());
+
+        // 以 ACK 確認的請求
+        let acked_rx = correlator.expect_reply(&request.message_id);
+        assert!(correlator.resolve(ack(&request)).is_none());
+        assert_eq!(acked_rx.await.unwrap().message_type, MessageType::Ack);
+    }
+
+    #[tokio::test]
+    async fn test_retransmit_until_acked() {
+        let timeout = Duration::from_millis(50);
+        let correlator = Correlator::new(timeout);
+        let msg = create_task_reject("task-001".to_string(), "busy".to_string(), String::new());
+        correlator.track(&msg);
+
+        assert!(correlator.due().is_empty());
+
+        tokio::time::sleep_until(correlator.next_retransmit().unwrap()).await;
+        let due = correlator.due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].message_id, msg.message_id);
+
+        // 重送間隔加倍
+        let next = correlator.next_retransmit().unwrap();
+        assert!(next >= Instant::now() + timeout);
+
+        assert!(correlator.resolve(ack(&msg)).is_none());
+        assert_eq!(correlator.unacked_count(), 0);
+        assert_eq!(correlator.next_retransmit(), None);
+    }
+}
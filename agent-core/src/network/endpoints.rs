@@ -0,0 +1,228 @@
+// 平台端點選擇與故障轉移
+//
+// `platform_url` 與 `[network] endpoints` 組成候選端點：
+// - 啟動時並行量測各端點的連線時間，無法連線者視為不健康，依延遲排序後選用最快的健康端點
+// - 目前端點連續連線失敗 `failover_after_failures` 次後依排序切換到下一個端點，
+//   切換後不自動切回，直到新端點也連續失敗
+// - 端點可標註區域，註冊時回報實際使用端點的區域
+
+use super::proxy::ProxySettings;
+use crate::config::EndpointConfig;
+use crate::AgentConfig;
+
+use futures::future::join_all;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// 平台端點
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub url: String,
+    pub region: Option<String>,
+}
+
+#[derive(Debug)]
+struct Selection {
+    /// 候選順序（端點索引）
+    order: Vec<usize>,
+    /// 目前端點在 `order` 中的位置
+    position: usize,
+    /// 目前端點連續失敗次數
+    failures: u32,
+}
+
+/// 候選端點及目前使用的端點，由各傳輸層共用
+#[derive(Debug)]
+pub struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    failover_after: u32,
+    proxy: ProxySettings,
+    selection: Mutex<Selection>,
+}
+
+impl Endpoints {
+    /// 依配置建立，尚未量測前使用 `platform_url`
+    pub fn from_config(config: &AgentConfig) -> crate::Result<Self> {
+        let mut endpoints = vec![Endpoint {
+            url: config.platform_url.clone(),
+            region: None,
+        }];
+
+        for EndpointConfig { url, region } in &config.network.endpoints {
+            match endpoints.iter_mut().find(|endpoint| &endpoint.url == url) {
+                // 為 platform_url 標註區域
+                Some(existing) => existing.region = region.clone(),
+                None => endpoints.push(Endpoint {
+                    url: url.clone(),
+                    region: region.clone(),
+                }),
+            }
+        }
+
+        Ok(Self {
+            selection: Mutex::new(Selection {
+                order: (0..endpoints.len()).collect(),
+                position: 0,
+                failures: 0,
+            }),
+            endpoints,
+            failover_after: config.network.failover_after_failures.max(1),
+            proxy: ProxySettings::from_config(&config.network.proxy)?,
+        })
+    }
+
+    /// 目前使用的端點
+    pub fn current(&self) -> Endpoint {
+        let selection = self.selection.lock().unwrap();
+        self.endpoints[selection.order[selection.position]].clone()
+    }
+
+    /// 量測各端點的連線時間並選用最快的健康端點，只有一個端點時不量測
+    pub async fn select(&self, timeout: Duration) -> Endpoint {
+        if self.endpoints.len() > 1 {
+            let probes = join_all(self.endpoints.iter().map(|endpoint| self.probe(endpoint, timeout))).await;
+            let order = rank(&probes);
+
+            for (index, probe) in probes.iter().enumerate() {
+                match probe {
+                    Some(rtt) => debug!("Endpoint {} reachable in {:?}", self.endpoints[index].url, rtt),
+                    None => warn!("Endpoint {} unreachable", self.endpoints[index].url),
+                }
+            }
+
+            *self.selection.lock().unwrap() = Selection {
+                order,
+                position: 0,
+                failures: 0,
+            };
+        }
+
+        let current = self.current();
+        info!(
+            "Using platform endpoint {} (region {})",
+            current.url,
+            current.region.as_deref().unwrap_or("unspecified")
+        );
+        current
+    }
+
+    /// 建立 TCP 連線（經由代理時為建立通道）所需的時間，無法連線時返回 None
+    async fn probe(&self, endpoint: &Endpoint, timeout: Duration) -> Option<Duration> {
+        let url = reqwest::Url::parse(&endpoint.url).ok()?;
+        let started = Instant::now();
+        self.proxy.connect(&url, timeout).await.ok()?;
+        Some(started.elapsed())
+    }
+
+    /// 連線成功，重置失敗計數
+    pub fn record_success(&self) {
+        self.selection.lock().unwrap().failures = 0;
+    }
+
+    /// 連線失敗，達到門檻時切換到下一個端點並返回
+    pub fn record_failure(&self) -> Option<Endpoint> {
+        let mut selection = self.selection.lock().unwrap();
+        selection.failures += 1;
+
+        if self.endpoints.len() < 2 || selection.failures < self.failover_after {
+            return None;
+        }
+
+        let failed = &self.endpoints[selection.order[selection.position]];
+        selection.position = (selection.position + 1) % selection.order.len();
+        selection.failures = 0;
+
+        let next = self.endpoints[selection.order[selection.position]].clone();
+        warn!(
+            "Endpoint {} failed {} times in a row, failing over to {}",
+            failed.url, self.failover_after, next.url
+        );
+        Some(next)
+    }
+}
+
+/// 健康端點依延遲排序在前，其餘維持配置順序
+fn rank(probes: &[Option<Duration>]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..probes.len()).collect();
+    order.sort_by_key(|&index| match probes[index] {
+        Some(rtt) => (false, rtt),
+        None => (true, Duration::ZERO),
+    });
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentConfig, Availability};
+
+    fn config(endpoints: Vec<EndpointConfig>, failover_after_failures: u32) -> AgentConfig {
+        let mut config = AgentConfig {
+            display_name: None,
+            platform_url: "wss://primary.orban.ai".to_string(),
+            private_key_path: String::new(),
+            key: Default::default(),
+            availability: Availability {
+                hours_per_day: 24,
+                reliability_score: 1.0,
+            },
+            network: Default::default(),
+            data_dir: std::env::temp_dir(),
+        };
+        config.network.endpoints = endpoints;
+        config.network.failover_after_failures = failover_after_failures;
+        config
+    }
+
+    fn endpoint(url: &str, region: &str) -> EndpointConfig {
+        EndpointConfig {
+            url: url.to_string(),
+            region: Some(region.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_rank_prefers_fast_healthy_endpoints() {
+        let ms = Duration::from_millis;
+        assert_eq!(rank(&[None, Some(ms(80)), Some(ms(20)), None]), vec![2, 1, 0, 3]);
+        assert_eq!(rank(&[None, None]), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_platform_url_region_from_endpoints() {
+        let endpoints = Endpoints::from_config(&config(
+            vec![endpoint("wss://primary.orban.ai", "asia-east1"), endpoint("wss://eu.orban.ai", "europe-west1")],
+            3,
+        ))
+        .unwrap();
+
+        assert_eq!(endpoints.endpoints.len(), 2);
+        assert_eq!(endpoints.current().region.as_deref(), Some("asia-east1"));
+    }
+
+    #[test]
+    fn test_failover_is_sticky() {
+        let endpoints = Endpoints::from_config(&config(vec![endpoint("wss://eu.orban.ai", "europe-west1")], 2)).unwrap();
+
+        assert_eq!(endpoints.record_failure(), None);
+        endpoints.record_success();
+        assert_eq!(endpoints.record_failure(), None);
+
+        let next = endpoints.record_failure().unwrap();
+        assert_eq!(next.url, "wss://eu.orban.ai");
+        assert_eq!(endpoints.current(), next);
+
+        // 新端點的失敗重新計數
+        assert_eq!(endpoints.record_failure(), None);
+        assert_eq!(endpoints.current(), next);
+    }
+
+    #[test]
+    fn test_single_endpoint_never_fails_over() {
+        let endpoints = Endpoints::from_config(&config(Vec::new(), 1)).unwrap();
+
+        assert_eq!(endpoints.record_failure(), None);
+        assert_eq!(endpoints.current().url, "wss://primary.orban.ai");
+    }
+}
@@ -0,0 +1,254 @@
+// 錯誤處理策略
+//
+// 平台以 `ERROR` 訊息通知 Agent，依錯誤碼與 `recoverable` 決定處理方式：
+// - `AUTH_FAILED`：可恢復時重新認證，否則停止
+// - `RATE_LIMITED`：暫停心跳等非必要上報（`context.retry_after_secs`）
+// - `VERSION_UNSUPPORTED`：停止，需升級 Agent
+// - `TASK_CANCELLED`：中止 `context.task_id` 指定的任務
+// - 其他錯誤：可恢復時記錄後繼續，不可恢復時中止相關任務，無相關任務時停止
+//
+// Agent 本地的錯誤依 `Error::is_recoverable` 決定記錄後繼續或停止
+
+use super::orban_protocol::{ErrorPayload, Message, MessagePayload};
+use crate::error::{Error, Result};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// 平台錯誤碼
+pub mod codes {
+    pub const AUTH_FAILED: &str = "AUTH_FAILED";
+    pub const RATE_LIMITED: &str = "RATE_LIMITED";
+    pub const VERSION_UNSUPPORTED: &str = "VERSION_UNSUPPORTED";
+    pub const TASK_CANCELLED: &str = "TASK_CANCELLED";
+
+    /// 平台拒絕金鑰輪換聲明（回覆 KEY_ROTATION，Agent 繼續使用舊金鑰）
+    pub const KEY_ROTATION_REJECTED: &str = "KEY_ROTATION_REJECTED";
+
+    /// 平台拒絕硬體證明（簽名無效、雜湊不符或 GPU 已由其他 Agent 申報）
+    pub const ATTESTATION_REJECTED: &str = "ATTESTATION_REJECTED";
+
+    /// 握手階段使用的舊錯誤碼，與 `VERSION_UNSUPPORTED` 相同處理
+    pub const UNSUPPORTED_VERSION: &str = "UNSUPPORTED_VERSION";
+}
+
+/// 未指定 `retry_after_secs` 時的暫停時間
+pub const DEFAULT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 錯誤的處理方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// 記錄後繼續
+    Continue,
+
+    /// 重新認證
+    Reauthenticate,
+
+    /// 暫停非必要的上報
+    Backoff(Duration),
+
+    /// 中止指定任務
+    AbortTask(String),
+
+    /// 停止 Agent
+    Shutdown,
+}
+
+impl ErrorAction {
+    /// 平台 `ERROR` 訊息的處理方式
+    pub fn for_platform_error(err: &ErrorPayload) -> Self {
+        match err.code.as_str() {
+            codes::AUTH_FAILED if err.recoverable => Self::Reauthenticate,
+            codes::AUTH_FAILED => Self::Shutdown,
+            codes::RATE_LIMITED => Self::Backoff(
+                context_u64(err, "retry_after_secs")
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_BACKOFF),
+            ),
+            codes::VERSION_UNSUPPORTED | codes::UNSUPPORTED_VERSION => Self::Shutdown,
+            codes::TASK_CANCELLED => match context_task_id(err) {
+                Some(task_id) => Self::AbortTask(task_id),
+                None => Self::Continue,
+            },
+            _ if err.recoverable => Self::Continue,
+            _ => match context_task_id(err) {
+                Some(task_id) => Self::AbortTask(task_id),
+                None => Self::Shutdown,
+            },
+        }
+    }
+
+    /// Agent 本地錯誤的處理方式
+    pub fn for_local_error(err: &Error) -> Self {
+        if err.is_recoverable() {
+            Self::Continue
+        } else {
+            Self::Shutdown
+        }
+    }
+}
+
+impl fmt::Display for ErrorAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Continue => write!(f, "continue"),
+            Self::Reauthenticate => write!(f, "re-authenticate"),
+            Self::Backoff(delay) => write!(f, "backoff {}s", delay.as_secs()),
+            Self::AbortTask(task_id) => write!(f, "abort task {}", task_id),
+            Self::Shutdown => write!(f, "shutdown"),
+        }
+    }
+}
+
+/// 錯誤來源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorSource {
+    /// 平台下發的 `ERROR` 訊息
+    Platform,
+
+    /// Agent 本地的錯誤
+    Agent,
+}
+
+/// 最近一次錯誤，供 `orban-agent status` 顯示
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorRecord {
+    pub source: ErrorSource,
+    pub code: String,
+    pub message: String,
+    pub recoverable: bool,
+    /// 採取的處理方式
+    pub action: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl ErrorRecord {
+    /// 記錄平台錯誤
+    pub fn platform(err: &ErrorPayload, action: &ErrorAction) -> Self {
+        Self {
+            source: ErrorSource::Platform,
+            code: err.code.clone(),
+            message: err.message.clone(),
+            recoverable: err.recoverable,
+            action: action.to_string(),
+            occurred_at: Utc::now(),
+        }
+    }
+
+    /// 記錄本地錯誤
+    pub fn local(err: &Error, action: &ErrorAction) -> Self {
+        Self {
+            source: ErrorSource::Agent,
+            code: err.error_code().to_string(),
+            message: err.to_string(),
+            recoverable: err.is_recoverable(),
+            action: action.to_string(),
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+/// 請求的回覆：ACK 表示平台已接受，ERROR 轉為 `Error::PlatformError`，`what` 為請求名稱
+pub(crate) fn ack_or_platform_error(reply: Message, what: &str) -> Result<()> {
+    match reply.payload {
+        MessagePayload::Ack(_) => Ok(()),
+        MessagePayload::Error(err) => Err(Error::PlatformError {
+            code: err.code,
+            message: err.message,
+        }),
+        _ => Err(Error::ProtocolError(format!(
+            "Unexpected {:?} reply to {}",
+            reply.message_type, what
+        ))),
+    }
+}
+
+fn context_u64(err: &ErrorPayload, key: &str) -> Option<u64> {
+    err.context.as_ref()?.get(key)?.as_u64()
+}
+
+fn context_task_id(err: &ErrorPayload) -> Option<String> {
+    err.context.as_ref()?.get("task_id")?.as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload(code: &str, recoverable: bool, context: Option<serde_json::Value>) -> ErrorPayload {
+        ErrorPayload {
+            code: code.to_string(),
+            message: "test".to_string(),
+            context,
+            recoverable,
+        }
+    }
+
+    #[test]
+    fn test_auth_failed_follows_recoverable_flag() {
+        assert_eq!(
+            ErrorAction::for_platform_error(&payload(codes::AUTH_FAILED, true, None)),
+            ErrorAction::Reauthenticate
+        );
+        assert_eq!(
+            ErrorAction::for_platform_error(&payload(codes::AUTH_FAILED, false, None)),
+            ErrorAction::Shutdown
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_backs_off() {
+        let err = payload(codes::RATE_LIMITED, true, Some(json!({ "retry_after_secs": 5 })));
+        assert_eq!(ErrorAction::for_platform_error(&err), ErrorAction::Backoff(Duration::from_secs(5)));
+
+        let err = payload(codes::RATE_LIMITED, true, None);
+        assert_eq!(ErrorAction::for_platform_error(&err), ErrorAction::Backoff(DEFAULT_BACKOFF));
+    }
+
+    #[test]
+    fn test_task_errors_abort_the_task() {
+        let context = Some(json!({ "task_id": "task-001" }));
+        let abort = ErrorAction::AbortTask("task-001".to_string());
+
+        assert_eq!(
+            ErrorAction::for_platform_error(&payload(codes::TASK_CANCELLED, true, context.clone())),
+            abort
+        );
+        assert_eq!(
+            ErrorAction::for_platform_error(&payload("VALIDATION_FAILED", false, context.clone())),
+            abort
+        );
+        assert_eq!(
+            ErrorAction::for_platform_error(&payload("DOWNLOAD_FAILED", true, context)),
+            ErrorAction::Continue
+        );
+    }
+
+    #[test]
+    fn test_unrecoverable_errors_shut_down() {
+        for code in [codes::VERSION_UNSUPPORTED, codes::UNSUPPORTED_VERSION] {
+            assert_eq!(ErrorAction::for_platform_error(&payload(code, true, None)), ErrorAction::Shutdown);
+        }
+        assert_eq!(
+            ErrorAction::for_platform_error(&payload("AGENT_BANNED", false, None)),
+            ErrorAction::Shutdown
+        );
+    }
+
+    #[test]
+    fn test_local_errors_use_is_recoverable() {
+        let err = Error::DownloadFailed("timeout".to_string());
+        assert_eq!(ErrorAction::for_local_error(&err), ErrorAction::Continue);
+
+        let record = ErrorRecord::local(&err, &ErrorAction::Continue);
+        assert_eq!(record.code, "DOWNLOAD_FAILED");
+        assert!(record.recoverable);
+
+        let err = Error::GPUNotFound;
+        assert_eq!(ErrorAction::for_local_error(&err), ErrorAction::Shutdown);
+    }
+}
@@ -0,0 +1,196 @@
+// gRPC 雙向串流連線
+//
+// 啟用 `grpc` 功能並設定 `transport = "grpc"` 時，OrbanClient 改經由 AgentGateway.Session
+// 雙向串流與平台通訊，訊息流與 WebSocket 相同（認證、註冊或 StateSync、任務、心跳、PoW）：
+// - 每次連線或重新認證建立新的 HTTP/2 連線並開啟一條串流
+// - 訊息一律以 Protocol Buffers 編碼，由 HTTP/2 分框，不使用 zstd 壓縮
+// - 經由出站代理與共用的 TLS 設定建立連線，TLS 以 ALPN 協商 `h2`
+// - 保活使用 HTTP/2 PING，間隔與逾時沿用 `keepalive_*` 設定
+// - 認證完成後出站佇列接續作為請求串流，讀取任務將回應串流的訊息送入入站通道
+
+use super::connection::{Connection, Inbound, KeepaliveSettings, OutboundQueue};
+use super::orban_protocol::Message;
+use super::outbox::Outbox;
+use super::proto::pb;
+use super::proto::pb::agent_gateway_client::AgentGatewayClient;
+use super::proxy::ProxySettings;
+use super::recorder::{Direction, SessionRecorder};
+use super::tls::TlsSettings;
+use super::transport::http_base_url;
+use crate::config::NetworkConfig;
+use crate::error::{Error, Result};
+
+use futures::StreamExt;
+use reqwest::Url;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tonic::transport::{Channel, Endpoint, Uri};
+use tonic::Streaming;
+use tracing::{debug, error, info, warn};
+
+/// HTTP/2 的 ALPN 協議名稱
+const ALPN_H2: &[u8] = b"h2";
+
+/// 認證期間的請求通道容量
+const HANDSHAKE_CAPACITY: usize = 4;
+
+/// 已開啟、尚未啟動讀取任務的串流
+pub(crate) struct GrpcLink {
+    channel: Channel,
+    handshake: mpsc::Sender<pb::Message>,
+    responses: Streaming<pb::Message>,
+    shutdown: oneshot::Sender<()>,
+}
+
+impl GrpcLink {
+    /// 連接平台並開啟 AgentGateway.Session 串流
+    ///
+    /// 請求串流先送出認證期間的訊息，`spawn` 後改由出站佇列供應
+    pub(crate) async fn open(
+        url: &str,
+        network: &NetworkConfig,
+        proxy: Arc<ProxySettings>,
+        tls: &TlsSettings,
+        outbound: &OutboundQueue,
+        outbox: Arc<Outbox>,
+        recorder: Arc<SessionRecorder>,
+    ) -> Result<Self> {
+        let base = http_base_url(url);
+        let target = Url::parse(&base).map_err(|e| Error::ConnectionFailed(format!("Invalid URL: {}", e)))?;
+        let timeout = Duration::from_secs(network.connection_timeout_secs);
+
+        let mut endpoint = Endpoint::from_shared(base.clone())
+            .map_err(|e| Error::ConnectionFailed(format!("Invalid URL: {}", e)))?
+            .connect_timeout(timeout);
+        if let Some(keepalive) = KeepaliveSettings::from_config(network) {
+            endpoint = endpoint
+                .http2_keep_alive_interval(keepalive.interval)
+                .keep_alive_timeout(keepalive.timeout)
+                .keep_alive_while_idle(true);
+        }
+
+        // 與 WebSocket 相同：先經由代理建立通道，再於其上進行 TLS 握手
+        let tls = tls.with_alpn(&[ALPN_H2]);
+        let connector = tower::service_fn(move |_: Uri| {
+            let proxy = proxy.clone();
+            let tls = tls.clone();
+            let target = target.clone();
+            async move {
+                let stream = proxy.connect(&target, timeout).await?;
+                tls.wrap(stream, &target).await
+            }
+        });
+
+        let channel = endpoint.connect_with_connector(connector).await.map_err(connect_error)?;
+
+        let (handshake, handshake_rx) = mpsc::channel(HANDSHAKE_CAPACITY);
+        let (shutdown, shutdown_rx) = oneshot::channel();
+
+        // 認證期間的請求通道關閉後，接續出站佇列
+        let handshake_stream = futures::stream::unfold(handshake_rx, |mut rx| async move {
+            rx.recv().await.map(|msg| (msg, rx))
+        });
+        let queued = outbound.stream(shutdown_rx, outbox, recorder).filter_map(|msg| async move {
+            match pb::Message::try_from(msg.clone()) {
+                Ok(encoded) => Some(encoded),
+                Err(e) => {
+                    error!("Failed to encode {:?} message {}: {}", msg.message_type, msg.message_id, e);
+                    None
+                }
+            }
+        });
+
+        let mut client = AgentGatewayClient::new(channel.clone())
+            .max_decoding_message_size(network.inbound.max_frame_bytes);
+        let response = tokio::time::timeout(timeout, client.session(handshake_stream.chain(queued)))
+            .await
+            .map_err(|_| Error::RequestTimeout("gRPC session".to_string()))?
+            .map_err(|status| Error::ConnectionFailed(format!("gRPC session rejected: {}", status)))?;
+
+        info!("gRPC stream established with {}", base);
+
+        Ok(Self {
+            channel,
+            handshake,
+            responses: response.into_inner(),
+            shutdown,
+        })
+    }
+
+    /// 送出認證期間的訊息
+    pub(crate) async fn send(&mut self, msg: &Message) -> Result<()> {
+        let encoded = pb::Message::try_from(msg.clone())?;
+        self.handshake
+            .send(encoded)
+            .await
+            .map_err(|_| Error::ConnectionFailed("gRPC stream closed".to_string()))
+    }
+
+    /// 讀取下一則訊息（認證期間使用）
+    pub(crate) async fn next(&mut self) -> Result<Message> {
+        match self.responses.message().await {
+            Ok(Some(msg)) => Message::try_from(msg),
+            Ok(None) => Err(Error::ConnectionFailed("gRPC stream closed by server".to_string())),
+            Err(status) => Err(Error::ConnectionFailed(format!("gRPC stream failed: {}", status))),
+        }
+    }
+
+    /// 結束認證階段並啟動讀取任務
+    pub(crate) fn spawn(self, generation: u64, inbound: mpsc::Sender<Inbound>, recorder: Arc<SessionRecorder>) -> Connection {
+        let Self {
+            channel,
+            handshake,
+            responses,
+            shutdown,
+        } = self;
+        drop(handshake);
+
+        let reader = tokio::spawn(read_loop(responses, channel, generation, inbound, recorder));
+        Connection::streaming(shutdown, reader)
+    }
+}
+
+/// 讀取任務，持有 HTTP/2 連線直到結束
+async fn read_loop(
+    mut responses: Streaming<pb::Message>,
+    _channel: Channel,
+    generation: u64,
+    inbound: mpsc::Sender<Inbound>,
+    recorder: Arc<SessionRecorder>,
+) {
+    loop {
+        let item = match responses.message().await {
+            Ok(Some(msg)) => Message::try_from(msg).inspect(|msg| {
+                debug!("Received {:?} message {}", msg.message_type, msg.message_id);
+                recorder.record(Direction::Inbound, msg);
+            }),
+            Ok(None) => {
+                warn!("gRPC stream closed by server");
+                Err(Error::ConnectionFailed("Connection closed".to_string()))
+            }
+            Err(status) => Err(Error::ConnectionFailed(format!("gRPC stream failed: {}", status))),
+        };
+
+        let lost = matches!(item, Err(Error::ConnectionFailed(_)));
+        if inbound.send((generation, item)).await.is_err() || lost {
+            return;
+        }
+    }
+}
+
+/// 保留連接器回報的 TLS 錯誤，其餘視為連線失敗
+fn connect_error(e: tonic::transport::Error) -> Error {
+    let mut reasons = Vec::new();
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&e);
+
+    while let Some(err) = source {
+        if let Some(Error::TlsError(reason)) = err.downcast_ref::<Error>() {
+            return Error::TlsError(reason.clone());
+        }
+        reasons.push(err.to_string());
+        source = err.source();
+    }
+
+    Error::ConnectionFailed(format!("gRPC connection failed: {}", reasons.join(": ")))
+}
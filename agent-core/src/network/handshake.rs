@@ -0,0 +1,156 @@
+// 協議版本與功能協商
+//
+// Agent 在 AUTH_RESPONSE 中提供支援的協議版本、編碼格式與可選功能，
+// 平台在 AUTH_SUCCESS 中回覆選定的版本與雙方皆支援的功能：
+// - 版本取雙方皆支援的最高版本，沒有交集時平台以 UNSUPPORTED_VERSION 拒絕
+// - 未回覆協商結果的舊版平台視為協議 v1，僅支援 StateSync
+// - 無法識別的功能名稱一律忽略
+
+use super::codec::WireCodec;
+use super::orban_protocol::{ProtocolFeature, ProtocolOffer, ProtocolSelection};
+use crate::error::{Error, Result};
+
+use tracing::debug;
+
+/// 目前的協議版本
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Agent 支援的協議版本
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[u32] = &[PROTOCOL_VERSION];
+
+/// Agent 支援的可選功能
+pub const AGENT_FEATURES: &[ProtocolFeature] = &[ProtocolFeature::StateSync, ProtocolFeature::Compression];
+
+/// 協商後的協議
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedProtocol {
+    pub version: u32,
+    pub codec: WireCodec,
+    pub features: Vec<ProtocolFeature>,
+}
+
+impl NegotiatedProtocol {
+    /// 是否啟用指定功能
+    pub fn supports(&self, feature: ProtocolFeature) -> bool {
+        self.features.contains(&feature)
+    }
+}
+
+/// Agent 提供的協議能力，`features` 為配置中啟用的可選功能
+pub fn offer(preferred: WireCodec, features: &[ProtocolFeature]) -> ProtocolOffer {
+    ProtocolOffer {
+        versions: SUPPORTED_PROTOCOL_VERSIONS.to_vec(),
+        codecs: WireCodec::preference(preferred)
+            .iter()
+            .map(|codec| codec.subprotocol().to_string())
+            .collect(),
+        features: features.to_vec(),
+    }
+}
+
+/// 平台端依自身支援的版本與功能選擇協議，沒有共同版本時返回 `None`
+pub fn select_protocol(
+    offer: &ProtocolOffer,
+    versions: &[u32],
+    features: &[ProtocolFeature],
+) -> Option<ProtocolSelection> {
+    let version = offer.versions.iter().filter(|v| versions.contains(v)).max().copied()?;
+
+    let features = offer
+        .features
+        .iter()
+        .filter(|f| **f != ProtocolFeature::Unknown && features.contains(f))
+        .copied()
+        .collect();
+
+    Some(ProtocolSelection { version, features })
+}
+
+/// 驗證平台的選擇
+///
+/// 平台未回覆協商結果時視為舊版平台
+pub fn accept(
+    selection: Option<ProtocolSelection>,
+    codec: WireCodec,
+    offered: &[ProtocolFeature],
+) -> Result<NegotiatedProtocol> {
+    let Some(selection) = selection else {
+        return Ok(NegotiatedProtocol {
+            version: PROTOCOL_VERSION,
+            codec,
+            features: vec![ProtocolFeature::StateSync],
+        });
+    };
+
+    if !SUPPORTED_PROTOCOL_VERSIONS.contains(&selection.version) {
+        return Err(Error::ProtocolError(format!(
+            "Platform selected unsupported protocol version {} (supported: {:?})",
+            selection.version, SUPPORTED_PROTOCOL_VERSIONS
+        )));
+    }
+
+    // 只啟用自己提供過的功能
+    let (features, ignored): (Vec<_>, Vec<_>) = selection
+        .features
+        .into_iter()
+        .partition(|f| offered.contains(f));
+
+    if !ignored.is_empty() {
+        debug!("Ignoring features not offered by agent: {:?}", ignored);
+    }
+
+    Ok(NegotiatedProtocol {
+        version: selection.version,
+        codec,
+        features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_highest_common_version() {
+        let offer = ProtocolOffer {
+            versions: vec![1, 2, 3],
+            codecs: vec![WireCodec::Json.subprotocol().to_string()],
+            features: vec![ProtocolFeature::StateSync, ProtocolFeature::Compression, ProtocolFeature::Unknown],
+        };
+
+        let selection = select_protocol(&offer, &[1, 2], &[ProtocolFeature::StateSync, ProtocolFeature::MessageSigning]).unwrap();
+        assert_eq!(selection.version, 2);
+        assert_eq!(selection.features, [ProtocolFeature::StateSync]);
+
+        assert!(select_protocol(&offer, &[4], &[]).is_none());
+    }
+
+    #[test]
+    fn test_accept_selection() {
+        // 舊版平台
+        let legacy = accept(None, WireCodec::Json, AGENT_FEATURES).unwrap();
+        assert_eq!(legacy.version, PROTOCOL_VERSION);
+        assert!(legacy.supports(ProtocolFeature::StateSync));
+
+        let negotiated = accept(
+            Some(ProtocolSelection {
+                version: PROTOCOL_VERSION,
+                features: vec![ProtocolFeature::Compression],
+            }),
+            WireCodec::Protobuf,
+            &[ProtocolFeature::StateSync],
+        )
+        .unwrap();
+        assert!(negotiated.features.is_empty());
+
+        let unsupported = accept(
+            Some(ProtocolSelection {
+                version: PROTOCOL_VERSION + 1,
+                features: vec![],
+            }),
+            WireCodec::Protobuf,
+            AGENT_FEATURES,
+        );
+        assert!(matches!(unsupported, Err(Error::ProtocolError(_))));
+    }
+}
@@ -0,0 +1,299 @@
+// Agent 身分
+//
+// Agent ID 由身分金鑰的公鑰推導：`agent-` 接上公鑰 SHA-256 前 16 字節的十六進位，
+// 同一把金鑰在任何機器上都得到相同的 ID，配置只提供顯示名稱（display_name）。
+//
+// data_dir/identity.json 記錄上次啟動時的 Agent ID 與公鑰。金鑰更換、或由以配置指定
+// agent_id 的舊版升級時，啟動會遷移以舊 ID 寫入的資料：
+// - 發件匣中帶有舊 agent_id 的訊息改為目前的 ID
+// - 已簽名但無法以目前公鑰驗證的訊息以目前的金鑰重新簽名
+// - 舊 ID 保留於 previous_ids
+//
+// 記錄也保存註冊時提交的離線備份公鑰（`keys backup`），供身分金鑰遺失時恢復（見 rotation）
+
+use super::auth::Authenticator;
+use super::orban_protocol::{Message, MessagePayload};
+use super::outbox::Outbox;
+use super::signing;
+use crate::error::{Error, Result};
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 身分記錄文件名稱（位於 data_dir 下）
+const IDENTITY_FILE: &str = "identity.json";
+
+/// 從公鑰推導 Agent ID
+pub fn derive_agent_id(public_key: &[u8; 32]) -> String {
+    let hash = Sha256::digest(public_key);
+
+    // 使用哈希的前 16 字節作為 ID
+    format!("agent-{}", hex::encode(&hash[..16]))
+}
+
+/// 從 base64 編碼的公鑰推導 Agent ID（供平台核對 AUTH_RESPONSE）
+pub fn agent_id_for_public_key(public_key: &str) -> Result<String> {
+    let key_bytes: [u8; 32] = general_purpose::STANDARD
+        .decode(public_key)
+        .map_err(|e| Error::EncryptionError(e.to_string()))?
+        .try_into()
+        .map_err(|_| Error::EncryptionError("Public key must be 32 bytes".to_string()))?;
+
+    Ok(derive_agent_id(&key_bytes))
+}
+
+/// 上次啟動時使用的身分
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityRecord {
+    pub agent_id: String,
+
+    /// 公鑰 (base64)
+    pub public_key: String,
+
+    /// 先前使用過的 Agent ID，依遷移順序排列
+    #[serde(default)]
+    pub previous_ids: Vec<String>,
+
+    /// 離線備份金鑰的公鑰 (base64)，隨註冊提交給平台
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_public_key: Option<String>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+impl IdentityRecord {
+    /// 身分記錄文件
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(IDENTITY_FILE)
+    }
+
+    /// 讀取身分記錄，尚未記錄時返回 None
+    pub fn load(data_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(data_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&fs::read(path)?)?))
+    }
+
+    /// 寫入身分記錄
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        fs::create_dir_all(data_dir)?;
+        let path = Self::path(data_dir);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// 設定離線備份金鑰，下次註冊時提交給平台
+pub fn set_backup_public_key(data_dir: &Path, authenticator: &Authenticator, backup_public_key: String) -> Result<()> {
+    let mut record = IdentityRecord::load(data_dir)?
+        .filter(|record| record.public_key == authenticator.public_key_base64())
+        .unwrap_or_else(|| IdentityRecord {
+            agent_id: authenticator.agent_id().to_string(),
+            public_key: authenticator.public_key_base64(),
+            previous_ids: Vec::new(),
+            backup_public_key: None,
+            updated_at: Utc::now(),
+        });
+
+    record.backup_public_key = Some(backup_public_key);
+    record.updated_at = Utc::now();
+    record.save(data_dir)
+}
+
+/// 核對本地資料與目前的身分，遷移以舊 Agent ID 寫入的資料，返回目前的身分記錄
+pub(crate) fn reconcile(data_dir: &Path, authenticator: &Authenticator, outbox: &Outbox) -> Result<IdentityRecord> {
+    let agent_id = authenticator.agent_id();
+    let public_key = authenticator.public_key_base64();
+    let record = IdentityRecord::load(data_dir).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable identity record: {}", e);
+        None
+    });
+
+    // 舊版未記錄身分，只能由發件匣中的訊息得知舊 ID
+    let mut found = BTreeSet::new();
+    if let Some(record) = &record {
+        if record.agent_id != agent_id {
+            found.insert(record.agent_id.clone());
+        }
+    }
+
+    let rekeyed = record.as_ref().is_some_and(|record| record.public_key != public_key);
+    let migrated = outbox.rewrite(|msg| migrate_message(msg, authenticator, rekeyed, &mut found))?;
+
+    let mut previous_ids = record.as_ref().map(|record| record.previous_ids.clone()).unwrap_or_default();
+    let record_backup = record.as_ref().and_then(|record| record.backup_public_key.clone());
+    let mut changed = false;
+    for id in &found {
+        if !previous_ids.contains(id) {
+            previous_ids.push(id.clone());
+            changed = true;
+        }
+    }
+
+    if changed || migrated > 0 {
+        info!(
+            "Migrated local data from agent ID {} to {} ({} outbox message(s))",
+            found.iter().cloned().collect::<Vec<_>>().join(", "),
+            agent_id,
+            migrated
+        );
+    }
+
+    let unchanged = record.filter(|record| !changed && record.public_key == public_key);
+    match unchanged {
+        Some(record) => Ok(record),
+        None => {
+            let current = IdentityRecord {
+                agent_id: agent_id.to_string(),
+                public_key,
+                previous_ids,
+                // 備份金鑰在輪換後仍然有效
+                backup_public_key: record_backup,
+                updated_at: Utc::now(),
+            };
+            current.save(data_dir)?;
+            Ok(current)
+        }
+    }
+}
+
+/// 將訊息改為目前的身分，返回是否有變更
+fn migrate_message(
+    msg: &mut Message,
+    authenticator: &Authenticator,
+    rekeyed: bool,
+    found: &mut BTreeSet<String>,
+) -> Result<bool> {
+    let mut changed = false;
+
+    if let Some(agent_id) = agent_id_mut(&mut msg.payload) {
+        if agent_id != authenticator.agent_id() {
+            found.insert(std::mem::replace(agent_id, authenticator.agent_id().to_string()));
+            changed = true;
+        }
+    }
+
+    // 簽名涵蓋 agent_id；金鑰更換後舊簽名也無法以目前的公鑰驗證
+    if msg.signature.is_some()
+        && (changed || rekeyed)
+        && signing::verify(msg, &authenticator.public_key_base64()).is_err()
+    {
+        msg.signature = Some(authenticator.sign(&signing::signing_input(msg)?));
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+/// Agent 送出的負載中的 agent_id
+fn agent_id_mut(payload: &mut MessagePayload) -> Option<&mut String> {
+    match payload {
+        MessagePayload::AuthResponse(p) => Some(&mut p.agent_id),
+        MessagePayload::AgentRegister(p) => Some(&mut p.agent_id),
+        MessagePayload::TaskAccept(p) => Some(&mut p.agent_id),
+        MessagePayload::Heartbeat(p) => Some(&mut p.agent_id),
+        MessagePayload::MetricsBatch(p) => Some(&mut p.agent_id),
+        MessagePayload::StateSync(p) => Some(&mut p.agent_id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{
+        create_task_failed, AggregatedMetrics, MessageType, MetricsBatchPayload, TaskErrorInfo, TimeRange,
+    };
+
+    fn metrics_batch(agent_id: &str) -> Message {
+        Message::new(
+            MessageType::MetricsBatch,
+            MessagePayload::MetricsBatch(MetricsBatchPayload {
+                agent_id: agent_id.to_string(),
+                time_range: TimeRange {
+                    start: Utc::now(),
+                    end: Utc::now(),
+                },
+                aggregated_metrics: AggregatedMetrics {
+                    tasks_completed: 1,
+                    tasks_failed: 0,
+                    total_gpu_hours: 0.5,
+                    avg_gpu_utilization: 0.9,
+                    total_energy_kwh: 0.2,
+                    earnings_usd: Default::default(),
+                },
+            }),
+        )
+    }
+
+    fn failed(task_id: &str) -> Message {
+        create_task_failed(
+            task_id.to_string(),
+            TaskErrorInfo {
+                code: "OOM_ERROR".to_string(),
+                message: "Out of memory".to_string(),
+                details: String::new(),
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn test_agent_id_derived_from_public_key() {
+        let auth = Authenticator::generate();
+        assert_eq!(agent_id_for_public_key(&auth.public_key_base64()).unwrap(), auth.agent_id());
+        assert!(agent_id_for_public_key("c2hvcnQ=").is_err());
+    }
+
+    #[test]
+    fn test_outbox_migrated_to_key_derived_id() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let outbox = Outbox::open(data_dir.path(), 10).unwrap();
+
+        // 舊版以配置中的 agent_id 與舊金鑰寫入
+        let old_key = Authenticator::generate();
+        outbox.persist(&metrics_batch("agent-myhost")).unwrap();
+        outbox.persist(&signing::sign(failed("task-001"), &old_key).unwrap()).unwrap();
+        IdentityRecord {
+            agent_id: "agent-myhost".to_string(),
+            public_key: old_key.public_key_base64(),
+            previous_ids: Vec::new(),
+            backup_public_key: Some("backup".to_string()),
+            updated_at: Utc::now(),
+        }
+        .save(data_dir.path())
+        .unwrap();
+
+        let auth = Authenticator::generate();
+        assert_eq!(reconcile(data_dir.path(), &auth, &outbox).unwrap().previous_ids, vec!["agent-myhost".to_string()]);
+
+        for msg in outbox.pending().unwrap() {
+            match &msg.payload {
+                MessagePayload::MetricsBatch(payload) => assert_eq!(payload.agent_id, auth.agent_id()),
+                _ => {
+                    assert_eq!(msg.message_type, MessageType::TaskFailed);
+                    signing::verify(&msg, &auth.public_key_base64()).unwrap();
+                }
+            }
+        }
+
+        let record = IdentityRecord::load(data_dir.path()).unwrap().unwrap();
+        assert_eq!(record.agent_id, auth.agent_id());
+        assert_eq!(record.previous_ids, vec!["agent-myhost".to_string()]);
+        assert_eq!(record.backup_public_key.as_deref(), Some("backup"));
+
+        // 身分未變時不再遷移
+        assert_eq!(reconcile(data_dir.path(), &auth, &outbox).unwrap(), record);
+        assert_eq!(IdentityRecord::load(data_dir.path()).unwrap().unwrap().updated_at, record.updated_at);
+    }
+}
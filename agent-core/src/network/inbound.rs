@@ -0,0 +1,258 @@
+// 入站訊息防護
+//
+// 平台下發的訊息在交給 Agent 處理前依序檢查：
+// - 訊框大小：超過 `max_frame_bytes` 的訊框不解碼（壓縮訊框解壓後同樣受限）
+// - 時鐘偏差：`timestamp` 與本機時間相差超過 `max_clock_skew_secs` 時拒絕
+// - 重放：近期已處理的 `message_id` 再次出現時拒絕，保留時鐘偏差容許範圍內的紀錄
+// - 速率：依訊息類型限制每分鐘的數量
+//
+// 違規的訊息以 `Error::InboundRejected` 回報後捨棄，不影響連線上的其他訊息；
+// 超過大小上限的訊框無法完整讀取，WebSocket 連線隨即重連，長輪詢則重新認證
+
+use super::orban_protocol::{Message, MessageType};
+use crate::config::InboundConfig;
+use crate::error::{Error, Result};
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 速率限制的時間窗
+pub const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// 違反的防護規則
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InboundViolation {
+    /// 訊框超過大小上限
+    FrameTooLarge { size: usize, limit: usize },
+
+    /// 時間戳與本機時間相差過大（正值表示來自未來）
+    ClockSkew { message_id: String, skew_secs: i64 },
+
+    /// 重複的 message_id
+    Duplicate { message_id: String },
+
+    /// 超過該類型每分鐘的數量上限
+    RateLimited { message_type: MessageType, limit: u32 },
+}
+
+impl fmt::Display for InboundViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FrameTooLarge { size, limit } => {
+                write!(f, "frame of {} bytes exceeds limit of {} bytes", size, limit)
+            }
+            Self::ClockSkew { message_id, skew_secs } => {
+                write!(f, "message {} timestamp is {}s off local clock", message_id, skew_secs)
+            }
+            Self::Duplicate { message_id } => write!(f, "duplicate message {}", message_id),
+            Self::RateLimited { message_type, limit } => {
+                write!(f, "more than {} {:?} messages per minute", limit, message_type)
+            }
+        }
+    }
+}
+
+impl From<InboundViolation> for Error {
+    fn from(violation: InboundViolation) -> Self {
+        Error::InboundRejected(violation)
+    }
+}
+
+#[derive(Debug, Default)]
+struct GuardState {
+    /// 已處理的 message_id
+    seen: HashSet<String>,
+    /// 依接收順序排列的 (時間戳, message_id)
+    seen_order: VecDeque<(DateTime<Utc>, String)>,
+    /// 各類型在時間窗內的接收時間
+    rates: HashMap<MessageType, VecDeque<Instant>>,
+}
+
+/// 入站訊息防護，跨連線保留重放紀錄
+#[derive(Debug)]
+pub(crate) struct InboundGuard {
+    max_frame_bytes: usize,
+    max_clock_skew: chrono::Duration,
+    dedup_capacity: usize,
+    rate_limits: HashMap<MessageType, u32>,
+    state: Mutex<GuardState>,
+}
+
+impl InboundGuard {
+    pub(crate) fn from_config(config: &InboundConfig) -> Self {
+        Self {
+            max_frame_bytes: config.max_frame_bytes,
+            max_clock_skew: chrono::Duration::seconds(config.max_clock_skew_secs as i64),
+            dedup_capacity: config.dedup_capacity.max(1),
+            rate_limits: config.rate_limits.clone(),
+            state: Mutex::new(GuardState::default()),
+        }
+    }
+
+    /// 檢查訊框大小
+    pub(crate) fn check_size(&self, size: usize) -> Result<()> {
+        if size > self.max_frame_bytes {
+            return Err(InboundViolation::FrameTooLarge {
+                size,
+                limit: self.max_frame_bytes,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// 檢查已解碼的訊息，通過時記錄其 message_id
+    pub(crate) fn check(&self, msg: &Message) -> Result<()> {
+        self.check_at(msg, Utc::now(), Instant::now())
+    }
+
+    fn check_at(&self, msg: &Message, now: DateTime<Utc>, instant: Instant) -> Result<()> {
+        let skew = msg.timestamp - now;
+        if skew.abs() > self.max_clock_skew {
+            return Err(InboundViolation::ClockSkew {
+                message_id: msg.message_id.clone(),
+                skew_secs: skew.num_seconds(),
+            }
+            .into());
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        // 超出時鐘偏差範圍的紀錄已不需要，訊息本身會被拒絕
+        let horizon = now - self.max_clock_skew * 2;
+        while let Some((timestamp, _)) = state.seen_order.front() {
+            if *timestamp >= horizon && state.seen_order.len() < self.dedup_capacity {
+                break;
+            }
+            let (_, message_id) = state.seen_order.pop_front().unwrap();
+            state.seen.remove(&message_id);
+        }
+
+        if state.seen.contains(&msg.message_id) {
+            return Err(InboundViolation::Duplicate {
+                message_id: msg.message_id.clone(),
+            }
+            .into());
+        }
+
+        if let Some(&limit) = self.rate_limits.get(&msg.message_type) {
+            let window = state.rates.entry(msg.message_type).or_default();
+            while window.front().is_some_and(|at| instant.duration_since(*at) >= RATE_WINDOW) {
+                window.pop_front();
+            }
+
+            if window.len() >= limit as usize {
+                return Err(InboundViolation::RateLimited {
+                    message_type: msg.message_type,
+                    limit,
+                }
+                .into());
+            }
+            window.push_back(instant);
+        }
+
+        state.seen.insert(msg.message_id.clone());
+        state.seen_order.push_back((msg.timestamp, msg.message_id.clone()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::create_heartbeat;
+    use crate::network::AgentStatus;
+
+    fn guard(configure: impl FnOnce(&mut InboundConfig)) -> InboundGuard {
+        let mut config = InboundConfig::default();
+        configure(&mut config);
+        InboundGuard::from_config(&config)
+    }
+
+    fn message(message_type: MessageType) -> Message {
+        let mut msg = create_heartbeat("agent-001".to_string(), AgentStatus::Idle, None, Vec::new(), 0);
+        msg.message_type = message_type;
+        msg
+    }
+
+    fn violation(result: Result<()>) -> InboundViolation {
+        match result {
+            Err(Error::InboundRejected(violation)) => violation,
+            other => panic!("expected violation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_frame_size_limit() {
+        let guard = guard(|config| config.max_frame_bytes = 1024);
+        assert!(guard.check_size(1024).is_ok());
+        assert_eq!(
+            violation(guard.check_size(1025)),
+            InboundViolation::FrameTooLarge { size: 1025, limit: 1024 }
+        );
+    }
+
+    #[test]
+    fn test_clock_skew_window() {
+        let guard = guard(|config| config.max_clock_skew_secs = 60);
+        let now = Utc::now();
+
+        let mut msg = message(MessageType::TaskAssign);
+        msg.timestamp = now - chrono::Duration::seconds(59);
+        assert!(guard.check_at(&msg, now, Instant::now()).is_ok());
+
+        let mut stale = message(MessageType::TaskAssign);
+        stale.timestamp = now - chrono::Duration::seconds(3600);
+        assert!(matches!(
+            violation(guard.check_at(&stale, now, Instant::now())),
+            InboundViolation::ClockSkew { skew_secs: -3600, .. }
+        ));
+
+        let mut future = message(MessageType::TaskAssign);
+        future.timestamp = now + chrono::Duration::seconds(120);
+        assert!(guard.check_at(&future, now, Instant::now()).is_err());
+    }
+
+    #[test]
+    fn test_duplicates_rejected_within_window() {
+        let guard = guard(|config| config.dedup_capacity = 2);
+        let first = message(MessageType::PowChallenge);
+
+        assert!(guard.check(&first).is_ok());
+        assert_eq!(
+            violation(guard.check(&first)),
+            InboundViolation::Duplicate { message_id: first.message_id.clone() }
+        );
+
+        // 超出容量後最舊的紀錄被移除
+        assert!(guard.check(&message(MessageType::PowChallenge)).is_ok());
+        assert!(guard.check(&message(MessageType::PowChallenge)).is_ok());
+        assert!(guard.check(&first).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_per_type() {
+        let guard = guard(|config| {
+            config.rate_limits = HashMap::from([(MessageType::TaskAssign, 2)]);
+        });
+        let start = Instant::now();
+        let now = Utc::now();
+
+        assert!(guard.check_at(&message(MessageType::TaskAssign), now, start).is_ok());
+        assert!(guard.check_at(&message(MessageType::TaskAssign), now, start).is_ok());
+
+        let limited = message(MessageType::TaskAssign);
+        assert_eq!(
+            violation(guard.check_at(&limited, now, start)),
+            InboundViolation::RateLimited { message_type: MessageType::TaskAssign, limit: 2 }
+        );
+        // 被限流的訊息未記錄，平台稍後可重送
+        assert!(guard.check_at(&limited, now, start + RATE_WINDOW).is_ok());
+
+        // 未設限的類型不受影響
+        assert!(guard.check_at(&message(MessageType::EarningsRecord), now, start).is_ok());
+    }
+}
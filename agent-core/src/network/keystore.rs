@@ -0,0 +1,574 @@
+// 身分金鑰的保存方式
+//
+// `private_key_path` 依內容區分三種格式：
+// - 32 字節的原始私鑰（`storage = "file"`）
+// - JSON 加密金鑰：argon2id 由密碼推導金鑰，XChaCha20-Poly1305 加密私鑰（`storage = "encrypted"`）
+// - JSON 指標：私鑰保存於系統金鑰環，文件僅記錄項目名稱（`storage = "secret-service"` 或
+//   `"kernel-keyring"`，需以 `keyring` 功能編譯）
+//
+// 載入時依文件格式解讀，`storage` 僅決定新金鑰的保存方式；
+// 啟動時原始私鑰會依 `storage` 轉存（`migrate`）。加密金鑰的密碼依序取自
+// 啟動時解鎖取得的密碼、`passphrase_env` 指定的環境變數、`passphrase_file`
+
+use super::auth::{self, Authenticator};
+use crate::config::KeyConfig;
+use crate::error::{Error, Result};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// 加密金鑰文件的格式版本
+const FORMAT_VERSION: u32 = 1;
+
+/// 加密時的附加驗證資料，避免密文被挪作他用
+const KEY_AAD: &[u8] = b"orban-agent-key-v1";
+
+/// 金鑰環項目的服務名稱
+#[cfg_attr(not(feature = "keyring"), allow(dead_code))]
+const KEYRING_SERVICE: &str = "orban-agent";
+
+/// argon2id 預設參數（19 MiB、2 次迭代，OWASP 建議值）
+const DEFAULT_MEMORY_KIB: u32 = 19 * 1024;
+const DEFAULT_ITERATIONS: u32 = 2;
+const DEFAULT_PARALLELISM: u32 = 1;
+
+/// 新金鑰的保存方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyStorage {
+    /// 原始私鑰文件（僅擁有者可讀寫）
+    #[default]
+    File,
+
+    /// 以密碼加密的私鑰文件
+    Encrypted,
+
+    /// Linux Secret Service（需以 `keyring` 功能編譯）
+    SecretService,
+
+    /// Linux 核心金鑰環，供無桌面會話的後台進程讀取，並同時保存於 Secret Service（需 `keyring` 功能）
+    KernelKeyring,
+}
+
+impl fmt::Display for KeyStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KeyStorage::File => "file",
+            KeyStorage::Encrypted => "encrypted",
+            KeyStorage::SecretService => "secret-service",
+            KeyStorage::KernelKeyring => "kernel-keyring",
+        })
+    }
+}
+
+/// 解鎖加密金鑰的密碼，釋放時清除
+#[derive(Clone)]
+pub struct Passphrase(Zeroizing<String>);
+
+impl Passphrase {
+    pub fn new(passphrase: String) -> Self {
+        Self(Zeroizing::new(passphrase))
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Passphrase(..)")
+    }
+}
+
+/// 非原始私鑰的金鑰文件內容
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    #[serde(flatten)]
+    key: StoredKey,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "kebab-case")]
+enum StoredKey {
+    /// 以密碼加密的私鑰
+    Encrypted {
+        kdf: KdfParams,
+        nonce: String,
+        ciphertext: String,
+    },
+
+    /// 保存於系統金鑰環的私鑰
+    Keyring { backend: KeyStorage, account: String },
+}
+
+/// argon2id 參數
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+impl KdfParams {
+    fn generate() -> Self {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        Self {
+            algorithm: "argon2id".to_string(),
+            memory_kib: DEFAULT_MEMORY_KIB,
+            iterations: DEFAULT_ITERATIONS,
+            parallelism: DEFAULT_PARALLELISM,
+            salt: general_purpose::STANDARD.encode(salt),
+        }
+    }
+
+    /// 由密碼推導 32 字節的加密金鑰
+    fn derive(&self, passphrase: &Passphrase) -> Result<Zeroizing<[u8; 32]>> {
+        if self.algorithm != "argon2id" {
+            return Err(Error::EncryptionError(format!("Unsupported key derivation: {}", self.algorithm)));
+        }
+
+        let salt = decode(&self.salt)?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// 金鑰文件的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// 原始私鑰
+    Plain,
+
+    /// 加密私鑰
+    Encrypted,
+
+    /// 保存於金鑰環
+    Keyring(KeyStorage),
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyFormat::Plain => f.write_str("file (unencrypted)"),
+            KeyFormat::Encrypted => f.write_str("encrypted"),
+            KeyFormat::Keyring(backend) => write!(f, "{}", backend),
+        }
+    }
+}
+
+/// 讀取金鑰文件，檢查權限並解析格式
+fn read_key_file(path: &Path) -> Result<(Vec<u8>, Option<KeyFile>)> {
+    if !path.exists() {
+        return Err(Error::FileNotFound(format!(
+            "{} (run `orban-agent keys init` to generate a key)",
+            path.display()
+        )));
+    }
+
+    auth::check_key_permissions(path)?;
+    let contents = fs::read(path)?;
+    if contents.len() == 32 {
+        return Ok((contents, None));
+    }
+
+    let file: KeyFile = serde_json::from_slice(&contents)
+        .map_err(|e| Error::InvalidConfig(format!("Unrecognized key file {}: {}", path.display(), e)))?;
+    if file.version != FORMAT_VERSION {
+        return Err(Error::InvalidConfig(format!(
+            "Unsupported key file version {} in {}",
+            file.version,
+            path.display()
+        )));
+    }
+    Ok((contents, Some(file)))
+}
+
+/// 金鑰文件的格式
+pub fn format(path: &Path) -> Result<KeyFormat> {
+    Ok(match read_key_file(path)?.1 {
+        None => KeyFormat::Plain,
+        Some(KeyFile { key: StoredKey::Encrypted { .. }, .. }) => KeyFormat::Encrypted,
+        Some(KeyFile { key: StoredKey::Keyring { backend, .. }, .. }) => KeyFormat::Keyring(backend),
+    })
+}
+
+/// 載入身分金鑰
+pub fn load(config: &KeyConfig, path: &Path) -> Result<Authenticator> {
+    let (contents, file) = read_key_file(path)?;
+
+    match file {
+        None => Authenticator::from_secret_bytes(&contents),
+        Some(KeyFile { key: StoredKey::Encrypted { kdf, nonce, ciphertext }, .. }) => {
+            let passphrase = passphrase(config)?.ok_or_else(|| {
+                Error::AuthenticationFailed(format!(
+                    "Private key {} is encrypted; provide the passphrase with --passphrase-fd, ${} or key.passphrase_file",
+                    path.display(),
+                    config.passphrase_env
+                ))
+            })?;
+
+            let key = kdf.derive(&passphrase)?;
+            let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+            let nonce = decode(&nonce)?;
+            if nonce.len() != 24 {
+                return Err(Error::EncryptionError("Invalid nonce length".to_string()));
+            }
+
+            let seed = Zeroizing::new(
+                cipher
+                    .decrypt(XNonce::from_slice(&nonce), Payload { msg: &decode(&ciphertext)?, aad: KEY_AAD })
+                    .map_err(|_| Error::AuthenticationFailed("Incorrect key passphrase".to_string()))?,
+            );
+            Authenticator::from_secret_bytes(&seed)
+        }
+        Some(KeyFile { key: StoredKey::Keyring { backend, account }, .. }) => {
+            let seed = Zeroizing::new(keyring::get(backend, &account)?);
+            Authenticator::from_secret_bytes(&seed)
+        }
+    }
+}
+
+/// 依 `storage` 保存身分金鑰
+pub fn save(config: &KeyConfig, path: &Path, authenticator: &Authenticator) -> Result<()> {
+    let key = match config.storage {
+        KeyStorage::File => return authenticator.save_private_key(path),
+        KeyStorage::Encrypted => {
+            let passphrase = passphrase(config)?.ok_or_else(|| {
+                Error::InvalidConfig(format!(
+                    "key.storage is \"encrypted\" but no passphrase was provided (--passphrase-fd, ${} or key.passphrase_file)",
+                    config.passphrase_env
+                ))
+            })?;
+            encrypt(authenticator, &passphrase, KdfParams::generate())?
+        }
+        KeyStorage::SecretService | KeyStorage::KernelKeyring => {
+            // 每把金鑰一個項目，輪換後舊金鑰仍可由舊的指標文件取得
+            let account = hex::encode(authenticator.public_key_bytes());
+            let seed = Zeroizing::new(authenticator.secret_bytes());
+            keyring::set(config.storage, &account, seed.as_ref())?;
+            StoredKey::Keyring { backend: config.storage, account }
+        }
+    };
+
+    let file = KeyFile { version: FORMAT_VERSION, key };
+    auth::write_key_file(path, &serde_json::to_vec_pretty(&file)?)
+}
+
+/// 原始私鑰依 `storage` 轉存，返回是否已轉存
+///
+/// 轉存後重新載入確認一致，再取代原文件
+pub fn migrate(config: &KeyConfig, path: &Path) -> Result<bool> {
+    if config.storage == KeyStorage::File || format(path)? != KeyFormat::Plain {
+        return Ok(false);
+    }
+
+    let authenticator = load(config, path)?;
+    let staged = path.with_file_name(format!(
+        ".{}.migrate",
+        path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()
+    ));
+
+    save(config, &staged, &authenticator)?;
+    let result = load(config, &staged).and_then(|migrated| {
+        if migrated.public_key_bytes() == authenticator.public_key_bytes() {
+            Ok(())
+        } else {
+            Err(Error::EncryptionError("Migrated key does not match the original".to_string()))
+        }
+    });
+
+    match result {
+        Ok(()) => {
+            fs::rename(&staged, path)?;
+            Ok(true)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&staged);
+            Err(e)
+        }
+    }
+}
+
+/// 金鑰文件是否需要密碼才能載入
+pub fn is_encrypted(path: &Path) -> bool {
+    matches!(format(path), Ok(KeyFormat::Encrypted))
+}
+
+/// 取得非互動提供的密碼：啟動時解鎖的密碼、環境變數、密碼文件
+pub fn passphrase(config: &KeyConfig) -> Result<Option<Passphrase>> {
+    if let Some(passphrase) = &config.passphrase {
+        return Ok(Some(passphrase.clone()));
+    }
+
+    if let Ok(value) = std::env::var(&config.passphrase_env) {
+        return Ok(Some(Passphrase::new(value)));
+    }
+
+    match &config.passphrase_file {
+        Some(path) => {
+            auth::check_key_permissions(path)?;
+            let contents = Zeroizing::new(fs::read_to_string(path)?);
+            Ok(Some(Passphrase::new(first_line(&contents))))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 從文件描述符讀取密碼（讀至第一個換行或 EOF）
+#[cfg(unix)]
+pub fn read_passphrase_fd(fd: i32) -> Result<Passphrase> {
+    use std::io::Read;
+    use std::os::fd::BorrowedFd;
+
+    let invalid = || Error::InvalidConfig(format!("--passphrase-fd {} is not an open file descriptor", fd));
+
+    // 先確認描述符已開啟，再複製一份讀取，原描述符仍歸呼叫端所有
+    if fd < 0 || unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(invalid());
+    }
+    // SAFETY: fcntl 已確認描述符開啟，借用只持續到複製完成
+    let owned = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned().map_err(|_| invalid())?;
+
+    let mut file = fs::File::from(owned);
+    let mut contents = Zeroizing::new(String::new());
+    file.read_to_string(&mut contents)?;
+    Ok(Passphrase::new(first_line(&contents)))
+}
+
+/// 從標準輸入讀取密碼（Windows 僅支援描述符 0，後台進程經由繼承的管道取得密碼）
+#[cfg(windows)]
+pub fn read_passphrase_fd(fd: i32) -> Result<Passphrase> {
+    use std::io::Read;
+
+    if fd != 0 {
+        return Err(Error::InvalidConfig(
+            "--passphrase-fd only supports 0 (standard input) on Windows".to_string(),
+        ));
+    }
+
+    let mut contents = Zeroizing::new(String::new());
+    std::io::stdin().read_to_string(&mut contents)?;
+    Ok(Passphrase::new(first_line(&contents)))
+}
+
+/// 從文件描述符讀取密碼（僅支援 Unix 與 Windows）
+#[cfg(not(any(unix, windows)))]
+pub fn read_passphrase_fd(_fd: i32) -> Result<Passphrase> {
+    Err(Error::InvalidConfig("--passphrase-fd is only supported on Unix and Windows".to_string()))
+}
+
+fn first_line(contents: &str) -> String {
+    contents.lines().next().unwrap_or_default().to_string()
+}
+
+fn encrypt(authenticator: &Authenticator, passphrase: &Passphrase, kdf: KdfParams) -> Result<StoredKey> {
+    let key = kdf.derive(passphrase)?;
+    let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let seed = Zeroizing::new(authenticator.secret_bytes());
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: seed.as_ref(), aad: KEY_AAD })
+        .map_err(|e| Error::EncryptionError(e.to_string()))?;
+
+    Ok(StoredKey::Encrypted {
+        kdf,
+        nonce: general_purpose::STANDARD.encode(nonce),
+        ciphertext: general_purpose::STANDARD.encode(ciphertext),
+    })
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| Error::EncryptionError(e.to_string()))
+}
+
+/// 系統金鑰環
+///
+/// Secret Service 以 tokio 執行非同步 D-Bus 呼叫，須在獨立執行緒上進行以免阻塞執行時
+#[cfg(feature = "keyring")]
+mod keyring {
+    use super::{KeyStorage, KEYRING_SERVICE};
+    use crate::error::{Error, Result};
+    use keyring::keyutils_persistent::KeyutilsPersistentCredential;
+    use keyring::secret_service::SsCredential;
+    use keyring::Entry;
+
+    fn entry(backend: KeyStorage, account: &str) -> Result<Entry> {
+        let credential: Box<keyring::Credential> = match backend {
+            KeyStorage::SecretService => {
+                Box::new(SsCredential::new_with_target(None, KEYRING_SERVICE, account).map_err(keyring_error)?)
+            }
+            KeyStorage::KernelKeyring => Box::new(
+                KeyutilsPersistentCredential::new_with_target(None, KEYRING_SERVICE, account)
+                    .map_err(keyring_error)?,
+            ),
+            other => return Err(Error::InvalidConfig(format!("{} is not a keyring backend", other))),
+        };
+        Ok(Entry::new_with_credential(credential))
+    }
+
+    fn on_thread<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+        std::thread::spawn(f)
+            .join()
+            .map_err(|_| Error::EncryptionError("Keyring access panicked".to_string()))?
+    }
+
+    pub(super) fn get(backend: KeyStorage, account: &str) -> Result<Vec<u8>> {
+        let account = account.to_string();
+        on_thread(move || entry(backend, &account)?.get_secret().map_err(keyring_error))
+    }
+
+    pub(super) fn set(backend: KeyStorage, account: &str, secret: &[u8]) -> Result<()> {
+        let account = account.to_string();
+        let secret = zeroize::Zeroizing::new(secret.to_vec());
+        on_thread(move || entry(backend, &account)?.set_secret(&secret).map_err(keyring_error))
+    }
+
+    fn keyring_error(e: keyring::Error) -> Error {
+        Error::EncryptionError(format!("Keyring: {}", e))
+    }
+}
+
+/// 未啟用 `keyring` 功能時無法使用金鑰環
+#[cfg(not(feature = "keyring"))]
+mod keyring {
+    use super::KeyStorage;
+    use crate::error::{Error, Result};
+
+    fn unavailable(backend: KeyStorage) -> Error {
+        Error::InvalidConfig(format!(
+            "The {} key storage requires orban-agent built with the `keyring` feature",
+            backend
+        ))
+    }
+
+    pub(super) fn get(backend: KeyStorage, _account: &str) -> Result<Vec<u8>> {
+        Err(unavailable(backend))
+    }
+
+    pub(super) fn set(backend: KeyStorage, _account: &str, _secret: &[u8]) -> Result<()> {
+        Err(unavailable(backend))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 測試用的低成本參數
+    fn fast_kdf() -> KdfParams {
+        KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            ..KdfParams::generate()
+        }
+    }
+
+    fn encrypted_config(passphrase: &str) -> KeyConfig {
+        KeyConfig {
+            storage: KeyStorage::Encrypted,
+            passphrase_env: "ORBAN_TEST_UNSET_PASSPHRASE".to_string(),
+            passphrase: Some(Passphrase::new(passphrase.to_string())),
+            ..Default::default()
+        }
+    }
+
+    fn write_encrypted(path: &Path, authenticator: &Authenticator, passphrase: &str) {
+        let key = encrypt(authenticator, &Passphrase::new(passphrase.to_string()), fast_kdf()).unwrap();
+        let file = KeyFile { version: FORMAT_VERSION, key };
+        auth::write_key_file(path, &serde_json::to_vec(&file).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_key_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.key");
+        let authenticator = Authenticator::generate();
+        write_encrypted(&path, &authenticator, "correct horse");
+
+        assert_eq!(format(&path).unwrap(), KeyFormat::Encrypted);
+        assert!(!fs::read(&path).unwrap().windows(32).any(|w| w == authenticator.secret_bytes()));
+
+        let loaded = load(&encrypted_config("correct horse"), &path).unwrap();
+        assert_eq!(loaded.public_key_bytes(), authenticator.public_key_bytes());
+
+        assert!(matches!(
+            load(&encrypted_config("wrong"), &path),
+            Err(Error::AuthenticationFailed(_))
+        ));
+
+        let mut locked = encrypted_config("unused");
+        locked.passphrase = None;
+        assert!(load(&locked, &path).is_err());
+    }
+
+    #[test]
+    fn test_plaintext_key_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.key");
+        let authenticator = Authenticator::generate();
+        authenticator.save_private_key(&path).unwrap();
+
+        // 預設保存方式不轉存
+        assert!(!migrate(&KeyConfig::default(), &path).unwrap());
+        assert_eq!(format(&path).unwrap(), KeyFormat::Plain);
+
+        let config = encrypted_config("correct horse");
+        assert!(migrate(&config, &path).unwrap());
+        assert_eq!(format(&path).unwrap(), KeyFormat::Encrypted);
+        assert!(!migrate(&config, &path).unwrap());
+
+        let loaded = load(&config, &path).unwrap();
+        assert_eq!(loaded.public_key_bytes(), authenticator.public_key_bytes());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_passphrase_from_pipe_fd() {
+        use std::io::Write;
+        use std::os::fd::AsRawFd;
+
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"correct horse\nignored\n").unwrap();
+        drop(writer);
+
+        let passphrase = read_passphrase_fd(reader.as_raw_fd()).unwrap();
+        assert_eq!(passphrase.as_str(), "correct horse");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_passphrase_from_invalid_fd() {
+        assert!(matches!(read_passphrase_fd(-1), Err(Error::InvalidConfig(_))));
+        assert!(matches!(read_passphrase_fd(1 << 20), Err(Error::InvalidConfig(_))));
+    }
+}
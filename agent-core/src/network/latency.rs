@@ -0,0 +1,144 @@
+// 平台延遲量測
+//
+// WebSocket 以 ping/pong、HTTP 長輪詢以請求往返量測往返時間（RTT），
+// 認證的挑戰回應亦記錄為一筆樣本，註冊時即有量測值。
+// 保留最近的樣本計算百分位數與分佈
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 保留的樣本數
+pub const LATENCY_WINDOW: usize = 100;
+
+/// 分佈區間上限（毫秒）
+const BUCKETS_MS: [u64; 8] = [5, 10, 25, 50, 100, 250, 500, 1000];
+
+/// 往返時間統計（最近 `LATENCY_WINDOW` 筆樣本）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// 樣本數
+    pub samples: usize,
+
+    pub last_ms: f64,
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+
+    /// 各區間的樣本數
+    pub histogram: Vec<LatencyBucket>,
+}
+
+/// 分佈區間
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    /// 區間上限（毫秒），None 表示超過最大區間
+    pub le_ms: Option<u64>,
+    pub count: usize,
+}
+
+impl LatencyStats {
+    /// 回報給平台的延遲（中位數，無條件進位到毫秒），尚未量測時為 0
+    pub fn reported_ms(&self) -> u32 {
+        if self.samples == 0 {
+            0
+        } else {
+            self.p50_ms.ceil().max(1.0) as u32
+        }
+    }
+}
+
+/// 跨連線保留的往返時間樣本
+#[derive(Debug, Default)]
+pub(crate) struct LatencyTracker {
+    samples: Mutex<VecDeque<Duration>>,
+}
+
+impl LatencyTracker {
+    pub(crate) fn record(&self, rtt: Duration) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == LATENCY_WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(rtt);
+    }
+
+    pub(crate) fn snapshot(&self) -> LatencyStats {
+        let samples = self.samples.lock().unwrap();
+        let Some(last) = samples.back() else {
+            return LatencyStats::default();
+        };
+
+        let ms = |rtt: &Duration| rtt.as_secs_f64() * 1000.0;
+        let mut sorted: Vec<f64> = samples.iter().map(ms).collect();
+        sorted.sort_by(f64::total_cmp);
+        let percentile = |p: usize| sorted[((sorted.len() - 1) * p).div_ceil(100)];
+
+        let mut histogram: Vec<LatencyBucket> = BUCKETS_MS
+            .iter()
+            .map(|&le| LatencyBucket { le_ms: Some(le), count: 0 })
+            .chain(std::iter::once(LatencyBucket { le_ms: None, count: 0 }))
+            .collect();
+        for value in &sorted {
+            let index = BUCKETS_MS
+                .iter()
+                .position(|&le| *value <= le as f64)
+                .unwrap_or(BUCKETS_MS.len());
+            histogram[index].count += 1;
+        }
+
+        LatencyStats {
+            samples: sorted.len(),
+            last_ms: ms(last),
+            min_ms: sorted[0],
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            max_ms: sorted[sorted.len() - 1],
+            histogram,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles_and_histogram() {
+        let tracker = LatencyTracker::default();
+        assert_eq!(tracker.snapshot().reported_ms(), 0);
+
+        for ms in 1..=20 {
+            tracker.record(Duration::from_millis(ms));
+        }
+        tracker.record(Duration::from_millis(2000));
+
+        let stats = tracker.snapshot();
+        assert_eq!(stats.samples, 21);
+        assert_eq!(stats.last_ms, 2000.0);
+        assert_eq!(stats.min_ms, 1.0);
+        assert_eq!(stats.p50_ms, 11.0);
+        assert_eq!(stats.p95_ms, 20.0);
+        assert_eq!(stats.reported_ms(), 11);
+
+        let counts: Vec<usize> = stats.histogram.iter().map(|bucket| bucket.count).collect();
+        assert_eq!(counts, vec![5, 5, 10, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_latency_window_and_sub_millisecond_samples() {
+        let tracker = LatencyTracker::default();
+        tracker.record(Duration::from_secs(1));
+        for _ in 0..LATENCY_WINDOW {
+            tracker.record(Duration::from_micros(300));
+        }
+
+        let stats = tracker.snapshot();
+        assert_eq!(stats.samples, LATENCY_WINDOW);
+        assert!(stats.max_ms < 1.0);
+        // 低於 1 毫秒的量測值回報為 1
+        assert_eq!(stats.reported_ms(), 1);
+    }
+}
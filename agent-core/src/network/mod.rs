@@ -2,17 +2,66 @@
 
 mod client;
 mod simple_client;
-mod orban_protocol;
-mod auth;
+pub mod orban_protocol;
+pub mod attestation;
+pub(crate) mod auth;
 mod reconnect;
+mod codec;
+mod compression;
+mod connection;
+mod correlation;
+mod endpoints;
+#[cfg(feature = "grpc")]
+mod grpc;
+pub mod error_policy;
+pub mod keystore;
+pub mod identity;
+mod handshake;
+mod inbound;
+mod latency;
+mod outbox;
+mod proto;
+mod polling;
+pub mod recorder;
+mod replay;
+pub mod rotation;
+pub mod proxy;
+mod tls;
+mod session;
+pub mod signing;
+pub mod transport;
 
 pub use client::OrbanClient;
-pub use simple_client::{Client, RegistrationRequest, GpuInfo, GpuType, Task, TaskResult};
+pub use polling::PollingClient;
+pub use proxy::ProxySettings;
+pub use simple_client::{Client, GpuInfo, GpuType, Task, TaskResult};
 pub use orban_protocol::{
     Message, MessageType, MessagePayload,
     TaskAssignPayload, EarningsRecordPayload, EarningsDetail,
-    PowChallengePayload, AgentStatus
+    PowChallengePayload, AgentStatus, ProtocolFeature, UnknownPayload,
+    KeyRotationPayload, RotationAuthority, RotationReason, RotationStatement,
+    HardwareAttestationPayload
 };
-pub use auth::Authenticator;
+pub use auth::{check_key_permissions, Authenticator};
+pub use codec::{WireCodec, SUBPROTOCOL_JSON, SUBPROTOCOL_PROTOBUF};
+pub use compression::{is_compressed, Compression, CompressionStats};
+pub use connection::{OutboundStats, Priority};
+pub use correlation::requires_ack;
+pub use endpoints::{Endpoint, Endpoints};
+pub use error_policy::{ErrorAction, ErrorRecord, ErrorSource};
+pub use latency::{LatencyBucket, LatencyStats};
+pub use inbound::{InboundViolation, RATE_WINDOW};
+pub use keystore::{KeyFormat, KeyStorage, Passphrase};
+pub use identity::{agent_id_for_public_key, IdentityRecord};
+pub use handshake::{select_protocol, NegotiatedProtocol, PROTOCOL_VERSION, SUPPORTED_PROTOCOL_VERSIONS};
+pub use outbox::Outbox;
+pub use proto::pb;
+pub use session::{SessionClaims, SessionToken};
+pub use recorder::{Direction, SessionRecord, SessionRecorder};
+pub use replay::ReplayTransport;
+pub use rotation::{RotationEvent, RotationStatus};
+pub use signing::MessageSigner;
+pub use tls::{spki_pin, TlsSettings};
+pub use transport::{Transport, TransportKind};
 
 use crate::error::Result;
@@ -2,13 +2,14 @@
 //
 // 定義 Agent 與 Platform 之間的通訊協議
 
-use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use chrono::{DateTime, Utc};
 use crate::types::*;
 use crate::error::Result;
 
 /// 訊息類型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum MessageType {
     // 認證
@@ -45,23 +46,136 @@ pub enum MessageType {
 
     // 狀態同步
     StateSync,
+
+    // 送達確認
+    Ack,
+
+    // 金鑰輪換
+    KeyRotation,
+
+    // 硬體證明
+    HardwareAttestation,
+
+    // 無法識別的類型（較新版本的平台），內容保留在 MessagePayload::Unknown
+    #[serde(other)]
+    Unknown,
 }
 
 /// Orban Protocol 訊息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// 序列化時 `type` 與 payload 欄位位於同一層；
+/// 無法識別的 `type` 解碼為 `MessageType::Unknown`，原始內容原樣保留
+#[derive(Debug, Clone)]
 pub struct Message {
     pub message_id: String,
-
-    #[serde(with = "timestamp_deserialize")]
     pub timestamp: DateTime<Utc>,
-
-    #[serde(rename = "type")]
     pub message_type: MessageType,
 
-    #[serde(flatten)]
+    /// 所回覆訊息的 message_id
+    pub in_reply_to: Option<String>,
+
+    /// 逐訊息簽名（ed25519，base64），見 signing 模組；
+    /// JSON 欄位名稱為 `message_signature`，避免與負載的 `signature` 衝突
+    pub signature: Option<String>,
+
     pub payload: MessagePayload,
 }
 
+/// 訊息類型名稱，未知類型使用原始名稱
+#[derive(Serialize)]
+#[serde(untagged)]
+enum TypeName<'a> {
+    Known(MessageType),
+    Unknown(&'a str),
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Envelope<'a, P> {
+            message_id: &'a str,
+            #[serde(with = "timestamp_deserialize")]
+            timestamp: DateTime<Utc>,
+            #[serde(rename = "type")]
+            message_type: TypeName<'a>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            in_reply_to: Option<&'a str>,
+            #[serde(rename = "message_signature", skip_serializing_if = "Option::is_none")]
+            signature: Option<&'a str>,
+            #[serde(flatten)]
+            payload: P,
+        }
+
+        let message_id = &self.message_id;
+        let timestamp = self.timestamp;
+        let in_reply_to = self.in_reply_to.as_deref();
+        let signature = self.signature.as_deref();
+
+        match &self.payload {
+            MessagePayload::Unknown(unknown) => Envelope {
+                message_id,
+                timestamp,
+                message_type: TypeName::Unknown(&unknown.message_type),
+                in_reply_to,
+                signature,
+                payload: &unknown.body,
+            }
+            .serialize(serializer),
+            payload => Envelope {
+                message_id,
+                timestamp,
+                message_type: TypeName::Known(self.message_type),
+                in_reply_to,
+                signature,
+                payload,
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        struct Envelope {
+            message_id: String,
+            #[serde(with = "timestamp_deserialize")]
+            timestamp: DateTime<Utc>,
+            #[serde(rename = "type")]
+            type_name: String,
+            #[serde(default)]
+            in_reply_to: Option<String>,
+            #[serde(rename = "message_signature", default)]
+            signature: Option<String>,
+            #[serde(flatten)]
+            body: serde_json::Map<String, serde_json::Value>,
+        }
+
+        let envelope = Envelope::deserialize(deserializer)?;
+        let message_type = MessageType::deserialize(serde_json::Value::String(envelope.type_name.clone()))
+            .map_err(D::Error::custom)?;
+
+        let payload = match message_type {
+            MessageType::Unknown => MessagePayload::Unknown(UnknownPayload {
+                message_type: envelope.type_name,
+                body: envelope.body,
+            }),
+            _ => MessagePayload::decode(message_type, envelope.body).map_err(D::Error::custom)?,
+        };
+
+        Ok(Self {
+            message_id: envelope.message_id,
+            timestamp: envelope.timestamp,
+            message_type,
+            in_reply_to: envelope.in_reply_to,
+            signature: envelope.signature,
+            payload,
+        })
+    }
+}
+
 // 自定義反序列化：支持 Unix timestamp (整數) 和 RFC 3339 (字符串)
 mod timestamp_deserialize {
     use chrono::{DateTime, Utc, TimeZone};
@@ -102,7 +216,9 @@ mod timestamp_deserialize {
 }
 
 /// 訊息負載
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// 序列化時直接展開為負載欄位；反序列化由 `type` 決定負載類型，見 [`MessagePayload::decode`]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 pub enum MessagePayload {
     AuthChallenge(AuthChallengePayload),
@@ -124,6 +240,62 @@ pub enum MessagePayload {
     PowResponse(PowResponsePayload),
     Error(ErrorPayload),
     StateSync(StateSyncPayload),
+    Ack(AckPayload),
+    KeyRotation(KeyRotationPayload),
+    HardwareAttestation(HardwareAttestationPayload),
+    #[serde(skip)]
+    Unknown(UnknownPayload),
+}
+
+impl MessagePayload {
+    /// 依 `type` 解碼負載
+    ///
+    /// 只嘗試 `type` 對應的負載結構，錯誤訊息包含失敗的欄位路徑
+    pub fn decode(
+        message_type: MessageType,
+        body: serde_json::Map<String, serde_json::Value>,
+    ) -> std::result::Result<Self, String> {
+        fn parse<T: DeserializeOwned>(
+            body: serde_json::Map<String, serde_json::Value>,
+        ) -> std::result::Result<T, serde_path_to_error::Error<serde_json::Error>> {
+            serde_path_to_error::deserialize(serde_json::Value::Object(body))
+        }
+
+        let payload = match message_type {
+            MessageType::AuthChallenge => parse(body).map(Self::AuthChallenge),
+            MessageType::AuthResponse => parse(body).map(Self::AuthResponse),
+            MessageType::AuthSuccess => parse(body).map(Self::AuthSuccess),
+            MessageType::AgentRegister => parse(body).map(Self::AgentRegister),
+            MessageType::RegisterAck => parse(body).map(Self::RegisterAck),
+            MessageType::TaskAssign => parse(body).map(Self::TaskAssign),
+            MessageType::TaskAccept => parse(body).map(Self::TaskAccept),
+            MessageType::TaskReject => parse(body).map(Self::TaskReject),
+            MessageType::TaskProgress => parse(body).map(Self::TaskProgress),
+            MessageType::TaskComplete => parse(body).map(Self::TaskComplete),
+            MessageType::TaskFailed => parse(body).map(Self::TaskFailed),
+            MessageType::Heartbeat => parse(body).map(Self::Heartbeat),
+            MessageType::MetricsBatch => parse(body).map(Self::MetricsBatch),
+            MessageType::EarningsRecord => parse(body).map(Self::EarningsRecord),
+            MessageType::PayoutNotification => parse(body).map(Self::PayoutNotification),
+            MessageType::PowChallenge => parse(body).map(Self::PowChallenge),
+            MessageType::PowResponse => parse(body).map(Self::PowResponse),
+            MessageType::Error => parse(body).map(Self::Error),
+            MessageType::StateSync => parse(body).map(Self::StateSync),
+            MessageType::Ack => parse(body).map(Self::Ack),
+            MessageType::KeyRotation => parse(body).map(Self::KeyRotation),
+            MessageType::HardwareAttestation => parse(body).map(Self::HardwareAttestation),
+            MessageType::Unknown => return Err("unknown message type has no payload schema".to_string()),
+        };
+
+        payload.map_err(|e| {
+            let path = e.path().to_string();
+            if path == "." {
+                format!("invalid {:?} payload: {}", message_type, e.inner())
+            } else {
+                format!("invalid {:?} payload at `{}`: {}", message_type, path, e.inner())
+            }
+        })
+    }
 }
 
 // ==================== 認證訊息 ====================
@@ -138,23 +310,81 @@ pub struct AuthResponsePayload {
     pub agent_id: String,
     pub signature: String,
     pub public_key: String,
+
+    /// Agent 支援的協議版本、編碼格式與可選功能
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<ProtocolOffer>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthSuccessPayload {
     pub jwt_token: String,
     pub expires_in: u64,
+
+    /// 平台選定的協議版本與功能，舊版平台不提供
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<ProtocolSelection>,
+}
+
+/// 可選協議功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolFeature {
+    /// 重連後以 STATE_SYNC 恢復會話
+    StateSync,
+    /// 訊息壓縮
+    Compression,
+    /// 逐訊息簽名
+    MessageSigning,
+    /// 無法識別的功能（較新版本的對端）
+    #[serde(other)]
+    Unknown,
+}
+
+/// 協議能力
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolOffer {
+    /// 支援的協議版本
+    pub versions: Vec<u32>,
+    /// 支援的編碼格式（WebSocket 子協議名稱，依偏好排序）
+    pub codecs: Vec<String>,
+    /// 支援的可選功能
+    #[serde(default)]
+    pub features: Vec<ProtocolFeature>,
+}
+
+/// 協商結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolSelection {
+    /// 選定的協議版本
+    pub version: u32,
+    /// 雙方皆支援的功能
+    #[serde(default)]
+    pub features: Vec<ProtocolFeature>,
 }
 
 // ==================== 註冊訊息 ====================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentRegisterPayload {
+    /// 由身分金鑰的公鑰推導
     pub agent_id: String,
     pub hardware: HardwareInfo,
     pub capabilities: Capabilities,
     pub location: Location,
     pub availability: Availability,
+
+    /// 配置中的顯示名稱，僅供平台顯示，不作為識別
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// 離線備份金鑰的公鑰 (base64)，遺失身分金鑰時以之簽署輪換聲明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_public_key: Option<String>,
+
+    /// 以身分金鑰簽名的硬體清單
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<HardwareAttestationPayload>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -366,6 +596,104 @@ pub struct ActiveTaskInfo {
     pub started_at: DateTime<Utc>,
 }
 
+// ==================== 送達確認訊息 ====================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AckPayload {
+    pub status: AckStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AckStatus {
+    /// 首次收到
+    Received,
+    /// 重送的訊息，已去重
+    Duplicate,
+}
+
+// ==================== 金鑰輪換訊息 ====================
+
+/// 以舊金鑰（或備份金鑰）與新金鑰簽署的輪換聲明，見 rotation 模組
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyRotationPayload {
+    pub statement: RotationStatement,
+
+    /// `statement.authority` 指定的金鑰對聲明的簽名 (base64)
+    pub signature: String,
+
+    /// 新金鑰對聲明的簽名 (base64)，證明持有新金鑰
+    pub new_key_signature: String,
+}
+
+/// 輪換聲明
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationStatement {
+    /// 輪換前的 Agent ID
+    pub agent_id: String,
+
+    /// 輪換前的公鑰 (base64)
+    pub public_key: String,
+
+    pub new_agent_id: String,
+    pub new_public_key: String,
+    pub authority: RotationAuthority,
+    pub reason: RotationReason,
+    pub issued_at: DateTime<Utc>,
+}
+
+/// 簽署輪換聲明的金鑰
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationAuthority {
+    /// 輪換前的身分金鑰
+    PreviousKey,
+    /// 註冊時提交的離線備份金鑰
+    BackupKey,
+}
+
+/// 輪換原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationReason {
+    /// 例行更換
+    Routine,
+    /// 金鑰可能外洩
+    Compromised,
+    /// 金鑰遺失（重灌系統等），以備份金鑰恢復
+    Lost,
+}
+
+// ==================== 硬體證明訊息 ====================
+
+/// 以身分金鑰簽名的硬體清單，註冊時隨 AGENT_REGISTER 送出，硬體變更時以 HARDWARE_ATTESTATION 重新送出
+///
+/// 簽名涵蓋 Agent ID、清單雜湊與簽署時間，見 attestation 模組
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardwareAttestationPayload {
+    pub agent_id: String,
+    pub manifest: HardwareManifest,
+
+    /// 清單標準 JSON 的雜湊（`sha256:<hex>`）
+    pub manifest_hash: String,
+
+    pub issued_at: DateTime<Utc>,
+
+    /// 身分金鑰的簽名 (base64)
+    pub signature: String,
+}
+
+// ==================== 未知訊息 ====================
+
+/// 無法識別的訊息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnknownPayload {
+    /// 原始 `type` 名稱，Protocol Buffers 編碼時為空
+    pub message_type: String,
+    /// `type` 以外的其餘欄位
+    pub body: serde_json::Map<String, serde_json::Value>,
+}
+
 // ==================== 訊息構建器 ====================
 
 impl Message {
@@ -375,10 +703,18 @@ impl Message {
             message_id: uuid::Uuid::new_v4().to_string(),
             timestamp: Utc::now(),
             message_type,
+            in_reply_to: None,
+            signature: None,
             payload,
         }
     }
 
+    /// 標記為對指定訊息的回覆
+    pub fn reply_to(mut self, request: &Message) -> Self {
+        self.in_reply_to = Some(request.message_id.clone());
+        self
+    }
+
     /// 序列化為 JSON
     pub fn to_json(&self) -> Result<String> {
         Ok(serde_json::to_string(self)?)
@@ -397,6 +733,7 @@ pub fn create_auth_response(
     agent_id: String,
     signature: String,
     public_key: String,
+    protocol: Option<ProtocolOffer>,
 ) -> Message {
     Message::new(
         MessageType::AuthResponse,
@@ -404,13 +741,18 @@ pub fn create_auth_response(
             agent_id,
             signature,
             public_key,
+            protocol,
         }),
     )
 }
 
 /// 創建 Agent 註冊訊息
+#[allow(clippy::too_many_arguments)]
 pub fn create_agent_register(
     agent_id: String,
+    display_name: Option<String>,
+    backup_public_key: Option<String>,
+    attestation: Option<HardwareAttestationPayload>,
     hardware: HardwareInfo,
     capabilities: Capabilities,
     location: Location,
@@ -424,6 +766,9 @@ pub fn create_agent_register(
             capabilities,
             location,
             availability,
+            display_name,
+            backup_public_key,
+            attestation,
         }),
     )
 }
@@ -480,6 +825,91 @@ pub fn create_task_reject(task_id: String, reason: String, details: String) -> M
     )
 }
 
+/// 創建任務進度訊息
+pub fn create_task_progress(task_id: String, progress: f32, stage: String, metrics: TaskMetrics) -> Message {
+    Message::new(
+        MessageType::TaskProgress,
+        MessagePayload::TaskProgress(TaskProgressPayload {
+            task_id,
+            progress,
+            stage,
+            metrics,
+        }),
+    )
+}
+
+/// 創建任務完成訊息
+pub fn create_task_complete(
+    task_id: String,
+    result: TaskResult,
+    proof_of_work: ProofOfWork,
+    metrics: ExecutionMetrics,
+) -> Message {
+    Message::new(
+        MessageType::TaskComplete,
+        MessagePayload::TaskComplete(TaskCompletePayload {
+            task_id,
+            result,
+            proof_of_work,
+            metrics,
+        }),
+    )
+}
+
+/// 創建任務失敗訊息
+pub fn create_task_failed(task_id: String, error: TaskErrorInfo, partial_results: Option<String>) -> Message {
+    Message::new(
+        MessageType::TaskFailed,
+        MessagePayload::TaskFailed(TaskFailedPayload {
+            task_id,
+            error,
+            partial_results,
+        }),
+    )
+}
+
+/// 創建 PoW 響應訊息
+pub fn create_pow_response(response: crate::gpu::PowResponse) -> Message {
+    Message::new(
+        MessageType::PowResponse,
+        MessagePayload::PowResponse(PowResponsePayload {
+            challenge_id: response.challenge_id,
+            response: hex::encode(response.response),
+            computation_time_ms: response.computation_time_ms as u32,
+            gpu_signature: GpuSignature {
+                device_uuid: response.gpu_signature.device_uuid,
+                cuda_version: response.gpu_signature.cuda_version,
+            },
+        }),
+    )
+}
+
+/// 創建狀態同步訊息
+pub fn create_state_sync(
+    agent_id: String,
+    last_heartbeat: DateTime<Utc>,
+    active_tasks: Vec<ActiveTaskInfo>,
+) -> Message {
+    Message::new(
+        MessageType::StateSync,
+        MessagePayload::StateSync(StateSyncPayload {
+            agent_id,
+            last_heartbeat,
+            active_tasks,
+        }),
+    )
+}
+
+/// 創建金鑰輪換訊息
+pub fn create_key_rotation(rotation: KeyRotationPayload) -> Message {
+    Message::new(MessageType::KeyRotation, MessagePayload::KeyRotation(rotation))
+}
+
+/// 創建硬體證明訊息
+pub fn create_hardware_attestation(attestation: HardwareAttestationPayload) -> Message {
+    Message::new(MessageType::HardwareAttestation, MessagePayload::HardwareAttestation(attestation))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -499,5 +929,100 @@ mod tests {
 
         let deserialized = Message::from_json(&json).unwrap();
         assert_eq!(deserialized.message_type, MessageType::Heartbeat);
+        assert!(!json.contains("in_reply_to"));
+    }
+
+    #[test]
+    fn test_ack_serialization() {
+        let request = create_task_reject("task-001".to_string(), "busy".to_string(), String::new());
+        let ack = Message::new(
+            MessageType::Ack,
+            MessagePayload::Ack(AckPayload {
+                status: AckStatus::Duplicate,
+            }),
+        )
+        .reply_to(&request);
+
+        let json = ack.to_json().unwrap();
+        let deserialized = Message::from_json(&json).unwrap();
+
+        assert_eq!(deserialized.message_type, MessageType::Ack);
+        assert_eq!(deserialized.in_reply_to, Some(request.message_id));
+        assert!(matches!(
+            deserialized.payload,
+            MessagePayload::Ack(AckPayload { status: AckStatus::Duplicate })
+        ));
+    }
+
+    #[test]
+    fn test_payload_decoded_by_type() {
+        // TASK_REJECT 的欄位同時符合 TASK_REJECT 與其他較寬鬆的結構，須依 `type` 解碼
+        let json = r#"{
+            "message_id": "msg-001",
+            "timestamp": "2026-01-01T00:00:00Z",
+            "type": "TASK_REJECT",
+            "task_id": "task-001",
+            "reason": "busy",
+            "details": ""
+        }"#;
+
+        let msg = Message::from_json(json).unwrap();
+        assert!(matches!(msg.payload, MessagePayload::TaskReject(_)));
+
+        // `type` 與負載不符時不退而求其次
+        let mismatched = json.replace("TASK_REJECT", "TASK_ACCEPT");
+        let err = Message::from_json(&mismatched).unwrap_err().to_string();
+        assert!(err.contains("invalid TaskAccept payload"), "{}", err);
+        assert!(err.contains("missing field `agent_id`"), "{}", err);
+    }
+
+    #[test]
+    fn test_payload_error_names_field() {
+        let json = r#"{
+            "message_id": "msg-001",
+            "timestamp": "2026-01-01T00:00:00Z",
+            "type": "TASK_PROGRESS",
+            "task_id": "task-001",
+            "progress": 0.5,
+            "stage": "inference",
+            "metrics": {"gpu_utilization": "high", "memory_used_gb": 1.0, "throughput_tokens_per_sec": null}
+        }"#;
+
+        let err = Message::from_json(json).unwrap_err().to_string();
+        assert!(err.contains("`metrics.gpu_utilization`"), "{}", err);
+    }
+
+    #[test]
+    fn test_unknown_message_type_passthrough() {
+        let json = r#"{
+            "message_id": "msg-001",
+            "timestamp": "2026-01-01T00:00:00Z",
+            "type": "QUOTA_UPDATE",
+            "in_reply_to": "msg-000",
+            "quota": {"gpu_hours": 12}
+        }"#;
+
+        let msg = Message::from_json(json).unwrap();
+        assert_eq!(msg.message_type, MessageType::Unknown);
+        assert_eq!(msg.in_reply_to.as_deref(), Some("msg-000"));
+
+        let MessagePayload::Unknown(payload) = &msg.payload else {
+            panic!("expected unknown payload, got {:?}", msg.payload);
+        };
+        assert_eq!(payload.message_type, "QUOTA_UPDATE");
+        assert_eq!(payload.body["quota"]["gpu_hours"], 12);
+
+        // 原樣轉出
+        let value: serde_json::Value = serde_json::from_str(&msg.to_json().unwrap()).unwrap();
+        assert_eq!(value["type"], "QUOTA_UPDATE");
+        assert_eq!(value["quota"]["gpu_hours"], 12);
+    }
+
+    #[test]
+    fn test_unknown_protocol_feature() {
+        let selection: ProtocolSelection =
+            serde_json::from_str(r#"{"version": 2, "features": ["state_sync", "batching"]}"#).unwrap();
+
+        assert_eq!(selection.features, [ProtocolFeature::StateSync, ProtocolFeature::Unknown]);
     }
 }
@@ -0,0 +1,321 @@
+// 離線發件匣
+//
+// 任務結果、進度與指標在發送前先寫入 data_dir/outbox，每則訊息一個檔案：
+// - 檔名為遞增序號，重連後依序號重送
+// - 同一 message_id 只保存一次
+// - 需要確認的訊息在平台回覆後刪除，其餘訊息在寫入連線後刪除
+// - 超過上限時先捨棄最舊的進度與指標，任務結果不會被捨棄
+
+use super::correlation::requires_ack;
+use super::orban_protocol::{Message, MessageType};
+use crate::error::{Error, Result};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+/// 發件匣目錄名稱（位於 data_dir 下）
+const OUTBOX_DIR: &str = "outbox";
+
+/// 發件匣中的一則訊息
+#[derive(Debug, Clone)]
+struct Entry {
+    message_id: String,
+    message_type: MessageType,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: BTreeMap<u64, Entry>,
+    by_id: HashMap<String, u64>,
+    next_seq: u64,
+}
+
+/// 磁碟發件匣
+pub struct Outbox {
+    dir: PathBuf,
+    max_messages: usize,
+    index: Mutex<Index>,
+}
+
+impl Outbox {
+    /// 發件匣目錄
+    pub fn dir(data_dir: &Path) -> PathBuf {
+        data_dir.join(OUTBOX_DIR)
+    }
+
+    /// 是否保存此類型的訊息
+    pub fn stores(message_type: MessageType) -> bool {
+        matches!(
+            message_type,
+            MessageType::TaskComplete
+                | MessageType::TaskFailed
+                | MessageType::TaskProgress
+                | MessageType::MetricsBatch
+        )
+    }
+
+    /// 待發送的訊息數（不載入內容，供 status 命令使用）
+    pub fn pending_count(data_dir: &Path) -> Result<usize> {
+        let dir = Self::dir(data_dir);
+        if !dir.exists() {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        for entry in fs::read_dir(dir)? {
+            if parse_seq(&entry?.path()).is_some() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// 開啟發件匣，載入上次未送出的訊息
+    pub(crate) fn open(data_dir: &Path, max_messages: usize) -> Result<Self> {
+        let dir = Self::dir(data_dir);
+        fs::create_dir_all(&dir)?;
+
+        let mut index = Index::default();
+        for file in fs::read_dir(&dir)? {
+            let path = file?.path();
+            let Some(seq) = parse_seq(&path) else {
+                // 寫入中斷留下的暫存檔
+                if path.extension().is_some_and(|ext| ext == "tmp") {
+                    let _ = fs::remove_file(&path);
+                }
+                continue;
+            };
+
+            match read_message(&path) {
+                Ok(msg) => {
+                    index.by_id.insert(msg.message_id.clone(), seq);
+                    index.entries.insert(
+                        seq,
+                        Entry {
+                            message_id: msg.message_id,
+                            message_type: msg.message_type,
+                        },
+                    );
+                    index.next_seq = index.next_seq.max(seq + 1);
+                }
+                Err(e) => {
+                    warn!("Discarding unreadable outbox entry {}: {}", path.display(), e);
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+
+        if !index.entries.is_empty() {
+            debug!("Loaded {} pending outbox message(s)", index.entries.len());
+        }
+
+        Ok(Self {
+            dir,
+            max_messages: max_messages.max(1),
+            index: Mutex::new(index),
+        })
+    }
+
+    /// 寫入訊息，已存在相同 message_id 時返回 false
+    pub(crate) fn persist(&self, msg: &Message) -> Result<bool> {
+        let mut index = self.index.lock().unwrap();
+        if index.by_id.contains_key(&msg.message_id) {
+            return Ok(false);
+        }
+
+        while index.entries.len() >= self.max_messages {
+            let evictable = index
+                .entries
+                .iter()
+                .find(|(_, entry)| !requires_ack(entry.message_type))
+                .map(|(seq, _)| *seq);
+
+            let Some(seq) = evictable else {
+                return Err(Error::OutboxFull(index.entries.len()));
+            };
+
+            let entry = index.entries.remove(&seq).unwrap();
+            index.by_id.remove(&entry.message_id);
+            warn!("Outbox full, dropping {:?} message {}", entry.message_type, entry.message_id);
+            let _ = fs::remove_file(self.path(seq));
+        }
+
+        let seq = index.next_seq;
+        write_atomic(&self.path(seq), &serde_json::to_vec(msg)?)?;
+
+        index.next_seq += 1;
+        index.by_id.insert(msg.message_id.clone(), seq);
+        index.entries.insert(
+            seq,
+            Entry {
+                message_id: msg.message_id.clone(),
+                message_type: msg.message_type,
+            },
+        );
+
+        Ok(true)
+    }
+
+    /// 刪除已送達的訊息
+    pub(crate) fn remove(&self, message_id: &str) -> bool {
+        let mut index = self.index.lock().unwrap();
+        let Some(seq) = index.by_id.remove(message_id) else {
+            return false;
+        };
+
+        index.entries.remove(&seq);
+        if let Err(e) = fs::remove_file(self.path(seq)) {
+            warn!("Failed to remove outbox entry {}: {}", message_id, e);
+        }
+        true
+    }
+
+    /// 依寫入順序載入所有待發送的訊息
+    pub(crate) fn pending(&self) -> Result<Vec<Message>> {
+        let index = self.index.lock().unwrap();
+        index
+            .entries
+            .keys()
+            .map(|seq| read_message(&self.path(*seq)))
+            .collect()
+    }
+
+    /// 依寫入順序改寫待發送的訊息，`update` 返回 true 時寫回，返回改寫的筆數
+    ///
+    /// 供身分遷移使用；message_id 與訊息類型不可改變
+    pub(crate) fn rewrite(&self, mut update: impl FnMut(&mut Message) -> Result<bool>) -> Result<usize> {
+        let index = self.index.lock().unwrap();
+
+        let mut rewritten = 0;
+        for (seq, entry) in &index.entries {
+            let path = self.path(*seq);
+            let mut msg = read_message(&path)?;
+            if update(&mut msg)? {
+                debug_assert_eq!(msg.message_id, entry.message_id);
+                write_atomic(&path, &serde_json::to_vec(&msg)?)?;
+                rewritten += 1;
+            }
+        }
+        Ok(rewritten)
+    }
+
+    /// 待發送的訊息數
+    pub(crate) fn len(&self) -> usize {
+        self.index.lock().unwrap().entries.len()
+    }
+
+    fn path(&self, seq: u64) -> PathBuf {
+        self.dir.join(format!("{:020}.json", seq))
+    }
+}
+
+fn parse_seq(path: &Path) -> Option<u64> {
+    if path.extension()? != "json" {
+        return None;
+    }
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+fn read_message(path: &Path) -> Result<Message> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+/// 先寫入暫存檔再改名，避免中斷時留下不完整的檔案
+fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{create_task_failed, MessagePayload, TaskErrorInfo, TaskMetrics, TaskProgressPayload};
+
+    fn progress(task_id: &str) -> Message {
+        Message::new(
+            MessageType::TaskProgress,
+            MessagePayload::TaskProgress(TaskProgressPayload {
+                task_id: task_id.to_string(),
+                progress: 0.5,
+                stage: "inference".to_string(),
+                metrics: TaskMetrics {
+                    gpu_utilization: 0.9,
+                    memory_used_gb: 10.0,
+                    throughput_tokens_per_sec: None,
+                },
+            }),
+        )
+    }
+
+    fn failed(task_id: &str) -> Message {
+        create_task_failed(
+            task_id.to_string(),
+            TaskErrorInfo {
+                code: "OOM_ERROR".to_string(),
+                message: "Out of memory".to_string(),
+                details: String::new(),
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn test_outbox_survives_restart_in_order() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let first = failed("task-001");
+        let second = progress("task-002");
+
+        let outbox = Outbox::open(data_dir.path(), 10).unwrap();
+        assert!(outbox.persist(&first).unwrap());
+        assert!(outbox.persist(&second).unwrap());
+        assert!(!outbox.persist(&first).unwrap());
+        drop(outbox);
+
+        let outbox = Outbox::open(data_dir.path(), 10).unwrap();
+        assert_eq!(Outbox::pending_count(data_dir.path()).unwrap(), 2);
+
+        let pending = outbox.pending().unwrap();
+        assert_eq!(pending[0].message_id, first.message_id);
+        assert_eq!(pending[1].message_id, second.message_id);
+
+        assert!(outbox.remove(&first.message_id));
+        assert!(!outbox.remove(&first.message_id));
+        assert_eq!(outbox.len(), 1);
+
+        // 新訊息排在既有訊息之後
+        let third = progress("task-003");
+        outbox.persist(&third).unwrap();
+        let pending = outbox.pending().unwrap();
+        assert_eq!(pending[1].message_id, third.message_id);
+    }
+
+    #[test]
+    fn test_outbox_cap_keeps_results() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let outbox = Outbox::open(data_dir.path(), 2).unwrap();
+
+        let result = failed("task-001");
+        outbox.persist(&result).unwrap();
+        outbox.persist(&progress("task-001")).unwrap();
+
+        // 捨棄最舊的進度，保留任務結果
+        let latest = progress("task-002");
+        outbox.persist(&latest).unwrap();
+        let pending = outbox.pending().unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].message_id, result.message_id);
+        assert_eq!(pending[1].message_id, latest.message_id);
+
+        outbox.remove(&latest.message_id);
+        outbox.persist(&failed("task-002")).unwrap();
+        assert!(matches!(outbox.persist(&failed("task-003")), Err(Error::OutboxFull(2))));
+    }
+}
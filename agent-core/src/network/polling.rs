@@ -0,0 +1,893 @@
+// HTTP 長輪詢傳輸
+//
+// 供封鎖 WebSocket 的網路使用。訊息與 WebSocket 相同，以 JSON 編碼經由 REST 端點交換：
+// - POST /api/v1/agents/{agent_id}/challenge          取得 AUTH_CHALLENGE
+// - POST /api/v1/agents/{agent_id}/auth               提交 AUTH_RESPONSE，回應 AUTH_SUCCESS
+// - POST /api/v1/agents/{agent_id}/messages           發送訊息，平台的回覆（REGISTER_ACK、ACK 等）作為回應返回
+// - GET  /api/v1/agents/{agent_id}/messages?wait_secs  長輪詢平台下發的訊息，逾時返回空陣列
+//
+// 成功的回應即表示訊息已送達，發件匣中的訊息在請求成功後刪除；此傳輸不使用壓縮
+
+use super::attestation;
+use super::auth::Authenticator;
+use super::client::OrbanClient;
+use super::codec::WireCodec;
+use super::endpoints::{Endpoint, Endpoints};
+use super::error_policy::{self, codes, ErrorAction};
+use super::handshake::{self, NegotiatedProtocol};
+use super::inbound::InboundGuard;
+use super::latency::{LatencyStats, LatencyTracker};
+use super::orban_protocol::{
+    ActiveTaskInfo, AgentStatus, KeyRotationPayload, Message, MessagePayload, ProtocolFeature, TaskErrorInfo,
+    TaskMetrics,
+};
+use super::outbox::Outbox;
+use super::reconnect::ReconnectStrategy;
+use super::recorder::{Direction, SessionRecorder};
+use super::session::SessionToken;
+use super::signing::MessageSigner;
+use super::proxy::ProxySettings;
+use super::simple_client::Client;
+use super::tls::{self, TlsSettings};
+use super::transport::{http_base_url, LocalState, Transport};
+use crate::error::{Error, Result};
+use crate::gpu::PowResponse;
+use crate::types::*;
+use crate::AgentConfig;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// HTTP 長輪詢客戶端
+pub struct PollingClient {
+    config: AgentConfig,
+    endpoints: Arc<Endpoints>,
+    authenticator: Arc<Authenticator>,
+    backup_public_key: Option<String>,
+    signer: MessageSigner,
+    http: Client,
+    outbox: Arc<Outbox>,
+    reconnect_strategy: Mutex<ReconnectStrategy>,
+    jwt_token: Mutex<Option<SessionToken>>,
+    protocol: Mutex<Option<NegotiatedProtocol>>,
+    registration: Mutex<Option<Message>>,
+    inbox: Mutex<VecDeque<Message>>,
+    guard: InboundGuard,
+    recorder: SessionRecorder,
+    active_tasks: Mutex<HashMap<String, ActiveTaskInfo>>,
+    last_heartbeat: Mutex<DateTime<Utc>>,
+    latency: LatencyTracker,
+}
+
+impl PollingClient {
+    /// 創建新的客戶端
+    pub async fn new(config: &AgentConfig) -> Result<Self> {
+        Self::with_endpoints(config, Arc::new(Endpoints::from_config(config)?)).await
+    }
+
+    /// 使用共用的平台端點創建客戶端
+    pub async fn with_endpoints(config: &AgentConfig, endpoints: Arc<Endpoints>) -> Result<Self> {
+        Self::with_state(config, endpoints, LocalState::open(config)?).await
+    }
+
+    /// 使用共用的平台端點與已開啟的本地狀態創建客戶端
+    pub async fn with_state(config: &AgentConfig, endpoints: Arc<Endpoints>, state: LocalState) -> Result<Self> {
+        let LocalState { authenticator, outbox, backup_public_key } = state;
+
+        let reconnect_strategy = ReconnectStrategy::with_limits(
+            config.network.reconnect_max_retries,
+            Duration::from_millis(config.network.reconnect_base_delay_ms),
+            Duration::from_secs(config.network.reconnect_max_delay_secs),
+        );
+
+        let http = Client::new(
+            http_base_url(&endpoints.current().url),
+            &ProxySettings::from_config(&config.network.proxy)?,
+            &TlsSettings::from_config(&config.network.tls, &authenticator)?,
+        )?;
+
+        Ok(Self {
+            config: config.clone(),
+            endpoints,
+            signer: MessageSigner::from_config(&config.network.signing)?,
+            authenticator,
+            backup_public_key,
+            http,
+            outbox,
+            reconnect_strategy: Mutex::new(reconnect_strategy),
+            jwt_token: Mutex::new(None),
+            protocol: Mutex::new(None),
+            registration: Mutex::new(None),
+            inbox: Mutex::new(VecDeque::new()),
+            guard: InboundGuard::from_config(&config.network.inbound),
+            recorder: SessionRecorder::from_config(&config.network)?,
+            active_tasks: Mutex::new(HashMap::new()),
+            last_heartbeat: Mutex::new(Utc::now()),
+            latency: LatencyTracker::default(),
+        })
+    }
+
+    /// 連接到平台
+    pub async fn connect(&self) -> Result<()> {
+        info!("Connecting to Orban Platform at {} (HTTP long polling)", self.endpoints.current().url);
+
+        self.authenticate().await?;
+        self.reconnect_strategy.lock().await.reset();
+
+        Ok(())
+    }
+
+    /// Agent 專屬端點的路徑
+    fn path(&self, endpoint: &str) -> String {
+        format!("/api/v1/agents/{}/{}", self.authenticator.agent_id(), endpoint)
+    }
+
+    /// 以 JSON 編碼的訊息作為請求內容
+    fn post(&self, endpoint: &str, msg: &Message) -> Result<RequestBuilder> {
+        self.recorder.record(Direction::Outbound, msg);
+        Ok(self
+            .http
+            .request(Method::POST, &self.path(endpoint))
+            .header(CONTENT_TYPE, "application/json")
+            .body(msg.to_json()?))
+    }
+
+    /// 發送請求，返回平台的回覆訊息（無回覆時為 `None`）
+    ///
+    /// 錯誤狀態碼的回應內容為 ERROR 訊息時照常返回，由呼叫方依錯誤碼處理
+    async fn exchange(&self, request: RequestBuilder) -> Result<Option<Message>> {
+        let response = request.send().await.map_err(|e| match tls::describe_error(&e) {
+            Some(reason) => Error::TlsError(reason),
+            None => Error::ConnectionFailed(e.to_string()),
+        })?;
+
+        let status = response.status();
+        if status == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+
+        let body = self.read_body(response).await?;
+
+        match Message::from_json(&body) {
+            Ok(msg) => {
+                self.recorder.record(Direction::Inbound, &msg);
+                Ok(Some(msg))
+            }
+            Err(_) if status.is_success() && body.trim().is_empty() => Ok(None),
+            Err(e) if status.is_success() => Err(e),
+            Err(_) => Err(Error::ConnectionFailed(format!("Platform responded with HTTP {}", status))),
+        }
+    }
+
+    /// 讀取回應內容，超過訊框大小上限時中止，不緩衝其餘部分
+    async fn read_body(&self, mut response: reqwest::Response) -> Result<String> {
+        if let Some(length) = response.content_length() {
+            self.guard.check_size(usize::try_from(length).unwrap_or(usize::MAX))?;
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::ConnectionFailed(e.to_string()))?
+        {
+            self.guard.check_size(body.len() + chunk.len())?;
+            body.extend_from_slice(&chunk);
+        }
+
+        String::from_utf8(body).map_err(|e| Error::ProtocolError(e.to_string()))
+    }
+
+    /// 認證並協商協議版本與功能
+    async fn authenticate(&self) -> Result<()> {
+        info!("Authenticating with platform...");
+
+        // 故障轉移後改用新端點
+        self.http.set_base_url(http_base_url(&self.endpoints.current().url));
+
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+
+        // 接收認證挑戰
+        let request = self.http.request(Method::POST, &self.path("challenge")).timeout(timeout);
+        let challenge = match self.exchange(request).await?.map(|msg| msg.payload) {
+            Some(MessagePayload::AuthChallenge(challenge)) => challenge,
+            Some(MessagePayload::Error(err)) => return Err(OrbanClient::rejected(&err.code, &err.message)),
+            _ => return Err(Error::AuthenticationFailed("Invalid auth challenge".to_string())),
+        };
+
+        // 響應挑戰，此傳輸只使用 JSON
+        let (signature, public_key) = self
+            .authenticator
+            .respond_to_challenge(&challenge.challenge)?;
+
+        let features = self.offered_features();
+        let mut offer = handshake::offer(WireCodec::Json, &features);
+        offer.codecs = vec![WireCodec::Json.subprotocol().to_string()];
+
+        let response = super::orban_protocol::create_auth_response(
+            self.authenticator.agent_id().to_string(),
+            signature,
+            public_key,
+            Some(offer),
+        );
+
+        // 接收認證結果，挑戰請求已建立連線，此往返記錄為延遲樣本
+        let request = self.post("auth", &response)?.timeout(timeout);
+        let sent_at = Instant::now();
+        let reply = self.exchange(request).await?;
+        self.latency.record(sent_at.elapsed());
+
+        match reply.map(|msg| msg.payload) {
+            Some(MessagePayload::AuthSuccess(success)) => {
+                let token = SessionToken::parse(
+                    &success.jwt_token,
+                    success.expires_in,
+                    self.authenticator.agent_id(),
+                )?;
+                info!("Authentication successful, token valid until {}", token.expires_at());
+
+                let protocol = handshake::accept(success.protocol, WireCodec::Json, &features)?;
+                info!("Using protocol v{} over HTTP, features: {:?}", protocol.version, protocol.features);
+
+                self.http.set_session_token(Some(token.as_str().to_string()));
+                *self.jwt_token.lock().await = Some(token);
+                *self.protocol.lock().await = Some(protocol);
+                Ok(())
+            }
+            Some(MessagePayload::Error(err)) if err.code == codes::UNSUPPORTED_VERSION => Err(Error::ProtocolError(format!(
+                "Platform does not support protocol versions {:?}: {}",
+                handshake::SUPPORTED_PROTOCOL_VERSIONS,
+                err.message
+            ))),
+            Some(MessagePayload::Error(err)) => Err(OrbanClient::rejected(&err.code, &err.message)),
+            _ => Err(Error::AuthenticationFailed("Invalid response".to_string())),
+        }
+    }
+
+    /// 提供的可選協議功能（不含壓縮）
+    fn offered_features(&self) -> Vec<ProtocolFeature> {
+        handshake::AGENT_FEATURES
+            .iter()
+            .copied()
+            .filter(|feature| *feature != ProtocolFeature::Compression)
+            .collect()
+    }
+
+    /// 令牌即將過期時重新認證
+    async fn ensure_session(&self) -> Result<()> {
+        let margin = Duration::from_secs(self.config.network.token_refresh_margin_secs);
+
+        let refresh = match self.jwt_token.lock().await.as_ref() {
+            Some(token) => token.refresh_at(margin) <= Utc::now(),
+            None => return Err(Error::ConnectionFailed("Not connected".to_string())),
+        };
+
+        if refresh {
+            info!("Session token expiring, re-authenticating...");
+            self.authenticate().await?;
+        }
+
+        Ok(())
+    }
+
+    /// 發送訊息並返回平台的回覆
+    ///
+    /// 平台以可恢復的 AUTH_FAILED 拒絕會話時重新認證後重試一次
+    async fn request(&self, msg: &Message) -> Result<Option<Message>> {
+        self.ensure_session().await?;
+
+        let timeout = Duration::from_secs(self.config.network.connection_timeout_secs);
+        let sent_at = Instant::now();
+        let reply = self.exchange(self.post("messages", msg)?.timeout(timeout)).await?;
+        self.latency.record(sent_at.elapsed());
+
+        match reply.as_ref().map(|reply| &reply.payload) {
+            Some(MessagePayload::Error(err)) if ErrorAction::for_platform_error(err) == ErrorAction::Reauthenticate => {
+                warn!("Session rejected by platform, re-authenticating: {}", err.message);
+                self.authenticate().await?;
+                self.exchange(self.post("messages", msg)?.timeout(timeout)).await
+            }
+            Some(MessagePayload::Error(err)) if err.code == codes::AUTH_FAILED => {
+                Err(OrbanClient::rejected(&err.code, &err.message))
+            }
+            _ => Ok(reply),
+        }
+    }
+
+    /// 發送訊息
+    ///
+    /// 任務結果、進度與指標先寫入發件匣，請求失敗時保留到恢復連線後重送；
+    /// 啟用簽名時在寫入發件匣前簽署
+    pub async fn send_message(&self, msg: &Message) -> Result<()> {
+        let msg = self.signer.sign(msg, &self.authenticator)?;
+        let msg = msg.as_ref();
+
+        let durable = Outbox::stores(msg.message_type);
+        if durable && !self.outbox.persist(msg)? {
+            debug!("{:?} message {} already in outbox", msg.message_type, msg.message_id);
+            return Ok(());
+        }
+
+        match self.request(msg).await {
+            Ok(reply) => {
+                if durable {
+                    self.outbox.remove(&msg.message_id);
+                }
+
+                if let Some(MessagePayload::Error(err)) = reply.map(|reply| reply.payload) {
+                    warn!("Platform rejected {:?} message {} ({}): {}", msg.message_type, msg.message_id, err.code, err.message);
+                }
+
+                Ok(())
+            }
+            Err(e) if durable && e.is_recoverable() => {
+                info!("{:?} message {} kept in outbox: {}", msg.message_type, msg.message_id, e);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 註冊 Agent
+    pub async fn register(
+        &self,
+        hardware: HardwareInfo,
+        manifest: HardwareManifest,
+        capabilities: Capabilities,
+        location: Location,
+        availability: Availability,
+    ) -> Result<()> {
+        info!("Registering agent...");
+
+        let msg = super::orban_protocol::create_agent_register(
+            self.authenticator.agent_id().to_string(),
+            self.config.display_name.clone(),
+            self.backup_public_key.clone(),
+            Some(attestation::sign(&self.authenticator, manifest)?),
+            hardware,
+            capabilities,
+            location,
+            availability,
+        );
+
+        *self.registration.lock().await = Some(msg.clone());
+        self.send_registration(&msg).await
+    }
+
+    /// 發送註冊並等待確認
+    async fn send_registration(&self, msg: &Message) -> Result<()> {
+        let reply = self.request(msg).await?;
+
+        let reply = reply.ok_or_else(|| Error::ProtocolError("No reply to registration".to_string()))?;
+
+        match reply.payload {
+            MessagePayload::RegisterAck(_) => {
+                info!("Agent registered successfully");
+                *self.last_heartbeat.lock().await = Utc::now();
+
+                // 上次運行未送達的訊息
+                self.replay_outbox().await
+            }
+            MessagePayload::Error(err) => Err(Error::ProtocolError(format!(
+                "Registration rejected ({}): {}",
+                err.code, err.message
+            ))),
+            _ => Err(Error::ProtocolError(format!(
+                "Unexpected reply to registration: {:?}",
+                reply.message_type
+            ))),
+        }
+    }
+
+    /// 恢復會話
+    ///
+    /// 平台支援時發送 StateSync，否則重送上次的註冊
+    async fn resume_session(&self) -> Result<()> {
+        let state_sync = self
+            .protocol
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|p| p.supports(ProtocolFeature::StateSync));
+
+        if state_sync {
+            let active_tasks: Vec<ActiveTaskInfo> = self.active_tasks.lock().await.values().cloned().collect();
+            info!("Syncing state with {} active task(s)", active_tasks.len());
+
+            let msg = super::orban_protocol::create_state_sync(
+                self.authenticator.agent_id().to_string(),
+                *self.last_heartbeat.lock().await,
+                active_tasks,
+            );
+
+            self.request(&msg).await?;
+            return self.replay_outbox().await;
+        }
+
+        let registration = self.registration.lock().await.clone();
+        match registration {
+            Some(previous) => {
+                info!("Platform does not support StateSync, registering again");
+                let msg = Message::new(previous.message_type, previous.payload);
+                self.send_registration(&msg).await
+            }
+            None => self.replay_outbox().await,
+        }
+    }
+
+    /// 切換端點後以新端點的區域重新註冊，並同步進行中的任務
+    async fn register_at(&self, endpoint: &Endpoint) -> Result<()> {
+        let registration = {
+            let mut registration = self.registration.lock().await;
+            if let (Some(msg), Some(region)) = (registration.as_mut(), &endpoint.region) {
+                if let MessagePayload::AgentRegister(payload) = &mut msg.payload {
+                    payload.location.region = region.clone();
+                }
+            }
+            registration.clone()
+        };
+
+        let Some(previous) = registration else {
+            return self.resume_session().await;
+        };
+
+        info!("Registering with failover endpoint {}", endpoint.url);
+        let msg = Message::new(previous.message_type, previous.payload);
+        self.send_registration(&msg).await?;
+
+        let state_sync = self
+            .protocol
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|p| p.supports(ProtocolFeature::StateSync));
+        let active_tasks: Vec<ActiveTaskInfo> = self.active_tasks.lock().await.values().cloned().collect();
+        if state_sync && !active_tasks.is_empty() {
+            let msg = super::orban_protocol::create_state_sync(
+                self.authenticator.agent_id().to_string(),
+                *self.last_heartbeat.lock().await,
+                active_tasks,
+            );
+            self.request(&msg).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 依序重送發件匣中的訊息，遇到連線錯誤時停止，其餘留待下次恢復
+    async fn replay_outbox(&self) -> Result<()> {
+        let pending = self.outbox.pending()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        info!("Replaying {} outbox message(s)", pending.len());
+
+        for msg in pending {
+            self.request(&msg).await?;
+            self.outbox.remove(&msg.message_id);
+        }
+
+        Ok(())
+    }
+
+    /// 輪詢失敗後依重連策略退避，重新認證並恢復會話
+    ///
+    /// 連續失敗達門檻時切換平台端點，切換後以新端點的區域重新註冊
+    async fn recover(&self) -> Result<()> {
+        let mut failover = None;
+        loop {
+            let delay = self.reconnect_strategy.lock().await.next_delay();
+            let delay = delay.ok_or_else(|| {
+                Error::ConnectionFailed("Max reconnection attempts reached".to_string())
+            })?;
+
+            tokio::time::sleep(delay).await;
+
+            let resumed = match self.authenticate().await {
+                Ok(()) => {
+                    self.endpoints.record_success();
+                    match &failover {
+                        Some(endpoint) => self.register_at(endpoint).await,
+                        None => self.resume_session().await,
+                    }
+                }
+                Err(e) => Err(e),
+            };
+
+            match resumed {
+                Ok(()) => break,
+                Err(e @ Error::AuthenticationFailed(_)) => return Err(e),
+                Err(e) => {
+                    warn!("Reconnection failed: {}", e);
+
+                    if let Some(endpoint) = self.endpoints.record_failure() {
+                        failover = Some(endpoint);
+                        // 新端點不需等待舊端點累積的退避
+                        self.reconnect_strategy.lock().await.reset();
+                    }
+                }
+            }
+        }
+
+        info!("Session resumed");
+        Ok(())
+    }
+
+    /// 長輪詢平台下發的訊息
+    async fn poll(&self) -> Result<Vec<Message>> {
+        self.ensure_session().await?;
+
+        let wait_secs = self.config.network.poll_wait_secs;
+        let timeout = Duration::from_secs(wait_secs + self.config.network.connection_timeout_secs);
+
+        let response = self
+            .http
+            .request(Method::GET, &self.path("messages"))
+            .query(&[("wait_secs", wait_secs)])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| Error::ConnectionFailed(e.to_string()))?;
+
+        let status = response.status();
+        let body = self.read_body(response).await?;
+
+        if !status.is_success() {
+            // 平台拒絕會話時回應 ERROR 訊息
+            return match Message::from_json(&body) {
+                Ok(msg) => {
+                    self.recorder.record(Direction::Inbound, &msg);
+                    Ok(vec![msg])
+                }
+                Err(_) => Err(Error::ConnectionFailed(format!("Platform responded with HTTP {}", status))),
+            };
+        }
+
+        let values: Vec<serde_json::Value> = serde_json::from_str(&body)?;
+        let messages = values
+            .into_iter()
+            .filter_map(|value| match serde_json::from_value::<Message>(value) {
+                Ok(msg) => {
+                    self.recorder.record(Direction::Inbound, &msg);
+                    Some(msg)
+                }
+                Err(e) => {
+                    warn!("Ignoring invalid message from platform: {}", e);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(messages)
+    }
+
+    /// 接收訊息
+    ///
+    /// 輪詢失敗時依重連策略重新認證並恢復會話，令牌即將過期時重新認證。
+    /// 僅在無法恢復（重連次數用盡、平台拒絕會話）時返回錯誤
+    pub async fn receive(&self) -> Result<Message> {
+        loop {
+            let next = self.inbox.lock().await.pop_front();
+
+            if let Some(msg) = next {
+                if let Err(e) = self.guard.check(&msg) {
+                    warn!("Dropping inbound message: {}", e);
+                    continue;
+                }
+
+                // 平台拒絕目前的會話
+                if let MessagePayload::Error(err) = &msg.payload {
+                    match ErrorAction::for_platform_error(err) {
+                        ErrorAction::Reauthenticate => {
+                            warn!("Session rejected by platform, re-authenticating: {}", err.message);
+                            self.authenticate().await?;
+                            continue;
+                        }
+                        ErrorAction::Shutdown if err.code == codes::AUTH_FAILED => {
+                            self.jwt_token.lock().await.take();
+                            return Err(OrbanClient::rejected(&err.code, &err.message));
+                        }
+                        _ => {}
+                    }
+                }
+
+                debug!("Received {:?} message {}", msg.message_type, msg.message_id);
+                return Ok(msg);
+            }
+
+            match self.poll().await {
+                Ok(messages) => {
+                    self.reconnect_strategy.lock().await.reset();
+                    self.inbox.lock().await.extend(messages);
+                }
+                Err(e @ Error::AuthenticationFailed(_)) => return Err(e),
+                Err(e) => {
+                    warn!("Polling failed: {}", e);
+                    self.recover().await?;
+                }
+            }
+        }
+    }
+
+    /// 發送心跳
+    pub async fn send_heartbeat(
+        &self,
+        status: AgentStatus,
+        current_task_id: Option<String>,
+        gpu_status: Vec<GPUStatus>,
+        uptime_sec: u64,
+    ) -> Result<()> {
+        let msg = super::orban_protocol::create_heartbeat(
+            self.authenticator.agent_id().to_string(),
+            status,
+            current_task_id,
+            gpu_status,
+            uptime_sec,
+        );
+
+        self.send_message(&msg).await?;
+        *self.last_heartbeat.lock().await = Utc::now();
+        Ok(())
+    }
+
+    /// 接受任務
+    pub async fn accept_task(&self, task_id: &str) -> Result<()> {
+        let msg = super::orban_protocol::create_task_accept(
+            task_id.to_string(),
+            self.authenticator.agent_id().to_string(),
+            0,
+            0,
+        );
+
+        self.send_message(&msg).await?;
+
+        self.active_tasks.lock().await.insert(
+            task_id.to_string(),
+            ActiveTaskInfo {
+                task_id: task_id.to_string(),
+                progress: 0.0,
+                started_at: Utc::now(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// 拒絕任務
+    pub async fn reject_task(&self, task_id: &str, reason: &str) -> Result<()> {
+        let msg = super::orban_protocol::create_task_reject(
+            task_id.to_string(),
+            reason.to_string(),
+            String::new(),
+        );
+
+        self.send_message(&msg).await
+    }
+
+    /// 回報任務進度
+    pub async fn send_task_progress(
+        &self,
+        task_id: &str,
+        progress: f32,
+        stage: &str,
+        metrics: TaskMetrics,
+    ) -> Result<()> {
+        if let Some(task) = self.active_tasks.lock().await.get_mut(task_id) {
+            task.progress = progress.clamp(0.0, 1.0);
+        }
+
+        let msg = super::orban_protocol::create_task_progress(
+            task_id.to_string(),
+            progress.clamp(0.0, 1.0),
+            stage.to_string(),
+            metrics,
+        );
+
+        self.send_message(&msg).await
+    }
+
+    /// 完成任務
+    pub async fn complete_task(
+        &self,
+        task_id: &str,
+        result: TaskResult,
+        proof_of_work: ProofOfWork,
+        metrics: ExecutionMetrics,
+    ) -> Result<()> {
+        let msg = super::orban_protocol::create_task_complete(
+            task_id.to_string(),
+            result,
+            proof_of_work,
+            metrics,
+        );
+
+        self.send_message(&msg).await?;
+        self.active_tasks.lock().await.remove(task_id);
+        info!("Task {} completed", task_id);
+        Ok(())
+    }
+
+    /// 回報任務失敗
+    pub async fn fail_task(&self, task_id: &str, error: TaskErrorInfo) -> Result<()> {
+        let msg = super::orban_protocol::create_task_failed(task_id.to_string(), error, None);
+
+        self.send_message(&msg).await?;
+        self.active_tasks.lock().await.remove(task_id);
+        warn!("Task {} failed", task_id);
+        Ok(())
+    }
+
+    /// 發送 PoW 響應
+    pub async fn send_pow_response(&self, response: PowResponse) -> Result<()> {
+        let msg = super::orban_protocol::create_pow_response(response);
+        self.send_message(&msg).await
+    }
+
+    /// 送出金鑰輪換聲明，平台以 ACK 確認或以 ERROR 拒絕
+    pub async fn rotate_key(&self, rotation: KeyRotationPayload) -> Result<()> {
+        let msg = super::orban_protocol::create_key_rotation(rotation);
+
+        match self.request(&msg).await? {
+            Some(reply) => error_policy::ack_or_platform_error(reply, "key rotation"),
+            None => Err(Error::ProtocolError("No reply to key rotation".to_string())),
+        }
+    }
+
+    /// 硬體變更時重新送出簽名的硬體清單，平台以 ACK 確認或以 ERROR 拒絕
+    ///
+    /// 之後重新註冊時沿用新的清單
+    pub async fn attest_hardware(&self, manifest: HardwareManifest) -> Result<()> {
+        let attestation = attestation::sign(&self.authenticator, manifest)?;
+        let msg = super::orban_protocol::create_hardware_attestation(attestation.clone());
+        match self.request(&msg).await? {
+            Some(reply) => error_policy::ack_or_platform_error(reply, "hardware attestation")?,
+            None => return Err(Error::ProtocolError("No reply to hardware attestation".to_string())),
+        }
+
+        // 平台接受後才用於重新註冊，被拒絕的清單不隨註冊送出
+        if let Some(MessagePayload::AgentRegister(registration)) =
+            self.registration.lock().await.as_mut().map(|msg| &mut msg.payload)
+        {
+            registration.attestation = Some(attestation);
+        }
+        Ok(())
+    }
+
+    /// 進行中的任務
+    pub async fn active_tasks(&self) -> Vec<ActiveTaskInfo> {
+        self.active_tasks.lock().await.values().cloned().collect()
+    }
+
+    /// 中止平台已取消的任務，不再於心跳與狀態同步中回報
+    pub async fn abort_task(&self, task_id: &str) {
+        if self.active_tasks.lock().await.remove(task_id).is_some() {
+            info!("Task {} aborted", task_id);
+        }
+    }
+
+    /// 平台端點
+    pub fn endpoints(&self) -> Arc<Endpoints> {
+        self.endpoints.clone()
+    }
+
+    /// 與平台之間的往返時間統計（請求往返）
+    pub fn latency(&self) -> LatencyStats {
+        self.latency.snapshot()
+    }
+
+    /// 發件匣中待送達的訊息數
+    pub fn outbox_depth(&self) -> usize {
+        self.outbox.len()
+    }
+
+    /// 斷線（捨棄會話令牌，平台端的會話逾時後失效）
+    pub async fn disconnect(&self) -> Result<()> {
+        if self.jwt_token.lock().await.take().is_some() {
+            self.http.set_session_token(None);
+            info!("Disconnected from platform");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Transport for PollingClient {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn connect(&self) -> Result<()> {
+        PollingClient::connect(self).await
+    }
+
+    async fn register(
+        &self,
+        hardware: HardwareInfo,
+        manifest: HardwareManifest,
+        capabilities: Capabilities,
+        location: Location,
+        availability: Availability,
+    ) -> Result<()> {
+        PollingClient::register(self, hardware, manifest, capabilities, location, availability).await
+    }
+
+    async fn receive(&self) -> Result<Message> {
+        PollingClient::receive(self).await
+    }
+
+    async fn send_heartbeat(
+        &self,
+        status: AgentStatus,
+        current_task_id: Option<String>,
+        gpu_status: Vec<GPUStatus>,
+        uptime_sec: u64,
+    ) -> Result<()> {
+        PollingClient::send_heartbeat(self, status, current_task_id, gpu_status, uptime_sec).await
+    }
+
+    async fn accept_task(&self, task_id: &str) -> Result<()> {
+        PollingClient::accept_task(self, task_id).await
+    }
+
+    async fn reject_task(&self, task_id: &str, reason: &str) -> Result<()> {
+        PollingClient::reject_task(self, task_id, reason).await
+    }
+
+    async fn send_task_progress(&self, task_id: &str, progress: f32, stage: &str, metrics: TaskMetrics) -> Result<()> {
+        PollingClient::send_task_progress(self, task_id, progress, stage, metrics).await
+    }
+
+    async fn complete_task(
+        &self,
+        task_id: &str,
+        result: TaskResult,
+        proof_of_work: ProofOfWork,
+        metrics: ExecutionMetrics,
+    ) -> Result<()> {
+        PollingClient::complete_task(self, task_id, result, proof_of_work, metrics).await
+    }
+
+    async fn fail_task(&self, task_id: &str, error: TaskErrorInfo) -> Result<()> {
+        PollingClient::fail_task(self, task_id, error).await
+    }
+
+    async fn send_pow_response(&self, response: PowResponse) -> Result<()> {
+        PollingClient::send_pow_response(self, response).await
+    }
+
+    async fn active_tasks(&self) -> Vec<ActiveTaskInfo> {
+        PollingClient::active_tasks(self).await
+    }
+
+    async fn abort_task(&self, task_id: &str) {
+        PollingClient::abort_task(self, task_id).await
+    }
+
+    async fn rotate_key(&self, rotation: KeyRotationPayload) -> Result<()> {
+        PollingClient::rotate_key(self, rotation).await
+    }
+
+    async fn attest_hardware(&self, manifest: HardwareManifest) -> Result<()> {
+        PollingClient::attest_hardware(self, manifest).await
+    }
+
+    fn latency(&self) -> LatencyStats {
+        PollingClient::latency(self)
+    }
+
+    fn endpoints(&self) -> Arc<Endpoints> {
+        PollingClient::endpoints(self)
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        PollingClient::disconnect(self).await
+    }
+}
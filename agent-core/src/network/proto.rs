@@ -0,0 +1,1443 @@
+// Orban Protocol 的 Protocol Buffers 編碼
+//
+// pb 模組由 build.rs 從 proto/orban.proto 生成，這裡提供它與
+// orban_protocol 中 serde 類型之間的無損轉換：
+// - 金額 (Decimal) 與 GPU 時數寫入原有的 float 欄位，另以 `*_exact` 欄位傳輸十進位字串與 double，
+//   解碼時優先使用 `*_exact`，舊版平台只送 float 時以其換算
+// - 時間以 RFC 3339 字串傳輸，訊息時間戳為 Unix 奈秒
+// - 任意 JSON (任務配置、錯誤上下文) 寫入原有的 map<string, string>，另以 `*_json` 欄位傳輸完整 JSON
+// - 認證簽名與公鑰 (base64)、工作證明的 nonce 與 response (hex) 以原始位元組傳輸
+
+use super::orban_protocol::*;
+use crate::error::{Error, Result};
+use crate::types::{
+    Availability, CPUInfo, Capabilities, ExecutionMetrics, GPUInfo, GPUStatus, GpuManifest, HardwareInfo,
+    HardwareManifest, Location, Pricing, ProofOfWork, TaskPayload, TaskRequirements, TaskResult,
+};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, TimeZone, Utc};
+use prost::Message as ProstMessage;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+
+/// 由 proto/orban.proto 生成的類型
+pub mod pb {
+    include!(concat!(env!("OUT_DIR"), "/orban.protocol.v1.rs"));
+}
+
+use pb::message::Payload;
+
+impl Message {
+    /// 編碼為 Protocol Buffers 二進制格式
+    pub fn to_protobuf(&self) -> Result<Vec<u8>> {
+        let msg = pb::Message::try_from(self.clone())?;
+        Ok(msg.encode_to_vec())
+    }
+
+    /// 從 Protocol Buffers 二進制格式解碼
+    pub fn from_protobuf(data: &[u8]) -> Result<Self> {
+        let msg = pb::Message::decode(data)?;
+        Message::try_from(msg)
+    }
+}
+
+// ==================== 輔助函數 ====================
+
+fn required<T>(value: Option<T>, field: &str) -> Result<T> {
+    value.ok_or_else(|| Error::ProtocolError(format!("missing field `{}`", field)))
+}
+
+fn decimal(value: &str, field: &str) -> Result<Decimal> {
+    value
+        .parse()
+        .map_err(|e| Error::ProtocolError(format!("invalid decimal in `{}`: {}", field, e)))
+}
+
+/// 金額的 float 近似值，供只讀取 float 欄位的舊版平台使用
+fn approx(value: &Decimal) -> f32 {
+    value.to_f32().unwrap_or_default()
+}
+
+/// 優先解析 `*_exact` 十進位字串，缺少時以 float 欄位換算
+fn amount(exact: Option<String>, approx: f32, field: &str) -> Result<Decimal> {
+    match exact {
+        Some(value) => decimal(&value, field),
+        None => Decimal::from_f32(approx)
+            .ok_or_else(|| Error::ProtocolError(format!("invalid amount in `{}`: {}", field, approx))),
+    }
+}
+
+fn base64_bytes(value: &str, field: &str) -> Result<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| Error::ProtocolError(format!("invalid base64 in `{}`: {}", field, e)))
+}
+
+fn hex_bytes(value: &str, field: &str) -> Result<Vec<u8>> {
+    hex::decode(value).map_err(|e| Error::ProtocolError(format!("invalid hex in `{}`: {}", field, e)))
+}
+
+/// JSON 物件的頂層欄位，字串值原樣保留，其他值為 JSON 編碼；非物件時為空
+fn json_map(value: &serde_json::Value) -> HashMap<String, String> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(s) => (key.clone(), s.clone()),
+                other => (key.clone(), other.to_string()),
+            })
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// 優先解析 `*_json` 欄位，缺少時以 map 的字串值組成物件
+fn json_or_map(json: Option<String>, map: HashMap<String, String>, field: &str) -> Result<Option<serde_json::Value>> {
+    match json {
+        Some(json) => parse_json(&json, field).map(Some),
+        None if map.is_empty() => Ok(None),
+        None => Ok(Some(serde_json::Value::Object(
+            map.into_iter().map(|(key, value)| (key, serde_json::Value::String(value))).collect(),
+        ))),
+    }
+}
+
+fn datetime(value: &str, field: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| Error::ProtocolError(format!("invalid timestamp in `{}`: {}", field, e)))
+}
+
+fn rfc3339(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339()
+}
+
+/// 以 serde 名稱表示枚舉值，確保與 JSON 編碼一致
+fn enum_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn parse_enum<T: DeserializeOwned>(value: &str, field: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|e| Error::ProtocolError(format!("invalid value in `{}`: {}", field, e)))
+}
+
+fn parse_json(value: &str, field: &str) -> Result<serde_json::Value> {
+    serde_json::from_str(value)
+        .map_err(|e| Error::ProtocolError(format!("invalid JSON in `{}`: {}", field, e)))
+}
+
+// ==================== 訊息包裝器 ====================
+
+impl TryFrom<Message> for pb::Message {
+    type Error = Error;
+
+    fn try_from(msg: Message) -> Result<Self> {
+        let timestamp = msg
+            .timestamp
+            .timestamp_nanos_opt()
+            .and_then(|nanos| u64::try_from(nanos).ok())
+            .ok_or_else(|| Error::ProtocolError("timestamp out of range".to_string()))?;
+
+        let payload = match msg.payload {
+            MessagePayload::AuthChallenge(p) => Payload::AuthChallenge(p.into()),
+            MessagePayload::AuthResponse(p) => Payload::AuthResponse(p.try_into()?),
+            MessagePayload::AuthSuccess(p) => Payload::AuthSuccess(p.into()),
+            MessagePayload::AgentRegister(p) => Payload::AgentRegister(p.into()),
+            MessagePayload::RegisterAck(p) => Payload::RegisterAck(p.into()),
+            MessagePayload::TaskAssign(p) => Payload::TaskAssign(p.into()),
+            MessagePayload::TaskAccept(p) => Payload::TaskAccept(p.into()),
+            MessagePayload::TaskReject(p) => Payload::TaskReject(p.into()),
+            MessagePayload::TaskProgress(p) => Payload::TaskProgress(p.into()),
+            MessagePayload::TaskComplete(p) => Payload::TaskComplete(p.into()),
+            MessagePayload::TaskFailed(p) => Payload::TaskFailed(p.into()),
+            MessagePayload::Heartbeat(p) => Payload::Heartbeat(p.into()),
+            MessagePayload::MetricsBatch(p) => Payload::MetricsBatch(p.into()),
+            MessagePayload::EarningsRecord(p) => Payload::EarningsRecord(p.into()),
+            MessagePayload::PayoutNotification(p) => Payload::PayoutNotification(p.into()),
+            MessagePayload::PowChallenge(p) => Payload::PowChallenge(p.try_into()?),
+            MessagePayload::PowResponse(p) => Payload::PowResponse(p.try_into()?),
+            MessagePayload::Error(p) => Payload::Error(p.into()),
+            MessagePayload::StateSync(p) => Payload::StateSync(p.into()),
+            MessagePayload::Ack(p) => Payload::Ack(p.into()),
+            MessagePayload::KeyRotation(p) => Payload::KeyRotation(p.into()),
+            MessagePayload::HardwareAttestation(p) => Payload::HardwareAttestation(p.into()),
+            MessagePayload::Unknown(p) => {
+                return Err(Error::ProtocolError(format!(
+                    "cannot encode unknown message type `{}`",
+                    p.message_type
+                )))
+            }
+        };
+
+        Ok(Self {
+            message_id: msg.message_id,
+            timestamp,
+            in_reply_to: msg.in_reply_to,
+            signature: msg.signature,
+            payload: Some(payload),
+        })
+    }
+}
+
+impl TryFrom<pb::Message> for Message {
+    type Error = Error;
+
+    fn try_from(msg: pb::Message) -> Result<Self> {
+        let timestamp = i64::try_from(msg.timestamp)
+            .map(|nanos| Utc.timestamp_nanos(nanos))
+            .map_err(|_| Error::ProtocolError("timestamp out of range".to_string()))?;
+
+        // 較新版本的 payload 不在 oneof 中，解碼後為空
+        let Some(payload) = msg.payload else {
+            return Ok(Self {
+                message_id: msg.message_id,
+                timestamp,
+                message_type: MessageType::Unknown,
+                in_reply_to: msg.in_reply_to,
+                signature: msg.signature,
+                payload: MessagePayload::Unknown(UnknownPayload::default()),
+            });
+        };
+
+        let (message_type, payload) = match payload {
+            Payload::AuthChallenge(p) => (MessageType::AuthChallenge, MessagePayload::AuthChallenge(p.into())),
+            Payload::AuthResponse(p) => (MessageType::AuthResponse, MessagePayload::AuthResponse(p.into())),
+            Payload::AuthSuccess(p) => (MessageType::AuthSuccess, MessagePayload::AuthSuccess(p.into())),
+            Payload::AgentRegister(p) => (MessageType::AgentRegister, MessagePayload::AgentRegister(p.try_into()?)),
+            Payload::RegisterAck(p) => (MessageType::RegisterAck, MessagePayload::RegisterAck(p.try_into()?)),
+            Payload::TaskAssign(p) => (MessageType::TaskAssign, MessagePayload::TaskAssign(p.try_into()?)),
+            Payload::TaskAccept(p) => (MessageType::TaskAccept, MessagePayload::TaskAccept(p.try_into()?)),
+            Payload::TaskReject(p) => (MessageType::TaskReject, MessagePayload::TaskReject(p.into())),
+            Payload::TaskProgress(p) => (MessageType::TaskProgress, MessagePayload::TaskProgress(p.try_into()?)),
+            Payload::TaskComplete(p) => (MessageType::TaskComplete, MessagePayload::TaskComplete(p.try_into()?)),
+            Payload::TaskFailed(p) => (MessageType::TaskFailed, MessagePayload::TaskFailed(p.try_into()?)),
+            Payload::Heartbeat(p) => (MessageType::Heartbeat, MessagePayload::Heartbeat(p.try_into()?)),
+            Payload::MetricsBatch(p) => (MessageType::MetricsBatch, MessagePayload::MetricsBatch(p.try_into()?)),
+            Payload::EarningsRecord(p) => (MessageType::EarningsRecord, MessagePayload::EarningsRecord(p.try_into()?)),
+            Payload::PayoutNotification(p) => (MessageType::PayoutNotification, MessagePayload::PayoutNotification(p.try_into()?)),
+            Payload::PowChallenge(p) => (MessageType::PowChallenge, MessagePayload::PowChallenge(p.try_into()?)),
+            Payload::PowResponse(p) => (MessageType::PowResponse, MessagePayload::PowResponse(p.try_into()?)),
+            Payload::Error(p) => (MessageType::Error, MessagePayload::Error(p.try_into()?)),
+            Payload::StateSync(p) => (MessageType::StateSync, MessagePayload::StateSync(p.try_into()?)),
+            Payload::Ack(p) => (MessageType::Ack, MessagePayload::Ack(p.try_into()?)),
+            Payload::KeyRotation(p) => (MessageType::KeyRotation, MessagePayload::KeyRotation(p.try_into()?)),
+            Payload::HardwareAttestation(p) => (MessageType::HardwareAttestation, MessagePayload::HardwareAttestation(p.try_into()?)),
+        };
+
+        Ok(Self {
+            message_id: msg.message_id,
+            timestamp,
+            message_type,
+            in_reply_to: msg.in_reply_to,
+            signature: msg.signature,
+            payload,
+        })
+    }
+}
+
+// ==================== 認證訊息 ====================
+
+impl From<AuthChallengePayload> for pb::AuthChallenge {
+    fn from(p: AuthChallengePayload) -> Self {
+        Self { challenge: p.challenge, timestamp: 0 }
+    }
+}
+
+impl From<pb::AuthChallenge> for AuthChallengePayload {
+    fn from(p: pb::AuthChallenge) -> Self {
+        Self { challenge: p.challenge }
+    }
+}
+
+impl TryFrom<AuthResponsePayload> for pb::AuthResponse {
+    type Error = Error;
+
+    fn try_from(p: AuthResponsePayload) -> Result<Self> {
+        Ok(Self {
+            agent_id: p.agent_id,
+            signature: base64_bytes(&p.signature, "signature")?,
+            public_key: base64_bytes(&p.public_key, "public_key")?,
+            protocol: p.protocol.map(Into::into),
+        })
+    }
+}
+
+impl From<pb::AuthResponse> for AuthResponsePayload {
+    fn from(p: pb::AuthResponse) -> Self {
+        Self {
+            agent_id: p.agent_id,
+            signature: general_purpose::STANDARD.encode(p.signature),
+            public_key: general_purpose::STANDARD.encode(p.public_key),
+            protocol: p.protocol.map(Into::into),
+        }
+    }
+}
+
+impl From<AuthSuccessPayload> for pb::AuthSuccess {
+    fn from(p: AuthSuccessPayload) -> Self {
+        Self {
+            jwt_token: p.jwt_token,
+            expires_in: p.expires_in,
+            protocol: p.protocol.map(Into::into),
+        }
+    }
+}
+
+impl From<pb::AuthSuccess> for AuthSuccessPayload {
+    fn from(p: pb::AuthSuccess) -> Self {
+        Self {
+            jwt_token: p.jwt_token,
+            expires_in: p.expires_in,
+            protocol: p.protocol.map(Into::into),
+        }
+    }
+}
+
+/// 無法識別的功能名稱解碼為 `ProtocolFeature::Unknown`
+fn features(names: Vec<String>) -> Vec<ProtocolFeature> {
+    names
+        .iter()
+        .map(|name| parse_enum(name, "features").unwrap_or(ProtocolFeature::Unknown))
+        .collect()
+}
+
+impl From<ProtocolOffer> for pb::ProtocolOffer {
+    fn from(p: ProtocolOffer) -> Self {
+        Self {
+            versions: p.versions,
+            codecs: p.codecs,
+            features: p.features.iter().map(enum_name).collect(),
+        }
+    }
+}
+
+impl From<pb::ProtocolOffer> for ProtocolOffer {
+    fn from(p: pb::ProtocolOffer) -> Self {
+        Self {
+            versions: p.versions,
+            codecs: p.codecs,
+            features: features(p.features),
+        }
+    }
+}
+
+impl From<ProtocolSelection> for pb::ProtocolSelection {
+    fn from(p: ProtocolSelection) -> Self {
+        Self {
+            version: p.version,
+            features: p.features.iter().map(enum_name).collect(),
+        }
+    }
+}
+
+impl From<pb::ProtocolSelection> for ProtocolSelection {
+    fn from(p: pb::ProtocolSelection) -> Self {
+        Self {
+            version: p.version,
+            features: features(p.features),
+        }
+    }
+}
+
+// ==================== 註冊訊息 ====================
+
+impl From<AgentRegisterPayload> for pb::AgentRegister {
+    fn from(p: AgentRegisterPayload) -> Self {
+        Self {
+            agent_id: p.agent_id,
+            hardware: Some(p.hardware.into()),
+            capabilities: Some(p.capabilities.into()),
+            location: Some(p.location.into()),
+            availability: Some(p.availability.into()),
+            display_name: p.display_name,
+            backup_public_key: p.backup_public_key,
+            attestation: p.attestation.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<pb::AgentRegister> for AgentRegisterPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::AgentRegister) -> Result<Self> {
+        Ok(Self {
+            agent_id: p.agent_id,
+            hardware: required(p.hardware, "hardware")?.try_into()?,
+            capabilities: required(p.capabilities, "capabilities")?.into(),
+            location: required(p.location, "location")?.into(),
+            availability: required(p.availability, "availability")?.into(),
+            display_name: p.display_name,
+            backup_public_key: p.backup_public_key,
+            attestation: p.attestation.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl From<HardwareInfo> for pb::Hardware {
+    fn from(h: HardwareInfo) -> Self {
+        Self {
+            gpus: h.gpus.into_iter().map(Into::into).collect(),
+            cpu: Some(h.cpu.into()),
+            memory_gb: h.memory_gb,
+            storage_available_gb: h.storage_available_gb,
+        }
+    }
+}
+
+impl TryFrom<pb::Hardware> for HardwareInfo {
+    type Error = Error;
+
+    fn try_from(h: pb::Hardware) -> Result<Self> {
+        Ok(Self {
+            gpus: h.gpus.into_iter().map(TryInto::try_into).collect::<Result<_>>()?,
+            cpu: required(h.cpu, "cpu")?.into(),
+            memory_gb: h.memory_gb,
+            storage_available_gb: h.storage_available_gb,
+        })
+    }
+}
+
+impl From<GPUInfo> for pb::GpuInfo {
+    fn from(g: GPUInfo) -> Self {
+        Self {
+            index: g.index,
+            vendor: enum_name(&g.vendor),
+            model: g.model,
+            vram_gb: g.vram_gb,
+            compute_capability: g.compute_capability,
+            cuda_cores: g.cuda_cores,
+            pcie_bandwidth_gbps: g.pcie_bandwidth_gbps,
+        }
+    }
+}
+
+impl TryFrom<pb::GpuInfo> for GPUInfo {
+    type Error = Error;
+
+    fn try_from(g: pb::GpuInfo) -> Result<Self> {
+        Ok(Self {
+            index: g.index,
+            vendor: parse_enum(&g.vendor, "vendor")?,
+            model: g.model,
+            vram_gb: g.vram_gb,
+            compute_capability: g.compute_capability,
+            cuda_cores: g.cuda_cores,
+            pcie_bandwidth_gbps: g.pcie_bandwidth_gbps,
+        })
+    }
+}
+
+impl From<CPUInfo> for pb::CpuInfo {
+    fn from(c: CPUInfo) -> Self {
+        Self { model: c.model, cores: c.cores, threads: c.threads }
+    }
+}
+
+impl From<pb::CpuInfo> for CPUInfo {
+    fn from(c: pb::CpuInfo) -> Self {
+        Self { model: c.model, cores: c.cores, threads: c.threads }
+    }
+}
+
+impl From<HardwareAttestationPayload> for pb::HardwareAttestation {
+    fn from(p: HardwareAttestationPayload) -> Self {
+        Self {
+            agent_id: p.agent_id,
+            manifest: Some(p.manifest.into()),
+            manifest_hash: p.manifest_hash,
+            issued_at: rfc3339(&p.issued_at),
+            signature: p.signature,
+        }
+    }
+}
+
+impl TryFrom<pb::HardwareAttestation> for HardwareAttestationPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::HardwareAttestation) -> Result<Self> {
+        Ok(Self {
+            agent_id: p.agent_id,
+            manifest: required(p.manifest, "manifest")?.try_into()?,
+            manifest_hash: p.manifest_hash,
+            issued_at: datetime(&p.issued_at, "issued_at")?,
+            signature: p.signature,
+        })
+    }
+}
+
+impl From<HardwareManifest> for pb::HardwareManifest {
+    fn from(m: HardwareManifest) -> Self {
+        Self {
+            gpus: m.gpus.into_iter().map(Into::into).collect(),
+            cpu_model: m.cpu_model,
+            cpu_cores: m.cpu_cores,
+            cpu_threads: m.cpu_threads,
+            memory_bytes: m.memory_bytes,
+            os: m.os,
+            os_version: m.os_version,
+            kernel_version: m.kernel_version,
+            arch: m.arch,
+        }
+    }
+}
+
+impl TryFrom<pb::HardwareManifest> for HardwareManifest {
+    type Error = Error;
+
+    fn try_from(m: pb::HardwareManifest) -> Result<Self> {
+        Ok(Self {
+            gpus: m.gpus.into_iter().map(TryInto::try_into).collect::<Result<_>>()?,
+            cpu_model: m.cpu_model,
+            cpu_cores: m.cpu_cores,
+            cpu_threads: m.cpu_threads,
+            memory_bytes: m.memory_bytes,
+            os: m.os,
+            os_version: m.os_version,
+            kernel_version: m.kernel_version,
+            arch: m.arch,
+        })
+    }
+}
+
+impl From<GpuManifest> for pb::GpuManifest {
+    fn from(g: GpuManifest) -> Self {
+        Self {
+            index: g.index,
+            vendor: enum_name(&g.vendor),
+            model: g.model,
+            uuid: g.uuid,
+            pci_bus_id: g.pci_bus_id,
+            pci_device_id: g.pci_device_id,
+            vram_bytes: g.vram_bytes,
+            compute_capability: g.compute_capability,
+            driver_version: g.driver_version,
+            cuda_version: g.cuda_version,
+        }
+    }
+}
+
+impl TryFrom<pb::GpuManifest> for GpuManifest {
+    type Error = Error;
+
+    fn try_from(g: pb::GpuManifest) -> Result<Self> {
+        Ok(Self {
+            index: g.index,
+            vendor: parse_enum(&g.vendor, "vendor")?,
+            model: g.model,
+            uuid: g.uuid,
+            pci_bus_id: g.pci_bus_id,
+            pci_device_id: g.pci_device_id,
+            vram_bytes: g.vram_bytes,
+            compute_capability: g.compute_capability,
+            driver_version: g.driver_version,
+            cuda_version: g.cuda_version,
+        })
+    }
+}
+
+impl From<Capabilities> for pb::Capabilities {
+    fn from(c: Capabilities) -> Self {
+        Self {
+            supported_frameworks: c.supported_frameworks,
+            max_batch_size: c.max_batch_size,
+            fp16_support: c.fp16_support,
+            int8_support: c.int8_support,
+        }
+    }
+}
+
+impl From<pb::Capabilities> for Capabilities {
+    fn from(c: pb::Capabilities) -> Self {
+        Self {
+            supported_frameworks: c.supported_frameworks,
+            max_batch_size: c.max_batch_size,
+            fp16_support: c.fp16_support,
+            int8_support: c.int8_support,
+        }
+    }
+}
+
+impl From<Location> for pb::Location {
+    fn from(l: Location) -> Self {
+        Self {
+            country: l.country,
+            region: l.region,
+            latency_to_platform_ms: l.latency_to_platform_ms,
+        }
+    }
+}
+
+impl From<pb::Location> for Location {
+    fn from(l: pb::Location) -> Self {
+        Self {
+            country: l.country,
+            region: l.region,
+            latency_to_platform_ms: l.latency_to_platform_ms,
+        }
+    }
+}
+
+impl From<Availability> for pb::Availability {
+    fn from(a: Availability) -> Self {
+        Self { hours_per_day: a.hours_per_day, reliability_score: a.reliability_score }
+    }
+}
+
+impl From<pb::Availability> for Availability {
+    fn from(a: pb::Availability) -> Self {
+        Self { hours_per_day: a.hours_per_day, reliability_score: a.reliability_score }
+    }
+}
+
+impl From<RegisterAckPayload> for pb::RegisterAck {
+    fn from(p: RegisterAckPayload) -> Self {
+        Self {
+            agent_id: p.agent_id,
+            status: p.status,
+            pricing: Some(p.pricing.into()),
+        }
+    }
+}
+
+impl TryFrom<pb::RegisterAck> for RegisterAckPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::RegisterAck) -> Result<Self> {
+        Ok(Self {
+            agent_id: p.agent_id,
+            status: p.status,
+            pricing: required(p.pricing, "pricing")?.try_into()?,
+        })
+    }
+}
+
+impl From<Pricing> for pb::Pricing {
+    fn from(p: Pricing) -> Self {
+        Self {
+            base_rate_usd_per_hour: approx(&p.base_rate_usd_per_hour),
+            gpu_multiplier: approx(&p.gpu_multiplier),
+            effective_rate: approx(&p.effective_rate),
+            base_rate_usd_per_hour_exact: Some(p.base_rate_usd_per_hour.to_string()),
+            gpu_multiplier_exact: Some(p.gpu_multiplier.to_string()),
+            effective_rate_exact: Some(p.effective_rate.to_string()),
+        }
+    }
+}
+
+impl TryFrom<pb::Pricing> for Pricing {
+    type Error = Error;
+
+    fn try_from(p: pb::Pricing) -> Result<Self> {
+        Ok(Self {
+            base_rate_usd_per_hour: amount(
+                p.base_rate_usd_per_hour_exact,
+                p.base_rate_usd_per_hour,
+                "base_rate_usd_per_hour",
+            )?,
+            gpu_multiplier: amount(p.gpu_multiplier_exact, p.gpu_multiplier, "gpu_multiplier")?,
+            effective_rate: amount(p.effective_rate_exact, p.effective_rate, "effective_rate")?,
+        })
+    }
+}
+
+// ==================== 任務訊息 ====================
+
+impl From<TaskAssignPayload> for pb::TaskAssign {
+    fn from(p: TaskAssignPayload) -> Self {
+        Self {
+            task_id: p.task_id,
+            job_id: p.job_id,
+            priority: p.priority,
+            estimated_duration_sec: p.estimated_duration_sec,
+            requirements: Some(p.requirements.into()),
+            payload: Some(p.payload.into()),
+            pricing: Some(p.pricing.into()),
+        }
+    }
+}
+
+impl TryFrom<pb::TaskAssign> for TaskAssignPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::TaskAssign) -> Result<Self> {
+        Ok(Self {
+            task_id: p.task_id,
+            job_id: p.job_id,
+            priority: p.priority,
+            estimated_duration_sec: p.estimated_duration_sec,
+            requirements: required(p.requirements, "requirements")?.into(),
+            payload: required(p.payload, "payload")?.try_into()?,
+            pricing: required(p.pricing, "pricing")?.try_into()?,
+        })
+    }
+}
+
+impl From<TaskRequirements> for pb::TaskRequirements {
+    fn from(r: TaskRequirements) -> Self {
+        Self {
+            min_vram_gb: r.min_vram_gb,
+            min_compute_capability: r.min_compute_capability,
+            framework: r.framework,
+            fp16: r.fp16,
+        }
+    }
+}
+
+impl From<pb::TaskRequirements> for TaskRequirements {
+    fn from(r: pb::TaskRequirements) -> Self {
+        Self {
+            min_vram_gb: r.min_vram_gb,
+            min_compute_capability: r.min_compute_capability,
+            framework: r.framework,
+            fp16: r.fp16,
+        }
+    }
+}
+
+impl From<TaskPayload> for pb::TaskPayload {
+    fn from(p: TaskPayload) -> Self {
+        Self {
+            model_url: p.model_url,
+            model_hash: p.model_hash,
+            input_data_url: p.input_data_url,
+            output_url: p.output_url,
+            config: json_map(&p.config),
+            config_json: Some(p.config.to_string()),
+        }
+    }
+}
+
+impl TryFrom<pb::TaskPayload> for TaskPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::TaskPayload) -> Result<Self> {
+        Ok(Self {
+            model_url: p.model_url,
+            model_hash: p.model_hash,
+            input_data_url: p.input_data_url,
+            output_url: p.output_url,
+            config: json_or_map(p.config_json, p.config, "config")?.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<TaskAcceptPayload> for pb::TaskAccept {
+    fn from(p: TaskAcceptPayload) -> Self {
+        Self {
+            task_id: p.task_id,
+            agent_id: p.agent_id,
+            gpu_allocated: p.gpu_allocated,
+            estimated_completion: rfc3339(&p.estimated_completion),
+        }
+    }
+}
+
+impl TryFrom<pb::TaskAccept> for TaskAcceptPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::TaskAccept) -> Result<Self> {
+        Ok(Self {
+            task_id: p.task_id,
+            agent_id: p.agent_id,
+            gpu_allocated: p.gpu_allocated,
+            estimated_completion: datetime(&p.estimated_completion, "estimated_completion")?,
+        })
+    }
+}
+
+impl From<TaskRejectPayload> for pb::TaskReject {
+    fn from(p: TaskRejectPayload) -> Self {
+        Self { task_id: p.task_id, reason: p.reason, details: p.details }
+    }
+}
+
+impl From<pb::TaskReject> for TaskRejectPayload {
+    fn from(p: pb::TaskReject) -> Self {
+        Self { task_id: p.task_id, reason: p.reason, details: p.details }
+    }
+}
+
+impl From<TaskProgressPayload> for pb::TaskProgress {
+    fn from(p: TaskProgressPayload) -> Self {
+        Self {
+            task_id: p.task_id,
+            progress: p.progress,
+            stage: p.stage,
+            metrics: Some(p.metrics.into()),
+            timestamp: 0,
+        }
+    }
+}
+
+impl TryFrom<pb::TaskProgress> for TaskProgressPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::TaskProgress) -> Result<Self> {
+        Ok(Self {
+            task_id: p.task_id,
+            progress: p.progress,
+            stage: p.stage,
+            metrics: required(p.metrics, "metrics")?.into(),
+        })
+    }
+}
+
+impl From<TaskMetrics> for pb::TaskMetrics {
+    fn from(m: TaskMetrics) -> Self {
+        Self {
+            gpu_utilization: m.gpu_utilization,
+            memory_used_gb: m.memory_used_gb,
+            throughput_tokens_per_sec: m.throughput_tokens_per_sec,
+        }
+    }
+}
+
+impl From<pb::TaskMetrics> for TaskMetrics {
+    fn from(m: pb::TaskMetrics) -> Self {
+        Self {
+            gpu_utilization: m.gpu_utilization,
+            memory_used_gb: m.memory_used_gb,
+            throughput_tokens_per_sec: m.throughput_tokens_per_sec,
+        }
+    }
+}
+
+impl From<TaskCompletePayload> for pb::TaskComplete {
+    fn from(p: TaskCompletePayload) -> Self {
+        Self {
+            task_id: p.task_id,
+            result: Some(p.result.into()),
+            proof_of_work: Some(p.proof_of_work.into()),
+            metrics: Some(p.metrics.into()),
+        }
+    }
+}
+
+impl TryFrom<pb::TaskComplete> for TaskCompletePayload {
+    type Error = Error;
+
+    fn try_from(p: pb::TaskComplete) -> Result<Self> {
+        Ok(Self {
+            task_id: p.task_id,
+            result: required(p.result, "result")?.into(),
+            proof_of_work: required(p.proof_of_work, "proof_of_work")?.into(),
+            metrics: required(p.metrics, "metrics")?.into(),
+        })
+    }
+}
+
+impl From<TaskResult> for pb::TaskResult {
+    fn from(r: TaskResult) -> Self {
+        Self {
+            output_url: r.output_url,
+            output_hash: r.output_hash,
+            execution_time_sec: r.execution_time_sec,
+            gpu_time_sec: r.gpu_time_sec,
+        }
+    }
+}
+
+impl From<pb::TaskResult> for TaskResult {
+    fn from(r: pb::TaskResult) -> Self {
+        Self {
+            output_url: r.output_url,
+            output_hash: r.output_hash,
+            execution_time_sec: r.execution_time_sec,
+            gpu_time_sec: r.gpu_time_sec,
+        }
+    }
+}
+
+impl From<ProofOfWork> for pb::ProofOfWork {
+    fn from(p: ProofOfWork) -> Self {
+        Self {
+            method: p.method,
+            challenge_id: p.challenge_id,
+            response: p.response,
+            gpu_signature: p.gpu_signature,
+        }
+    }
+}
+
+impl From<pb::ProofOfWork> for ProofOfWork {
+    fn from(p: pb::ProofOfWork) -> Self {
+        Self {
+            method: p.method,
+            challenge_id: p.challenge_id,
+            response: p.response,
+            gpu_signature: p.gpu_signature,
+        }
+    }
+}
+
+impl From<ExecutionMetrics> for pb::ExecutionMetrics {
+    fn from(m: ExecutionMetrics) -> Self {
+        Self {
+            avg_gpu_utilization: m.avg_gpu_utilization,
+            peak_memory_gb: m.peak_memory_gb,
+            energy_kwh: m.energy_kwh,
+        }
+    }
+}
+
+impl From<pb::ExecutionMetrics> for ExecutionMetrics {
+    fn from(m: pb::ExecutionMetrics) -> Self {
+        Self {
+            avg_gpu_utilization: m.avg_gpu_utilization,
+            peak_memory_gb: m.peak_memory_gb,
+            energy_kwh: m.energy_kwh,
+        }
+    }
+}
+
+impl From<TaskFailedPayload> for pb::TaskFailed {
+    fn from(p: TaskFailedPayload) -> Self {
+        Self {
+            task_id: p.task_id,
+            error: Some(pb::ErrorInfo {
+                code: p.error.code,
+                message: p.error.message,
+                details: p.error.details,
+            }),
+            partial_results: p.partial_results,
+        }
+    }
+}
+
+impl TryFrom<pb::TaskFailed> for TaskFailedPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::TaskFailed) -> Result<Self> {
+        let error = required(p.error, "error")?;
+        Ok(Self {
+            task_id: p.task_id,
+            error: TaskErrorInfo {
+                code: error.code,
+                message: error.message,
+                details: error.details,
+            },
+            partial_results: p.partial_results,
+        })
+    }
+}
+
+// ==================== 監控訊息 ====================
+
+impl From<HeartbeatPayload> for pb::Heartbeat {
+    fn from(p: HeartbeatPayload) -> Self {
+        Self {
+            agent_id: p.agent_id,
+            status: enum_name(&p.status),
+            current_task_id: p.current_task_id,
+            gpu_status: p.gpu_status.into_iter().map(Into::into).collect(),
+            uptime_sec: p.uptime_sec,
+            timestamp: 0,
+        }
+    }
+}
+
+impl TryFrom<pb::Heartbeat> for HeartbeatPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::Heartbeat) -> Result<Self> {
+        Ok(Self {
+            agent_id: p.agent_id,
+            status: parse_enum(&p.status, "status")?,
+            current_task_id: p.current_task_id,
+            gpu_status: p.gpu_status.into_iter().map(Into::into).collect(),
+            uptime_sec: p.uptime_sec,
+        })
+    }
+}
+
+impl From<GPUStatus> for pb::GpuStatus {
+    fn from(s: GPUStatus) -> Self {
+        Self {
+            index: s.index,
+            utilization: s.utilization,
+            memory_used_gb: s.memory_used_gb,
+            memory_total_gb: s.memory_total_gb,
+            temperature_c: s.temperature_c,
+            power_draw_w: s.power_draw_w,
+            fan_speed_percent: s.fan_speed_percent,
+        }
+    }
+}
+
+impl From<pb::GpuStatus> for GPUStatus {
+    fn from(s: pb::GpuStatus) -> Self {
+        Self {
+            index: s.index,
+            utilization: s.utilization,
+            memory_used_gb: s.memory_used_gb,
+            memory_total_gb: s.memory_total_gb,
+            temperature_c: s.temperature_c,
+            power_draw_w: s.power_draw_w,
+            fan_speed_percent: s.fan_speed_percent,
+        }
+    }
+}
+
+impl From<MetricsBatchPayload> for pb::MetricsBatch {
+    fn from(p: MetricsBatchPayload) -> Self {
+        let m = p.aggregated_metrics;
+        Self {
+            agent_id: p.agent_id,
+            time_range: Some(pb::TimeRange {
+                start: rfc3339(&p.time_range.start),
+                end: rfc3339(&p.time_range.end),
+            }),
+            aggregated_metrics: Some(pb::AggregatedMetrics {
+                tasks_completed: m.tasks_completed,
+                tasks_failed: m.tasks_failed,
+                total_gpu_hours: m.total_gpu_hours as f32,
+                avg_gpu_utilization: m.avg_gpu_utilization,
+                total_energy_kwh: m.total_energy_kwh,
+                earnings_usd: approx(&m.earnings_usd),
+                total_gpu_hours_exact: Some(m.total_gpu_hours),
+                earnings_usd_exact: Some(m.earnings_usd.to_string()),
+            }),
+        }
+    }
+}
+
+impl TryFrom<pb::MetricsBatch> for MetricsBatchPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::MetricsBatch) -> Result<Self> {
+        let range = required(p.time_range, "time_range")?;
+        let m = required(p.aggregated_metrics, "aggregated_metrics")?;
+        Ok(Self {
+            agent_id: p.agent_id,
+            time_range: TimeRange {
+                start: datetime(&range.start, "start")?,
+                end: datetime(&range.end, "end")?,
+            },
+            aggregated_metrics: AggregatedMetrics {
+                tasks_completed: m.tasks_completed,
+                tasks_failed: m.tasks_failed,
+                total_gpu_hours: m.total_gpu_hours_exact.unwrap_or(m.total_gpu_hours as f64),
+                avg_gpu_utilization: m.avg_gpu_utilization,
+                total_energy_kwh: m.total_energy_kwh,
+                earnings_usd: amount(m.earnings_usd_exact, m.earnings_usd, "earnings_usd")?,
+            },
+        })
+    }
+}
+
+// ==================== 收益訊息 ====================
+
+impl From<EarningsRecordPayload> for pb::EarningsRecord {
+    fn from(p: EarningsRecordPayload) -> Self {
+        Self {
+            task_id: p.task_id,
+            earnings: Some(p.earnings.into()),
+            status: enum_name(&p.status),
+            estimated_payout_date: p.estimated_payout_date,
+        }
+    }
+}
+
+impl TryFrom<pb::EarningsRecord> for EarningsRecordPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::EarningsRecord) -> Result<Self> {
+        Ok(Self {
+            task_id: p.task_id,
+            earnings: required(p.earnings, "earnings")?.try_into()?,
+            status: parse_enum(&p.status, "status")?,
+            estimated_payout_date: p.estimated_payout_date,
+        })
+    }
+}
+
+impl From<EarningsDetail> for pb::EarningsDetail {
+    fn from(e: EarningsDetail) -> Self {
+        Self {
+            gpu_hours: e.gpu_hours as f32,
+            rate_usd_per_hour: approx(&e.rate_usd_per_hour),
+            amount_usd: approx(&e.amount_usd),
+            bonus_multiplier: e.bonus_multiplier,
+            final_amount_usd: approx(&e.final_amount_usd),
+            gpu_hours_exact: Some(e.gpu_hours),
+            rate_usd_per_hour_exact: Some(e.rate_usd_per_hour.to_string()),
+            amount_usd_exact: Some(e.amount_usd.to_string()),
+            final_amount_usd_exact: Some(e.final_amount_usd.to_string()),
+        }
+    }
+}
+
+impl TryFrom<pb::EarningsDetail> for EarningsDetail {
+    type Error = Error;
+
+    fn try_from(e: pb::EarningsDetail) -> Result<Self> {
+        Ok(Self {
+            gpu_hours: e.gpu_hours_exact.unwrap_or(e.gpu_hours as f64),
+            rate_usd_per_hour: amount(e.rate_usd_per_hour_exact, e.rate_usd_per_hour, "rate_usd_per_hour")?,
+            amount_usd: amount(e.amount_usd_exact, e.amount_usd, "amount_usd")?,
+            bonus_multiplier: e.bonus_multiplier,
+            final_amount_usd: amount(e.final_amount_usd_exact, e.final_amount_usd, "final_amount_usd")?,
+        })
+    }
+}
+
+impl From<PayoutNotificationPayload> for pb::PayoutNotification {
+    fn from(p: PayoutNotificationPayload) -> Self {
+        let s = p.summary;
+        Self {
+            payout_id: p.payout_id,
+            period: Some(pb::PayoutPeriod { start: p.period.start, end: p.period.end }),
+            summary: Some(pb::PayoutSummary {
+                total_tasks: s.total_tasks,
+                total_gpu_hours: s.total_gpu_hours as f32,
+                gross_amount_usd: approx(&s.gross_amount_usd),
+                platform_fee_usd: approx(&s.platform_fee_usd),
+                net_amount_usd: approx(&s.net_amount_usd),
+                total_gpu_hours_exact: Some(s.total_gpu_hours),
+                gross_amount_usd_exact: Some(s.gross_amount_usd.to_string()),
+                platform_fee_usd_exact: Some(s.platform_fee_usd.to_string()),
+                net_amount_usd_exact: Some(s.net_amount_usd.to_string()),
+            }),
+            payment_method: p.payment_method,
+            payment_address: p.payment_address,
+            status: enum_name(&p.status),
+        }
+    }
+}
+
+impl TryFrom<pb::PayoutNotification> for PayoutNotificationPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::PayoutNotification) -> Result<Self> {
+        let period = required(p.period, "period")?;
+        let s = required(p.summary, "summary")?;
+        Ok(Self {
+            payout_id: p.payout_id,
+            period: PayoutPeriod { start: period.start, end: period.end },
+            summary: PayoutSummary {
+                total_tasks: s.total_tasks,
+                total_gpu_hours: s.total_gpu_hours_exact.unwrap_or(s.total_gpu_hours as f64),
+                gross_amount_usd: amount(s.gross_amount_usd_exact, s.gross_amount_usd, "gross_amount_usd")?,
+                platform_fee_usd: amount(s.platform_fee_usd_exact, s.platform_fee_usd, "platform_fee_usd")?,
+                net_amount_usd: amount(s.net_amount_usd_exact, s.net_amount_usd, "net_amount_usd")?,
+            },
+            payment_method: p.payment_method,
+            payment_address: p.payment_address,
+            status: parse_enum(&p.status, "status")?,
+        })
+    }
+}
+
+// ==================== 工作證明訊息 ====================
+
+impl TryFrom<PowChallengePayload> for pb::PowChallenge {
+    type Error = Error;
+
+    fn try_from(p: PowChallengePayload) -> Result<Self> {
+        Ok(Self {
+            challenge_id: p.challenge_id,
+            nonce: hex_bytes(&p.nonce, "nonce")?,
+            difficulty: p.difficulty,
+            deadline: rfc3339(&p.deadline),
+        })
+    }
+}
+
+impl TryFrom<pb::PowChallenge> for PowChallengePayload {
+    type Error = Error;
+
+    fn try_from(p: pb::PowChallenge) -> Result<Self> {
+        Ok(Self {
+            challenge_id: p.challenge_id,
+            nonce: hex::encode(p.nonce),
+            difficulty: p.difficulty,
+            deadline: datetime(&p.deadline, "deadline")?,
+        })
+    }
+}
+
+impl TryFrom<PowResponsePayload> for pb::PowResponse {
+    type Error = Error;
+
+    fn try_from(p: PowResponsePayload) -> Result<Self> {
+        Ok(Self {
+            challenge_id: p.challenge_id,
+            response: hex_bytes(&p.response, "response")?,
+            computation_time_ms: p.computation_time_ms,
+            gpu_signature: Some(pb::GpuSignature {
+                device_uuid: p.gpu_signature.device_uuid,
+                cuda_version: p.gpu_signature.cuda_version,
+            }),
+        })
+    }
+}
+
+impl TryFrom<pb::PowResponse> for PowResponsePayload {
+    type Error = Error;
+
+    fn try_from(p: pb::PowResponse) -> Result<Self> {
+        let signature = required(p.gpu_signature, "gpu_signature")?;
+        Ok(Self {
+            challenge_id: p.challenge_id,
+            response: hex::encode(p.response),
+            computation_time_ms: p.computation_time_ms,
+            gpu_signature: GpuSignature {
+                device_uuid: signature.device_uuid,
+                cuda_version: signature.cuda_version,
+            },
+        })
+    }
+}
+
+// ==================== 錯誤訊息 ====================
+
+impl From<ErrorPayload> for pb::Error {
+    fn from(p: ErrorPayload) -> Self {
+        Self {
+            code: p.code,
+            message: p.message,
+            context: p.context.as_ref().map(json_map).unwrap_or_default(),
+            recoverable: p.recoverable,
+            context_json: p.context.map(|c| c.to_string()),
+        }
+    }
+}
+
+impl TryFrom<pb::Error> for ErrorPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::Error) -> Result<Self> {
+        Ok(Self {
+            code: p.code,
+            message: p.message,
+            context: json_or_map(p.context_json, p.context, "context")?,
+            recoverable: p.recoverable,
+        })
+    }
+}
+
+// ==================== 狀態同步訊息 ====================
+
+impl From<StateSyncPayload> for pb::StateSync {
+    fn from(p: StateSyncPayload) -> Self {
+        Self {
+            agent_id: p.agent_id,
+            last_heartbeat: rfc3339(&p.last_heartbeat),
+            active_tasks: p
+                .active_tasks
+                .into_iter()
+                .map(|t| pb::ActiveTaskInfo {
+                    task_id: t.task_id,
+                    progress: t.progress,
+                    started_at: rfc3339(&t.started_at),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::StateSync> for StateSyncPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::StateSync) -> Result<Self> {
+        Ok(Self {
+            agent_id: p.agent_id,
+            last_heartbeat: datetime(&p.last_heartbeat, "last_heartbeat")?,
+            active_tasks: p
+                .active_tasks
+                .into_iter()
+                .map(|t| {
+                    Ok(ActiveTaskInfo {
+                        task_id: t.task_id,
+                        progress: t.progress,
+                        started_at: datetime(&t.started_at, "started_at")?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+// ==================== 送達確認訊息 ====================
+
+impl From<AckPayload> for pb::Ack {
+    fn from(p: AckPayload) -> Self {
+        Self {
+            status: enum_name(&p.status),
+        }
+    }
+}
+
+impl TryFrom<pb::Ack> for AckPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::Ack) -> Result<Self> {
+        Ok(Self {
+            status: parse_enum(&p.status, "status")?,
+        })
+    }
+}
+
+// ==================== 金鑰輪換訊息 ====================
+
+impl From<KeyRotationPayload> for pb::KeyRotation {
+    fn from(p: KeyRotationPayload) -> Self {
+        let statement = p.statement;
+        Self {
+            statement: Some(pb::RotationStatement {
+                agent_id: statement.agent_id,
+                public_key: statement.public_key,
+                new_agent_id: statement.new_agent_id,
+                new_public_key: statement.new_public_key,
+                authority: enum_name(&statement.authority),
+                reason: enum_name(&statement.reason),
+                issued_at: rfc3339(&statement.issued_at),
+            }),
+            signature: p.signature,
+            new_key_signature: p.new_key_signature,
+        }
+    }
+}
+
+impl TryFrom<pb::KeyRotation> for KeyRotationPayload {
+    type Error = Error;
+
+    fn try_from(p: pb::KeyRotation) -> Result<Self> {
+        let statement = required(p.statement, "statement")?;
+        Ok(Self {
+            statement: RotationStatement {
+                agent_id: statement.agent_id,
+                public_key: statement.public_key,
+                new_agent_id: statement.new_agent_id,
+                new_public_key: statement.new_public_key,
+                authority: parse_enum(&statement.authority, "authority")?,
+                reason: parse_enum(&statement.reason, "reason")?,
+                issued_at: datetime(&statement.issued_at, "issued_at")?,
+            },
+            signature: p.signature,
+            new_key_signature: p.new_key_signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EarningStatus;
+
+    #[test]
+    fn test_protobuf_roundtrip_heartbeat() {
+        let msg = create_heartbeat(
+            "agent-test-001".to_string(),
+            AgentStatus::Working,
+            Some("task-001".to_string()),
+            vec![GPUStatus {
+                index: 0,
+                utilization: 0.98,
+                memory_used_gb: 11.2,
+                memory_total_gb: 24.0,
+                temperature_c: 72.0,
+                power_draw_w: 350.0,
+                fan_speed_percent: 65.0,
+            }],
+            86400,
+        );
+
+        let bytes = msg.to_protobuf().unwrap();
+        let decoded = Message::from_protobuf(&bytes).unwrap();
+
+        assert_eq!(decoded.message_type, MessageType::Heartbeat);
+        assert_eq!(decoded.to_json().unwrap(), msg.to_json().unwrap());
+        assert!(bytes.len() < msg.to_json().unwrap().len());
+    }
+
+    #[test]
+    fn test_protobuf_roundtrip_earnings_is_lossless() {
+        let msg = Message::new(
+            MessageType::EarningsRecord,
+            MessagePayload::EarningsRecord(EarningsRecordPayload {
+                task_id: "task-2024-001".to_string(),
+                earnings: EarningsDetail {
+                    gpu_hours: 0.079,
+                    rate_usd_per_hour: "1.25".parse().unwrap(),
+                    amount_usd: "0.09875".parse().unwrap(),
+                    bonus_multiplier: 1.05,
+                    final_amount_usd: "0.1036875".parse().unwrap(),
+                },
+                status: EarningStatus::Pending,
+                estimated_payout_date: "2024-11-21".to_string(),
+            }),
+        );
+
+        let decoded = Message::from_protobuf(&msg.to_protobuf().unwrap()).unwrap();
+
+        assert_eq!(decoded.timestamp, msg.timestamp);
+        assert_eq!(decoded.to_json().unwrap(), msg.to_json().unwrap());
+    }
+
+    #[test]
+    fn test_protobuf_decodes_fields_from_original_contract() {
+        // 舊版平台只送 float 金額與 map 形式的錯誤上下文
+        let pricing = Pricing::try_from(pb::Pricing {
+            base_rate_usd_per_hour: 1.25,
+            gpu_multiplier: 1.5,
+            effective_rate: 1.875,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(pricing.effective_rate, "1.875".parse::<Decimal>().unwrap());
+
+        let error = ErrorPayload::try_from(pb::Error {
+            code: "DOWNLOAD_FAILED".to_string(),
+            message: "Failed to download model".to_string(),
+            context: HashMap::from([("task_id".to_string(), "task-001".to_string())]),
+            recoverable: false,
+            context_json: None,
+        })
+        .unwrap();
+        assert_eq!(error.context, Some(serde_json::json!({ "task_id": "task-001" })));
+
+        // 新版同時寫入兩種欄位
+        let encoded = pb::Pricing::from(pricing);
+        assert_eq!(encoded.effective_rate, 1.875);
+        assert_eq!(encoded.effective_rate_exact.as_deref(), Some("1.875"));
+    }
+
+    #[test]
+    fn test_protobuf_unknown_payload() {
+        let msg = pb::Message {
+            message_id: "msg-001".to_string(),
+            timestamp: 0,
+            in_reply_to: None,
+            signature: None,
+            payload: None,
+        };
+
+        let decoded = Message::try_from(msg).unwrap();
+        assert_eq!(decoded.message_type, MessageType::Unknown);
+        assert!(matches!(decoded.to_protobuf(), Err(Error::ProtocolError(_))));
+    }
+
+    #[test]
+    fn test_protobuf_roundtrip_protocol_offer() {
+        let msg = create_auth_response(
+            "agent-test-001".to_string(),
+            "c2lnbmF0dXJl".to_string(),
+            "cHVibGljLWtleQ==".to_string(),
+            Some(ProtocolOffer {
+                versions: vec![1, 2],
+                codecs: vec!["agent.orban.v1+proto".to_string()],
+                features: vec![ProtocolFeature::StateSync, ProtocolFeature::MessageSigning],
+            }),
+        );
+
+        let decoded = Message::from_protobuf(&msg.to_protobuf().unwrap()).unwrap();
+        assert_eq!(decoded.to_json().unwrap(), msg.to_json().unwrap());
+    }
+}
@@ -6,18 +6,27 @@ use tracing::{info, warn};
 /// 重連策略
 pub struct ReconnectStrategy {
     max_retries: u32,
-    base_delay_secs: u64,
-    max_delay_secs: u64,
+    base_delay: Duration,
+    max_delay: Duration,
     current_attempt: u32,
 }
 
 impl ReconnectStrategy {
     /// 創建新的重連策略
     pub fn new() -> Self {
+        Self::with_limits(
+            10,
+            Duration::from_secs(1),
+            Duration::from_secs(300), // 最多 5 分鐘
+        )
+    }
+
+    /// 使用指定的次數與延遲上下限創建
+    pub fn with_limits(max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
         Self {
-            max_retries: 10,
-            base_delay_secs: 1,
-            max_delay_secs: 300, // 最多 5 分鐘
+            max_retries,
+            base_delay,
+            max_delay,
             current_attempt: 0,
         }
     }
@@ -34,17 +43,19 @@ impl ReconnectStrategy {
         }
 
         // 指數退避: delay = min(base * 2^attempt, max_delay)
-        let delay_secs = (self.base_delay_secs * (2_u64.pow(self.current_attempt)))
-            .min(self.max_delay_secs);
+        let delay = self
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(self.current_attempt))
+            .min(self.max_delay);
 
         self.current_attempt += 1;
 
         info!(
-            "Reconnection attempt {}/{}, waiting {} seconds",
-            self.current_attempt, self.max_retries, delay_secs
+            "Reconnection attempt {}/{}, waiting {:?}",
+            self.current_attempt, self.max_retries, delay
         );
 
-        Some(Duration::from_secs(delay_secs))
+        Some(delay)
     }
 
     /// 執行重連邏輯
@@ -99,4 +110,18 @@ mod tests {
         strategy.reset();
         assert_eq!(strategy.next_delay(), Some(Duration::from_secs(1)));
     }
+
+    #[test]
+    fn test_reconnect_strategy_limits() {
+        let mut strategy = ReconnectStrategy::with_limits(
+            3,
+            Duration::from_millis(100),
+            Duration::from_millis(250),
+        );
+
+        assert_eq!(strategy.next_delay(), Some(Duration::from_millis(100)));
+        assert_eq!(strategy.next_delay(), Some(Duration::from_millis(200)));
+        assert_eq!(strategy.next_delay(), Some(Duration::from_millis(250)));
+        assert_eq!(strategy.next_delay(), None);
+    }
 }
@@ -0,0 +1,208 @@
+// 協議會話記錄
+//
+// 設定 `record_path`（`orban-agent start --record <file>`）後，傳輸層將收發的每則協議訊息
+// 附上時間與方向，以 JSONL（每行一則）附加寫入檔案，供與平台核對或以 `orban-agent replay` 離線重現：
+// - WebSocket 記錄寫入連線及解碼後的每則訊息，包含認證握手、重送與被入站防護捨棄的訊息
+// - HTTP 長輪詢記錄每次請求的內容及回應中的訊息
+// - 會話令牌 (`jwt_token`)、認證簽名與逐訊息簽名以 `[redacted]` 取代
+
+use super::orban_protocol::{Message, MessagePayload, MessageType};
+use crate::config::NetworkConfig;
+use crate::error::{Error, Result};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// 取代敏感欄位的內容
+pub const REDACTED: &str = "[redacted]";
+
+/// 訊息方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// 平台 → Agent
+    Inbound,
+    /// Agent → 平台
+    Outbound,
+}
+
+/// 記錄檔中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub at: DateTime<Utc>,
+    pub direction: Direction,
+    pub message: Message,
+}
+
+/// 會話記錄器，未啟用時不做任何事
+#[derive(Debug, Default)]
+pub struct SessionRecorder {
+    file: Option<Mutex<File>>,
+}
+
+impl SessionRecorder {
+    /// 不記錄
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// 附加寫入指定檔案
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        info!("Recording protocol messages to {}", path.display());
+
+        Ok(Self {
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    /// 依配置建立
+    pub fn from_config(config: &NetworkConfig) -> Result<Self> {
+        match &config.record_path {
+            Some(path) => Self::create(path),
+            None => Ok(Self::disabled()),
+        }
+    }
+
+    /// 是否啟用
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// 記錄一則訊息，寫入失敗時僅記錄警告，不影響收發
+    pub fn record(&self, direction: Direction, msg: &Message) {
+        let Some(file) = &self.file else {
+            return;
+        };
+
+        let record = SessionRecord {
+            at: Utc::now(),
+            direction,
+            message: redact(msg),
+        };
+
+        let mut line = match serde_json::to_vec(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to record {:?} message {}: {}", msg.message_type, msg.message_id, e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        // 整行一次寫入，多個傳輸層附加寫入同一檔案時不交錯
+        if let Err(e) = file.lock().unwrap().write_all(&line) {
+            warn!("Failed to record {:?} message {}: {}", msg.message_type, msg.message_id, e);
+        }
+    }
+}
+
+/// 去除會話令牌與簽名
+pub fn redact(msg: &Message) -> Message {
+    let mut msg = msg.clone();
+
+    if msg.signature.is_some() {
+        msg.signature = Some(REDACTED.to_string());
+    }
+
+    match &mut msg.payload {
+        MessagePayload::AuthSuccess(payload) => payload.jwt_token = REDACTED.to_string(),
+        MessagePayload::AuthResponse(payload) => payload.signature = REDACTED.to_string(),
+        _ => {}
+    }
+
+    msg
+}
+
+/// 讀取記錄檔
+pub fn read_session(path: &Path) -> Result<Vec<SessionRecord>> {
+    let file = File::open(path).map_err(|_| Error::FileNotFound(path.display().to_string()))?;
+
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = serde_json::from_str(&line)
+            .map_err(|e| Error::ProtocolError(format!("{}:{}: {}", path.display(), index + 1, e)))?;
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// 傳輸層會交給 Agent 處理的訊息
+///
+/// 認證握手、註冊確認、送達確認與請求的回覆由傳輸層處理，重放時略過
+pub fn reaches_agent(msg: &Message) -> bool {
+    msg.in_reply_to.is_none()
+        && !matches!(
+            msg.message_type,
+            MessageType::AuthChallenge | MessageType::AuthSuccess | MessageType::RegisterAck | MessageType::Ack
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{create_auth_response, create_heartbeat, AgentStatus, AuthSuccessPayload};
+
+    #[test]
+    fn test_redacts_tokens_and_signatures() {
+        let response = create_auth_response(
+            "agent-001".to_string(),
+            "c2lnbmF0dXJl".to_string(),
+            "cHVibGljLWtleQ==".to_string(),
+            None,
+        );
+        match redact(&response).payload {
+            MessagePayload::AuthResponse(payload) => {
+                assert_eq!(payload.signature, REDACTED);
+                assert_eq!(payload.public_key, "cHVibGljLWtleQ==");
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+
+        let success = Message::new(
+            MessageType::AuthSuccess,
+            MessagePayload::AuthSuccess(AuthSuccessPayload {
+                jwt_token: "eyJhbGciOi.payload.sig".to_string(),
+                expires_in: 86400,
+                protocol: None,
+            }),
+        );
+        assert!(!serde_json::to_string(&redact(&success)).unwrap().contains("eyJhbGciOi"));
+
+        let mut heartbeat = create_heartbeat("agent-001".to_string(), AgentStatus::Idle, None, Vec::new(), 0);
+        assert!(redact(&heartbeat).signature.is_none());
+        heartbeat.signature = Some("c2lnbmF0dXJl".to_string());
+        assert_eq!(redact(&heartbeat).signature.as_deref(), Some(REDACTED));
+    }
+
+    #[test]
+    fn test_record_and_read_back() {
+        let path = std::env::temp_dir().join(format!("orban-session-{}.jsonl", uuid::Uuid::new_v4()));
+        let heartbeat = create_heartbeat("agent-001".to_string(), AgentStatus::Idle, None, Vec::new(), 0);
+
+        let recorder = SessionRecorder::create(&path).unwrap();
+        recorder.record(Direction::Outbound, &heartbeat);
+        recorder.record(Direction::Inbound, &heartbeat);
+        SessionRecorder::disabled().record(Direction::Outbound, &heartbeat);
+
+        let records = read_session(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, Direction::Outbound);
+        assert_eq!(records[1].direction, Direction::Inbound);
+        assert_eq!(records[0].message.message_id, heartbeat.message_id);
+        assert!(std::fs::read_to_string(&path).unwrap().lines().all(|line| line.contains("\"direction\"")));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
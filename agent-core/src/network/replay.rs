@@ -0,0 +1,205 @@
+// 重放傳輸層
+//
+// 離線重現記錄的會話時代替實際的傳輸層：不連線、不發送，
+// Agent 送出的訊息保留於記憶體並可另外寫入記錄檔，以便與原始記錄比對
+
+use super::endpoints::Endpoints;
+use super::latency::LatencyStats;
+use super::orban_protocol::{self, ActiveTaskInfo, AgentStatus, KeyRotationPayload, Message, TaskErrorInfo, TaskMetrics};
+use super::recorder::{Direction, SessionRecorder};
+use super::transport::Transport;
+use crate::error::{Error, Result};
+use crate::gpu::PowResponse;
+use crate::types::*;
+use crate::AgentConfig;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 記錄 Agent 送出訊息的傳輸層
+pub struct ReplayTransport {
+    agent_id: String,
+    display_name: Option<String>,
+    endpoints: Arc<Endpoints>,
+    recorder: SessionRecorder,
+    sent: Mutex<Vec<Message>>,
+    active_tasks: Mutex<HashMap<String, ActiveTaskInfo>>,
+}
+
+impl ReplayTransport {
+    /// 以記錄中的 `agent_id` 重放，`recorder` 記錄 Agent 重放時送出的訊息
+    pub fn new(agent_id: impl Into<String>, config: &AgentConfig, recorder: SessionRecorder) -> Result<Self> {
+        Ok(Self {
+            agent_id: agent_id.into(),
+            display_name: config.display_name.clone(),
+            endpoints: Arc::new(Endpoints::from_config(config)?),
+            recorder,
+            sent: Mutex::new(Vec::new()),
+            active_tasks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 取出上次呼叫後 Agent 送出的訊息
+    pub fn take_sent(&self) -> Vec<Message> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+
+    fn send(&self, msg: Message) {
+        self.recorder.record(Direction::Outbound, &msg);
+        self.sent.lock().unwrap().push(msg);
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    async fn connect(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 重放時不持有身分金鑰，註冊不附硬體證明
+    async fn register(
+        &self,
+        hardware: HardwareInfo,
+        _manifest: HardwareManifest,
+        capabilities: Capabilities,
+        location: Location,
+        availability: Availability,
+    ) -> Result<()> {
+        self.send(orban_protocol::create_agent_register(
+            self.agent_id.clone(),
+            self.display_name.clone(),
+            None,
+            None,
+            hardware,
+            capabilities,
+            location,
+            availability,
+        ));
+        Ok(())
+    }
+
+    /// 平台訊息由重放方直接交給 Agent
+    async fn receive(&self) -> Result<Message> {
+        Err(Error::ConnectionFailed("Replay transport does not receive messages".to_string()))
+    }
+
+    async fn send_heartbeat(
+        &self,
+        status: AgentStatus,
+        current_task_id: Option<String>,
+        gpu_status: Vec<GPUStatus>,
+        uptime_sec: u64,
+    ) -> Result<()> {
+        self.send(orban_protocol::create_heartbeat(
+            self.agent_id.clone(),
+            status,
+            current_task_id,
+            gpu_status,
+            uptime_sec,
+        ));
+        Ok(())
+    }
+
+    async fn accept_task(&self, task_id: &str) -> Result<()> {
+        self.send(orban_protocol::create_task_accept(
+            task_id.to_string(),
+            self.agent_id.clone(),
+            0,
+            0,
+        ));
+        self.active_tasks.lock().unwrap().insert(
+            task_id.to_string(),
+            ActiveTaskInfo {
+                task_id: task_id.to_string(),
+                progress: 0.0,
+                started_at: Utc::now(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn reject_task(&self, task_id: &str, reason: &str) -> Result<()> {
+        self.send(orban_protocol::create_task_reject(
+            task_id.to_string(),
+            reason.to_string(),
+            String::new(),
+        ));
+        Ok(())
+    }
+
+    async fn send_task_progress(&self, task_id: &str, progress: f32, stage: &str, metrics: TaskMetrics) -> Result<()> {
+        if let Some(task) = self.active_tasks.lock().unwrap().get_mut(task_id) {
+            task.progress = progress.clamp(0.0, 1.0);
+        }
+        self.send(orban_protocol::create_task_progress(
+            task_id.to_string(),
+            progress.clamp(0.0, 1.0),
+            stage.to_string(),
+            metrics,
+        ));
+        Ok(())
+    }
+
+    async fn complete_task(
+        &self,
+        task_id: &str,
+        result: TaskResult,
+        proof_of_work: ProofOfWork,
+        metrics: ExecutionMetrics,
+    ) -> Result<()> {
+        self.send(orban_protocol::create_task_complete(
+            task_id.to_string(),
+            result,
+            proof_of_work,
+            metrics,
+        ));
+        self.active_tasks.lock().unwrap().remove(task_id);
+        Ok(())
+    }
+
+    async fn fail_task(&self, task_id: &str, error: TaskErrorInfo) -> Result<()> {
+        self.send(orban_protocol::create_task_failed(task_id.to_string(), error, None));
+        self.active_tasks.lock().unwrap().remove(task_id);
+        Ok(())
+    }
+
+    async fn send_pow_response(&self, response: PowResponse) -> Result<()> {
+        self.send(orban_protocol::create_pow_response(response));
+        Ok(())
+    }
+
+    async fn active_tasks(&self) -> Vec<ActiveTaskInfo> {
+        self.active_tasks.lock().unwrap().values().cloned().collect()
+    }
+
+    async fn abort_task(&self, task_id: &str) {
+        self.active_tasks.lock().unwrap().remove(task_id);
+    }
+
+    async fn rotate_key(&self, rotation: KeyRotationPayload) -> Result<()> {
+        self.send(orban_protocol::create_key_rotation(rotation));
+        Ok(())
+    }
+
+    async fn attest_hardware(&self, _manifest: HardwareManifest) -> Result<()> {
+        Ok(())
+    }
+
+    fn latency(&self) -> LatencyStats {
+        LatencyStats::default()
+    }
+
+    fn endpoints(&self) -> Arc<Endpoints> {
+        self.endpoints.clone()
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        Ok(())
+    }
+}
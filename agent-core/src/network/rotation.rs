@@ -0,0 +1,381 @@
+// 金鑰輪換
+//
+// 更換身分金鑰時 Agent ID 隨之改變，平台依輪換聲明將舊 ID 的信譽與收益記錄轉移到新 ID：
+// - Agent 生成新金鑰，聲明（RotationStatement）由舊金鑰與新金鑰分別簽名，以 KEY_ROTATION 送出
+// - 平台回覆 ACK 後才改用新金鑰；回覆 ERROR 時捨棄新金鑰，繼續使用舊金鑰
+// - 新金鑰在送出前保存於 `<私鑰文件>.pending`，送出失敗時保留，重試時沿用同一把新金鑰
+// - 確認後舊金鑰改名保存（`<私鑰文件>.<時間>.old`），新金鑰取代原文件
+// - 身分金鑰遺失時以註冊時提交的離線備份金鑰代替舊金鑰簽名，並以新金鑰連線送出
+//
+// 每次輪換的請求與結果附加於 data_dir/key-rotations.jsonl。
+// 輪換以獨立的會話進行（數據目錄為 data_dir/rotation），不影響發件匣
+//
+// 簽名內容為 `ROTATION_CONTEXT` 接上聲明的標準 JSON（見 signing::canonical_json）
+
+use super::auth::Authenticator;
+use super::identity::{self, IdentityRecord};
+use super::keystore;
+use super::orban_protocol::{
+    KeyRotationPayload, RotationAuthority, RotationReason, RotationStatement,
+};
+use super::signing;
+use super::transport;
+use crate::error::{Error, Result};
+use crate::AgentConfig;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// 輪換聲明的簽名前綴
+pub const ROTATION_CONTEXT: &[u8] = b"orban-key-rotation-v1\n";
+
+/// 輪換記錄文件名稱（位於 data_dir 下）
+const ROTATION_LOG: &str = "key-rotations.jsonl";
+
+/// 輪換會話的數據目錄名稱（位於 data_dir 下）
+const SESSION_DIR: &str = "rotation";
+
+/// 聲明的簽名內容
+pub fn signing_input(statement: &RotationStatement) -> Result<Vec<u8>> {
+    Ok([ROTATION_CONTEXT, &signing::canonical_json(statement)?].concat())
+}
+
+/// 建立輪換聲明
+pub fn statement(
+    agent_id: String,
+    public_key: String,
+    new_key: &Authenticator,
+    authority: RotationAuthority,
+    reason: RotationReason,
+) -> RotationStatement {
+    RotationStatement {
+        agent_id,
+        public_key,
+        new_agent_id: new_key.agent_id().to_string(),
+        new_public_key: new_key.public_key_base64(),
+        authority,
+        reason,
+        issued_at: Utc::now(),
+    }
+}
+
+/// 以 `authority` 指定的金鑰與新金鑰簽署聲明
+pub fn sign(statement: RotationStatement, authority: &Authenticator, new_key: &Authenticator) -> Result<KeyRotationPayload> {
+    if statement.new_public_key != new_key.public_key_base64() {
+        return Err(Error::InvalidConfig("Rotation statement does not name the new key".to_string()));
+    }
+
+    let input = signing_input(&statement)?;
+    Ok(KeyRotationPayload {
+        signature: authority.sign(&input),
+        new_key_signature: new_key.sign(&input),
+        statement,
+    })
+}
+
+/// 驗證輪換聲明，`authority_public_key` 為舊公鑰或平台記錄的備份公鑰 (base64)
+///
+/// Agent ID 須由對應的公鑰推導，兩個簽名皆須有效
+pub fn verify(rotation: &KeyRotationPayload, authority_public_key: &str) -> Result<()> {
+    let statement = &rotation.statement;
+
+    for (agent_id, public_key) in [
+        (&statement.agent_id, &statement.public_key),
+        (&statement.new_agent_id, &statement.new_public_key),
+    ] {
+        if identity::agent_id_for_public_key(public_key)? != *agent_id {
+            return Err(Error::ProtocolError(format!("Agent ID {} does not match its public key", agent_id)));
+        }
+    }
+    if statement.agent_id == statement.new_agent_id {
+        return Err(Error::ProtocolError("Rotation does not change the key".to_string()));
+    }
+    if statement.authority == RotationAuthority::PreviousKey && authority_public_key != statement.public_key {
+        return Err(Error::SignatureVerificationFailed);
+    }
+
+    let input = signing_input(statement)?;
+    for (public_key, signature) in [
+        (authority_public_key, &rotation.signature),
+        (statement.new_public_key.as_str(), &rotation.new_key_signature),
+    ] {
+        if !Authenticator::verify_with_public_key(public_key, &input, signature).unwrap_or(false) {
+            return Err(Error::SignatureVerificationFailed);
+        }
+    }
+    Ok(())
+}
+
+/// 以舊金鑰簽署輪換聲明，平台確認後改用新金鑰，返回新金鑰
+///
+/// Agent 須已停止；新金鑰依 `config.key` 保存
+pub async fn rotate_key(config: &AgentConfig, reason: RotationReason) -> Result<Authenticator> {
+    let path = Path::new(&config.private_key_path);
+    let previous = keystore::load(&config.key, path)?;
+    let new_key = pending_key(config)?;
+
+    let statement = statement(
+        previous.agent_id().to_string(),
+        previous.public_key_base64(),
+        &new_key,
+        RotationAuthority::PreviousKey,
+        reason,
+    );
+    let rotation = sign(statement, &previous, &new_key)?;
+
+    // 以舊金鑰認證
+    submit(config, path, rotation).await?;
+    Ok(new_key)
+}
+
+/// 身分金鑰遺失時以離線備份金鑰簽署輪換聲明，平台確認後改用新金鑰，返回新金鑰
+///
+/// 舊的 Agent ID 與公鑰取自 identity.json，無記錄時取自目前的私鑰文件
+pub async fn recover_key(config: &AgentConfig, backup_key: &Authenticator, reason: RotationReason) -> Result<Authenticator> {
+    let path = Path::new(&config.private_key_path);
+    let (agent_id, public_key, registered_backup) = match IdentityRecord::load(&config.data_dir)? {
+        Some(record) => (record.agent_id, record.public_key, record.backup_public_key),
+        None => {
+            let current = keystore::load(&config.key, path).map_err(|e| {
+                Error::InvalidConfig(format!("No record of the agent identity to recover ({})", e))
+            })?;
+            (current.agent_id().to_string(), current.public_key_base64(), None)
+        }
+    };
+
+    if registered_backup.is_some_and(|registered| registered != backup_key.public_key_base64()) {
+        return Err(Error::InvalidConfig(format!(
+            "Backup key does not match the backup key registered for {}",
+            agent_id
+        )));
+    }
+
+    let new_key = pending_key(config)?;
+    let statement = statement(agent_id, public_key, &new_key, RotationAuthority::BackupKey, reason);
+    let rotation = sign(statement, backup_key, &new_key)?;
+
+    // 舊金鑰已不可用，以新金鑰認證
+    submit(config, &pending_key_path(path), rotation).await?;
+    Ok(new_key)
+}
+
+/// 送出輪換聲明，依平台回覆改用新金鑰或捨棄新金鑰
+async fn submit(config: &AgentConfig, session_key: &Path, rotation: KeyRotationPayload) -> Result<()> {
+    let path = Path::new(&config.private_key_path);
+    let pending = pending_key_path(path);
+    let statement = &rotation.statement;
+    info!(
+        "Requesting key rotation from {} to {} ({:?})",
+        statement.agent_id, statement.new_agent_id, statement.authority
+    );
+    record(&config.data_dir, RotationStatus::Requested, &rotation, None)?;
+
+    let mut session = config.clone();
+    session.private_key_path = session_key.to_string_lossy().to_string();
+    session.data_dir = config.data_dir.join(SESSION_DIR);
+
+    let result = async {
+        let client = transport::connect(&session, transport::create(&session).await?).await?;
+        let result = client.rotate_key(rotation.clone()).await;
+        if let Err(e) = client.disconnect().await {
+            warn!("Failed to close rotation session: {}", e);
+        }
+        result
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            if path.exists() {
+                fs::rename(path, archived_key_path(path))?;
+            }
+            fs::rename(&pending, path)?;
+            record(&config.data_dir, RotationStatus::Accepted, &rotation, None)?;
+            info!("Key rotation accepted, now using agent ID {}", statement.new_agent_id);
+            Ok(())
+        }
+        Err(e @ Error::PlatformError { .. }) => {
+            let _ = fs::remove_file(&pending);
+            record(&config.data_dir, RotationStatus::Rejected, &rotation, Some(e.to_string()))?;
+            Err(e)
+        }
+        Err(e) => {
+            // 平台可能已收到聲明，保留新金鑰供重試
+            record(&config.data_dir, RotationStatus::Failed, &rotation, Some(e.to_string()))?;
+            Err(e)
+        }
+    }
+}
+
+/// 上次未完成的輪換所生成的新金鑰，沒有時生成並保存
+fn pending_key(config: &AgentConfig) -> Result<Authenticator> {
+    let pending = pending_key_path(Path::new(&config.private_key_path));
+    if pending.exists() {
+        info!("Resuming key rotation with pending key {}", pending.display());
+        return keystore::load(&config.key, &pending);
+    }
+
+    let new_key = Authenticator::generate();
+    keystore::save(&config.key, &pending, &new_key)?;
+    Ok(new_key)
+}
+
+/// 等待平台確認的新金鑰：`<原文件名>.pending`
+pub fn pending_key_path(path: &Path) -> PathBuf {
+    path.with_file_name(format!("{}.pending", file_name(path)))
+}
+
+/// 舊金鑰的保存路徑：`<原文件名>.<時間>.old`
+pub fn archived_key_path(path: &Path) -> PathBuf {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    path.with_file_name(format!("{}.{}.old", file_name(path), stamp))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "agent.key".to_string())
+}
+
+/// 輪換記錄中的狀態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationStatus {
+    /// 已送出聲明
+    Requested,
+    /// 平台已確認，改用新金鑰
+    Accepted,
+    /// 平台拒絕，新金鑰已捨棄
+    Rejected,
+    /// 未收到平台回覆，新金鑰保留供重試
+    Failed,
+}
+
+/// 一筆輪換記錄
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RotationEvent {
+    pub at: DateTime<Utc>,
+    pub status: RotationStatus,
+
+    #[serde(flatten)]
+    pub statement: RotationStatement,
+
+    /// `authority` 對聲明的簽名，可供日後核對
+    pub signature: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// 依時間順序讀取輪換記錄
+pub fn history(data_dir: &Path) -> Result<Vec<RotationEvent>> {
+    let path = data_dir.join(ROTATION_LOG);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+/// 附加一筆輪換記錄
+fn record(data_dir: &Path, status: RotationStatus, rotation: &KeyRotationPayload, detail: Option<String>) -> Result<()> {
+    let event = RotationEvent {
+        at: Utc::now(),
+        status,
+        statement: rotation.statement.clone(),
+        signature: rotation.signature.clone(),
+        detail,
+    };
+
+    fs::create_dir_all(data_dir)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir.join(ROTATION_LOG))?;
+    let mut line = serde_json::to_vec(&event)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{Message, MessagePayload};
+
+    fn rotation(authority: RotationAuthority, signer: &Authenticator) -> (Authenticator, KeyRotationPayload) {
+        let previous = Authenticator::generate();
+        let new_key = Authenticator::generate();
+        let statement = statement(
+            previous.agent_id().to_string(),
+            previous.public_key_base64(),
+            &new_key,
+            authority,
+            RotationReason::Routine,
+        );
+        let signer = if authority == RotationAuthority::PreviousKey { &previous } else { signer };
+        let rotation = sign(statement, signer, &new_key).unwrap();
+        (previous, rotation)
+    }
+
+    #[test]
+    fn test_rotation_signed_by_previous_key() {
+        let (previous, rotation) = rotation(RotationAuthority::PreviousKey, &Authenticator::generate());
+        verify(&rotation, &previous.public_key_base64()).unwrap();
+
+        // 經 Protocol Buffers 往返後簽名仍有效
+        let msg = super::super::orban_protocol::create_key_rotation(rotation.clone());
+        let decoded = Message::from_protobuf(&msg.to_protobuf().unwrap()).unwrap();
+        match decoded.payload {
+            MessagePayload::KeyRotation(decoded) => verify(&decoded, &previous.public_key_base64()).unwrap(),
+            other => panic!("unexpected payload {:?}", other),
+        }
+
+        // 竄改聲明
+        let mut tampered = rotation.clone();
+        tampered.statement.reason = RotationReason::Compromised;
+        assert!(verify(&tampered, &previous.public_key_base64()).is_err());
+
+        // 以其他金鑰冒充舊金鑰
+        let other = Authenticator::generate();
+        assert!(verify(&rotation, &other.public_key_base64()).is_err());
+    }
+
+    #[test]
+    fn test_rotation_signed_by_backup_key() {
+        let backup = Authenticator::generate();
+        let (_, rotation) = rotation(RotationAuthority::BackupKey, &backup);
+
+        verify(&rotation, &backup.public_key_base64()).unwrap();
+        assert!(verify(&rotation, &Authenticator::generate().public_key_base64()).is_err());
+
+        // 新金鑰的簽名須有效
+        let mut forged = rotation.clone();
+        forged.new_key_signature = backup.sign(&signing_input(&rotation.statement).unwrap());
+        assert!(verify(&forged, &backup.public_key_base64()).is_err());
+    }
+
+    #[test]
+    fn test_rotation_history_appended() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let (_, rotation) = rotation(RotationAuthority::PreviousKey, &Authenticator::generate());
+
+        record(data_dir.path(), RotationStatus::Requested, &rotation, None).unwrap();
+        record(data_dir.path(), RotationStatus::Accepted, &rotation, None).unwrap();
+
+        let history = history(data_dir.path()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].status, RotationStatus::Accepted);
+        assert_eq!(history[1].statement, rotation.statement);
+        assert_eq!(history[1].signature, rotation.signature);
+    }
+}
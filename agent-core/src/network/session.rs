@@ -0,0 +1,177 @@
+// JWT 會話管理
+//
+// 平台在 AUTH_SUCCESS 中簽發 JWT。Agent 沒有平台的簽名密鑰，
+// 因此只解析並檢查聲明（主體、有效期），簽名由平台在使用時驗證
+
+use crate::error::{Error, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use jsonwebtoken::{DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+/// JWT 聲明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionClaims {
+    /// Agent ID
+    pub sub: String,
+
+    /// 過期時間 (Unix 秒)
+    pub exp: i64,
+
+    /// 簽發時間 (Unix 秒)
+    #[serde(default)]
+    pub iat: Option<i64>,
+
+    /// 簽發者
+    #[serde(default)]
+    pub iss: Option<String>,
+}
+
+/// 平台簽發的會話令牌
+#[derive(Debug, Clone)]
+pub struct SessionToken {
+    token: String,
+    claims: SessionClaims,
+    issued_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl SessionToken {
+    /// 解析並檢查 AUTH_SUCCESS 中的令牌
+    ///
+    /// 過期時間取 `exp` 聲明與 `expires_in` 中較早者
+    pub fn parse(token: &str, expires_in: u64, agent_id: &str) -> Result<Self> {
+        let mut validation = Validation::default();
+        validation.insecure_disable_signature_validation();
+        validation.leeway = 0;
+
+        let claims = jsonwebtoken::decode::<SessionClaims>(
+            token,
+            &DecodingKey::from_secret(&[]),
+            &validation,
+        )
+        .map_err(|e| Error::AuthenticationFailed(format!("Invalid session token: {}", e)))?
+        .claims;
+
+        if claims.sub != agent_id {
+            return Err(Error::AuthenticationFailed(format!(
+                "Session token issued for {}, expected {}",
+                claims.sub, agent_id
+            )));
+        }
+
+        let now = Utc::now();
+        let exp = Utc
+            .timestamp_opt(claims.exp, 0)
+            .single()
+            .ok_or_else(|| Error::AuthenticationFailed("Invalid exp claim".to_string()))?;
+        let expires_at = exp.min(now + Duration::seconds(expires_in as i64));
+
+        let issued_at = claims
+            .iat
+            .and_then(|iat| Utc.timestamp_opt(iat, 0).single())
+            .filter(|iat| *iat <= now)
+            .unwrap_or(now);
+
+        Ok(Self {
+            token: token.to_string(),
+            claims,
+            issued_at,
+            expires_at,
+        })
+    }
+
+    /// 原始令牌
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+
+    /// 令牌聲明
+    pub fn claims(&self) -> &SessionClaims {
+        &self.claims
+    }
+
+    /// 過期時間
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
+    /// 是否已過期
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+
+    /// 應重新認證的時間
+    ///
+    /// 在過期前 `margin` 更新，但最多提前有效期的一半
+    pub fn refresh_at(&self, margin: std::time::Duration) -> DateTime<Utc> {
+        let lifetime = self.expires_at - self.issued_at;
+        let margin = Duration::from_std(margin)
+            .unwrap_or(Duration::zero())
+            .min(lifetime / 2);
+
+        self.expires_at - margin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    fn issue(sub: &str, iat: i64, exp: i64) -> String {
+        let claims = SessionClaims {
+            sub: sub.to_string(),
+            exp,
+            iat: Some(iat),
+            iss: Some("orban-platform".to_string()),
+        };
+
+        jsonwebtoken::encode(&Header::default(), &claims, &EncodingKey::from_secret(b"secret")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_session_token() {
+        let now = Utc::now().timestamp();
+        let token = issue("agent-001", now, now + 3600);
+
+        let session = SessionToken::parse(&token, 86400, "agent-001").unwrap();
+        assert_eq!(session.as_str(), token);
+        assert_eq!(session.claims().sub, "agent-001");
+        assert_eq!(session.expires_at().timestamp(), now + 3600);
+        assert!(!session.is_expired());
+
+        // 提前 5 分鐘更新
+        let refresh_at = session.refresh_at(std::time::Duration::from_secs(300));
+        assert_eq!(refresh_at.timestamp(), now + 3300);
+
+        // 有效期短於兩倍提前量時，在一半時更新
+        let refresh_at = session.refresh_at(std::time::Duration::from_secs(7200));
+        assert_eq!(refresh_at.timestamp(), now + 1800);
+
+        // expires_in 較短時以其為準
+        let session = SessionToken::parse(&token, 60, "agent-001").unwrap();
+        assert!(session.expires_at().timestamp() <= now + 61);
+    }
+
+    #[test]
+    fn test_reject_invalid_session_token() {
+        let now = Utc::now().timestamp();
+
+        let expired = issue("agent-001", now - 7200, now - 3600);
+        assert!(matches!(
+            SessionToken::parse(&expired, 86400, "agent-001"),
+            Err(Error::AuthenticationFailed(_))
+        ));
+
+        let other_agent = issue("agent-002", now, now + 3600);
+        assert!(matches!(
+            SessionToken::parse(&other_agent, 86400, "agent-001"),
+            Err(Error::AuthenticationFailed(_))
+        ));
+
+        assert!(matches!(
+            SessionToken::parse("not-a-jwt", 86400, "agent-001"),
+            Err(Error::AuthenticationFailed(_))
+        ));
+    }
+}
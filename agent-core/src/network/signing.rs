@@ -0,0 +1,244 @@
+// 逐訊息簽名
+//
+// 認證後的訊息預設只依賴傳輸層保護，啟用後：
+// - Agent 以身分金鑰為 TASK_COMPLETE、POW_RESPONSE、HEARTBEAT 附加 ed25519 簽名
+// - TASK_ASSIGN、POW_CHALLENGE、PAYOUT_NOTIFICATION 須帶有平台簽名，
+//   以配置中固定的平台公鑰驗證，缺少或無效時拒絕處理
+//
+// 簽名內容為 `SIGNING_CONTEXT` 接上去除 `signature` 欄位後訊息的標準 JSON
+// （鍵依字典序排列、無空白），與實際使用的編碼格式無關
+
+use super::auth::Authenticator;
+use super::orban_protocol::{Message, MessageType};
+use crate::config::SigningConfig;
+use crate::error::{Error, Result};
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use tracing::warn;
+
+/// 簽名內容的前綴，避免與認證挑戰等其他簽名混用
+pub const SIGNING_CONTEXT: &[u8] = b"orban-message-v1\n";
+
+/// Agent 簽名的出站訊息
+pub const SIGNED_OUTBOUND: &[MessageType] = &[
+    MessageType::TaskComplete,
+    MessageType::PowResponse,
+    MessageType::Heartbeat,
+];
+
+/// 須驗證平台簽名的入站訊息
+pub const VERIFIED_INBOUND: &[MessageType] = &[
+    MessageType::TaskAssign,
+    MessageType::PowChallenge,
+    MessageType::PayoutNotification,
+];
+
+/// 標準 JSON：物件的鍵依字典序排列、無空白
+///
+/// 訊息簽名、金鑰輪換聲明與硬體證明的簽名內容皆以此編碼。
+/// 明確排序而不依賴 serde_json 的 Map 實作（啟用 `preserve_order` 時保留插入順序）
+pub fn canonical_json(value: &impl Serialize) -> Result<Vec<u8>> {
+    fn sorted(value: Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut entries: Vec<(String, Value)> = map.into_iter().map(|(k, v)| (k, sorted(v))).collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                Value::Object(entries.into_iter().collect())
+            }
+            Value::Array(items) => Value::Array(items.into_iter().map(sorted).collect()),
+            other => other,
+        }
+    }
+
+    Ok(serde_json::to_vec(&sorted(serde_json::to_value(value)?))?)
+}
+
+/// 訊息的簽名內容
+pub fn signing_input(msg: &Message) -> Result<Vec<u8>> {
+    let mut unsigned = msg.clone();
+    unsigned.signature = None;
+
+    Ok([SIGNING_CONTEXT, &canonical_json(&unsigned)?].concat())
+}
+
+/// 以指定金鑰簽署訊息
+pub fn sign(mut msg: Message, key: &Authenticator) -> Result<Message> {
+    msg.signature = Some(key.sign(&signing_input(&msg)?));
+    Ok(msg)
+}
+
+/// 以公鑰 (base64) 驗證訊息簽名，缺少或無效時返回 `Error::SignatureVerificationFailed`
+pub fn verify(msg: &Message, public_key: &str) -> Result<()> {
+    let signature = msg.signature.as_deref().ok_or(Error::SignatureVerificationFailed)?;
+
+    match Authenticator::verify_with_public_key(public_key, &signing_input(msg)?, signature) {
+        Ok(true) => Ok(()),
+        Ok(false) | Err(_) => Err(Error::SignatureVerificationFailed),
+    }
+}
+
+/// 依配置簽署出站訊息、驗證入站訊息
+#[derive(Debug, Clone, Default)]
+pub struct MessageSigner {
+    sign_outbound: bool,
+    platform_public_key: Option<String>,
+}
+
+impl MessageSigner {
+    pub fn from_config(config: &SigningConfig) -> Result<Self> {
+        if let Some(key) = &config.platform_public_key {
+            let valid = general_purpose::STANDARD
+                .decode(key)
+                .is_ok_and(|bytes| bytes.len() == 32);
+            if !valid {
+                return Err(Error::InvalidConfig(
+                    "network.signing.platform_public_key must be a base64 ed25519 public key".to_string(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            sign_outbound: config.sign_outbound,
+            platform_public_key: config.platform_public_key.clone(),
+        })
+    }
+
+    /// 需要時為出站訊息附加簽名，已簽名的訊息（發件匣重送）不重新簽署
+    pub fn sign<'a>(&self, msg: &'a Message, key: &Authenticator) -> Result<Cow<'a, Message>> {
+        if !self.sign_outbound || msg.signature.is_some() || !SIGNED_OUTBOUND.contains(&msg.message_type) {
+            return Ok(Cow::Borrowed(msg));
+        }
+
+        sign(msg.clone(), key).map(Cow::Owned)
+    }
+
+    /// 驗證入站訊息，未固定平台公鑰時不驗證
+    pub fn verify(&self, msg: &Message) -> Result<()> {
+        let Some(public_key) = &self.platform_public_key else {
+            return Ok(());
+        };
+
+        if !VERIFIED_INBOUND.contains(&msg.message_type) {
+            return Ok(());
+        }
+
+        verify(msg, public_key).inspect_err(|_| {
+            warn!(
+                "Rejecting {:?} message {}: missing or invalid platform signature",
+                msg.message_type, msg.message_id
+            );
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::orban_protocol::{create_heartbeat, create_task_reject, AgentStatus};
+
+    fn signer(sign_outbound: bool, platform: Option<&Authenticator>) -> MessageSigner {
+        MessageSigner::from_config(&SigningConfig {
+            sign_outbound,
+            platform_public_key: platform.map(|key| key.public_key_base64()),
+        })
+        .unwrap()
+    }
+
+    fn heartbeat() -> Message {
+        create_heartbeat("agent-001".to_string(), AgentStatus::Idle, None, Vec::new(), 60)
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys_without_whitespace() {
+        #[derive(Serialize)]
+        struct Inner {
+            zeta: u32,
+            alpha: Option<String>,
+        }
+
+        #[derive(Serialize)]
+        struct Outer {
+            name: &'static str,
+            inner: Inner,
+            items: Vec<Inner>,
+            aardvark: bool,
+        }
+
+        let value = Outer {
+            name: "a b",
+            inner: Inner { zeta: 1, alpha: None },
+            items: vec![Inner { zeta: 2, alpha: Some("x".to_string()) }],
+            aardvark: true,
+        };
+
+        assert_eq!(
+            String::from_utf8(canonical_json(&value).unwrap()).unwrap(),
+            r#"{"aardvark":true,"inner":{"alpha":null,"zeta":1},"items":[{"alpha":"x","zeta":2}],"name":"a b"}"#
+        );
+    }
+
+    #[test]
+    fn test_signature_survives_both_codecs() {
+        let key = Authenticator::generate();
+        let msg = sign(heartbeat(), &key).unwrap();
+
+        let json = Message::from_json(&msg.to_json().unwrap()).unwrap();
+        verify(&json, &key.public_key_base64()).unwrap();
+
+        let proto = Message::from_protobuf(&msg.to_protobuf().unwrap()).unwrap();
+        verify(&proto, &key.public_key_base64()).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_signatures_rejected() {
+        let key = Authenticator::generate();
+        let msg = sign(heartbeat(), &key).unwrap();
+
+        let mut tampered = msg.clone();
+        tampered.message_id = "forged".to_string();
+        assert!(matches!(
+            verify(&tampered, &key.public_key_base64()),
+            Err(Error::SignatureVerificationFailed)
+        ));
+
+        let other = Authenticator::generate();
+        assert!(verify(&msg, &other.public_key_base64()).is_err());
+        assert!(verify(&heartbeat(), &key.public_key_base64()).is_err());
+    }
+
+    #[test]
+    fn test_signer_only_signs_selected_messages() {
+        let key = Authenticator::generate();
+        let reject = create_task_reject("task-001".to_string(), "busy".to_string(), String::new());
+
+        assert!(signer(true, None).sign(&heartbeat(), &key).unwrap().signature.is_some());
+        assert!(signer(true, None).sign(&reject, &key).unwrap().signature.is_none());
+        assert!(signer(false, None).sign(&heartbeat(), &key).unwrap().signature.is_none());
+    }
+
+    #[test]
+    fn test_pinned_key_required_for_verified_messages() {
+        let platform = Authenticator::generate();
+        let mut assign = heartbeat();
+        assign.message_type = MessageType::TaskAssign;
+
+        assert!(signer(false, None).verify(&assign).is_ok());
+        assert!(signer(false, Some(&platform)).verify(&assign).is_err());
+        assert!(signer(false, Some(&platform)).verify(&heartbeat()).is_ok());
+
+        let signed = sign(assign, &platform).unwrap();
+        assert!(signer(false, Some(&platform)).verify(&signed).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_platform_key_rejected() {
+        let config = SigningConfig {
+            sign_outbound: false,
+            platform_public_key: Some("not-a-key".to_string()),
+        };
+        assert!(matches!(MessageSigner::from_config(&config), Err(Error::InvalidConfig(_))));
+    }
+}
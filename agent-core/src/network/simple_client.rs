@@ -1,84 +1,56 @@
-//! 简化的网络客户端 - 适配 lib.rs 使用
+//! REST 客户端 - 供 HTTP 长轮询传输及其他需要会话认证的 REST 请求使用
 
-use crate::types::*;
+use super::proxy::ProxySettings;
+use super::tls::TlsSettings;
 use crate::error::{Error, Result};
-use tracing::{info, warn};
+use std::sync::{Arc, RwLock};
 
-/// 简化的网络客户端
+/// REST 客户端
 #[derive(Clone)]
 pub struct Client {
-    base_url: String,
+    base_url: Arc<RwLock<String>>,
     client: reqwest::Client,
+    session_token: Arc<RwLock<Option<String>>>,
 }
 
 impl Client {
-    /// 创建新客户端
-    pub fn new(base_url: String) -> Result<Self> {
-        let client = reqwest::Client::builder()
+    /// 创建新客户端，请求经由 `proxy` 指定的代理发送，HTTPS 使用 `tls` 的验证设置
+    pub fn new(base_url: String, proxy: &ProxySettings, tls: &TlsSettings) -> Result<Self> {
+        let client = tls
+            .apply(proxy.apply(reqwest::Client::builder()))
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .map_err(|e| Error::HTTPError(e))?;
 
-        Ok(Self { base_url, client })
+        Ok(Self {
+            base_url: Arc::new(RwLock::new(base_url)),
+            client,
+            session_token: Arc::new(RwLock::new(None)),
+        })
     }
 
-    /// 注册到平台
-    pub async fn register(&self, req: RegistrationRequest) -> Result<String> {
-        info!("Registering agent to platform...");
-
-        // 实际环境：发送 HTTP POST 到平台
-        // let url = format!("{}/api/v1/agents/register", self.base_url);
-        // let response = self.client.post(&url)
-        //     .json(&req)
-        //     .send()
-        //     .await?;
-
-        // 暂时：模拟注册成功
-        let agent_id = format!("agent-{}", uuid::Uuid::new_v4());
-        warn!("Platform not available - using mock agent ID: {}", agent_id);
-
-        Ok(agent_id)
+    /// 切换平台地址（端点故障转移）
+    pub fn set_base_url(&self, base_url: String) {
+        *self.base_url.write().unwrap() = base_url;
     }
 
-    /// 发送心跳
-    pub async fn heartbeat(&self, agent_id: &str) -> Result<()> {
-        // 实际环境：发送心跳到平台
-        // let url = format!("{}/api/v1/agents/{}/heartbeat", self.base_url, agent_id);
-        // self.client.post(&url).send().await?;
-
-        // 暂时：模拟成功
-        Ok(())
+    /// 设置会话 JWT（来自认证结果或 `OrbanClient::session_token`）
+    pub fn set_session_token(&self, token: Option<String>) {
+        *self.session_token.write().unwrap() = token;
     }
 
-    /// 领取任务
-    pub async fn fetch_task(&self, agent_id: &str) -> Result<Option<Task>> {
-        // 实际环境：从平台获取任务
-        // let url = format!("{}/api/v1/agents/{}/tasks/fetch", self.base_url, agent_id);
-        // let response = self.client.get(&url).send().await?;
+    /// 构建请求，已设置会话 JWT 时附带 Bearer 认证
+    pub fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}{}", self.base_url.read().unwrap(), path);
+        let builder = self.client.request(method, url);
 
-        // 暂时：返回 None (无任务)
-        Ok(None)
-    }
-
-    /// 提交任务结果
-    pub async fn submit_result(&self, result: TaskResult) -> Result<()> {
-        // 实际环境：提交结果到平台
-        // let url = format!("{}/api/v1/tasks/{}/result", self.base_url, result.task_id);
-        // self.client.post(&url).json(&result).send().await?;
-
-        info!("Task result submitted (mock)");
-        Ok(())
+        match self.session_token.read().unwrap().as_deref() {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
     }
 }
 
-/// 注册请求
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct RegistrationRequest {
-    pub hostname: String,
-    pub gpus: Vec<GpuInfo>,
-    pub version: String,
-}
-
 /// GPU 信息（简化版）
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct GpuInfo {
@@ -120,9 +92,29 @@ pub struct TaskResult {
 mod tests {
     use super::*;
 
+    use super::super::auth::Authenticator;
+    use crate::config::TlsConfig;
+
+    fn client() -> Result<Client> {
+        let tls = TlsSettings::from_config(&TlsConfig::default(), &Authenticator::generate())?;
+        Client::new("https://platform.orban.ai".to_string(), &ProxySettings::default(), &tls)
+    }
+
     #[tokio::test]
     async fn test_client_creation() {
-        let client = Client::new("https://platform.orban.ai".to_string());
-        assert!(client.is_ok());
+        assert!(client().is_ok());
+    }
+
+    #[test]
+    fn test_request_with_session_token() {
+        let client = client().unwrap();
+
+        let request = client.request(reqwest::Method::GET, "/api/v1/agents").build().unwrap();
+        assert_eq!(request.url().as_str(), "https://platform.orban.ai/api/v1/agents");
+        assert!(request.headers().get("Authorization").is_none());
+
+        client.set_session_token(Some("token-123".to_string()));
+        let request = client.request(reqwest::Method::GET, "/api/v1/agents").build().unwrap();
+        assert_eq!(request.headers()["Authorization"], "Bearer token-123");
     }
 }
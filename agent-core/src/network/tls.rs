@@ -0,0 +1,474 @@
+// 平台連線的 TLS
+//
+// WebSocket 與 HTTP 長輪詢共用同一份 rustls 設定：
+// - 信任系統根憑證及 `ca_bundle` 中的 CA
+// - 設定 `pinned_spki` 時，憑證鏈驗證通過後還須有一張憑證的 SPKI 指紋符合其一
+// - 啟用 `client_auth` 時以 Agent 的 ed25519 身分金鑰出示客戶端憑證：
+//   使用 CA 為該金鑰簽發的 `client_cert`，或由身分金鑰自簽的憑證
+//
+// WebSocket 與 gRPC 的 TLS 握手在此完成（代理通道之上），再交由 tungstenite 升級或 HTTP/2 使用
+
+use super::auth::Authenticator;
+use crate::config::TlsConfig;
+use crate::error::{Error, Result};
+
+use base64::{engine::general_purpose, Engine as _};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+use tracing::{debug, warn};
+
+/// SPKI 指紋前綴
+const PIN_PREFIX: &str = "sha256/";
+
+/// ed25519 的演算法 OID (1.3.101.112)
+const ED25519_OID: &str = "1.3.101.112";
+
+/// 已載入的 TLS 設定
+#[derive(Clone)]
+pub struct TlsSettings {
+    config: Arc<ClientConfig>,
+}
+
+impl TlsSettings {
+    /// 依配置建立，客戶端憑證綁定 `authenticator` 的身分金鑰
+    pub fn from_config(config: &TlsConfig, authenticator: &Authenticator) -> Result<Self> {
+        let provider = Arc::new(ring::default_provider());
+        let roots = Arc::new(load_roots(config)?);
+
+        let webpki = WebPkiServerVerifier::builder_with_provider(roots, provider.clone())
+            .build()
+            .map_err(|e| Error::InvalidConfig(format!("Invalid TLS trust roots: {}", e)))?;
+
+        let verifier: Arc<dyn ServerCertVerifier> = if config.pinned_spki.is_empty() {
+            webpki
+        } else {
+            let pins = config
+                .pinned_spki
+                .iter()
+                .map(|pin| parse_pin(pin))
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(PinnedVerifier { inner: webpki, pins })
+        };
+
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| Error::InvalidConfig(format!("Invalid TLS configuration: {}", e)))?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier);
+
+        let client_config = if config.client_auth {
+            let chain = match &config.client_cert {
+                Some(path) => load_bound_certificate(path, authenticator)?,
+                None => vec![self_signed_certificate(authenticator)?],
+            };
+            let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(authenticator.private_key_pkcs8()));
+
+            builder
+                .with_client_auth_cert(chain, key)
+                .map_err(|e| Error::InvalidConfig(format!("Invalid client certificate: {}", e)))?
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        Ok(Self {
+            config: Arc::new(client_config),
+        })
+    }
+
+    /// `wss://` 與 `https://` 在已建立的連線上完成 TLS 握手，其餘保持明文
+    pub(crate) async fn wrap(&self, stream: TcpStream, target: &Url) -> Result<PlatformStream> {
+        if !matches!(target.scheme(), "wss" | "https") {
+            return Ok(PlatformStream::Plain(stream));
+        }
+
+        let host = target
+            .host_str()
+            .ok_or_else(|| Error::ConnectionFailed(format!("No host in {}", target)))?
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let server_name = ServerName::try_from(host.to_string())
+            .map_err(|e| Error::ConnectionFailed(format!("Invalid server name {}: {}", host, e)))?;
+
+        let stream = TlsConnector::from(self.config.clone())
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| match describe_error(&e) {
+                Some(reason) => Error::TlsError(reason),
+                None => Error::ConnectionFailed(format!("TLS handshake with {} failed: {}", host, e)),
+            })?;
+
+        Ok(PlatformStream::Tls(Box::new(stream)))
+    }
+
+    /// 在 TLS 握手中以 ALPN 宣告應用層協議（gRPC 需要 `h2`）
+    #[cfg(feature = "grpc")]
+    pub(crate) fn with_alpn(&self, protocols: &[&[u8]]) -> Self {
+        let mut config = (*self.config).clone();
+        config.alpn_protocols = protocols.iter().map(|protocol| protocol.to_vec()).collect();
+
+        Self {
+            config: Arc::new(config),
+        }
+    }
+
+    /// 讓 reqwest 客戶端使用相同的 TLS 設定
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        builder.use_preconfigured_tls((*self.config).clone())
+    }
+}
+
+/// 到平台的連線（明文或 TLS）
+pub(crate) enum PlatformStream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for PlatformStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            PlatformStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            PlatformStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for PlatformStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            PlatformStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            PlatformStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            PlatformStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            PlatformStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            PlatformStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            PlatformStream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// 憑證的 SPKI 指紋（`sha256/<base64>`），可用於 `pinned_spki`
+pub fn spki_pin(certificate: &[u8]) -> Result<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(certificate)
+        .map_err(|e| Error::InvalidConfig(format!("Invalid certificate: {}", e)))?;
+
+    let digest = Sha256::digest(cert.public_key().raw);
+    Ok(format!("{}{}", PIN_PREFIX, general_purpose::STANDARD.encode(digest)))
+}
+
+/// 從錯誤鏈中找出 TLS 錯誤，返回其說明（例如憑證固定失敗的原因）
+pub(crate) fn describe_error(error: &(dyn std::error::Error + 'static)) -> Option<String> {
+    let mut current = Some(error);
+
+    while let Some(error) = current {
+        if let Some(tls) = error.downcast_ref::<rustls::Error>() {
+            return Some(tls.to_string());
+        }
+
+        // io::Error 的 source() 會略過其包裝的錯誤
+        current = match error.downcast_ref::<io::Error>().and_then(|io| io.get_ref()) {
+            Some(inner) => Some(inner as &(dyn std::error::Error + 'static)),
+            None => error.source(),
+        };
+    }
+
+    None
+}
+
+/// 信任的根憑證
+fn load_roots(config: &TlsConfig) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+
+    if config.system_roots {
+        let native = rustls_native_certs::load_native_certs();
+        for error in &native.errors {
+            warn!("Failed to load system certificate: {}", error);
+        }
+
+        let (added, ignored) = roots.add_parsable_certificates(native.certs);
+        debug!("Loaded {} system root certificates ({} ignored)", added, ignored);
+    }
+
+    if let Some(path) = &config.ca_bundle {
+        for cert in read_certificates(path)? {
+            roots
+                .add(cert)
+                .map_err(|e| Error::InvalidConfig(format!("Invalid CA certificate in {}: {}", path.display(), e)))?;
+        }
+    }
+
+    if roots.is_empty() {
+        return Err(Error::InvalidConfig(
+            "No trusted root certificates (set network.tls.ca_bundle or enable system_roots)".to_string(),
+        ));
+    }
+
+    Ok(roots)
+}
+
+/// 讀取 PEM 檔案中的憑證
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path)
+        .map_err(|e| Error::InvalidConfig(format!("Cannot read {}: {}", path.display(), e)))?;
+
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| Error::InvalidConfig(format!("Invalid PEM in {}: {}", path.display(), e)))?;
+
+    if certs.is_empty() {
+        return Err(Error::InvalidConfig(format!("No certificates in {}", path.display())));
+    }
+
+    Ok(certs)
+}
+
+/// 讀取客戶端憑證鏈，首張憑證的公鑰須為 Agent 的身分金鑰
+fn load_bound_certificate(path: &Path, authenticator: &Authenticator) -> Result<Vec<CertificateDer<'static>>> {
+    let chain = read_certificates(path)?;
+
+    let (_, leaf) = x509_parser::parse_x509_certificate(&chain[0])
+        .map_err(|e| Error::InvalidConfig(format!("Invalid client certificate {}: {}", path.display(), e)))?;
+    let spki = leaf.public_key();
+
+    let bound = spki.algorithm.algorithm.to_id_string() == ED25519_OID
+        && spki.subject_public_key.data.as_ref() == authenticator.public_key_bytes();
+
+    if !bound {
+        return Err(Error::InvalidConfig(format!(
+            "Client certificate {} is not issued for the agent identity key {}",
+            path.display(),
+            authenticator.public_key_base64()
+        )));
+    }
+
+    Ok(chain)
+}
+
+/// 由 Agent 身分金鑰自簽的客戶端憑證
+fn self_signed_certificate(authenticator: &Authenticator) -> Result<CertificateDer<'static>> {
+    let invalid = |e: rcgen::Error| Error::InvalidConfig(format!("Cannot create client certificate: {}", e));
+
+    let key_pair = rcgen::KeyPair::try_from(authenticator.private_key_pkcs8().as_slice()).map_err(invalid)?;
+    let mut params = rcgen::CertificateParams::new(vec![authenticator.agent_id().to_string()]).map_err(invalid)?;
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, authenticator.agent_id());
+
+    Ok(params.self_signed(&key_pair).map_err(invalid)?.der().clone())
+}
+
+/// 解析 `sha256/<base64>` 格式的指紋
+fn parse_pin(pin: &str) -> Result<String> {
+    let invalid = || Error::InvalidConfig(format!("Invalid SPKI pin '{}' (expected sha256/<base64>)", pin));
+
+    let digest = pin.trim().strip_prefix(PIN_PREFIX).ok_or_else(invalid)?;
+    let bytes = general_purpose::STANDARD.decode(digest).map_err(|_| invalid())?;
+    if bytes.len() != 32 {
+        return Err(invalid());
+    }
+
+    Ok(format!("{}{}", PIN_PREFIX, general_purpose::STANDARD.encode(bytes)))
+}
+
+/// 在一般憑證鏈驗證之外檢查 SPKI 指紋
+#[derive(Debug)]
+struct PinnedVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: Vec<String>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let verified = self
+            .inner
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+
+        let presented = std::iter::once(end_entity)
+            .chain(intermediates)
+            .map(|cert| spki_pin(cert).unwrap_or_else(|_| "<unparsable certificate>".to_string()))
+            .collect::<Vec<_>>();
+
+        if presented.iter().any(|pin| self.pins.contains(pin)) {
+            return Ok(verified);
+        }
+
+        Err(rustls::Error::General(format!(
+            "certificate pinning failed for {}: presented chain [{}] matches none of the pinned keys [{}]",
+            server_name.to_str(),
+            presented.join(", "),
+            self.pins.join(", ")
+        )))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 自建 CA 與其簽發的 localhost 憑證
+    fn issue() -> (rcgen::Certificate, rcgen::Certificate) {
+        let ca_key = rcgen::KeyPair::generate().unwrap();
+        let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let leaf_key = rcgen::KeyPair::generate().unwrap();
+        let leaf = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .signed_by(&leaf_key, &ca, &ca_key)
+            .unwrap();
+
+        (ca, leaf)
+    }
+
+    fn verifier(ca: &rcgen::Certificate, pins: Vec<String>) -> PinnedVerifier {
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.der().clone()).unwrap();
+        let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), Arc::new(ring::default_provider()))
+            .build()
+            .unwrap();
+        PinnedVerifier { inner, pins }
+    }
+
+    fn verify(verifier: &PinnedVerifier, leaf: &rcgen::Certificate) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        verifier.verify_server_cert(
+            leaf.der(),
+            &[],
+            &ServerName::try_from("localhost").unwrap(),
+            &[],
+            UnixTime::now(),
+        )
+    }
+
+    #[test]
+    fn test_spki_pinning() {
+        let (ca, leaf) = issue();
+
+        // 固定葉憑證或 CA 的公鑰皆可
+        let leaf_pin = spki_pin(leaf.der()).unwrap();
+        assert!(verify(&verifier(&ca, vec![leaf_pin.clone()]), &leaf).is_ok());
+
+        let (_, other) = issue();
+        let other_pin = spki_pin(other.der()).unwrap();
+        let err = verify(&verifier(&ca, vec![other_pin.clone()]), &leaf).unwrap_err().to_string();
+        assert!(err.contains("certificate pinning failed for localhost"), "{}", err);
+        assert!(err.contains(&leaf_pin) && err.contains(&other_pin), "{}", err);
+
+        // 憑證鏈驗證失敗時不檢查指紋
+        let err = verify(&verifier(&issue().0, vec![leaf_pin]), &leaf).unwrap_err();
+        assert!(matches!(err, rustls::Error::InvalidCertificate(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn test_parse_pin() {
+        let pin = format!("sha256/{}", general_purpose::STANDARD.encode([7u8; 32]));
+        assert_eq!(parse_pin(&pin).unwrap(), pin);
+        assert!(parse_pin("sha1/AAAA").is_err());
+        assert!(parse_pin("sha256/AAAA").is_err());
+    }
+
+    #[test]
+    fn test_client_certificate_bound_to_identity() {
+        let dir = tempfile::tempdir().unwrap();
+        let auth = Authenticator::generate();
+
+        // 自簽憑證的公鑰即身分金鑰
+        let cert = self_signed_certificate(&auth).unwrap();
+        let (_, parsed) = x509_parser::parse_x509_certificate(&cert).unwrap();
+        assert_eq!(parsed.public_key().subject_public_key.data.as_ref(), auth.public_key_bytes());
+
+        let path = dir.path().join("client.pem");
+        std::fs::write(&path, pem(&cert)).unwrap();
+        assert!(load_bound_certificate(&path, &auth).is_ok());
+
+        let other = Authenticator::generate();
+        let err = load_bound_certificate(&path, &other).unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig(ref msg) if msg.contains("not issued for the agent identity key")));
+
+        let config = TlsConfig {
+            client_auth: true,
+            client_cert: Some(path),
+            ..Default::default()
+        };
+        assert!(TlsSettings::from_config(&config, &auth).is_ok());
+    }
+
+    #[test]
+    fn test_ca_bundle_required_without_system_roots() {
+        let auth = Authenticator::generate();
+        let config = TlsConfig {
+            system_roots: false,
+            ..Default::default()
+        };
+        assert!(matches!(TlsSettings::from_config(&config, &auth), Err(Error::InvalidConfig(_))));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, issue().0.pem()).unwrap();
+        let config = TlsConfig {
+            ca_bundle: Some(path),
+            system_roots: false,
+            pinned_spki: vec![format!("sha256/{}", general_purpose::STANDARD.encode([1u8; 32]))],
+            ..Default::default()
+        };
+        assert!(TlsSettings::from_config(&config, &auth).is_ok());
+    }
+
+    fn pem(cert: &CertificateDer<'_>) -> String {
+        format!(
+            "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+            general_purpose::STANDARD.encode(cert.as_ref())
+        )
+    }
+}
@@ -0,0 +1,343 @@
+// 傳輸層
+//
+// OrbanAgent 經由 `Transport` 與平台通訊，不依賴底層的連線方式：
+// - `OrbanClient`：WebSocket 持久連線
+// - `PollingClient`：HTTP 長輪詢，供封鎖 WebSocket 的網路使用
+// - `auto` 模式先嘗試 WebSocket，升級失敗時改用 HTTP 長輪詢，直到 Agent 重新啟動
+// - `grpc` 模式下 `OrbanClient` 改經由 gRPC 雙向串流傳送相同的訊息流（需啟用 `grpc` 功能）
+
+use super::auth::Authenticator;
+use super::client::OrbanClient;
+use super::compression::CompressionStats;
+use super::endpoints::Endpoints;
+use super::identity;
+use super::keystore;
+use super::latency::LatencyStats;
+use super::orban_protocol::{ActiveTaskInfo, AgentStatus, KeyRotationPayload, Message, TaskErrorInfo, TaskMetrics};
+use super::outbox::Outbox;
+use super::polling::PollingClient;
+use crate::error::{Error, Result};
+use crate::gpu::PowResponse;
+use crate::types::*;
+use crate::AgentConfig;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 傳輸方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    /// 優先使用 WebSocket，升級失敗時改用 HTTP 長輪詢
+    #[default]
+    Auto,
+
+    /// 僅使用 WebSocket
+    WebSocket,
+
+    /// 僅使用 HTTP 長輪詢
+    Http,
+
+    /// gRPC 雙向串流（需以 `grpc` 功能編譯）
+    Grpc,
+}
+
+/// Agent 與平台之間的傳輸層
+///
+/// 實作負責認證、斷線恢復與任務結果的可靠送達，
+/// `receive` 僅在會話無法恢復時返回錯誤
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// 傳輸方式名稱
+    fn name(&self) -> &'static str;
+
+    /// 連接並完成認證
+    async fn connect(&self) -> Result<()>;
+
+    /// 註冊 Agent，硬體清單以身分金鑰簽名後一併送出
+    async fn register(
+        &self,
+        hardware: HardwareInfo,
+        manifest: HardwareManifest,
+        capabilities: Capabilities,
+        location: Location,
+        availability: Availability,
+    ) -> Result<()>;
+
+    /// 接收平台下發的訊息
+    async fn receive(&self) -> Result<Message>;
+
+    /// 發送心跳
+    async fn send_heartbeat(
+        &self,
+        status: AgentStatus,
+        current_task_id: Option<String>,
+        gpu_status: Vec<GPUStatus>,
+        uptime_sec: u64,
+    ) -> Result<()>;
+
+    /// 接受任務
+    async fn accept_task(&self, task_id: &str) -> Result<()>;
+
+    /// 拒絕任務
+    async fn reject_task(&self, task_id: &str, reason: &str) -> Result<()>;
+
+    /// 回報任務進度
+    async fn send_task_progress(&self, task_id: &str, progress: f32, stage: &str, metrics: TaskMetrics) -> Result<()>;
+
+    /// 完成任務
+    async fn complete_task(
+        &self,
+        task_id: &str,
+        result: TaskResult,
+        proof_of_work: ProofOfWork,
+        metrics: ExecutionMetrics,
+    ) -> Result<()>;
+
+    /// 回報任務失敗
+    async fn fail_task(&self, task_id: &str, error: TaskErrorInfo) -> Result<()>;
+
+    /// 發送 PoW 響應
+    async fn send_pow_response(&self, response: PowResponse) -> Result<()>;
+
+    /// 進行中的任務
+    async fn active_tasks(&self) -> Vec<ActiveTaskInfo>;
+
+    /// 中止任務（平台取消或任務相關的不可恢復錯誤）
+    async fn abort_task(&self, task_id: &str);
+
+    /// 送出金鑰輪換聲明，平台確認後返回，拒絕時返回 `Error::PlatformError`
+    async fn rotate_key(&self, rotation: KeyRotationPayload) -> Result<()>;
+
+    /// 硬體變更時重新送出簽名的硬體清單，平台確認後返回，拒絕時返回 `Error::PlatformError`
+    async fn attest_hardware(&self, manifest: HardwareManifest) -> Result<()>;
+
+    /// 壓縮統計
+    fn compression_stats(&self) -> CompressionStats {
+        CompressionStats::default()
+    }
+
+    /// 與平台之間的往返時間統計
+    fn latency(&self) -> LatencyStats;
+
+    /// 平台端點（故障轉移後為目前使用的端點）
+    fn endpoints(&self) -> Arc<Endpoints>;
+
+    /// 已開啟的本地狀態，改用其他傳輸層時沿用
+    fn local_state(&self) -> Option<LocalState> {
+        None
+    }
+
+    /// 斷線
+    async fn disconnect(&self) -> Result<()>;
+}
+
+/// 傳輸層共用的本地狀態：身分金鑰、發件匣與身分記錄
+///
+/// 每個數據目錄只開啟一次，`auto` 模式改用 HTTP 長輪詢時直接交給新的客戶端
+#[derive(Clone)]
+pub struct LocalState {
+    pub authenticator: Arc<Authenticator>,
+    pub outbox: Arc<Outbox>,
+    pub backup_public_key: Option<String>,
+}
+
+impl LocalState {
+    /// 載入身分金鑰、開啟發件匣並核對身分記錄
+    pub fn open(config: &AgentConfig) -> Result<Self> {
+        let authenticator = keystore::load(&config.key, Path::new(&config.private_key_path))?;
+        let outbox = Outbox::open(&config.data_dir, config.network.outbox_max_messages)?;
+        let identity = identity::reconcile(&config.data_dir, &authenticator, &outbox)?;
+
+        Ok(Self {
+            authenticator: Arc::new(authenticator),
+            outbox: Arc::new(outbox),
+            backup_public_key: identity.backup_public_key,
+        })
+    }
+}
+
+/// 依配置建立傳輸層（尚未連線），`auto` 模式先建立 WebSocket 客戶端
+pub async fn create(config: &AgentConfig) -> Result<Arc<dyn Transport>> {
+    Ok(match config.network.transport {
+        TransportKind::Auto | TransportKind::WebSocket => Arc::new(OrbanClient::new(config).await?),
+        TransportKind::Http => Arc::new(PollingClient::new(config).await?),
+        #[cfg(feature = "grpc")]
+        TransportKind::Grpc => Arc::new(OrbanClient::new(config).await?),
+        #[cfg(not(feature = "grpc"))]
+        TransportKind::Grpc => {
+            return Err(Error::InvalidConfig(
+                "The grpc transport requires orban-agent built with the `grpc` feature".to_string(),
+            ))
+        }
+    })
+}
+
+/// 連接到平台，返回實際使用的傳輸層
+///
+/// 先量測各平台端點並選用延遲最低的健康端點；
+/// `auto` 模式下 WebSocket 升級失敗時改用 HTTP 長輪詢
+pub async fn connect(config: &AgentConfig, transport: Arc<dyn Transport>) -> Result<Arc<dyn Transport>> {
+    let endpoints = transport.endpoints();
+    endpoints
+        .select(Duration::from_secs(config.network.connection_timeout_secs))
+        .await;
+
+    match transport.connect().await {
+        Ok(()) => Ok(transport),
+        Err(Error::UpgradeFailed(reason)) if config.network.transport == TransportKind::Auto => {
+            warn!("WebSocket unavailable ({}), falling back to HTTP long polling", reason);
+
+            let state = match transport.local_state() {
+                Some(state) => state,
+                None => LocalState::open(config)?,
+            };
+            let polling: Arc<dyn Transport> = Arc::new(PollingClient::with_state(config, endpoints, state).await?);
+            polling.connect().await?;
+
+            info!("Connected using {} transport", polling.name());
+            Ok(polling)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 平台的 HTTP 基礎 URL（`ws://` 對應 `http://`，`wss://` 對應 `https://`）
+pub fn http_base_url(platform_url: &str) -> String {
+    let url = platform_url.trim_end_matches('/');
+
+    if let Some(rest) = url.strip_prefix("ws://") {
+        format!("http://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else {
+        url.to_string()
+    }
+}
+
+#[async_trait]
+impl Transport for OrbanClient {
+    fn name(&self) -> &'static str {
+        OrbanClient::transport_name(self)
+    }
+
+    async fn connect(&self) -> Result<()> {
+        OrbanClient::connect(self).await
+    }
+
+    async fn register(
+        &self,
+        hardware: HardwareInfo,
+        manifest: HardwareManifest,
+        capabilities: Capabilities,
+        location: Location,
+        availability: Availability,
+    ) -> Result<()> {
+        OrbanClient::register(self, hardware, manifest, capabilities, location, availability).await
+    }
+
+    async fn receive(&self) -> Result<Message> {
+        OrbanClient::receive(self).await
+    }
+
+    async fn send_heartbeat(
+        &self,
+        status: AgentStatus,
+        current_task_id: Option<String>,
+        gpu_status: Vec<GPUStatus>,
+        uptime_sec: u64,
+    ) -> Result<()> {
+        OrbanClient::send_heartbeat(self, status, current_task_id, gpu_status, uptime_sec).await
+    }
+
+    async fn accept_task(&self, task_id: &str) -> Result<()> {
+        OrbanClient::accept_task(self, task_id).await
+    }
+
+    async fn reject_task(&self, task_id: &str, reason: &str) -> Result<()> {
+        OrbanClient::reject_task(self, task_id, reason).await
+    }
+
+    async fn send_task_progress(&self, task_id: &str, progress: f32, stage: &str, metrics: TaskMetrics) -> Result<()> {
+        OrbanClient::send_task_progress(self, task_id, progress, stage, metrics).await
+    }
+
+    async fn complete_task(
+        &self,
+        task_id: &str,
+        result: TaskResult,
+        proof_of_work: ProofOfWork,
+        metrics: ExecutionMetrics,
+    ) -> Result<()> {
+        OrbanClient::complete_task(self, task_id, result, proof_of_work, metrics).await
+    }
+
+    async fn fail_task(&self, task_id: &str, error: TaskErrorInfo) -> Result<()> {
+        OrbanClient::fail_task(self, task_id, error).await
+    }
+
+    async fn send_pow_response(&self, response: PowResponse) -> Result<()> {
+        OrbanClient::send_pow_response(self, response).await
+    }
+
+    async fn active_tasks(&self) -> Vec<ActiveTaskInfo> {
+        OrbanClient::active_tasks(self).await
+    }
+
+    async fn abort_task(&self, task_id: &str) {
+        OrbanClient::abort_task(self, task_id).await
+    }
+
+    async fn rotate_key(&self, rotation: KeyRotationPayload) -> Result<()> {
+        OrbanClient::rotate_key(self, rotation).await
+    }
+
+    async fn attest_hardware(&self, manifest: HardwareManifest) -> Result<()> {
+        OrbanClient::attest_hardware(self, manifest).await
+    }
+
+    fn compression_stats(&self) -> CompressionStats {
+        OrbanClient::compression_stats(self)
+    }
+
+    fn latency(&self) -> LatencyStats {
+        OrbanClient::latency(self)
+    }
+
+    fn endpoints(&self) -> Arc<Endpoints> {
+        OrbanClient::endpoints(self)
+    }
+
+    fn local_state(&self) -> Option<LocalState> {
+        Some(OrbanClient::local_state(self))
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        OrbanClient::disconnect(self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_base_url() {
+        assert_eq!(http_base_url("ws://127.0.0.1:8080"), "http://127.0.0.1:8080");
+        assert_eq!(http_base_url("wss://platform.orban.ai/"), "https://platform.orban.ai");
+        assert_eq!(http_base_url("https://platform.orban.ai"), "https://platform.orban.ai");
+    }
+
+    #[test]
+    fn test_transport_kind_names() {
+        let kind: TransportKind = serde_json::from_str("\"websocket\"").unwrap();
+        assert_eq!(kind, TransportKind::WebSocket);
+        assert_eq!(serde_json::to_string(&TransportKind::Http).unwrap(), "\"http\"");
+        assert_eq!(serde_json::from_str::<TransportKind>("\"grpc\"").unwrap(), TransportKind::Grpc);
+        assert_eq!(TransportKind::default(), TransportKind::Auto);
+    }
+}
@@ -38,6 +38,40 @@ pub struct CPUInfo {
     pub threads: u32,
 }
 
+/// 硬體清單
+///
+/// 以 Agent 金鑰簽名後於註冊時送出，硬體變更時重新送出（見 network::attestation）。
+/// 只包含不隨負載變化的欄位，未取得的欄位為 null
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardwareManifest {
+    /// 依索引排序
+    pub gpus: Vec<GpuManifest>,
+    pub cpu_model: String,
+    pub cpu_cores: u32,
+    pub cpu_threads: u32,
+    pub memory_bytes: u64,
+    pub os: String,
+    pub os_version: String,
+    pub kernel_version: String,
+    pub arch: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuManifest {
+    pub index: u32,
+    pub vendor: GPUVendor,
+    pub model: String,
+    pub uuid: String,
+    /// PCI 匯流排位址（如 "00000000:01:00.0"）
+    pub pci_bus_id: Option<String>,
+    /// PCI 廠商與裝置 ID（如 "10de:2684"）
+    pub pci_device_id: Option<String>,
+    pub vram_bytes: u64,
+    pub compute_capability: String,
+    pub driver_version: Option<String>,
+    pub cuda_version: Option<String>,
+}
+
 // ==================== 能力與可用性 ====================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,86 @@
+// Orban Protocol 線上格式的黃金樣本
+//
+// tests/fixtures/protocol/ 下每種訊息類型一個 JSON 檔，檔名為 `type` 的小寫形式。
+// 樣本解碼後重新編碼必須與原檔一致，經 Protocol Buffers 往返亦同
+
+use orban_agent_core::network::{Message, MessagePayload, MessageType};
+use std::path::{Path, PathBuf};
+
+const ALL_TYPES: &[MessageType] = &[
+    MessageType::AuthChallenge,
+    MessageType::AuthResponse,
+    MessageType::AuthSuccess,
+    MessageType::AgentRegister,
+    MessageType::RegisterAck,
+    MessageType::TaskAssign,
+    MessageType::TaskAccept,
+    MessageType::TaskReject,
+    MessageType::TaskProgress,
+    MessageType::TaskComplete,
+    MessageType::TaskFailed,
+    MessageType::Heartbeat,
+    MessageType::MetricsBatch,
+    MessageType::EarningsRecord,
+    MessageType::PayoutNotification,
+    MessageType::PowChallenge,
+    MessageType::PowResponse,
+    MessageType::Error,
+    MessageType::StateSync,
+    MessageType::Ack,
+];
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/protocol")
+}
+
+fn fixture_path(message_type: MessageType) -> PathBuf {
+    let name = serde_json::to_value(message_type).unwrap();
+    fixtures_dir().join(format!("{}.json", name.as_str().unwrap().to_lowercase()))
+}
+
+fn load(message_type: MessageType) -> (String, serde_json::Value) {
+    let path = fixture_path(message_type);
+    let json = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("missing fixture {}: {}", path.display(), e));
+    let value = serde_json::from_str(&json).unwrap();
+    (json, value)
+}
+
+#[test]
+fn test_fixture_for_every_message_type() {
+    let count = std::fs::read_dir(fixtures_dir()).unwrap().count();
+    assert_eq!(count, ALL_TYPES.len(), "fixtures without a matching message type");
+
+    for message_type in ALL_TYPES {
+        assert!(fixture_path(*message_type).exists(), "no fixture for {:?}", message_type);
+    }
+}
+
+#[test]
+fn test_json_fixtures_roundtrip() {
+    for message_type in ALL_TYPES {
+        let (json, expected) = load(*message_type);
+
+        let msg = Message::from_json(&json)
+            .unwrap_or_else(|e| panic!("failed to decode {:?} fixture: {}", message_type, e));
+        assert_eq!(msg.message_type, *message_type);
+        assert!(!matches!(msg.payload, MessagePayload::Unknown(_)));
+
+        let encoded: serde_json::Value = serde_json::from_str(&msg.to_json().unwrap()).unwrap();
+        assert_eq!(encoded, expected, "{:?} does not re-encode to its fixture", message_type);
+    }
+}
+
+#[test]
+fn test_protobuf_fixtures_roundtrip() {
+    for message_type in ALL_TYPES {
+        let (json, expected) = load(*message_type);
+
+        let msg = Message::from_json(&json).unwrap();
+        let decoded = Message::from_protobuf(&msg.to_protobuf().unwrap())
+            .unwrap_or_else(|e| panic!("failed to decode {:?} from protobuf: {}", message_type, e));
+
+        let encoded: serde_json::Value = serde_json::from_str(&decoded.to_json().unwrap()).unwrap();
+        assert_eq!(encoded, expected, "{:?} changed after a protobuf roundtrip", message_type);
+    }
+}